@@ -1,49 +1,68 @@
+use glam::{Quat, Vec3A, Vec4};
 use glam_ext::{Mat4, Transform3A};
 use ozz_animation_rs::{
-    Animation, BlendingJob, BlendingLayer, LocalToModelJob, SamplingContext, SamplingJob, Skeleton, SoaTransform,
-    ozz_rc_buf,
+    Animation, BlendingJobArc, BlendingLayer, LocalToModelJobArc, SamplingContext, SamplingJobArc, Skeleton,
+    SoaTransform, ozz_arc_buf,
 };
-use std::cell::RefCell;
+use static_assertions::assert_impl_all;
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
+use crate::animation::foot_ik::FootIk;
 use crate::animation::hit_motion::{HitMotion, HitMotionSampler};
+use crate::animation::look_at::LookAt;
 use crate::animation::rest_poses_to_model_transforms;
-use crate::animation::utils::{WeaponTransform, matrices_to_transforms};
+use crate::animation::shape_key::{ShapeKey, normalize_shape_key_by_weight, sample_shape_key_by_name_weight};
+use crate::animation::utils::{JointMask, ShapeKeyValue, WeaponTransform, matrices_to_transforms};
 use crate::animation::weapon_motion::{WeaponMotion, normalize_weapons_by_weight, sample_weapons_by_name_weight};
 use crate::asset::AssetLoader;
 use crate::consts::{INVALID_ACTION_ID, INVALID_ANIMATION_ID};
-use crate::logic::{StateActionAnimation, StateActionAny};
-use crate::utils::{HistoryQueue, SmallVec, Symbol, TmplID, XError, XResult, xfrom, xres};
+use crate::logic::{StateActionAnimation, StateActionAny, StateAdditiveAnimation};
+use crate::utils::{HistoryQueue, SmallVec, Symbol, TmplID, XError, XResult, xfrom, xres, xresf};
 
 #[derive(Debug)]
 pub struct Animator {
-    skeleton: Rc<Skeleton>,
-    blending_job: BlendingJob,
-    l2m_job: LocalToModelJob,
+    skeleton: Arc<Skeleton>,
+    blending_job: BlendingJobArc,
+    l2m_job: LocalToModelJobArc,
     weapon_transforms: Vec<WeaponTransform>,
+    shape_keys: Vec<ShapeKeyValue>,
     action_queue: HistoryQueue<ActionData>,
     sampling_arena: SamplingArena,
+    additives: Vec<AdditiveData>,
+    foot_ik: Option<FootIk>,
+    look_at: Option<LookAt>,
+    joint_masks: Vec<JointMask>,
+    mask_coverage: Vec<Vec4>,
     model_transforms: Vec<Transform3A>,
 }
 
+// Animators of different characters are sampled on worker threads.
+assert_impl_all!(Animator: Send);
+
 impl Animator {
-    pub fn new(skeleton: Rc<Skeleton>, action_cap: usize, sampling_cap: usize) -> XResult<Animator> {
+    pub fn new(skeleton: Arc<Skeleton>, action_cap: usize, sampling_cap: usize) -> XResult<Animator> {
         let mut model_transforms = vec![Transform3A::ZERO; skeleton.num_joints()];
         rest_poses_to_model_transforms(&skeleton, &mut model_transforms)?;
 
         let mut animator: Animator = Animator {
             skeleton: skeleton.clone(),
-            blending_job: BlendingJob::default(),
-            l2m_job: LocalToModelJob::default(),
+            blending_job: BlendingJobArc::default(),
+            l2m_job: LocalToModelJobArc::default(),
             weapon_transforms: Vec::with_capacity(4),
+            shape_keys: Vec::new(),
             action_queue: HistoryQueue::with_capacity(action_cap.max(1)),
             sampling_arena: SamplingArena::new(sampling_cap.max(1)),
+            additives: Vec::new(),
+            foot_ik: None,
+            look_at: None,
+            joint_masks: Vec::new(),
+            mask_coverage: Vec::new(),
             model_transforms,
         };
 
         animator.blending_job.set_skeleton(animator.skeleton.clone());
-        animator.blending_job.set_output(ozz_rc_buf(vec![
+        animator.blending_job.set_output(ozz_arc_buf(vec![
             SoaTransform::default();
             animator.skeleton.num_soa_joints()
         ]));
@@ -54,7 +73,7 @@ impl Animator {
             .set_input(animator.blending_job.output().unwrap().clone());
         animator
             .l2m_job
-            .set_output(ozz_rc_buf(vec![Mat4::default(); animator.skeleton.num_joints()]));
+            .set_output(ozz_arc_buf(vec![Mat4::default(); animator.skeleton.num_joints()]));
         Ok(animator)
     }
 
@@ -81,7 +100,7 @@ impl Animator {
             if state.id != ad.id {
                 return xres!(LogicBadState; "state id");
             }
-            ad.update(&mut self.sampling_arena, frame, state, &self.skeleton, loader)?;
+            ad.update(&mut self.sampling_arena, frame, state, &self.skeleton, &self.joint_masks, loader)?;
         }
 
         // 3. try reuse actions
@@ -91,7 +110,7 @@ impl Animator {
                 .action_queue
                 .enqueue_reuse(|ad| {
                     if ad.tmpl_id == state.tmpl_id {
-                        ad.reuse(&mut self.sampling_arena, frame, state, &self.skeleton, loader)?;
+                        ad.reuse(&mut self.sampling_arena, frame, state, &self.skeleton, &self.joint_masks, loader)?;
                     }
                     Ok(ad.tmpl_id == state.tmpl_id)
                 })?
@@ -105,12 +124,40 @@ impl Animator {
         for idx in self.action_queue.len()..states.len() {
             let state = &states[idx];
             let mut ad = ActionData::default();
-            ad.init(&mut self.sampling_arena, frame, state, &self.skeleton, loader)?;
+            ad.init(&mut self.sampling_arena, frame, state, &self.skeleton, &self.joint_masks, loader)?;
             self.action_queue.enqueue_new(ad);
         }
         Ok(())
     }
 
+    /// Syncs the additive animations with the states. Additive animations are rebuilt from the states
+    /// every frame, so they need nothing in `restore()` and `discard()`.
+    pub fn update_additives(
+        &mut self,
+        time: f32,
+        states: &[StateAdditiveAnimation],
+        loader: &mut AssetLoader,
+    ) -> XResult<()> {
+        self.additives.retain(|ad| states.iter().any(|state| ad.is_same(state)));
+
+        for state in states {
+            let pos = match self.additives.iter().position(|ad| ad.is_same(state)) {
+                Some(pos) => pos,
+                None => {
+                    let animation = loader.load_animation(state.files)?;
+                    self.additives.push(AdditiveData::new(state, &self.skeleton, animation));
+                    self.additives.len() - 1
+                }
+            };
+
+            let ad = &mut self.additives[pos];
+            ad.weight = state.current_weight(time);
+            let duration = ad.sampling_job.animation().unwrap().duration();
+            ad.sampling_job.set_ratio(state.ratio(time, duration));
+        }
+        Ok(())
+    }
+
     pub fn restore(&mut self, frame: u32, states: &[Box<dyn StateActionAny>]) -> XResult<()> {
         if states.is_empty() {
             return xres!(LogicBadState; "states empty");
@@ -153,23 +200,73 @@ impl Animator {
 
     pub fn animate(&mut self) -> XResult<()> {
         self.blending_job.layers_mut().clear();
+        self.blending_job.additive_layers_mut().clear();
         self.weapon_transforms.clear();
+        self.shape_keys.clear();
         for ad in self.action_queue.iter_mut() {
             ad.animate(
                 &mut self.sampling_arena,
                 &mut self.blending_job,
                 &mut self.weapon_transforms,
+                &mut self.shape_keys,
             )?;
         }
+        self.override_masked_layers();
+        for ad in self.additives.iter_mut().filter(|ad| ad.weight > 0.0) {
+            ad.sampling_job.run().map_err(xfrom!())?;
+            self.blending_job.additive_layers_mut().push(BlendingLayer::with_weight(
+                ad.sampling_job.output().unwrap().clone(),
+                ad.weight,
+            ));
+        }
         self.blending_job.run().map_err(xfrom!())?;
         self.l2m_job.run().map_err(xfrom!())?;
         matrices_to_transforms(
-            self.l2m_job.output().unwrap().borrow().as_slice(),
+            self.l2m_job.output().unwrap().read().unwrap().as_slice(),
             &mut self.model_transforms,
         )?;
 
         normalize_weapons_by_weight(&mut self.weapon_transforms);
+        normalize_shape_key_by_weight(&mut self.shape_keys);
+        if let Some(look_at) = &self.look_at {
+            look_at.solve(&mut self.model_transforms, &mut self.weapon_transforms);
+        }
+
+        // With foot IK, the hit motions are sampled after the IK pass in `apply_foot_ik()`.
+        if self.foot_ik.is_none() {
+            self.animate_hit_motion()?;
+        }
+        Ok(())
+    }
+
+    /// Lets the partial-body layers override the layers below them, instead of being averaged with them.
+    ///
+    /// From the top partial-body layer down, each layer is weakened by the coverage of the partial-body layers
+    /// above it, then adds its own `mask * weight` to the coverage. Full-body layers above the top partial-body
+    /// layer are not affected.
+    fn override_masked_layers(&mut self) {
+        let layers = self.blending_job.layers_mut();
+        let Some(top) = layers.iter().rposition(|layer| !layer.joint_weights.is_empty())
+        else {
+            return;
+        };
+
+        self.mask_coverage.clear();
+        self.mask_coverage.resize(self.skeleton.num_soa_joints(), Vec4::ZERO);
+        for layer in layers[..=top].iter_mut().rev() {
+            if layer.joint_weights.is_empty() {
+                layer.joint_weights.extend(self.mask_coverage.iter().map(|coverage| Vec4::ONE - *coverage));
+            }
+            else {
+                for (mask, coverage) in layer.joint_weights.iter_mut().zip(self.mask_coverage.iter_mut()) {
+                    *mask *= Vec4::ONE - *coverage;
+                    *coverage = (*coverage + *mask * layer.weight).min(Vec4::ONE);
+                }
+            }
+        }
+    }
 
+    fn animate_hit_motion(&mut self) -> XResult<()> {
         if let Some(current_action) = self.action_queue.last_mut() {
             current_action.animate_hit_motion(
                 &mut self.sampling_arena,
@@ -180,6 +277,59 @@ impl Animator {
         Ok(())
     }
 
+    /// Sets the joint masks of the character, which the partial-body animations refer to by name.
+    #[inline]
+    pub fn set_joint_masks(&mut self, joint_masks: Vec<JointMask>) {
+        self.joint_masks = joint_masks;
+    }
+
+    /// Sets the optional look-at post-process, applied in `animate()` before the hit motions.
+    #[inline]
+    pub fn set_look_at(&mut self, look_at: Option<LookAt>) {
+        self.look_at = look_at;
+    }
+
+    #[inline]
+    pub fn look_at(&self) -> Option<&LookAt> {
+        self.look_at.as_ref()
+    }
+
+    /// Sets the look-at target (in model space) and weight of the next `animate()`. Does nothing without look-at.
+    #[inline]
+    pub fn set_look_at_target(&mut self, target: Vec3A, weight: f32) {
+        if let Some(look_at) = &mut self.look_at {
+            look_at.set_target(target, weight);
+        }
+    }
+
+    /// Sets the optional foot IK post-process. See `apply_foot_ik()`.
+    #[inline]
+    pub fn set_foot_ik(&mut self, foot_ik: Option<FootIk>) {
+        self.foot_ik = foot_ik;
+    }
+
+    #[inline]
+    pub fn foot_ik(&self) -> Option<&FootIk> {
+        self.foot_ik.as_ref()
+    }
+
+    /// Places the feet of the pose from `animate()` on the ground, then samples the hit motions.
+    ///
+    /// `position` and `rotation` transform the model space into the world space. `find_ground` returns
+    /// the ground point (in world space) under a world position. Does nothing without foot IK.
+    pub fn apply_foot_ik<F>(&mut self, position: Vec3A, rotation: Quat, find_ground: F) -> XResult<()>
+    where
+        F: FnMut(Vec3A) -> XResult<Option<Vec3A>>,
+    {
+        let Some(foot_ik) = &mut self.foot_ik
+        else {
+            return Ok(());
+        };
+        foot_ik.update_grounds(&self.model_transforms, position, rotation, find_ground)?;
+        foot_ik.solve(&mut self.model_transforms);
+        self.animate_hit_motion()
+    }
+
     #[inline]
     pub fn model_transforms(&self) -> &[Transform3A] {
         &self.model_transforms
@@ -190,6 +340,25 @@ impl Animator {
         &self.weapon_transforms
     }
 
+    /// The shape key values blended by the animation weights, in the first appearance order of the names.
+    #[inline]
+    pub fn shape_keys(&self) -> &[ShapeKeyValue] {
+        &self.shape_keys
+    }
+
+    /// Takes the shape key values out, the next `animate()` fills a new vector.
+    #[inline]
+    pub fn take_shape_keys(&mut self) -> Vec<ShapeKeyValue> {
+        mem::take(&mut self.shape_keys)
+    }
+
+    /// Same as `take_shape_keys()`, but swaps with the given vector to reuse its capacity.
+    #[inline]
+    pub fn take_shape_keys_into(&mut self, shape_keys: &mut Vec<ShapeKeyValue>) {
+        shape_keys.clear();
+        mem::swap(&mut self.shape_keys, shape_keys);
+    }
+
     #[inline]
     pub fn action_animation_id(&self) -> (u32, u16) {
         match self.action_queue.last() {
@@ -257,6 +426,7 @@ impl ActionData {
         frame: u32,
         state: &Box<dyn StateActionAny>,
         skeleton: &Skeleton,
+        joint_masks: &[JointMask],
         loader: &mut AssetLoader,
     ) -> XResult<()> {
         if state.animations.is_empty() {
@@ -271,14 +441,17 @@ impl ActionData {
         for anim_state in &state.animations {
             let pos = arena.alloc_and_reptr(&mut pnext);
             let sd = arena.get_mut(pos);
-            let (animation, weapon_motion, hit_motion) = Self::load_resources(loader, anim_state)?;
+            let (animation, weapon_motion, hit_motion, shape_key) = Self::load_resources(loader, anim_state)?;
             sd.init(
                 anim_state.animation_id,
                 anim_state.files,
+                anim_state.joint_mask,
                 skeleton,
+                joint_masks,
                 animation,
                 weapon_motion,
                 hit_motion,
+                shape_key,
             )?;
             sd.frame = frame;
             sd.weight = anim_state.weight * state.fade_in_weight;
@@ -294,7 +467,12 @@ impl ActionData {
     fn load_resources(
         loader: &mut AssetLoader,
         anim_state: &StateActionAnimation,
-    ) -> XResult<(Rc<Animation>, Option<Rc<WeaponMotion>>, Option<Rc<HitMotion>>)> {
+    ) -> XResult<(
+        Arc<Animation>,
+        Option<Arc<WeaponMotion>>,
+        Option<Arc<HitMotion>>,
+        Option<Arc<ShapeKey>>,
+    )> {
         let animation = loader.load_animation(anim_state.files)?;
         let weapon_motion = match anim_state.weapon_motion() {
             true => Some(loader.load_weapon_motion(anim_state.files)?),
@@ -304,7 +482,11 @@ impl ActionData {
             true => Some(loader.load_hit_motion(anim_state.files)?),
             false => None,
         };
-        Ok((animation, weapon_motion, hit_motion))
+        let shape_key = match anim_state.shape_key() {
+            true => Some(loader.load_shape_key(anim_state.files)?),
+            false => None,
+        };
+        Ok((animation, weapon_motion, hit_motion, shape_key))
     }
 
     fn reuse(
@@ -313,13 +495,14 @@ impl ActionData {
         frame: u32,
         state: &Box<dyn StateActionAny>,
         skeleton: &Skeleton,
+        joint_masks: &[JointMask],
         loader: &mut AssetLoader,
     ) -> XResult<()> {
         self.id = state.id;
         self.job_future = self.job_past;
         // self.job_past = self.job_past;
         self.job_current = self.job_past;
-        self.update(arena, frame, state, skeleton, loader)
+        self.update(arena, frame, state, skeleton, joint_masks, loader)
     }
 
     fn update(
@@ -328,6 +511,7 @@ impl ActionData {
         frame: u32,
         state: &Box<dyn StateActionAny>,
         skeleton: &Skeleton,
+        joint_masks: &[JointMask],
         loader: &mut AssetLoader,
     ) -> XResult<()> {
         if state.animations.is_empty() {
@@ -375,7 +559,7 @@ impl ActionData {
             let anim_state = animation_state!(state.animations, state_idx, break);
 
             let sd = arena.get_mut(self.job_future);
-            if sd.animation_file == anim_state.files {
+            if sd.animation_file == anim_state.files && sd.joint_mask == anim_state.joint_mask {
                 // reuse job already in jobs, don't modify sd.next
                 sd.animation_id = anim_state.animation_id;
                 sd.frame = frame;
@@ -405,14 +589,17 @@ impl ActionData {
 
             let pos = arena.alloc_and_reptr(&mut pnext);
             let sd = arena.get_mut(pos);
-            let (animation, weapon_motion, hit_motion) = Self::load_resources(loader, anim_state)?;
+            let (animation, weapon_motion, hit_motion, shape_key) = Self::load_resources(loader, anim_state)?;
             sd.init(
                 anim_state.animation_id,
                 anim_state.files,
+                anim_state.joint_mask,
                 skeleton,
+                joint_masks,
                 animation,
                 weapon_motion,
                 hit_motion,
+                shape_key,
             )?;
             sd.frame = frame;
             sd.weight = anim_state.weight * state.fade_in_weight;
@@ -508,8 +695,9 @@ impl ActionData {
     fn animate(
         &mut self,
         arena: &mut SamplingArena,
-        blending_job: &mut BlendingJob,
+        blending_job: &mut BlendingJobArc,
         weapon_transforms: &mut Vec<WeaponTransform>,
+        shape_keys: &mut Vec<ShapeKeyValue>,
     ) -> XResult<()> {
         let mut iter = self.job_current;
         while iter != self.job_future {
@@ -517,14 +705,18 @@ impl ActionData {
             iter = sd.next;
 
             sd.sampling_job.run().map_err(xfrom!())?;
-            blending_job.layers_mut().push(BlendingLayer::with_weight(
-                sd.sampling_job.output().unwrap().clone(),
-                sd.weight,
-            ));
+            let mut layer = BlendingLayer::with_weight(sd.sampling_job.output().unwrap().clone(), sd.weight);
+            if !sd.joint_weights.is_empty() {
+                layer.joint_weights = sd.joint_weights.clone();
+            }
+            blending_job.layers_mut().push(layer);
 
             if let Some(weapon_motion) = &sd.weapon_motion {
                 sample_weapons_by_name_weight(weapon_motion, sd.sampling_job.ratio(), sd.weight, weapon_transforms)?;
             }
+            if let Some(shape_key) = &sd.shape_key {
+                sample_shape_key_by_name_weight(shape_key, sd.sampling_job.ratio(), sd.weight, shape_keys)?;
+            }
         }
         Ok(())
     }
@@ -571,9 +763,12 @@ struct SamplingData {
     frame: u32,
     weight: f32,
     animation_file: Symbol,
-    sampling_job: SamplingJob,
-    weapon_motion: Option<Rc<WeaponMotion>>,
+    joint_mask: Symbol,
+    joint_weights: Vec<Vec4>, // Empty for full-body animations
+    sampling_job: SamplingJobArc,
+    weapon_motion: Option<Arc<WeaponMotion>>,
     hit_motion_sampler: Option<HitMotionSampler>,
+    shape_key: Option<Arc<ShapeKey>>,
 }
 
 impl Default for SamplingData {
@@ -584,9 +779,12 @@ impl Default for SamplingData {
             frame: 0,
             weight: 0.0,
             animation_file: Symbol::default(),
-            sampling_job: SamplingJob::default(),
+            joint_mask: Symbol::default(),
+            joint_weights: Vec::new(),
+            sampling_job: SamplingJobArc::default(),
             weapon_motion: None,
             hit_motion_sampler: None,
+            shape_key: None,
         }
     }
 }
@@ -596,25 +794,36 @@ impl SamplingData {
         &mut self,
         animation_id: u16,
         animation_file: Symbol,
+        joint_mask: Symbol,
         skeleton: &Skeleton,
-        animation: Rc<Animation>,
-        weapon_motion: Option<Rc<WeaponMotion>>,
-        hit_motion: Option<Rc<HitMotion>>,
+        joint_masks: &[JointMask],
+        animation: Arc<Animation>,
+        weapon_motion: Option<Arc<WeaponMotion>>,
+        hit_motion: Option<Arc<HitMotion>>,
+        shape_key: Option<Arc<ShapeKey>>,
     ) -> XResult<()> {
         self.animation_id = animation_id;
         self.frame = 0;
         self.weight = 0.0;
         self.animation_file = animation_file;
 
+        self.joint_mask = joint_mask;
+        self.joint_weights.clear();
+        if !joint_mask.is_empty() {
+            let Some(mask) = joint_masks.iter().find(|mask| mask.name == joint_mask)
+            else {
+                return xresf!(BadAsset; "joint_mask={}", joint_mask);
+            };
+            self.joint_weights.extend_from_slice(&mask.weights);
+        }
+
         let ctx = SamplingContext::from_animation(&animation);
         self.sampling_job.set_animation(animation);
         self.sampling_job.set_context(ctx);
-        self.sampling_job.set_output(Rc::new(RefCell::new(vec![
-            SoaTransform::default();
-            skeleton.num_soa_joints()
-        ])));
+        self.sampling_job.set_output(ozz_arc_buf(vec![SoaTransform::default(); skeleton.num_soa_joints()]));
 
         self.weapon_motion = weapon_motion;
+        self.shape_key = shape_key;
 
         if let Some(hit_motion) = hit_motion {
             self.hit_motion_sampler = Some(HitMotionSampler::new(hit_motion, skeleton)?);
@@ -625,6 +834,35 @@ impl SamplingData {
     }
 }
 
+#[derive(Debug)]
+struct AdditiveData {
+    files: Symbol,
+    start_frame: u32,
+    weight: f32,
+    sampling_job: SamplingJobArc,
+}
+
+impl AdditiveData {
+    fn new(state: &StateAdditiveAnimation, skeleton: &Skeleton, animation: Arc<Animation>) -> AdditiveData {
+        let mut sampling_job = SamplingJobArc::default();
+        let ctx = SamplingContext::from_animation(&animation);
+        sampling_job.set_animation(animation);
+        sampling_job.set_context(ctx);
+        sampling_job.set_output(ozz_arc_buf(vec![SoaTransform::default(); skeleton.num_soa_joints()]));
+        AdditiveData {
+            files: state.files,
+            start_frame: state.start_frame,
+            weight: 0.0,
+            sampling_job,
+        }
+    }
+
+    #[inline]
+    fn is_same(&self, state: &StateAdditiveAnimation) -> bool {
+        self.files == state.files && self.start_frame == state.start_frame
+    }
+}
+
 #[derive(Debug)]
 struct SamplingArena {
     init_cap: usize,
@@ -711,6 +949,7 @@ mod tests {
     use super::*;
     use crate::asset::AssetLoader;
     use crate::consts::TEST_ASSET_PATH;
+    use crate::instance::{InstFootIk, InstFootIkLeg, InstJointMask, InstJointMaskJoint};
     use crate::logic::{StateActionAnimation, StateActionEmpty};
     use crate::utils::{id, sb};
     use std::ptr;
@@ -735,7 +974,7 @@ mod tests {
         linked_list
     }
 
-    fn prepare_resource() -> (AssetLoader, Rc<Skeleton>) {
+    fn prepare_resource() -> (AssetLoader, Arc<Skeleton>) {
         let mut asset_loader = AssetLoader::new(TEST_ASSET_PATH).unwrap();
         let skeleton = asset_loader.load_skeleton(sb!("Girl/Girl.*")).unwrap();
         (asset_loader, skeleton)
@@ -950,7 +1189,7 @@ mod tests {
     fn test_action_data_restore() {
         fn prepare() -> (
             AssetLoader,
-            Rc<Skeleton>,
+            Arc<Skeleton>,
             SamplingArena,
             ActionData,
             Box<dyn StateActionAny>,
@@ -1147,6 +1386,301 @@ mod tests {
         assert_eq!(animator.action_queue.all_len(), 3);
     }
 
+    #[test]
+    fn test_skeleton_animator_update_additives() {
+        let (mut asset_loader, skeleton) = prepare_resource();
+        let mut animator = Animator::new(skeleton, 0, 3).unwrap();
+
+        let mut additives = vec![
+            StateAdditiveAnimation {
+                files: sb!("Girl/Idle_Empty.*"),
+                action_id: 1,
+                start_frame: 30,
+                start_time: 1.0,
+                duration: 0.5,
+                weight: 0.8,
+                looping: false,
+            },
+            StateAdditiveAnimation {
+                files: sb!("Girl/Attack_01A.*"),
+                action_id: 1,
+                start_frame: 36,
+                start_time: 1.2,
+                duration: 1.0,
+                weight: 1.0,
+                looping: false,
+            },
+        ];
+        animator.update_additives(1.25, &additives, &mut asset_loader).unwrap();
+        assert_eq!(animator.additives.len(), 2);
+        assert_eq!(animator.additives[0].files, sb!("Girl/Idle_Empty.*"));
+        assert!((animator.additives[0].weight - 0.4).abs() < 1e-5);
+        assert_eq!(animator.additives[1].files, sb!("Girl/Attack_01A.*"));
+        assert!((animator.additives[1].weight - 0.95).abs() < 1e-5);
+        assert!(animator.additives[1].sampling_job.ratio() > 0.0);
+        animator.animate().unwrap();
+        assert_eq!(animator.blending_job.additive_layers().len(), 2);
+
+        additives.remove(0);
+        animator.update_additives(1.6, &additives, &mut asset_loader).unwrap();
+        assert_eq!(animator.additives.len(), 1);
+        assert_eq!(animator.additives[0].start_frame, 36);
+        assert!((animator.additives[0].weight - 0.6).abs() < 1e-5);
+
+        // The same clip played again in a later frame is a new additive.
+        additives[0].start_frame = 45;
+        additives[0].start_time = 1.5;
+        animator.update_additives(1.6, &additives, &mut asset_loader).unwrap();
+        assert_eq!(animator.additives.len(), 1);
+        assert_eq!(animator.additives[0].start_frame, 45);
+        assert!((animator.additives[0].weight - 0.9).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_skeleton_animator_looping_additives() {
+        let (mut asset_loader, skeleton) = prepare_resource();
+        let mut animator = Animator::new(skeleton, 0, 3).unwrap();
+
+        let additives = vec![StateAdditiveAnimation {
+            files: sb!("Girl/Idle_Empty.*"),
+            action_id: 1,
+            start_frame: 0,
+            start_time: 0.0,
+            duration: 0.0,
+            weight: 0.5,
+            looping: true,
+        }];
+        let anim_duration = asset_loader
+            .load_animation(sb!("Girl/Idle_Empty.*"))
+            .unwrap()
+            .duration();
+        for time in [0.5, anim_duration + 0.5, anim_duration * 3.0 + 0.25] {
+            animator.update_additives(time, &additives, &mut asset_loader).unwrap();
+            assert_eq!(animator.additives.len(), 1);
+            assert_eq!(animator.additives[0].weight, 0.5);
+            let ratio = animator.additives[0].sampling_job.ratio();
+            assert!((ratio - (time / anim_duration).fract()).abs() < 1e-4);
+            assert!(!additives[0].is_finished(time));
+        }
+    }
+
+    #[test]
+    fn test_skeleton_animator_partial_layer() {
+        let (mut asset_loader, skeleton) = prepare_resource();
+        let inst_mask = InstJointMask {
+            name: sb!("UpperBody"),
+            joints: vec![InstJointMaskJoint {
+                joint: sb!("Chest"),
+                weight: 1.0,
+            }],
+        };
+        let joint_masks = vec![JointMask::new(&inst_mask, &skeleton).unwrap()];
+
+        let new_states = |run: bool, attack: Option<f32>| {
+            let mut states: Vec<Box<dyn StateActionAny>> = Vec::new();
+            if run {
+                let mut state = Box::new(StateActionEmpty::default());
+                state.id = 31;
+                state.tmpl_id = id!("Action.Empty^1");
+                state.animations.push(StateActionAnimation::new_no_motion(
+                    sb!("Girl/Run_Empty.*"),
+                    101,
+                    0.3,
+                    1.0,
+                ));
+                states.push(state);
+            }
+            if let Some(weight) = attack {
+                let mut state = Box::new(StateActionEmpty::default());
+                state.id = 32;
+                state.tmpl_id = id!("Action.Empty^2");
+                state.fade_in_weight = weight;
+                let mut anim = StateActionAnimation::new_no_motion(sb!("Girl/Attack_01A.*"), 102, 0.5, 1.0);
+                if run {
+                    anim.joint_mask = sb!("UpperBody");
+                }
+                state.animations.push(anim);
+                states.push(state);
+            }
+            states
+        };
+        let mut animate = |states: &[Box<dyn StateActionAny>]| {
+            let mut animator = Animator::new(skeleton.clone(), 0, 3).unwrap();
+            animator.set_joint_masks(joint_masks.clone());
+            animator.update(0, states, &mut asset_loader).unwrap();
+            animator.animate().unwrap();
+            animator.model_transforms().to_vec()
+        };
+        let relative = |transforms: &[Transform3A], parent: &str, child: &str| {
+            let parent = transforms[skeleton.joint_by_name(parent).unwrap() as usize];
+            let child = transforms[skeleton.joint_by_name(child).unwrap() as usize];
+            parent.rotation.inverse() * child.rotation
+        };
+
+        let run = animate(&new_states(true, None));
+        let attack = animate(&new_states(false, Some(1.0)));
+        let partial = animate(&new_states(true, Some(1.0)));
+        for joint in ["Hips", "Spine", "LeftUpperLeg", "LeftFoot", "RightLowerLeg", "RightFoot"] {
+            let idx = skeleton.joint_by_name(joint).unwrap() as usize;
+            assert!(partial[idx].translation.abs_diff_eq(run[idx].translation, 1e-4), "{}", joint);
+            assert!(partial[idx].rotation.abs_diff_eq(run[idx].rotation, 1e-4), "{}", joint);
+        }
+        for (parent, child) in [("Spine", "Chest"), ("Chest", "Head"), ("UpperChest", "LeftHand")] {
+            let expected = relative(&attack, parent, child);
+            assert!(relative(&partial, parent, child).abs_diff_eq(expected, 1e-4), "{}", child);
+        }
+        let hand = relative(&attack, "UpperChest", "LeftHand");
+        assert!(!relative(&run, "UpperChest", "LeftHand").abs_diff_eq(hand, 1e-4));
+
+        // A fading-in partial layer blends with the locomotion, without affecting the legs.
+        let fading = animate(&new_states(true, Some(0.5)));
+        let foot = skeleton.joint_by_name("LeftFoot").unwrap() as usize;
+        assert!(fading[foot].rotation.abs_diff_eq(run[foot].rotation, 1e-4));
+        let half = relative(&fading, "UpperChest", "LeftHand");
+        assert!(!half.abs_diff_eq(relative(&run, "UpperChest", "LeftHand"), 1e-4));
+        assert!(!half.abs_diff_eq(hand, 1e-4));
+    }
+
+    #[test]
+    fn test_skeleton_animator_foot_ik() {
+        let (mut asset_loader, skeleton) = prepare_resource();
+        let inst_foot_ik = InstFootIk {
+            pelvis: sb!("Hips"),
+            legs: vec![
+                InstFootIkLeg {
+                    hip: sb!("LeftUpperLeg"),
+                    knee: sb!("LeftLowerLeg"),
+                    ankle: sb!("LeftFoot"),
+                },
+                InstFootIkLeg {
+                    hip: sb!("RightUpperLeg"),
+                    knee: sb!("RightLowerLeg"),
+                    ankle: sb!("RightFoot"),
+                },
+            ],
+            max_pelvis_offset: 0.3,
+            max_foot_offset: 0.4,
+            root_tilt: 0.0,
+        };
+
+        let mut state = Box::new(StateActionEmpty::default());
+        state.id = 41;
+        state.tmpl_id = id!("Action.Empty^1");
+        state.animations.push(StateActionAnimation::new_no_motion(
+            sb!("Girl/Idle_Empty.*"),
+            101,
+            0.0,
+            1.0,
+        ));
+        let states: Vec<Box<dyn StateActionAny>> = vec![state];
+        let mut animate = |foot_ik: bool, find_ground: &dyn Fn(Vec3A) -> Option<Vec3A>| {
+            let mut animator = Animator::new(skeleton.clone(), 0, 3).unwrap();
+            if foot_ik {
+                animator.set_foot_ik(Some(FootIk::new(&inst_foot_ik, &skeleton).unwrap()));
+            }
+            animator.update(0, &states, &mut asset_loader).unwrap();
+            animator.animate().unwrap();
+            animator
+                .apply_foot_ik(Vec3A::ZERO, Quat::IDENTITY, |point| Ok(find_ground(point)))
+                .unwrap();
+            animator.model_transforms().to_vec()
+        };
+        let hips = skeleton.joint_by_name("Hips").unwrap() as usize;
+        let left = skeleton.joint_by_name("LeftFoot").unwrap() as usize;
+        let right = skeleton.joint_by_name("RightFoot").unwrap() as usize;
+
+        // Flat ground, or no ground
+        let base = animate(false, &|point| Some(Vec3A::new(point.x, 0.0, point.z)));
+        assert!(base[left].translation.x > 0.0 && base[right].translation.x < 0.0);
+        let flat = animate(true, &|point| Some(Vec3A::new(point.x, 0.0, point.z)));
+        for (transform, expected) in flat.iter().zip(base.iter()) {
+            assert!(transform.translation.abs_diff_eq(expected.translation, 1e-4));
+        }
+        assert_eq!(animate(true, &|_| None), base);
+
+        // A step under the left foot, the left leg bends
+        let edge = base[left].translation.x * 0.5;
+        let step = animate(true, &|point| {
+            let height = if point.x > edge { 0.1 } else { 0.0 };
+            Some(Vec3A::new(point.x, height, point.z))
+        });
+        assert!(step[hips].translation.abs_diff_eq(base[hips].translation, 1e-4));
+        assert!(step[right].translation.abs_diff_eq(base[right].translation, 1e-3));
+        let expected = base[left].translation + Vec3A::new(0.0, 0.1, 0.0);
+        assert!(step[left].translation.abs_diff_eq(expected, 1e-3));
+
+        // Lower ground, the pelvis moves down
+        let lower = animate(true, &|point| Some(Vec3A::new(point.x, -0.2, point.z)));
+        for joint in [hips, left, right] {
+            let expected = base[joint].translation - Vec3A::new(0.0, 0.2, 0.0);
+            assert!(lower[joint].translation.abs_diff_eq(expected, 1e-3));
+        }
+    }
+
+    #[test]
+    fn test_skeleton_animator_shape_keys() {
+        let mut asset_loader = AssetLoader::new(TEST_ASSET_PATH).unwrap();
+        let skeleton = asset_loader.load_skeleton(sb!("Slime/Slime.*")).unwrap();
+        let start = asset_loader.load_shape_key(sb!("Slime/RunStart.*")).unwrap();
+        let run = asset_loader.load_shape_key(sb!("Slime/RunLoop.*")).unwrap();
+        let sample = |shape_key: &ShapeKey, name: &str, ratio: f32| {
+            let track = shape_key.iter().find(|track| track.name() == name).unwrap();
+            track.sample(ratio).unwrap()
+        };
+
+        let mut animate = |weight0: f32, weight1: f32| {
+            let mut states: Vec<Box<dyn StateActionAny>> = vec![
+                Box::new(StateActionEmpty::default()),
+                Box::new(StateActionEmpty::default()),
+            ];
+            states[0].id = 51;
+            states[0].tmpl_id = id!("Action.Empty^1");
+            states[0].animations.push(StateActionAnimation::new(
+                sb!("Slime/RunStart.*"),
+                101,
+                false,
+                false,
+                true,
+                0.3,
+                weight0,
+            ));
+            states[1].id = 52;
+            states[1].tmpl_id = id!("Action.Empty^2");
+            states[1].animations.push(StateActionAnimation::new(
+                sb!("Slime/RunLoop.*"),
+                102,
+                false,
+                false,
+                true,
+                0.6,
+                weight1,
+            ));
+            let mut animator = Animator::new(skeleton.clone(), 0, 3).unwrap();
+            animator.update(0, &states, &mut asset_loader).unwrap();
+            animator.animate().unwrap();
+            animator.take_shape_keys()
+        };
+
+        // The values are blended by the weights, and normalized by the total weight
+        for (weight0, weight1) in [(0.25, 0.75), (0.2, 0.3), (0.0, 0.5)] {
+            let shape_keys = animate(weight0, weight1);
+            assert_eq!(shape_keys.len(), 2);
+            for (skv, name) in shape_keys.iter().zip(["Air", "Land"]) {
+                assert_eq!(skv.name, name);
+                assert_eq!(skv.weight, 1.0);
+                let expected = (sample(&start, name, 0.3) * weight0 + sample(&run, name, 0.6) * weight1)
+                    / (weight0 + weight1);
+                assert!((skv.value - expected).abs() < 1e-5);
+            }
+        }
+
+        // No weight at all
+        for skv in animate(0.0, 0.0).iter() {
+            assert_eq!(skv.weight, 1.0);
+            assert_eq!(skv.value, 0.0);
+        }
+    }
+
     #[test]
     fn test_skeleton_animator_restore() {
         fn prepare() -> (
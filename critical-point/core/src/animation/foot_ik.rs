@@ -0,0 +1,402 @@
+use glam::{Quat, Vec3A};
+use glam_ext::Transform3A;
+use ozz_animation_rs::Skeleton;
+
+use crate::animation::utils::{joint_subtree, rotate_joints};
+use crate::consts::MAX_FOOT_IK_LEG;
+use crate::instance::InstFootIk;
+use crate::utils::{ArrayVec, XResult, xresf};
+
+const LEN_THRESHOLD: f32 = 1e-4;
+const NORMAL_PROBE_DISTANCE: f32 = 0.1;
+
+/// The ground under a foot, in model space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FootIkGround {
+    /// Ground height relative to the floor of the character.
+    pub offset: f32,
+    pub normal: Vec3A,
+}
+
+impl Default for FootIkGround {
+    fn default() -> FootIkGround {
+        FootIkGround::FLAT
+    }
+}
+
+impl FootIkGround {
+    pub const FLAT: FootIkGround = FootIkGround {
+        offset: 0.0,
+        normal: Vec3A::Y,
+    };
+}
+
+#[derive(Debug)]
+struct FootIkLeg {
+    hip: i16,
+    knee: i16,
+    ankle: i16,
+    hip_joints: Vec<u16>,
+    knee_joints: Vec<u16>,
+    ankle_joints: Vec<u16>,
+}
+
+/// Post-processes the animated model transforms, places the feet on the ground.
+///
+/// The grounds under the animated ankles are probed by `update_grounds()`, then `solve()` moves the pelvis
+/// down, bends the legs with two-bone IK, and aligns the feet to the slope.
+#[derive(Debug)]
+pub struct FootIk {
+    all_joints: Vec<u16>,
+    pelvis_joints: Vec<u16>,
+    legs: Vec<FootIkLeg>,
+    max_pelvis_offset: f32,
+    max_foot_offset: f32,
+    root_tilt: f32,
+    grounds: Vec<FootIkGround>,
+}
+
+impl FootIk {
+    pub fn new(inst: &InstFootIk, skeleton: &Skeleton) -> XResult<FootIk> {
+        let parents = skeleton.joint_parents();
+        let find_joint = |name: &str| -> XResult<i16> {
+            match skeleton.joint_by_name(name) {
+                Some(joint) => Ok(joint),
+                None => xresf!(BadAsset; "joint={}", name),
+            }
+        };
+
+        if inst.legs.len() > MAX_FOOT_IK_LEG {
+            return xresf!(Overflow; "legs={}", inst.legs.len());
+        }
+
+        let mut legs = Vec::with_capacity(inst.legs.len());
+        for leg in inst.legs.iter() {
+            let hip = find_joint(&leg.hip)?;
+            let knee = find_joint(&leg.knee)?;
+            let ankle = find_joint(&leg.ankle)?;
+            legs.push(FootIkLeg {
+                hip,
+                knee,
+                ankle,
+                hip_joints: joint_subtree(parents, hip),
+                knee_joints: joint_subtree(parents, knee),
+                ankle_joints: joint_subtree(parents, ankle),
+            });
+        }
+
+        Ok(FootIk {
+            all_joints: (0..parents.len() as u16).collect(),
+            pelvis_joints: joint_subtree(parents, find_joint(&inst.pelvis)?),
+            grounds: vec![FootIkGround::FLAT; legs.len()],
+            legs,
+            max_pelvis_offset: inst.max_pelvis_offset.max(0.0),
+            max_foot_offset: inst.max_foot_offset.max(0.0),
+            root_tilt: inst.root_tilt.clamp(0.0, 1.0),
+        })
+    }
+
+    #[inline]
+    pub fn grounds(&self) -> &[FootIkGround] {
+        &self.grounds
+    }
+
+    /// Probes the grounds under the ankles.
+    ///
+    /// `position` and `rotation` transform the model space into the world space. `find_ground` returns
+    /// the ground point (in world space) under a world position, or None if there is no ground.
+    pub fn update_grounds<F>(
+        &mut self,
+        model_transforms: &[Transform3A],
+        position: Vec3A,
+        rotation: Quat,
+        mut find_ground: F,
+    ) -> XResult<()>
+    where
+        F: FnMut(Vec3A) -> XResult<Option<Vec3A>>,
+    {
+        let inv_rotation = rotation.inverse();
+        for (leg, ground) in self.legs.iter().zip(self.grounds.iter_mut()) {
+            let Some(ankle) = model_transforms.get(leg.ankle as usize)
+            else {
+                *ground = FootIkGround::FLAT;
+                continue;
+            };
+
+            let world_ankle = position + rotation * ankle.translation;
+            let Some(point) = find_ground(world_ankle)?
+            else {
+                *ground = FootIkGround::FLAT;
+                continue;
+            };
+
+            let dx = find_ground(world_ankle + Vec3A::X * NORMAL_PROBE_DISTANCE)?.map(|p| p - point);
+            let dz = find_ground(world_ankle + Vec3A::Z * NORMAL_PROBE_DISTANCE)?.map(|p| p - point);
+            let normal = match (dx, dz) {
+                (Some(dx), Some(dz)) => dz.cross(dx).try_normalize().unwrap_or(Vec3A::Y),
+                _ => Vec3A::Y,
+            };
+
+            *ground = FootIkGround {
+                offset: point.y - position.y,
+                normal: match normal.y > 0.0 {
+                    true => inv_rotation * normal,
+                    false => Vec3A::Y,
+                },
+            };
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_grounds(&mut self, grounds: &[FootIkGround]) {
+        for (dst, src) in self.grounds.iter_mut().zip(grounds.iter()) {
+            *dst = *src;
+        }
+    }
+
+    pub fn solve(&self, transforms: &mut [Transform3A]) {
+        if self.legs.is_empty() || self.grounds.iter().all(|g| *g == FootIkGround::FLAT) {
+            return;
+        }
+
+        // Slope-aligned root tilt
+        if self.root_tilt > 0.0 {
+            let normal = self
+                .grounds
+                .iter()
+                .fold(Vec3A::ZERO, |acc, g| acc + g.normal)
+                .try_normalize()
+                .unwrap_or(Vec3A::Y);
+            let tilt = Quat::IDENTITY.slerp(Quat::from_rotation_arc(Vec3A::Y.into(), normal.into()), self.root_tilt);
+            rotate_joints(transforms, &self.all_joints, Vec3A::ZERO, tilt);
+        }
+
+        let foot_offsets: ArrayVec<f32, MAX_FOOT_IK_LEG> = self
+            .grounds
+            .iter()
+            .map(|g| g.offset.clamp(-self.max_foot_offset, self.max_foot_offset))
+            .collect();
+        let targets: ArrayVec<Vec3A, MAX_FOOT_IK_LEG> = self
+            .legs
+            .iter()
+            .zip(foot_offsets.iter())
+            .map(|(leg, offset)| transforms[leg.ankle as usize].translation + Vec3A::Y * *offset)
+            .collect();
+
+        // Moves the pelvis down, so that the lower foot can reach the ground.
+        let min_offset = foot_offsets.iter().cloned().fold(0.0, f32::min);
+        let pelvis_offset = min_offset.max(-self.max_pelvis_offset);
+        if pelvis_offset < 0.0 {
+            for idx in self.pelvis_joints.iter() {
+                transforms[*idx as usize].translation.y += pelvis_offset;
+            }
+        }
+
+        for ((leg, ground), target) in self.legs.iter().zip(self.grounds.iter()).zip(targets) {
+            solve_two_bone(transforms, leg, target);
+
+            let align = Quat::from_rotation_arc(Vec3A::Y.into(), ground.normal.into());
+            let ankle = transforms[leg.ankle as usize].translation;
+            rotate_joints(transforms, &leg.ankle_joints, ankle, align);
+        }
+    }
+}
+
+/// Bends the knee to fit the distance between the hip and the target, then turns the hip to the target.
+fn solve_two_bone(transforms: &mut [Transform3A], leg: &FootIkLeg, target: Vec3A) {
+    let hip = transforms[leg.hip as usize].translation;
+    let knee = transforms[leg.knee as usize].translation;
+    let ankle = transforms[leg.ankle as usize].translation;
+
+    let upper = knee - hip;
+    let lower = ankle - knee;
+    let upper_len = upper.length();
+    let lower_len = lower.length();
+    if upper_len < LEN_THRESHOLD || lower_len < LEN_THRESHOLD {
+        return;
+    }
+
+    let min_dist = (upper_len - lower_len).abs() + LEN_THRESHOLD;
+    let max_dist = upper_len + lower_len - LEN_THRESHOLD;
+    let target_dist = (target - hip).length().clamp(min_dist, max_dist);
+
+    // Rotating the lower bone positively around the bend axis bends the knee more.
+    let axis = match upper.cross(lower).try_normalize() {
+        Some(axis) => axis,
+        None => upper.any_orthonormal_vector(),
+    };
+    let current_angle = (-upper).angle_between(lower);
+    let cos_angle =
+        (upper_len * upper_len + lower_len * lower_len - target_dist * target_dist) / (2.0 * upper_len * lower_len);
+    let target_angle = cos_angle.clamp(-1.0, 1.0).acos();
+    let knee_rotation = Quat::from_axis_angle(axis.into(), current_angle - target_angle);
+    rotate_joints(transforms, &leg.knee_joints, knee, knee_rotation);
+
+    let ankle = transforms[leg.ankle as usize].translation;
+    let (Some(from), Some(to)) = ((ankle - hip).try_normalize(), (target - hip).try_normalize())
+    else {
+        return;
+    };
+    let hip_rotation = Quat::from_rotation_arc(from.into(), to.into());
+    rotate_joints(transforms, &leg.hip_joints, hip, hip_rotation);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn leg_transforms() -> Vec<Transform3A> {
+        // 0:pelvis - 1:hip - 2:knee - 3:ankle - 4:toe
+        [
+            Vec3A::new(0.0, 1.0, 0.0),
+            Vec3A::new(0.1, 0.9, 0.0),
+            Vec3A::new(0.1, 0.5, 0.05),
+            Vec3A::new(0.1, 0.1, 0.0),
+            Vec3A::new(0.1, 0.0, 0.1),
+        ]
+        .into_iter()
+        .map(|translation| {
+            let mut transform = Transform3A::IDENTITY;
+            transform.translation = translation;
+            transform
+        })
+        .collect()
+    }
+
+    fn leg() -> FootIkLeg {
+        let parents = [-1, 0, 1, 2, 3];
+        FootIkLeg {
+            hip: 1,
+            knee: 2,
+            ankle: 3,
+            hip_joints: joint_subtree(&parents, 1),
+            knee_joints: joint_subtree(&parents, 2),
+            ankle_joints: joint_subtree(&parents, 3),
+        }
+    }
+
+    #[test]
+    fn test_solve_two_bone() {
+        let leg = leg();
+        let origin = leg_transforms();
+        let upper_len = (origin[2].translation - origin[1].translation).length();
+        let lower_len = (origin[3].translation - origin[2].translation).length();
+        let toe_offset = origin[4].translation - origin[3].translation;
+
+        // Reachable target
+        let mut transforms = origin.clone();
+        let target = Vec3A::new(0.1, 0.25, 0.05);
+        solve_two_bone(&mut transforms, &leg, target);
+        assert_abs_diff_eq!(transforms[3].translation, target, epsilon = 1e-4);
+        assert_abs_diff_eq!(
+            (transforms[2].translation - transforms[1].translation).length(),
+            upper_len,
+            epsilon = 1e-4
+        );
+        assert_abs_diff_eq!(
+            (transforms[3].translation - transforms[2].translation).length(),
+            lower_len,
+            epsilon = 1e-4
+        );
+        assert_eq!(transforms[0], origin[0]);
+        assert_abs_diff_eq!(
+            (transforms[4].translation - transforms[3].translation).length(),
+            toe_offset.length(),
+            epsilon = 1e-4
+        );
+
+        // Unreachable target, the leg is straightened towards the target
+        let mut transforms = origin.clone();
+        let target = Vec3A::new(0.1, -1.0, 0.0);
+        solve_two_bone(&mut transforms, &leg, target);
+        let dir = (transforms[3].translation - transforms[1].translation).normalize();
+        assert_abs_diff_eq!(dir, Vec3A::NEG_Y, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_foot_ik_solve() {
+        let parents = [-1, 0, 1, 2, 3];
+        let foot_ik = |grounds: Vec<FootIkGround>| FootIk {
+            all_joints: joint_subtree(&parents, 0),
+            pelvis_joints: joint_subtree(&parents, 0),
+            legs: vec![leg()],
+            max_pelvis_offset: 0.2,
+            max_foot_offset: 0.2,
+            root_tilt: 0.0,
+            grounds,
+        };
+
+        // Flat ground, nothing changes
+        let mut transforms = leg_transforms();
+        foot_ik(vec![FootIkGround::FLAT]).solve(&mut transforms);
+        assert_eq!(transforms, leg_transforms());
+
+        // Higher ground
+        let mut transforms = leg_transforms();
+        let ground = FootIkGround {
+            offset: 0.15,
+            normal: Vec3A::Y,
+        };
+        foot_ik(vec![ground]).solve(&mut transforms);
+        assert_eq!(transforms[0], leg_transforms()[0]);
+        assert_abs_diff_eq!(transforms[3].translation.y, 0.25, epsilon = 1e-4);
+
+        // Lower ground, the pelvis moves down and the offset is clamped
+        let mut transforms = leg_transforms();
+        let ground = FootIkGround {
+            offset: -0.5,
+            normal: Vec3A::Y,
+        };
+        foot_ik(vec![ground]).solve(&mut transforms);
+        assert_abs_diff_eq!(transforms[0].translation.y, 0.8, epsilon = 1e-4);
+        assert_abs_diff_eq!(transforms[3].translation.y, -0.1, epsilon = 1e-4);
+
+        // Slope, the foot is aligned to the ground
+        let mut transforms = leg_transforms();
+        let normal = Vec3A::new(0.0, 1.0, -1.0).normalize();
+        let ground = FootIkGround { offset: 0.0, normal };
+        foot_ik(vec![ground]).solve(&mut transforms);
+        assert_abs_diff_eq!(
+            transforms[3].translation,
+            leg_transforms()[3].translation,
+            epsilon = 1e-4
+        );
+        assert_abs_diff_eq!(transforms[3].rotation * Vec3A::Y, normal, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_foot_ik_update_grounds() {
+        let parents = [-1, 0, 1, 2, 3];
+        let mut foot_ik = FootIk {
+            all_joints: joint_subtree(&parents, 0),
+            pelvis_joints: joint_subtree(&parents, 0),
+            legs: vec![leg()],
+            max_pelvis_offset: 0.2,
+            max_foot_offset: 0.3,
+            root_tilt: 0.0,
+            grounds: vec![FootIkGround::FLAT],
+        };
+
+        // A slope rising along +Z
+        let position = Vec3A::new(5.0, 1.0, 5.0);
+        foot_ik
+            .update_grounds(&leg_transforms(), position, Quat::IDENTITY, |p| {
+                Ok(Some(Vec3A::new(p.x, 1.0 + (p.z - 5.0) * 0.5, p.z)))
+            })
+            .unwrap();
+        assert_abs_diff_eq!(foot_ik.grounds()[0].offset, 0.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(
+            foot_ik.grounds()[0].normal,
+            Vec3A::new(0.0, 1.0, -0.5).normalize(),
+            epsilon = 1e-4
+        );
+
+        // No ground
+        foot_ik
+            .update_grounds(&leg_transforms(), position, Quat::IDENTITY, |_| Ok(None))
+            .unwrap();
+        assert_eq!(foot_ik.grounds()[0], FootIkGround::FLAT);
+    }
+}
@@ -8,6 +8,7 @@ use std::path::Path;
 use std::{fs, mem, slice};
 
 use crate::asset::{AssetIndxedCompoundShape, AssetShape};
+use crate::consts::MAX_HIT_SWEEP_STEPS;
 use crate::utils::{HitType, Symbol, XResult, loose_ge, loose_le, xerrf, xfrom, xresf};
 
 //
@@ -21,6 +22,14 @@ struct RawHitMotion {
     #[serde(default)]
     compound_shapes: Vec<AssetIndxedCompoundShape>,
     boxes: Vec<RawHitBox>,
+    #[serde(default)]
+    sweeps: Vec<RawHitSweep>,
+}
+
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, serde::Serialize, serde::Deserialize)]
+struct RawHitSweep {
+    group: Symbol,
+    steps: u8,
 }
 
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, serde::Serialize, serde::Deserialize)]
@@ -178,6 +187,9 @@ impl HitMotion {
         assert_eq!(hit_motion.weapon_boxes.len(), weapon_count);
 
         hit_motion.init_groups();
+        for sweep in raw.sweeps.iter() {
+            hit_motion.init_sweep(sweep.group, sweep.steps, path)?;
+        }
         hit_motion.init_box_ptrs();
         Ok(hit_motion)
     }
@@ -304,6 +316,9 @@ impl HitMotion {
         assert_eq!(hit_motion.weapon_boxes.len(), weapon_count);
 
         hit_motion.init_groups();
+        for sweep in raw.sweeps.iter() {
+            hit_motion.init_sweep(Symbol::try_from(&sweep.group)?, sweep.steps, path)?;
+        }
         hit_motion.init_box_ptrs();
         Ok(hit_motion)
     }
@@ -370,6 +385,15 @@ impl HitMotion {
         }
     }
 
+    fn init_sweep(&mut self, group: Symbol, steps: u8, path: Option<&str>) -> XResult<()> {
+        let Some(asset_group) = self.groups.iter_mut().find(|g| g.name == group)
+        else {
+            return xresf!(BadAsset; "path={}, sweep group={}", path.unwrap_or(""), group);
+        };
+        asset_group.sweep_steps = steps.min(MAX_HIT_SWEEP_STEPS);
+        Ok(())
+    }
+
     fn init_box_ptrs(&mut self) {
         for bx in &self.joint_boxes {
             debug_assert_eq!(bx.box_index as usize, self.boxes_ptrs.len());
@@ -467,6 +491,8 @@ pub struct HitGroup {
     pub name: Symbol,
     pub start_time: f32,
     pub finish_time: f32,
+    /// Sub-frame steps of the swept hit detection, 0 or 1 means no sweep.
+    pub sweep_steps: u8,
 }
 
 impl HitGroup {
@@ -476,9 +502,15 @@ impl HitGroup {
             name,
             start_time,
             finish_time,
+            sweep_steps: 0,
         }
     }
 
+    #[inline]
+    pub fn is_swept(&self) -> bool {
+        self.sweep_steps > 1
+    }
+
     #[inline]
     pub fn in_time_loose(&self, time: f32) -> bool {
         loose_ge!(time, self.start_time) && loose_le!(time, self.finish_time)
@@ -767,7 +799,10 @@ mod tests {
         assert_eq!(hit_motion.groups.as_slice(), &[
             HitGroup::new(sb!("Health"), 0.5, 0.8333333),
             HitGroup::new(sb!("Counter"), 1.0, 1.13333333),
-            HitGroup::new(sb!("Axe"), 1.05, 1.36666667),
+            HitGroup {
+                sweep_steps: 4,
+                ..HitGroup::new(sb!("Axe"), 1.05, 1.36666667)
+            },
         ]);
 
         assert_eq!(hit_motion.joint_boxes.len(), 2);
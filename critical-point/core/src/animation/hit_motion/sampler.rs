@@ -2,23 +2,23 @@ use glam::{Quat, Vec3A};
 use glam_ext::{Isometry3A, Transform3A};
 use ozz_animation_rs::Skeleton;
 use std::ops::{Deref, DerefMut};
-use std::rc::Rc;
+use std::sync::Arc;
 use thin_vec::ThinVec;
 
 use crate::animation::hit_motion::{HitKeyPosition, HitKeyRotation, HitMotion};
 use crate::animation::{HitBoxJoint, HitBoxWeapon, WeaponTransform};
-use crate::utils::{XResult, strict_gt, strict_lt, xerrf};
+use crate::utils::{HitInteraction, HitType, XResult, strict_gt, strict_lt, xerrf};
 
 #[derive(Debug)]
 pub(crate) struct HitMotionSampler {
-    pub(crate) hit_motion: Rc<HitMotion>,
+    pub(crate) hit_motion: Arc<HitMotion>,
     time: f32,
     joints: ThinVec<HitSamplerJoint>,
     weapons: ThinVec<HitSamplerWeapon>,
 }
 
 impl HitMotionSampler {
-    pub(crate) fn new(hit_motion: Rc<HitMotion>, skeleton: &Skeleton) -> XResult<HitMotionSampler> {
+    pub(crate) fn new(hit_motion: Arc<HitMotion>, skeleton: &Skeleton) -> XResult<HitMotionSampler> {
         let mut sampler = HitMotionSampler {
             hit_motion: hit_motion.clone(),
             time: f32::NEG_INFINITY,
@@ -66,6 +66,27 @@ impl HitMotionSampler {
     pub(crate) fn weapons(&self) -> &[HitSamplerWeapon] {
         &self.weapons
     }
+
+    /// Resolves how an attack meets the owner of this sampler, by the types of the boxes it touches.
+    pub(crate) fn hit_interaction<I: Iterator<Item = u16>>(&self, touched_boxes: I) -> HitInteraction {
+        resolve_hit_interaction(
+            touched_boxes.filter_map(|box_index| self.hit_motion.find_box(box_index).map(|asset_box| asset_box.typ)),
+        )
+    }
+}
+
+/// Guard wins over Counter, and Counter wins over Attack.
+fn resolve_hit_interaction<I: Iterator<Item = HitType>>(types: I) -> HitInteraction {
+    let mut interaction = HitInteraction::Normal;
+    for typ in types {
+        match typ {
+            HitType::Guard => return HitInteraction::Blocked,
+            HitType::Counter => interaction = HitInteraction::Counter,
+            HitType::Attack if interaction == HitInteraction::Normal => interaction = HitInteraction::Clash,
+            _ => {}
+        }
+    }
+    interaction
 }
 
 #[derive(Debug)]
@@ -388,6 +409,30 @@ mod tests {
         check3(&sampler, false, 0, 0);
     }
 
+    #[test]
+    fn test_resolve_hit_interaction() {
+        use HitType::*;
+
+        assert_eq!(resolve_hit_interaction([].into_iter()), HitInteraction::Normal);
+        assert_eq!(resolve_hit_interaction([Health].into_iter()), HitInteraction::Normal);
+        assert_eq!(
+            resolve_hit_interaction([Health, Attack].into_iter()),
+            HitInteraction::Clash
+        );
+        assert_eq!(
+            resolve_hit_interaction([Attack, Counter].into_iter()),
+            HitInteraction::Counter
+        );
+        assert_eq!(
+            resolve_hit_interaction([Counter, Attack].into_iter()),
+            HitInteraction::Counter
+        );
+        assert_eq!(
+            resolve_hit_interaction([Counter, Guard, Attack].into_iter()),
+            HitInteraction::Blocked
+        );
+    }
+
     fn check3<T>(sampler: &HitSampler<T>, active: bool, pos_cursor: u32, rot_cursor: u32) {
         assert_eq!(sampler.active, active);
         assert_eq!(sampler.pos_cursor, pos_cursor);
@@ -0,0 +1,221 @@
+use glam::{Quat, Vec3A};
+use glam_ext::Transform3A;
+use ozz_animation_rs::Skeleton;
+
+use crate::animation::utils::{WeaponTransform, joint_subtree, rotate_joints};
+use crate::instance::InstLookAt;
+use crate::utils::{XResult, xresf};
+
+const LEN_THRESHOLD: f32 = 1e-3;
+const WEIGHT_THRESHOLD: f32 = 1e-3;
+
+#[derive(Debug)]
+struct LookAtJoint {
+    joint: i16,
+    weight: f32,
+    joints: Vec<u16>,
+}
+
+/// Post-processes the animated model transforms, turns a joint chain (such as the spine and head)
+/// towards a target.
+///
+/// The yaw and pitch from the end of the chain to the target are clamped by the limits, then distributed
+/// over the chain joints by their weights. Optionally, the weapons turn with the chain.
+#[derive(Debug)]
+pub struct LookAt {
+    joints: Vec<LookAtJoint>,
+    max_yaw: f32,
+    max_pitch: f32,
+    rotate_weapons: bool,
+    forward: Vec3A,
+    target: Vec3A,
+    weight: f32,
+}
+
+impl LookAt {
+    /// `forward` is the toward direction of the character in model space.
+    pub fn new(inst: &InstLookAt, skeleton: &Skeleton, forward: Vec3A) -> XResult<LookAt> {
+        let parents = skeleton.joint_parents();
+        let mut joints = Vec::with_capacity(inst.joints.len());
+        for joint in inst.joints.iter() {
+            let Some(idx) = skeleton.joint_by_name(&joint.joint)
+            else {
+                return xresf!(BadAsset; "joint={}", joint.joint);
+            };
+            joints.push(LookAtJoint {
+                joint: idx,
+                weight: joint.weight.max(0.0),
+                joints: joint_subtree(parents, idx),
+            });
+        }
+
+        Ok(LookAt {
+            joints,
+            max_yaw: inst.max_yaw.abs(),
+            max_pitch: inst.max_pitch.abs(),
+            rotate_weapons: inst.rotate_weapons,
+            forward: Vec3A::new(forward.x, 0.0, forward.z)
+                .try_normalize()
+                .unwrap_or(Vec3A::Z),
+            target: Vec3A::ZERO,
+            weight: 0.0,
+        })
+    }
+
+    #[inline]
+    pub fn target(&self) -> (Vec3A, f32) {
+        (self.target, self.weight)
+    }
+
+    /// Sets the target (in model space) and the blending weight. A zero weight disables the look-at.
+    #[inline]
+    pub fn set_target(&mut self, target: Vec3A, weight: f32) {
+        self.target = target;
+        self.weight = weight.clamp(0.0, 1.0);
+    }
+
+    pub fn solve(&self, transforms: &mut [Transform3A], weapons: &mut [WeaponTransform]) {
+        if self.weight < WEIGHT_THRESHOLD {
+            return;
+        }
+        let Some(end) = self.joints.last()
+        else {
+            return;
+        };
+
+        let to_target = self.target - transforms[end.joint as usize].translation;
+        let horizontal = Vec3A::new(to_target.x, 0.0, to_target.z);
+        if horizontal.length() < LEN_THRESHOLD {
+            return;
+        }
+
+        let yaw = self.forward.cross(horizontal).y.atan2(self.forward.dot(horizontal));
+        let pitch = to_target.y.atan2(horizontal.length());
+        let yaw = yaw.clamp(-self.max_yaw, self.max_yaw) * self.weight;
+        let pitch = pitch.clamp(-self.max_pitch, self.max_pitch) * self.weight;
+
+        // Rotating the forward positively around `forward x Y` raises it.
+        let pitch_axis = (Quat::from_rotation_y(yaw) * self.forward).cross(Vec3A::Y);
+        for joint in self.joints.iter() {
+            let rotation = Quat::from_axis_angle(pitch_axis.into(), pitch * joint.weight)
+                * Quat::from_rotation_y(yaw * joint.weight);
+            let pivot = transforms[joint.joint as usize].translation;
+            rotate_joints(transforms, &joint.joints, pivot, rotation);
+
+            if self.rotate_weapons {
+                for weapon in weapons.iter_mut() {
+                    weapon.position = pivot + rotation * (weapon.position - pivot);
+                    weapon.rotation = rotation * weapon.rotation;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use std::f32::consts::FRAC_PI_2;
+
+    use crate::utils::sb;
+
+    fn chain_transforms() -> Vec<Transform3A> {
+        // 0:root - 1:spine - 2:neck - 3:head, 0:root - 4:leg
+        [
+            Vec3A::new(0.0, 1.0, 0.0),
+            Vec3A::new(0.0, 1.2, 0.0),
+            Vec3A::new(0.0, 1.5, 0.0),
+            Vec3A::new(0.0, 1.6, 0.0),
+            Vec3A::new(0.1, 0.5, 0.0),
+        ]
+        .into_iter()
+        .map(|translation| {
+            let mut transform = Transform3A::IDENTITY;
+            transform.translation = translation;
+            transform
+        })
+        .collect()
+    }
+
+    fn look_at(max_yaw: f32, max_pitch: f32) -> LookAt {
+        let parents = [-1, 0, 1, 2, 0];
+        LookAt {
+            joints: vec![
+                LookAtJoint {
+                    joint: 1,
+                    weight: 0.5,
+                    joints: joint_subtree(&parents, 1),
+                },
+                LookAtJoint {
+                    joint: 3,
+                    weight: 0.5,
+                    joints: joint_subtree(&parents, 3),
+                },
+            ],
+            max_yaw,
+            max_pitch,
+            rotate_weapons: true,
+            forward: Vec3A::Z,
+            target: Vec3A::ZERO,
+            weight: 0.0,
+        }
+    }
+
+    fn weapons() -> Vec<WeaponTransform> {
+        vec![WeaponTransform {
+            name: sb!("Sword"),
+            position: Vec3A::new(0.0, 1.2, 0.5),
+            rotation: Quat::IDENTITY,
+            weight: 1.0,
+        }]
+    }
+
+    #[test]
+    fn test_look_at_solve() {
+        let origin = chain_transforms();
+
+        // Zero weight, nothing changes
+        let mut la = look_at(FRAC_PI_2, FRAC_PI_2);
+        let mut transforms = origin.clone();
+        let mut ws = weapons();
+        la.set_target(Vec3A::new(5.0, 1.6, 0.0), 0.0);
+        la.solve(&mut transforms, &mut ws);
+        assert_eq!(transforms, origin);
+        assert_eq!(ws, weapons());
+
+        // Turns right, the head faces the target
+        la.set_target(Vec3A::new(5.0, 1.6, 0.0), 1.0);
+        la.solve(&mut transforms, &mut ws);
+        assert_eq!(transforms[0], origin[0]);
+        assert_eq!(transforms[4], origin[4]);
+        assert_abs_diff_eq!(transforms[3].rotation * Vec3A::Z, Vec3A::X, epsilon = 1e-4);
+        assert_abs_diff_eq!(
+            transforms[1].rotation * Vec3A::Z,
+            Quat::from_rotation_y(FRAC_PI_2 * 0.5) * Vec3A::Z,
+            epsilon = 1e-4
+        );
+        assert_abs_diff_eq!(ws[0].position, Vec3A::new(0.5, 1.2, 0.0), epsilon = 1e-4);
+
+        // Clamped by max_yaw
+        let mut la = look_at(0.4, 0.4);
+        let mut transforms = origin.clone();
+        let mut ws = weapons();
+        la.set_target(Vec3A::new(-5.0, 1.6, 0.0), 1.0);
+        la.solve(&mut transforms, &mut ws);
+        assert_abs_diff_eq!(
+            transforms[3].rotation * Vec3A::Z,
+            Quat::from_rotation_y(-0.4) * Vec3A::Z,
+            epsilon = 1e-4
+        );
+
+        // Looks up, clamped by max_pitch and scaled by the weight
+        let mut transforms = origin.clone();
+        let mut ws = weapons();
+        la.set_target(Vec3A::new(0.0, 10.0, 1.0), 0.5);
+        la.solve(&mut transforms, &mut ws);
+        let dir = transforms[3].rotation * Vec3A::Z;
+        assert_abs_diff_eq!(dir.y.asin(), 0.2, epsilon = 1e-4);
+        assert_abs_diff_eq!(dir.x, 0.0, epsilon = 1e-4);
+    }
+}
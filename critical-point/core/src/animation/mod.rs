@@ -1,5 +1,8 @@
+mod foot_ik;
 mod hit_motion;
+mod look_at;
 mod meta;
+mod notify;
 mod root_motion;
 mod shape_key;
 mod utils;
@@ -8,8 +11,11 @@ mod weapon_motion;
 #[cfg(not(feature = "for-turning-point"))]
 mod animator;
 
+pub use foot_ik::*;
 pub use hit_motion::*;
+pub use look_at::*;
 pub use meta::*;
+pub use notify::*;
 pub use root_motion::*;
 pub use shape_key::*;
 pub use utils::*;
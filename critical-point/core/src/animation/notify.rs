@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::Path;
+
+use crate::utils::{Symbol, XResult, xresf};
+
+#[derive(Debug, serde::Deserialize)]
+struct RawAnimationNotify {
+    duration: f32,
+    events: Vec<RawNotifyEvent>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RawNotifyEvent {
+    time: f32,
+    name: Symbol,
+}
+
+/// A named event (such as a footstep, a sound or an effect) at a time of the animation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NotifyEvent {
+    pub ratio: f32,
+    pub name: Symbol,
+}
+
+/// The notify track of an animation, loaded from the `.nt-json` file next to the animation.
+///
+/// ```json
+/// { "duration": 1.5, "events": [{ "time": 0.4, "name": "FootstepL" }] }
+/// ```
+#[derive(Debug, Default)]
+pub struct AnimationNotify {
+    duration: f32,
+    events: Vec<NotifyEvent>, // Sorted by ratio
+}
+
+impl AnimationNotify {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> XResult<AnimationNotify> {
+        let bytes = fs::read(path.as_ref())?;
+        AnimationNotify::from_json_bytes(&bytes, path.as_ref().to_str())
+    }
+
+    pub fn from_json_bytes(bytes: &[u8], path: Option<&str>) -> XResult<AnimationNotify> {
+        let raw: RawAnimationNotify = serde_json::from_slice(bytes)?;
+        if raw.duration <= 0.0 {
+            return xresf!(BadAsset; "file={}, duration={}", path.unwrap_or(""), raw.duration);
+        }
+
+        let mut events = Vec::with_capacity(raw.events.len());
+        for event in raw.events {
+            if event.time < 0.0 || event.time > raw.duration {
+                return xresf!(BadAsset; "file={}, event={}, time={}", path.unwrap_or(""), event.name, event.time);
+            }
+            events.push(NotifyEvent {
+                ratio: event.time / raw.duration,
+                name: event.name,
+            });
+        }
+        events.sort_by(|a, b| a.ratio.total_cmp(&b.ratio));
+        Ok(AnimationNotify {
+            duration: raw.duration,
+            events,
+        })
+    }
+
+    #[inline]
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    #[inline]
+    pub fn events(&self) -> &[NotifyEvent] {
+        &self.events
+    }
+
+    /// Finds the events passed from `prev_ratio` to `ratio`, in the window `(prev_ratio, ratio]`.
+    ///
+    /// A `ratio` less than `prev_ratio` means the animation looped.
+    pub fn find_events(&self, prev_ratio: f32, ratio: f32) -> impl Iterator<Item = &NotifyEvent> {
+        self.events.iter().filter(move |event| match prev_ratio <= ratio {
+            true => prev_ratio < event.ratio && event.ratio <= ratio,
+            false => prev_ratio < event.ratio || event.ratio <= ratio,
+        })
+    }
+
+    /// Finds the events of an animation just started at `start_ratio`, in the window `[start_ratio, ratio]`.
+    ///
+    /// The animation may start in the middle of the clip, the events before `start_ratio` are skipped.
+    pub fn find_started_events(&self, start_ratio: f32, ratio: f32) -> impl Iterator<Item = &NotifyEvent> {
+        self.events.iter().filter(move |event| match start_ratio <= ratio {
+            true => start_ratio <= event.ratio && event.ratio <= ratio,
+            false => start_ratio <= event.ratio || event.ratio <= ratio,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::sb;
+
+    const NOTIFY_JSON: &str = r#"{
+        "duration": 2.0,
+        "events": [
+            { "time": 1.5, "name": "FootstepR" },
+            { "time": 0.0, "name": "Start" },
+            { "time": 0.5, "name": "FootstepL" }
+        ]
+    }"#;
+
+    fn names<'t>(events: impl Iterator<Item = &'t NotifyEvent>) -> Vec<Symbol> {
+        events.map(|event| event.name).collect()
+    }
+
+    #[test]
+    fn test_animation_notify_from_json() {
+        let notify = AnimationNotify::from_json_bytes(NOTIFY_JSON.as_bytes(), None).unwrap();
+        assert_eq!(notify.duration(), 2.0);
+        assert_eq!(notify.events(), &[
+            NotifyEvent {
+                ratio: 0.0,
+                name: sb!("Start")
+            },
+            NotifyEvent {
+                ratio: 0.25,
+                name: sb!("FootstepL")
+            },
+            NotifyEvent {
+                ratio: 0.75,
+                name: sb!("FootstepR")
+            },
+        ]);
+
+        let bad_time = r#"{ "duration": 1.0, "events": [{ "time": 1.5, "name": "Bad" }] }"#;
+        assert!(AnimationNotify::from_json_bytes(bad_time.as_bytes(), None).is_err());
+        let bad_duration = r#"{ "duration": 0.0, "events": [] }"#;
+        assert!(AnimationNotify::from_json_bytes(bad_duration.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn test_animation_notify_find_events() {
+        let notify = AnimationNotify::from_json_bytes(NOTIFY_JSON.as_bytes(), None).unwrap();
+
+        // Playing
+        assert_eq!(names(notify.find_events(0.0, 0.2)), Vec::<Symbol>::new());
+        assert_eq!(names(notify.find_events(0.2, 0.25)), vec![sb!("FootstepL")]);
+        assert_eq!(names(notify.find_events(0.25, 0.3)), Vec::<Symbol>::new());
+        assert_eq!(names(notify.find_events(0.5, 0.5)), Vec::<Symbol>::new());
+
+        // Looped
+        assert_eq!(names(notify.find_events(0.7, 0.1)), vec![
+            sb!("Start"),
+            sb!("FootstepR")
+        ]);
+    }
+
+    #[test]
+    fn test_animation_notify_find_started_events() {
+        let notify = AnimationNotify::from_json_bytes(NOTIFY_JSON.as_bytes(), None).unwrap();
+
+        // Started from the beginning
+        assert_eq!(names(notify.find_started_events(0.0, 0.1)), vec![sb!("Start")]);
+        assert_eq!(names(notify.find_started_events(0.0, 0.25)), vec![
+            sb!("Start"),
+            sb!("FootstepL")
+        ]);
+
+        // Started in the middle
+        assert_eq!(names(notify.find_started_events(0.5, 0.6)), Vec::<Symbol>::new());
+        assert_eq!(names(notify.find_started_events(0.25, 0.3)), vec![sb!("FootstepL")]);
+        assert_eq!(names(notify.find_started_events(0.7, 0.8)), vec![sb!("FootstepR")]);
+
+        // Started and looped
+        assert_eq!(names(notify.find_started_events(0.9, 0.1)), vec![sb!("Start")]);
+    }
+}
@@ -1,4 +1,5 @@
 use critical_point_macros::csharp_enum;
+use enum_iterator::Sequence;
 use glam::{Quat, Vec3};
 use ozz_animation_rs::{Archive, OzzError, Track, TrackSamplingJobRef};
 use std::io::{ErrorKind, Read};
@@ -8,7 +9,7 @@ use crate::utils::{XResult, rkyv_self, xres, xresf};
 
 #[csharp_enum]
 #[repr(u8)]
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum RootTrackName {
     #[default]
     Default = 0,
@@ -16,7 +17,7 @@ pub enum RootTrackName {
     MoveEx = 2,
 }
 
-rkyv_self!(RootTrackName);
+rkyv_self!(RootTrackName: Sequence);
 
 #[derive(Debug)]
 pub struct RootMotion {
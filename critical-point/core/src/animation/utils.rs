@@ -1,9 +1,10 @@
 use critical_point_macros::csharp_out;
-use glam::{Quat, Vec3A};
+use glam::{Quat, Vec3A, Vec4};
 use glam_ext::{Mat4, Transform3A};
 use ozz_animation_rs::{LocalToModelJob, LocalToModelJobRef, Skeleton, SoaTransform};
 
-use crate::utils::{Symbol, XResult, xfrom, xres};
+use crate::instance::InstJointMask;
+use crate::utils::{Symbol, XResult, xfrom, xres, xresf};
 
 #[repr(C)]
 #[csharp_out(Value)]
@@ -74,6 +75,7 @@ pub struct WeaponTransform {
     rkyv::Serialize,
     rkyv::Deserialize,
 )]
+#[rkyv(derive(Debug))]
 pub struct ShapeKeyValue {
     pub name: Symbol,
     pub value: f32,
@@ -125,3 +127,107 @@ pub fn rest_poses_to_model_transforms(skeleton: &Skeleton, transfroms: &mut [Tra
     matrices_to_transforms(&matrices, transfroms)?;
     Ok(())
 }
+
+/// A joint mask resolved against a skeleton, used by the partial-body layers of the animator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JointMask {
+    pub name: Symbol,
+    pub weights: Vec<Vec4>, // SoA joint weights
+}
+
+impl JointMask {
+    pub fn new(inst: &InstJointMask, skeleton: &Skeleton) -> XResult<JointMask> {
+        let mut joints = Vec::with_capacity(inst.joints.len());
+        for joint in inst.joints.iter() {
+            let Some(idx) = skeleton.joint_by_name(&joint.joint)
+            else {
+                return xresf!(BadAsset; "joint={}", joint.joint);
+            };
+            joints.push((idx, joint.weight.clamp(0.0, 1.0)));
+        }
+        Ok(JointMask {
+            name: inst.name,
+            weights: joint_mask_weights(skeleton.joint_parents(), skeleton.num_soa_joints(), &joints)?,
+        })
+    }
+}
+
+/// Computes the SoA joint weights of a partial-body layer. Each `(joint, weight)` applies the weight to the
+/// joint and its children, later entries override earlier ones. Other joints are not affected (weight 0).
+///
+/// `parents` is the joint parents array of the skeleton, where parents always precede their children.
+pub fn joint_mask_weights(parents: &[i16], num_soa_joints: usize, joints: &[(i16, f32)]) -> XResult<Vec<Vec4>> {
+    let mut weights = vec![Vec4::ZERO; num_soa_joints.max((parents.len() + 3) / 4)];
+    for (root, weight) in joints.iter().cloned() {
+        if root < 0 || root as usize >= parents.len() {
+            return xres!(BadArgument; "root joint");
+        }
+        for idx in joint_subtree(parents, root) {
+            weights[idx as usize / 4][idx as usize % 4] = weight;
+        }
+    }
+    Ok(weights)
+}
+
+/// Rotates the joints (in model space) around the pivot.
+pub(crate) fn rotate_joints(transforms: &mut [Transform3A], joints: &[u16], pivot: Vec3A, rotation: Quat) {
+    for idx in joints {
+        let transform = &mut transforms[*idx as usize];
+        transform.translation = pivot + rotation * (transform.translation - pivot);
+        transform.rotation = rotation * transform.rotation;
+    }
+}
+
+/// Returns the joint and all its children. Parents always precede their children in the array.
+pub(crate) fn joint_subtree(parents: &[i16], root: i16) -> Vec<u16> {
+    let mut in_subtree = vec![false; parents.len()];
+    let mut joints = Vec::new();
+    for idx in (root.max(0) as usize)..parents.len() {
+        let parent = parents[idx];
+        if idx == root as usize || (parent >= 0 && in_subtree[parent as usize]) {
+            in_subtree[idx] = true;
+            joints.push(idx as u16);
+        }
+    }
+    joints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_joint_mask_weights() {
+        // 0 - 1 - 2
+        //   \ 3 - 4 - 5
+        let parents = [-1, 0, 1, 0, 3, 4];
+        let weights = joint_mask_weights(&parents, 2, &[(3, 1.0)]).unwrap();
+        assert_eq!(weights, vec![
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+            Vec4::new(1.0, 1.0, 0.0, 0.0)
+        ]);
+
+        let weights = joint_mask_weights(&parents, 2, &[(0, 1.0)]).unwrap();
+        assert_eq!(weights, vec![Vec4::ONE, Vec4::new(1.0, 1.0, 0.0, 0.0)]);
+
+        let weights = joint_mask_weights(&parents, 2, &[(2, 1.0)]).unwrap();
+        assert_eq!(weights, vec![Vec4::new(0.0, 0.0, 1.0, 0.0), Vec4::ZERO]);
+
+        let weights = joint_mask_weights(&parents, 2, &[(0, 0.5), (4, 1.0), (1, 0.0)]).unwrap();
+        assert_eq!(weights, vec![
+            Vec4::new(0.5, 0.0, 0.0, 0.5),
+            Vec4::new(1.0, 1.0, 0.0, 0.0)
+        ]);
+
+        assert!(joint_mask_weights(&parents, 2, &[(6, 1.0)]).is_err());
+        assert!(joint_mask_weights(&parents, 2, &[(0, 1.0), (-1, 1.0)]).is_err());
+    }
+
+    #[test]
+    fn test_joint_subtree() {
+        let parents = [-1, 0, 1, 0, 3, 4];
+        assert_eq!(joint_subtree(&parents, 0), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(joint_subtree(&parents, 3), vec![3, 4, 5]);
+        assert_eq!(joint_subtree(&parents, 2), vec![2]);
+    }
+}
@@ -1,31 +1,34 @@
 use ozz_animation_rs::{Animation, Archive, Skeleton};
 use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::animation::{HitMotion, RootMotion, WeaponMotion};
+use crate::animation::{AnimationNotify, HitMotion, RootMotion, ShapeKey, WeaponMotion};
 use crate::asset::loader::AssetLoader;
-use crate::utils::{Symbol, XResult, xfromf};
+use crate::utils::{Symbol, XResult, xfromf, xresf};
+
+const NOTIFY_DURATION_EPSILON: f32 = 1e-3;
 
 impl AssetLoader {
-    pub fn load_skeleton(&mut self, path_pattern: Symbol) -> XResult<Rc<Skeleton>> {
+    pub fn load_skeleton(&mut self, path_pattern: Symbol) -> XResult<Arc<Skeleton>> {
         if let Some(skeleton) = self.skeleton_cache.get(&path_pattern) {
             return Ok(skeleton.clone());
         }
         let path = format!("{}.ls-ozz", &path_pattern[0..path_pattern.len() - 2]);
         let data_buf = self.load_buffer(&path)?;
         let mut archive = Archive::from_vec(data_buf).map_err(xfromf!("path={:?}", &path))?;
-        let skeleton = Rc::new(Skeleton::from_archive(&mut archive).map_err(xfromf!("path={:?}", &path))?);
+        let skeleton = Arc::new(Skeleton::from_archive(&mut archive).map_err(xfromf!("path={:?}", &path))?);
         self.skeleton_cache.insert(path_pattern.clone(), skeleton.clone());
         Ok(skeleton)
     }
 
-    pub fn load_animation(&mut self, path_pattern: Symbol) -> XResult<Rc<Animation>> {
+    pub fn load_animation(&mut self, path_pattern: Symbol) -> XResult<Arc<Animation>> {
         if let Some(animation) = self.animation_cache.get(&path_pattern) {
             return Ok(animation.clone());
         }
         let path = format!("{}.la-ozz", &path_pattern[0..path_pattern.len() - 2]);
         let data_buf = self.load_buffer(&path)?;
         let mut archive = Archive::from_vec(data_buf).map_err(xfromf!("path={:?}", &path))?;
-        let animation = Rc::new(Animation::from_archive(&mut archive).map_err(xfromf!("path={:?}", &path))?);
+        let animation = Arc::new(Animation::from_archive(&mut archive).map_err(xfromf!("path={:?}", &path))?);
         self.animation_cache.insert(path_pattern.clone(), animation.clone());
         Ok(animation)
     }
@@ -42,28 +45,59 @@ impl AssetLoader {
         Ok(root_motion)
     }
 
-    pub fn load_weapon_motion(&mut self, path_pattern: Symbol) -> XResult<Rc<WeaponMotion>> {
+    pub fn load_weapon_motion(&mut self, path_pattern: Symbol) -> XResult<Arc<WeaponMotion>> {
         if let Some(weapon_motion) = self.weapon_motion_cache.get(&path_pattern) {
             return Ok(weapon_motion.clone());
         }
         let path = format!("{}.wm-ozz", &path_pattern[0..path_pattern.len() - 2]);
         let data_buf = self.load_buffer(&path)?;
         let mut archive = Archive::from_vec(data_buf).map_err(xfromf!("path={:?}", &path))?;
-        let weapon_motion = Rc::new(WeaponMotion::from_archive(&mut archive).map_err(xfromf!("path={:?}", &path))?);
+        let weapon_motion = Arc::new(WeaponMotion::from_archive(&mut archive).map_err(xfromf!("path={:?}", &path))?);
         self.weapon_motion_cache
             .insert(path_pattern.clone(), weapon_motion.clone());
         Ok(weapon_motion)
     }
 
-    pub fn load_hit_motion(&mut self, path_pattern: Symbol) -> XResult<Rc<HitMotion>> {
+    pub fn load_shape_key(&mut self, path_pattern: Symbol) -> XResult<Arc<ShapeKey>> {
+        if let Some(shape_key) = self.shape_key_cache.get(&path_pattern) {
+            return Ok(shape_key.clone());
+        }
+        let path = format!("{}.sk-ozz", &path_pattern[0..path_pattern.len() - 2]);
+        let data_buf = self.load_buffer(&path)?;
+        let mut archive = Archive::from_vec(data_buf).map_err(xfromf!("path={:?}", &path))?;
+        let shape_key = Arc::new(ShapeKey::from_archive(&mut archive).map_err(xfromf!("path={:?}", &path))?);
+        self.shape_key_cache.insert(path_pattern.clone(), shape_key.clone());
+        Ok(shape_key)
+    }
+
+    pub fn load_hit_motion(&mut self, path_pattern: Symbol) -> XResult<Arc<HitMotion>> {
         if let Some(hit_motion) = self.hit_motion_cache.get(&path_pattern) {
             return Ok(hit_motion.clone());
         }
         let path = format!("{}.hm-json", &path_pattern[0..path_pattern.len() - 2]);
         let data_buf = self.load_buffer(&path)?;
         let hit_motion =
-            Rc::new(HitMotion::from_json_bytes(&data_buf, Some(path.as_str())).map_err(xfromf!("path={:?}", &path))?);
+            Arc::new(HitMotion::from_json_bytes(&data_buf, Some(path.as_str())).map_err(xfromf!("path={:?}", &path))?);
         self.hit_motion_cache.insert(path_pattern.clone(), hit_motion.clone());
         Ok(hit_motion)
     }
+
+    pub fn load_animation_notify(&mut self, path_pattern: Symbol) -> XResult<Rc<AnimationNotify>> {
+        if let Some(notify) = self.notify_cache.get(&path_pattern) {
+            return Ok(notify.clone());
+        }
+        let path = format!("{}.nt-json", &path_pattern[0..path_pattern.len() - 2]);
+        let data_buf = self.load_buffer(&path)?;
+        let notify = Rc::new(
+            AnimationNotify::from_json_bytes(&data_buf, Some(path.as_str())).map_err(xfromf!("path={:?}", &path))?,
+        );
+
+        // The events are stored in ratios, a mismatched duration shifts all of them.
+        let animation = self.load_animation(path_pattern)?;
+        if (notify.duration() - animation.duration()).abs() > NOTIFY_DURATION_EPSILON {
+            return xresf!(BadAsset; "path={:?}, duration={}/{}", &path, notify.duration(), animation.duration());
+        }
+        self.notify_cache.insert(path_pattern.clone(), notify.clone());
+        Ok(notify)
+    }
 }
@@ -5,8 +5,9 @@ use std::fs::OpenOptions;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::animation::{HitMotion, RootMotion, WeaponMotion};
+use crate::animation::{AnimationNotify, HitMotion, RootMotion, ShapeKey, WeaponMotion};
 use crate::utils::{DtHashMap, Symbol, XResult, xfromf, xresf};
 
 pub struct AssetLoader {
@@ -14,11 +15,14 @@ pub struct AssetLoader {
 
     // pub(super) shape_mesh_cache: DtHashMap<Symbol, JRef<Shape>>,
     // pub(super) shape_heigh_tfield_cache: DtHashMap<Symbol, JRef<Shape>>,
-    pub(super) skeleton_cache: DtHashMap<Symbol, Rc<Skeleton>>,
-    pub(super) animation_cache: DtHashMap<Symbol, Rc<Animation>>,
+    // Resources sampled by the animator are shared through Arc, animators are sampled on worker threads.
+    pub(super) skeleton_cache: DtHashMap<Symbol, Arc<Skeleton>>,
+    pub(super) animation_cache: DtHashMap<Symbol, Arc<Animation>>,
     pub(super) root_motion_cache: DtHashMap<Symbol, Rc<RootMotion>>,
-    pub(super) weapon_motion_cache: DtHashMap<Symbol, Rc<WeaponMotion>>,
-    pub(super) hit_motion_cache: DtHashMap<Symbol, Rc<HitMotion>>,
+    pub(super) weapon_motion_cache: DtHashMap<Symbol, Arc<WeaponMotion>>,
+    pub(super) hit_motion_cache: DtHashMap<Symbol, Arc<HitMotion>>,
+    pub(super) notify_cache: DtHashMap<Symbol, Rc<AnimationNotify>>,
+    pub(super) shape_key_cache: DtHashMap<Symbol, Arc<ShapeKey>>,
 }
 
 #[cfg(feature = "debug-print")]
@@ -44,6 +48,8 @@ impl AssetLoader {
             root_motion_cache: DtHashMap::with_capacity_and_hasher(384, FxBuildHasher),
             weapon_motion_cache: DtHashMap::with_capacity_and_hasher(384, FxBuildHasher),
             hit_motion_cache: DtHashMap::with_capacity_and_hasher(384, FxBuildHasher),
+            notify_cache: DtHashMap::with_capacity_and_hasher(384, FxBuildHasher),
+            shape_key_cache: DtHashMap::with_capacity_and_hasher(128, FxBuildHasher),
         });
     }
 
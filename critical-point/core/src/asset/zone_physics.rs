@@ -4,7 +4,7 @@ use jolt_physics_rs::{self as jolt, JRef, Shape, StaticCompoundShapeSettings, Su
 use crate::asset::AssetIndxedCompoundShape;
 use crate::asset::loader::AssetLoader;
 use crate::asset::shape::AssetShape;
-use crate::utils::{Symbol, XResult, default_position, default_rotation, xerr, xerrf, xfrom};
+use crate::utils::{Symbol, XResult, default_position, default_rotation, sb, xerr, xerrf, xfrom};
 
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, serde::Serialize, serde::Deserialize)]
 pub struct AssetZonePhysics {
@@ -12,6 +12,8 @@ pub struct AssetZonePhysics {
     #[serde(default)]
     compound_shapes: Vec<AssetIndxedCompoundShape>,
     bodies: Vec<AssetZoneBody>,
+    #[serde(default)]
+    triggers: Vec<AssetZoneTrigger>,
 }
 
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, serde::Serialize, serde::Deserialize)]
@@ -23,9 +25,24 @@ pub struct AssetZoneBody {
     rotation: Quat,
 }
 
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, serde::Serialize, serde::Deserialize)]
+pub struct AssetZoneTrigger {
+    name: Symbol,
+    shape_index: u32,
+    #[serde(default = "default_position")]
+    position: Vec3A,
+    #[serde(default = "default_rotation")]
+    rotation: Quat,
+    #[serde(default)]
+    interactable: bool,
+    #[serde(default)]
+    once: bool,
+}
+
 #[derive(Debug, Default)]
 pub struct LoadedZonePhysics {
     pub bodies: Vec<LoadedZoneBody>,
+    pub triggers: Vec<LoadedZoneTrigger>,
 }
 
 #[derive(Debug)]
@@ -35,6 +52,19 @@ pub struct LoadedZoneBody {
     pub rotation: Quat,
 }
 
+#[derive(Debug)]
+pub struct LoadedZoneTrigger {
+    pub name: Symbol,
+    pub shape: JRef<Shape>,
+    pub position: Vec3A,
+    pub rotation: Quat,
+    /// The trigger fires an Interact event, when a character inside presses the Interact key.
+    pub interactable: bool,
+    /// The trigger is disabled after firing once. A non-interactable trigger is disabled by its first
+    /// Enter event, an interactable trigger by its first Interact event.
+    pub once: bool,
+}
+
 impl AssetLoader {
     pub fn load_zone_physics(&mut self, path_pattern: Symbol) -> XResult<LoadedZonePhysics> {
         let rkyv_path = format!("{}.zp-rkyv", &path_pattern[0..path_pattern.len() - 2]);
@@ -88,7 +118,22 @@ pub fn from_asset(path: &str, asset: AssetZonePhysics) -> XResult<LoadedZonePhys
         });
     }
 
-    Ok(LoadedZonePhysics { bodies })
+    let mut triggers = Vec::with_capacity(asset.triggers.len());
+    for asset_trigger in &asset.triggers {
+        let jolt_shape = jolt_shapes
+            .get(asset_trigger.shape_index as usize)
+            .ok_or_else(|| xerrf!(BadAsset; "file={}, shape_index={}", path, asset_trigger.shape_index))?;
+        triggers.push(LoadedZoneTrigger {
+            name: asset_trigger.name,
+            shape: jolt_shape.clone(),
+            position: asset_trigger.position,
+            rotation: asset_trigger.rotation,
+            interactable: asset_trigger.interactable,
+            once: asset_trigger.once,
+        });
+    }
+
+    Ok(LoadedZonePhysics { bodies, triggers })
 }
 
 pub fn from_archived_asset(path: &str, asset: &ArchivedAssetZonePhysics) -> XResult<LoadedZonePhysics> {
@@ -132,19 +177,37 @@ pub fn from_archived_asset(path: &str, asset: &ArchivedAssetZonePhysics) -> XRes
         });
     }
 
-    Ok(LoadedZonePhysics { bodies })
+    let mut triggers = Vec::with_capacity(asset.triggers.len());
+    for asset_trigger in asset.triggers.iter() {
+        let shape_index = asset_trigger.shape_index.to_native();
+        let jolt_shape = jolt_shapes
+            .get(shape_index as usize)
+            .ok_or_else(|| xerrf!(BadAsset; "file={}, shape_index={}", path, shape_index))?;
+        triggers.push(LoadedZoneTrigger {
+            name: sb!(asset_trigger.name.as_str()),
+            shape: jolt_shape.clone(),
+            position: asset_trigger.position,
+            rotation: asset_trigger.rotation,
+            interactable: asset_trigger.interactable,
+            once: asset_trigger.once,
+        });
+    }
+
+    Ok(LoadedZonePhysics { bodies, triggers })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::consts::TEST_ASSET_PATH;
-    use crate::utils::sb;
 
     #[test]
     fn test_load_zone_physics() {
         let mut loader = AssetLoader::new(TEST_ASSET_PATH).unwrap();
         let zone_phy = loader.load_zone_physics(sb!("Zones/TestZone.*")).unwrap();
         assert!(zone_phy.bodies.len() > 0);
+        assert_eq!(zone_phy.triggers.len(), 1);
+        assert_eq!(zone_phy.triggers[0].name, "Checkpoint");
+        assert!(zone_phy.triggers[0].interactable);
     }
 }
@@ -1,7 +1,18 @@
+use critical_point_macros::csharp_const;
 use glam::Vec3A;
 use glam_ext::Vec2xz;
 
-pub const FPS_U32: u32 = 30;
+/// Supported simulation tick rates (frames per second), chosen per game by `ParamGame::tick_rate`.
+#[csharp_const]
+pub const TICK_RATE_30: u32 = 30;
+#[csharp_const]
+pub const TICK_RATE_60: u32 = 60;
+/// The tick rate used when `ParamGame::tick_rate` is 0.
+#[csharp_const]
+pub const DEFAULT_TICK_RATE: u32 = 30;
+
+// The default tick rate. Running games take their rate from GameTime.
+pub const FPS_U32: u32 = DEFAULT_TICK_RATE;
 pub const FPS_USIZE: usize = FPS_U32 as usize;
 pub const FPS: f32 = FPS_U32 as f32; // frames per second
 pub const SPF: f32 = 1.0 / FPS; // seconds per frame
@@ -19,7 +30,7 @@ pub const MB: usize = 1024 * KB;
 pub const GB: usize = 1024 * MB;
 
 pub const MAX_PLAYER: usize = 8;
-pub const MAX_INPUT_WINDOW: u32 = 1 * FPS_U32;
+pub const MAX_INPUT_WINDOW_SECS: u32 = 1;
 
 pub const EQUIPMENT_MAX_COUNT: usize = 3;
 pub const ACCESSORY_MAX_COUNT: usize = 4;
@@ -30,8 +41,14 @@ pub const INVALID_ANIMATION_ID: u16 = u16::MAX;
 pub const INVALID_AI_TASK_ID: u32 = u32::MAX;
 
 pub const MAX_ACTION_ANIMATION: usize = 5;
+pub const MAX_ADDITIVE_ANIMATION: usize = 4;
 pub const ACTION_WEIGHT_THRESHOLD: f32 = 0.01;
 pub const ACTION_DEFAULT_FADE_IN: f32 = 10.0 / FPS;
+pub const LOOK_AT_BLEND_TIME: f32 = 15.0 / FPS;
+pub const MAX_FOOT_IK_LEG: usize = 4;
+/// the foot IK ground ray starts above the ankle, and ends below the ankle
+pub const FOOT_IK_RAY_UP: f32 = 0.5;
+pub const FOOT_IK_RAY_DOWN: f32 = 1.0;
 
 pub const MAX_WALK_DIR_LENGTH: f32 = 0.5;
 pub const MIN_RUN_DIR_LENGTH: f32 = 0.5;
@@ -47,6 +64,24 @@ pub const DEFAULT_TOWARD_DIR_2D: Vec2xz = Vec2xz::Z;
 pub const DEFAULT_TOWARD_DIR_3D: Vec3A = Vec3A::Z;
 
 pub const MAX_HIT_TIMES_PER_FRAME: u16 = 100;
+/// max sub-frame steps of swept hit detection per hit group
+pub const MAX_HIT_SWEEP_STEPS: u8 = 8;
+
+/// elemental build-up meter value that triggers the status
+pub const ELEMENTAL_BUILDUP_THRESHOLD: f32 = 100.0;
+/// elemental build-up meter decay per second
+pub const ELEMENTAL_BUILDUP_DECAY: f32 = 10.0;
+/// burn status duration, damages max_health * BURN_DAMAGE_RATIO per second
+pub const BURN_DURATION: f32 = 5.0;
+pub const BURN_DAMAGE_RATIO: f32 = 0.02;
+/// freeze status duration, slows the character to FREEZE_TIME_SPEED
+pub const FREEZE_DURATION: f32 = 3.0;
+pub const FREEZE_TIME_SPEED: f32 = 0.5;
+/// shock status duration, staggers the character with max_posture * SHOCK_POSTURE_RATIO damage
+pub const SHOCK_DURATION: f32 = 1.0;
+pub const SHOCK_POSTURE_RATIO: f32 = 0.3;
+/// a counter hit reduces the posture of the dst character by max_posture * COUNTER_POSTURE_RATIO
+pub const COUNTER_POSTURE_RATIO: f32 = 0.15;
 
 #[cfg(test)]
 pub const TEST_TMP_PATH: &str = "../../test-tmp";
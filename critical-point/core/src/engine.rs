@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 use crate::input::InputPlayerInputs;
 use crate::instance::{ContextAssemble, InstCharacter};
-use crate::logic::{LogicLoop, StateSet};
+use crate::logic::{LogicJoinSnapshot, LogicLoop, StateChecksum, StatePoolCounters, StateSet};
 use crate::parameter::{ContextVerify, ParamGame, ParamNpc, ParamPlayer, verify_npc, verify_player};
 use crate::template::TmplDatabase;
 use crate::utils::{XResult, xerr, xres};
@@ -149,6 +149,56 @@ impl LogicEngine {
         Ok(state_set)
     }
 
+    /// Joins a running match from a snapshot taken by the host, as a spectator or a reconnecting player.
+    /// The returned StateSet contains the init states of all living objects at the snapshot frame.
+    pub fn join_game(&mut self, join: LogicJoinSnapshot) -> XResult<Arc<StateSet>> {
+        log::info!("LogicEngine::join_game() frame={}", join.frame());
+
+        if self.logic_loop.is_some() {
+            return xres!(Unexpected; "game already running");
+        }
+
+        let (logic_loop, state_set) = LogicLoop::new_join(
+            self.tmpl_database.clone(),
+            #[allow(static_mut_refs)]
+            unsafe {
+                ENV_PATH.asset_path.clone()
+            },
+            join,
+        )?;
+        self.logic_loop = Some(logic_loop);
+
+        log::info!("LogicEngine::join_game() OK");
+        Ok(state_set)
+    }
+
+    #[inline]
+    pub fn join_snapshot(&self) -> XResult<LogicJoinSnapshot> {
+        let logic_loop = self
+            .logic_loop
+            .as_ref()
+            .ok_or_else(|| xerr!(Unexpected; "game not running"))?;
+        logic_loop.join_snapshot()
+    }
+
+    /// The allocation counters of the StateSet pool, zero if the game is not running.
+    #[inline]
+    pub fn state_pool_counters(&self) -> StatePoolCounters {
+        match &self.logic_loop {
+            Some(logic_loop) => logic_loop.state_pool_counters(),
+            None => StatePoolCounters::default(),
+        }
+    }
+
+    /// Gets the checksum of a frame in [synced_frame, current_frame], to compare with the other peers.
+    pub fn checksum(&self, frame: u32) -> XResult<StateChecksum> {
+        let logic_loop = self
+            .logic_loop
+            .as_ref()
+            .ok_or_else(|| xerr!(Unexpected; "game not running"))?;
+        logic_loop.checksum(frame)
+    }
+
     pub fn update_game(&mut self, player_events: Vec<InputPlayerInputs>) -> XResult<Arc<StateSet>> {
         // log::info!("player_events {:?}", player_events);
         let logic_loop = self
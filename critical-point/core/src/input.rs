@@ -146,6 +146,40 @@ impl InputManager {
         Ok(())
     }
 
+    /// Rebuilds the queues from the confirmed input history of all players.
+    /// All players must be synced to the same frame.
+    pub fn init_from_snapshots(&mut self, snapshots: &[InputQueueSnapshot]) -> XResult<()> {
+        if !self.queues.is_empty() {
+            return xres!(BadOperation; "queue not empty");
+        }
+        if snapshots.is_empty() || snapshots.len() > MAX_PLAYER {
+            return xres!(BadArgument; "player count");
+        }
+
+        let synced_frame = snapshots[0].synced_frame;
+        let mut queues = Vec::with_capacity(snapshots.len());
+        for (player_idx, snapshot) in snapshots.iter().enumerate() {
+            if snapshot.player_id != NumID::MIN_PLAYER + player_idx as u32 {
+                return xresf!(BadArgument; "player_id={}, player_idx={}", snapshot.player_id, player_idx);
+            }
+            if snapshot.synced_frame != synced_frame {
+                return xresf!(BadArgument; "synced_frame={}, player_id={}", snapshot.synced_frame, snapshot.player_id);
+            }
+            let queue = InputEventQueue::from_snapshot(self.input_window, snapshot)?;
+            queues.push(Rc::new(RefCell::new(queue)));
+        }
+
+        self.queues = queues;
+        self.synced_frame = synced_frame;
+        self.current_frames = vec![synced_frame; snapshots.len()];
+        Ok(())
+    }
+
+    /// Exports the confirmed input history of all players.
+    pub fn snapshots(&self) -> XResult<Vec<InputQueueSnapshot>> {
+        self.queues.iter().map(|queue| queue.borrow().snapshot()).collect()
+    }
+
     // Returns the frame which the game should restore to.
     pub fn produce(&mut self, player_inputs: &[InputPlayerInputs]) -> XResult<u32> {
         if player_inputs.is_empty() {
@@ -416,7 +450,18 @@ impl InputVariables {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 struct InputFrameMeta {
     frame: u32,
     start_event_id: u64,
@@ -439,6 +484,28 @@ impl InputFrameMeta {
     }
 }
 
+/// The confirmed input history of a player, within the pre-input window.
+/// Used to rebuild the InputEventQueue on a late-joined (or reconnected) client.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct InputQueueSnapshot {
+    pub player_id: NumID,
+    pub synced_frame: u32,
+    base_frame: u32,
+    base_id: u64,
+    inputs: Vec<VirtualInput>,
+    metas: Vec<InputFrameMeta>,
+}
+
 pub type RefInputEventQueue = Rc<RefCell<InputEventQueue>>;
 
 #[derive(Debug)]
@@ -513,6 +580,59 @@ impl InputEventQueue {
         self.base_id
     }
 
+    fn from_snapshot(input_window: u32, snapshot: &InputQueueSnapshot) -> XResult<InputEventQueue> {
+        let end_meta = snapshot.metas.last().cloned().unwrap_or(InputFrameMeta::EMPTY);
+        let expected_metas = (snapshot.synced_frame + 1).saturating_sub(snapshot.base_frame) as usize;
+        if snapshot.metas.len() != expected_metas {
+            return xresf!(BadArgument; "metas.len={}, expected={}", snapshot.metas.len(), expected_metas);
+        }
+        if snapshot.inputs.len() as u64 != end_meta.end_next_event_id.max(snapshot.base_id) - snapshot.base_id {
+            return xresf!(BadArgument; "inputs.len={}", snapshot.inputs.len());
+        }
+
+        let mut inputs = VecDeque::with_capacity(snapshot.inputs.len().max(256));
+        inputs.extend(snapshot.inputs.iter().cloned());
+        let mut metas = VecDeque::with_capacity((2 * FPS_USIZE) + (input_window as usize));
+        metas.extend(snapshot.metas.iter().cloned());
+
+        Ok(InputEventQueue {
+            player_id: snapshot.player_id,
+            input_window,
+            id_counter: end_meta.end_next_event_id.max(snapshot.base_id),
+            inputs,
+            metas,
+            current_frame: snapshot.synced_frame,
+            synced_frame: snapshot.synced_frame,
+            base_frame: snapshot.base_frame,
+            base_id: snapshot.base_id,
+            variables: end_meta.variables,
+        })
+    }
+
+    /// Exports the inputs in [base_frame, synced_frame]. Unsynced inputs are not included.
+    fn snapshot(&self) -> XResult<InputQueueSnapshot> {
+        let meta_count = (self.synced_frame + 1).saturating_sub(self.base_frame) as usize;
+        if meta_count > self.metas.len() {
+            return xresf!(Unexpected; "meta_count={}, metas.len={}", meta_count, self.metas.len());
+        }
+        let end_id = match meta_count {
+            0 => self.base_id,
+            _ => self.metas[meta_count - 1].end_next_event_id,
+        };
+        Ok(InputQueueSnapshot {
+            player_id: self.player_id,
+            synced_frame: self.synced_frame,
+            base_frame: self.base_frame,
+            base_id: self.base_id,
+            inputs: self
+                .inputs
+                .range(0..(end_id - self.base_id) as usize)
+                .cloned()
+                .collect(),
+            metas: self.metas.range(0..meta_count).cloned().collect(),
+        })
+    }
+
     fn produce(&mut self, current_frame: u32, inputs: &[RawInput]) -> XResult<()> {
         if current_frame != self.current_frame + 1 {
             return xresf!(BadArgument; "current_frame={}, self.current_frame={}", current_frame, self.current_frame);
@@ -1104,6 +1224,75 @@ mod tests {
         assert_eq!(iq.get_input(3), Some(&evt(3, 2, s1_down)));
     }
 
+    #[test]
+    fn test_input_queue_snapshot() {
+        let a1_down = RawInput::new_button(RawKey::Attack1, true);
+        let s1_down = RawInput::new_button(RawKey::Skill1, true);
+
+        let mut iq = InputEventQueue::new(NumID(0), 3);
+        let empty = iq.snapshot().unwrap();
+        let iq_empty = InputEventQueue::from_snapshot(3, &empty).unwrap();
+        assert_eq!(iq_empty.future_id(), FIRST_EVENT_ID);
+        assert_eq!(iq_empty.base_frame(), 1);
+
+        iq.produce(1, &[a1_down]).unwrap();
+        iq.produce(2, &[RawInput::new_move(Vec2::new(1.0, 0.0))]).unwrap();
+        iq.produce(3, &[s1_down]).unwrap();
+        iq.produce(4, &[a1_down]).unwrap();
+        iq.produce(5, &[s1_down]).unwrap();
+        iq.confirm(4).unwrap();
+
+        let snapshot = iq.snapshot().unwrap();
+        assert_eq!(snapshot.synced_frame, 4);
+        assert_eq!(snapshot.base_frame, 1);
+        assert_eq!(snapshot.metas.len(), 4);
+        assert_eq!(snapshot.inputs.len(), 3);
+
+        let mut iq2 = InputEventQueue::from_snapshot(3, &snapshot).unwrap();
+        assert_eq!(iq2.current_frame(), 4);
+        assert_eq!(iq2.synced_frame(), 4);
+        assert_eq!(iq2.future_id(), 4);
+        assert_eq!(iq2.variables(2).unwrap(), iq.variables(2).unwrap());
+        iq2.produce(5, &[s1_down]).unwrap();
+        assert_eq!(iq2.variables.device_move, iq.variables.device_move);
+        assert_eq!(
+            iq2.iter_preinput(5, 0).unwrap().collect::<Vec<_>>(),
+            iq.iter_preinput(5, 0).unwrap().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            iq2.iter_current(5).unwrap().collect::<Vec<_>>(),
+            iq.iter_current(5).unwrap().collect::<Vec<_>>()
+        );
+
+        let mut bad = snapshot.clone();
+        bad.metas.pop();
+        assert!(InputEventQueue::from_snapshot(3, &bad).is_err());
+    }
+
+    #[test]
+    fn test_input_system_snapshots() {
+        let mut si = InputManager::new(3);
+        si.init(2).unwrap();
+        si.produce(&[
+            InputPlayerInputs::new(NumID(100), 1, vec![RawInput::new_button(RawKey::Attack1, true)]),
+            InputPlayerInputs::new(NumID(101), 1, vec![]),
+        ])
+        .unwrap();
+        si.confirm().unwrap();
+
+        let snapshots = si.snapshots().unwrap();
+        assert_eq!(snapshots.len(), 2);
+        let mut si2 = InputManager::new(3);
+        si2.init_from_snapshots(&snapshots).unwrap();
+        assert!(si2.init_from_snapshots(&snapshots).is_err());
+        assert_eq!(si2.player_count(), 2);
+        assert_eq!(si2.synced_frame(), 1);
+        assert_eq!(si2.snapshots().unwrap(), snapshots);
+
+        let mut si3 = InputManager::new(3);
+        assert!(si3.init_from_snapshots(&snapshots[1..]).is_err());
+    }
+
     #[test]
     fn test_input_system() {
         let a1_down = RawInput::new_button(RawKey::Attack1, true);
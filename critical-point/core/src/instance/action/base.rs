@@ -6,14 +6,19 @@ use thin_vec::ThinVec;
 
 use crate::animation::AnimationFileMeta;
 use crate::template::{
-    ArchivedTmplActionAttributes, ArchivedTmplAnimation, ArchivedTmplDeriveRule, ArchivedTmplHit,
-    ArchivedTmplTimelinePoint, ArchivedTmplTimelineRange, ArchivedTmplVar,
+    ArchivedTmplActionAttributes, ArchivedTmplAdditiveAnimation, ArchivedTmplAnimation, ArchivedTmplDeriveRule,
+    ArchivedTmplHit, ArchivedTmplTimelinePoint, ArchivedTmplTimelineRange, ArchivedTmplVar, TmplActionLookAt,
+    TmplMotionWarp, TmplMotionWarpTarget,
 };
 use crate::utils::{
     ActionType, DtHashMap, InputDir, Symbol, TimeRange, TimeRangeWith, TimeWith, TmplID, VirtualKey, VirtualKeyDir,
     XResult, calc_fade_in, interface, ratio_saturating, ratio_warpping, sb,
 };
 
+pub type InstMotionWarp = TmplMotionWarp;
+pub type InstMotionWarpTarget = TmplMotionWarpTarget;
+pub type InstActionLookAt = TmplActionLookAt;
+
 pub unsafe trait InstActionAny: Debug + Any {
     fn typ(&self) -> ActionType;
     fn animations<'a>(&'a self, animations: &mut Vec<&'a InstAnimation>);
@@ -32,6 +37,8 @@ pub struct InstActionBase {
     pub cool_down_count: u16,
     pub cool_down_init_count: u16,
     pub hits: ThinVec<InstHit>,
+    pub hit_additive: Option<InstAdditiveAnimation>,
+    pub look_at: Option<InstActionLookAt>,
 }
 
 interface!(InstActionAny, InstActionBase);
@@ -69,6 +76,8 @@ pub struct InstAnimation {
     pub weapon_motion: bool,
     pub hit_motion: bool,
     pub shape_key: bool,
+    pub notify: bool,
+    pub joint_mask: Symbol,
 }
 
 impl InstAnimation {
@@ -83,6 +92,8 @@ impl InstAnimation {
             weapon_motion: archived.weapon_motion,
             hit_motion: archived.hit_motion,
             shape_key: archived.shape_key,
+            notify: archived.notify,
+            joint_mask: sb!(&archived.joint_mask),
         }
     }
 
@@ -117,12 +128,36 @@ impl InstAnimation {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstAdditiveAnimation {
+    pub files: Symbol,
+    pub duration: f32,
+    pub weight: f32,
+    pub looping: bool,
+}
+
+impl InstAdditiveAnimation {
+    #[inline]
+    pub fn from_rkyv(archived: &ArchivedTmplAdditiveAnimation) -> InstAdditiveAnimation {
+        InstAdditiveAnimation {
+            files: sb!(&archived.files),
+            duration: archived.duration.into(),
+            weight: archived.weight.into(),
+            looping: archived.looping,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct InstHit {
     pub group: Symbol,
     pub box_max_times: u16,
     pub box_min_interval: f32,
     pub group_max_times: u16,
+    pub buffs: Vec<TmplID>,
+    pub fire_buildup: f32,
+    pub ice_buildup: f32,
+    pub thunder_buildup: f32,
 }
 
 impl InstHit {
@@ -133,6 +168,10 @@ impl InstHit {
             box_max_times: ctx.solve_var(&archived.box_max_times).to_native(),
             box_min_interval: ctx.solve_var(&archived.box_min_interval).to_native(),
             group_max_times: ctx.solve_var(&archived.group_max_times).to_native(),
+            buffs: archived.buffs.iter().copied().collect(),
+            fire_buildup: archived.fire_buildup.to_native(),
+            ice_buildup: archived.ice_buildup.to_native(),
+            thunder_buildup: archived.thunder_buildup.to_native(),
         }
     }
 }
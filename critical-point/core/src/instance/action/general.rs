@@ -1,11 +1,12 @@
 use crate::instance::action::base::{
-    ContextActionAssemble, InstActionAny, InstActionAttributes, InstActionBase, InstAnimation, InstDeriveRule, InstHit,
-    InstTimelinePoint, InstTimelineRange,
+    ContextActionAssemble, InstActionAny, InstActionAttributes, InstActionBase, InstActionLookAt,
+    InstAdditiveAnimation, InstAnimation, InstDeriveRule, InstHit, InstMotionWarp, InstTimelinePoint,
+    InstTimelineRange,
 };
 use crate::template::{
     At, TmplActionGeneral, TmplActionGeneralMovement, TmplActionGeneralRootMotion, TmplActionGeneralRotation,
 };
-use crate::utils::{ActionType, Bitsetable, DeriveContinue, EnumBitset, Symbol, ThinVec, XResult, extend, sb};
+use crate::utils::{ActionType, Bitsetable, DeriveContinue, EnumBitset, Symbol, ThinVec, TmplID, XResult, extend, sb};
 
 pub type InstActionGeneralMovement = TmplActionGeneralMovement;
 pub type InstActionGeneralRootMotion = TmplActionGeneralRootMotion;
@@ -18,10 +19,13 @@ pub struct InstActionGeneral {
     pub anim_main: InstAnimation,
     pub attributes: InstTimelineRange<InstActionAttributes>,
     pub input_movements: InstTimelinePoint<InstActionGeneralMovement>,
+    pub motion_warps: InstTimelinePoint<InstMotionWarp>,
     pub keep_levels: InstTimelineRange<u16>,
     pub derives: ThinVec<InstDeriveRule>,
     pub derive_continues: EnumBitset<DeriveContinue, { DeriveContinue::LEN }>,
     pub custom_events: InstTimelinePoint<Symbol>,
+    pub hit_objects: InstTimelinePoint<TmplID>,
+    pub additive_animations: InstTimelinePoint<InstAdditiveAnimation>,
 }
 
 extend!(InstActionGeneral, InstActionBase);
@@ -62,6 +66,7 @@ impl InstActionGeneral {
 
         let input_movements =
             InstTimelinePoint::from_rkyv(&tmpl.input_movements, |t| InstActionGeneralMovement::from_rkyv(t))?;
+        let motion_warps = InstTimelinePoint::from_rkyv(&tmpl.motion_warps, |t| InstMotionWarp::from_rkyv(t))?;
 
         let attributes = InstTimelineRange::from_rkyv(&tmpl.attributes, |archived| {
             Ok(InstActionAttributes::from_rkyv(ctx, archived))
@@ -75,6 +80,10 @@ impl InstActionGeneral {
         }
 
         let custom_events = InstTimelinePoint::from_rkyv(&tmpl.custom_events, |s| Ok(sb!(s)))?;
+        let hit_objects = InstTimelinePoint::from_rkyv(&tmpl.hit_objects, |id| Ok(*id))?;
+
+        let additive_animations =
+            InstTimelinePoint::from_rkyv(&tmpl.additive_animations, |t| Ok(InstAdditiveAnimation::from_rkyv(t)))?;
 
         let inst = InstActionGeneral {
             _base: InstActionBase {
@@ -83,15 +92,20 @@ impl InstActionGeneral {
                 enter_key: tmpl.enter_key.as_ref().cloned(),
                 enter_level: tmpl.enter_level.into(),
                 hits,
+                hit_additive: tmpl.hit_additive.as_ref().map(InstAdditiveAnimation::from_rkyv),
+                look_at: tmpl.look_at.as_ref().map(InstActionLookAt::from_rkyv).transpose()?,
                 ..Default::default()
             },
             derives,
             anim_main: InstAnimation::from_rkyv(&tmpl.anim_main),
             input_movements,
+            motion_warps,
             attributes,
             keep_levels,
             derive_continues: tmpl.derive_continues,
             custom_events,
+            hit_objects,
+            additive_animations,
         };
         Ok(Some(inst))
     }
@@ -147,6 +161,7 @@ mod tests {
             assert_eq!(inst_act.anim_main.root_motion, true);
             assert_eq!(inst_act.anim_main.weapon_motion, false);
             assert_eq!(inst_act.anim_main.hit_motion, true);
+            assert_eq!(inst_act.anim_main.notify, true);
 
             assert_eq!(inst_act.input_movements.len(), 2);
             assert_eq!(inst_act.input_movements[0].time, 0.0);
@@ -193,10 +208,14 @@ mod tests {
             assert_eq!(inst_act.hits[0].box_max_times, 2);
             assert_eq!(inst_act.hits[0].box_min_interval, cf2s(1));
             assert_eq!(inst_act.hits[0].group_max_times, 4);
+            assert_eq!(inst_act.hits[0].buffs, vec![id!("Buff.Instance.Burning")]);
+            assert_eq!(inst_act.hits[0].fire_buildup, 25.0);
+            assert_eq!(inst_act.hits[0].ice_buildup, 0.0);
             assert_eq!(inst_act.hits[1].group, "Counter");
             assert_eq!(inst_act.hits[1].box_max_times, 1);
             assert_eq!(inst_act.hits[1].box_min_interval, 1e10);
             assert_eq!(inst_act.hits[1].group_max_times, 1);
+            assert!(inst_act.hits[1].buffs.is_empty());
             assert_eq!(inst_act.hits[2].group, "Axe");
             assert_eq!(inst_act.hits[2].box_max_times, 0);
             assert_eq!(inst_act.hits[2].box_min_interval, 1e10);
@@ -207,6 +226,27 @@ mod tests {
             assert_eq!(inst_act.custom_events[0].value, "Event1s");
             assert_eq!(inst_act.custom_events[1].time, 2.0);
             assert_eq!(inst_act.custom_events[1].value, "Event2s");
+
+            assert_eq!(inst_act.hit_objects.len(), 1);
+            assert_eq!(inst_act.hit_objects[0].time, 1.5);
+            assert_eq!(inst_act.hit_objects[0].value, id!("HitObject.Instance.Arrow"));
+
+            let hit_additive = inst_act.hit_additive.as_ref().unwrap();
+            assert_eq!(hit_additive.files, sb!("Girl/Idle_Empty.*"));
+            assert_eq!(hit_additive.duration, 0.5);
+            assert_eq!(hit_additive.weight, 0.5);
+            assert!(!hit_additive.looping);
+        }
+        {
+            let tmpl_act = db
+                .find_as::<TmplActionGeneral>(id!("Action.Instance.UpperAttack^1A"))
+                .unwrap();
+            let ctx = ContextActionAssemble {
+                var_indexes: &var_indexes,
+            };
+            let inst_act = InstActionGeneral::new_from_action(&ctx, tmpl_act).unwrap().unwrap();
+            assert_eq!(inst_act.anim_main.files, sb!("Girl/Attack_01A.*"));
+            assert_eq!(inst_act.anim_main.joint_mask, sb!("UpperBody"));
         }
     }
 }
@@ -1,6 +1,6 @@
 use crate::instance::action::base::{
-    ContextActionAssemble, InstActionAny, InstActionBase, InstAnimation, InstDeriveRule, InstHit, InstTimelinePoint,
-    InstTimelineRange,
+    ContextActionAssemble, InstActionAny, InstActionBase, InstActionLookAt, InstAdditiveAnimation, InstAnimation,
+    InstDeriveRule, InstHit, InstMotionWarp, InstTimelinePoint, InstTimelineRange,
 };
 use crate::template::{
     At, TmplActionGeneralNpc, TmplActionGeneralNpcMovement, TmplActionGeneralNpcRotation,
@@ -18,9 +18,11 @@ pub struct InstActionGeneralNpc {
     pub _base: InstActionBase,
     pub anim_main: InstAnimation,
     pub adjust_movements: InstTimelinePoint<InstActionGeneralNpcMovement>,
+    pub motion_warps: InstTimelinePoint<InstMotionWarp>,
     // pub attributes: InstTimelineRange<InstActionAttributes>,
     pub keep_levels: InstTimelineRange<u16>,
     pub custom_events: InstTimelinePoint<Symbol>,
+    pub additive_animations: InstTimelinePoint<InstAdditiveAnimation>,
 }
 
 extend!(InstActionGeneralNpc, InstActionBase);
@@ -49,6 +51,7 @@ impl InstActionGeneralNpc {
 
         let adjust_movements =
             InstTimelinePoint::from_rkyv(&tmpl.adjust_movements, |t| InstActionGeneralNpcMovement::from_rkyv(t))?;
+        let motion_warps = InstTimelinePoint::from_rkyv(&tmpl.motion_warps, |t| InstMotionWarp::from_rkyv(t))?;
 
         // let attributes = InstTimelineRange::from_rkyv(&tmpl.attributes, |archived| {
         //     Ok(InstActionAttributes::from_rkyv(ctx, archived))
@@ -63,18 +66,25 @@ impl InstActionGeneralNpc {
 
         let custom_events = InstTimelinePoint::from_rkyv(&tmpl.custom_events, |s| Ok(sb!(s)))?;
 
+        let additive_animations =
+            InstTimelinePoint::from_rkyv(&tmpl.additive_animations, |t| Ok(InstAdditiveAnimation::from_rkyv(t)))?;
+
         let inst = InstActionGeneralNpc {
             _base: InstActionBase {
                 tmpl_id: tmpl.id,
                 tags: tmpl.tags.iter().map(|t| sb!(t)).collect(),
                 hits,
+                hit_additive: tmpl.hit_additive.as_ref().map(InstAdditiveAnimation::from_rkyv),
+                look_at: tmpl.look_at.as_ref().map(InstActionLookAt::from_rkyv).transpose()?,
                 ..Default::default()
             },
             anim_main: InstAnimation::from_rkyv(&tmpl.anim_main),
             adjust_movements,
+            motion_warps,
             // attributes,
             keep_levels,
             custom_events,
+            additive_animations,
         };
         Ok(Some(inst))
     }
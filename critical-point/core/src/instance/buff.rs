@@ -0,0 +1,95 @@
+use crate::instance::base::ContextAssemble;
+use crate::template::{TmplAttribute, TmplBuff, TmplBuffStacking};
+use crate::utils::{TmplID, XResult, xresf};
+
+pub type InstBuffStacking = TmplBuffStacking;
+
+#[derive(Debug)]
+pub struct InstBuff {
+    pub tmpl_id: TmplID,
+    pub duration: f32,
+    pub stacking: InstBuffStacking,
+    pub max_stacks: u16,
+    pub tick_interval: f32,
+    pub tick_health: f32,
+    pub tick_posture: f32,
+    pub attributes: Vec<(TmplAttribute, Vec<f32>)>,
+}
+
+impl InstBuff {
+    pub fn new(ctx: &mut ContextAssemble<'_>, tmpl_id: TmplID) -> XResult<InstBuff> {
+        let tmpl = ctx.tmpl_db.find_as::<TmplBuff>(tmpl_id)?;
+        if tmpl.max_stacks == 0 {
+            return xresf!(BadAttribute; "tmpl_id={}, max_stacks=0", tmpl_id);
+        }
+        Ok(InstBuff {
+            tmpl_id,
+            duration: tmpl.duration.into(),
+            stacking: tmpl.stacking,
+            max_stacks: tmpl.max_stacks.into(),
+            tick_interval: tmpl.tick_interval.into(),
+            tick_health: tmpl.tick_health.into(),
+            tick_posture: tmpl.tick_posture.into(),
+            attributes: tmpl
+                .attributes
+                .iter()
+                .map(|attr| (attr.k, attr.v.iter().map(|v| v.to_native()).collect()))
+                .collect(),
+        })
+    }
+
+    /// Attribute modifiers at the given stack count.
+    #[inline]
+    pub fn attributes(&self, stacks: u16) -> impl Iterator<Item = (TmplAttribute, f32)> + '_ {
+        let idx = (stacks.min(self.max_stacks).max(1) - 1) as usize;
+        self.attributes
+            .iter()
+            .filter_map(move |(attr, values)| values.get(idx).map(|v| (*attr, *v)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::TmplDatabase;
+    use crate::utils::id;
+
+    #[test]
+    fn test_inst_buff() {
+        let db = TmplDatabase::new(10240, 150).unwrap();
+        let mut ctx = ContextAssemble::new(&db);
+
+        let attack_up = InstBuff::new(&mut ctx, id!("Buff.Instance.AttackUp")).unwrap();
+        assert_eq!(attack_up.tmpl_id, id!("Buff.Instance.AttackUp"));
+        assert_eq!(attack_up.duration, 10.0);
+        assert_eq!(attack_up.stacking, InstBuffStacking::Stack);
+        assert_eq!(attack_up.max_stacks, 3);
+        assert_eq!(attack_up.attributes(1).collect::<Vec<_>>(), vec![(
+            TmplAttribute::AttackUp,
+            0.1
+        )]);
+        assert_eq!(attack_up.attributes(3).collect::<Vec<_>>(), vec![(
+            TmplAttribute::AttackUp,
+            0.2
+        )]);
+        assert_eq!(attack_up.attributes(9).collect::<Vec<_>>(), vec![(
+            TmplAttribute::AttackUp,
+            0.2
+        )]);
+        assert_eq!(attack_up.attributes(0).collect::<Vec<_>>(), vec![(
+            TmplAttribute::AttackUp,
+            0.1
+        )]);
+
+        let burning = InstBuff::new(&mut ctx, id!("Buff.Instance.Burning")).unwrap();
+        assert_eq!(burning.stacking, InstBuffStacking::Refresh);
+        assert_eq!(burning.tick_interval, 1.0);
+        assert_eq!(burning.tick_health, -20.0);
+        assert_eq!(burning.attributes(1).collect::<Vec<_>>(), vec![(
+            TmplAttribute::FireDefenseDown,
+            0.2
+        )]);
+
+        assert!(InstBuff::new(&mut ctx, id!("Zone.Demo")).is_err());
+    }
+}
@@ -4,22 +4,118 @@ use std::collections::hash_map::Entry;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
-use crate::instance::InstAiBrain;
 use crate::instance::action::{
     ContextActionAssemble, InstActionAny, InstDeriveRule, assemble_action, collect_action_keys,
 };
 use crate::instance::base::ContextAssemble;
 use crate::instance::values::{PanelValues, PrimaryValues, SecondaryValues};
+use crate::instance::{InstAiBrain, InstBuff};
 use crate::parameter::{ParamNpc, ParamPlayer};
 use crate::template::{
-    TmplAccessory, TmplAccessoryPool, TmplAiBrain, TmplCharacter, TmplCharacterNpc, TmplEntry, TmplEquipment,
-    TmplJewel, TmplPerk, TmplStyle,
+    ArchivedTmplFootIk, ArchivedTmplJointMask, ArchivedTmplLookAt, TmplAccessory, TmplAccessoryPool, TmplAiBrain,
+    TmplCharacter, TmplCharacterNpc, TmplEntry, TmplEquipment, TmplJewel, TmplPerk, TmplStyle,
 };
 use crate::utils::{
     Castable, DtHashIndex, DtHashMap, JewelSlots, PiecePlus, Symbol, TmplID, VirtualKey, XResult, force_mut,
-    quat_from_dir_xz, sb,
+    quat_from_dir_xz, sb, xresf,
 };
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstFootIk {
+    pub pelvis: Symbol,
+    pub legs: Vec<InstFootIkLeg>,
+    pub max_pelvis_offset: f32,
+    pub max_foot_offset: f32,
+    pub root_tilt: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstFootIkLeg {
+    pub hip: Symbol,
+    pub knee: Symbol,
+    pub ankle: Symbol,
+}
+
+impl InstFootIk {
+    pub fn from_rkyv(archived: &ArchivedTmplFootIk) -> InstFootIk {
+        InstFootIk {
+            pelvis: sb!(&archived.pelvis),
+            legs: archived
+                .legs
+                .iter()
+                .map(|leg| InstFootIkLeg {
+                    hip: sb!(&leg.hip),
+                    knee: sb!(&leg.knee),
+                    ankle: sb!(&leg.ankle),
+                })
+                .collect(),
+            max_pelvis_offset: archived.max_pelvis_offset.into(),
+            max_foot_offset: archived.max_foot_offset.into(),
+            root_tilt: archived.root_tilt.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstLookAt {
+    pub joints: Vec<InstLookAtJoint>,
+    pub max_yaw: f32,
+    pub max_pitch: f32,
+    pub rotate_weapons: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstLookAtJoint {
+    pub joint: Symbol,
+    pub weight: f32,
+}
+
+impl InstLookAt {
+    pub fn from_rkyv(archived: &ArchivedTmplLookAt) -> InstLookAt {
+        InstLookAt {
+            joints: archived
+                .joints
+                .iter()
+                .map(|joint| InstLookAtJoint {
+                    joint: sb!(&joint.joint),
+                    weight: joint.weight.into(),
+                })
+                .collect(),
+            max_yaw: archived.max_yaw.into(),
+            max_pitch: archived.max_pitch.into(),
+            rotate_weapons: archived.rotate_weapons,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstJointMask {
+    pub name: Symbol,
+    pub joints: Vec<InstJointMaskJoint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstJointMaskJoint {
+    pub joint: Symbol,
+    pub weight: f32,
+}
+
+impl InstJointMask {
+    pub fn from_rkyv(archived: &ArchivedTmplJointMask) -> InstJointMask {
+        InstJointMask {
+            name: sb!(&archived.name),
+            joints: archived
+                .joints
+                .iter()
+                .map(|joint| InstJointMaskJoint {
+                    joint: sb!(&joint.joint),
+                    weight: joint.weight.into(),
+                })
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct InstCharacter {
     pub is_player: bool,
@@ -31,6 +127,9 @@ pub struct InstCharacter {
     pub skeleton_files: Symbol,
     pub skeleton_toward: Vec2xz,
     pub skeleton_rotation: Quat,
+    pub foot_ik: Option<InstFootIk>,
+    pub look_at: Option<InstLookAt>,
+    pub joint_masks: Vec<InstJointMask>,
 
     pub values: Box<InstValues>,
     pub slots: JewelSlots,
@@ -67,6 +166,21 @@ impl DerefMut for InstCharacter {
     }
 }
 
+impl InstValues {
+    /// Recomputes the panel values with the runtime buffs, as (buff, stacks) pairs.
+    pub fn panel_with_buffs<'t>(&self, buffs: impl Iterator<Item = (&'t InstBuff, u16)>) -> PanelValues {
+        let mut primary = self.primary;
+        let mut secondary = self.secondary;
+        for (buff, stacks) in buffs {
+            for (attr, value) in buff.attributes(stacks) {
+                primary.append_attribute(attr, value);
+                secondary.append_attribute(attr, value);
+            }
+        }
+        PanelValues::new(&primary, &secondary)
+    }
+}
+
 impl InstCharacter {
     pub fn new_player(ctx: &mut ContextAssemble<'_>, param: &ParamPlayer) -> XResult<Rc<InstCharacter>> {
         let mut inst = InstCharacter {
@@ -84,10 +198,46 @@ impl InstCharacter {
         Self::collect_player_jewels(ctx, param, &mut inst)?;
         Self::collect_player_actions(ctx, param, &mut inst)?;
         Self::handle_player_entries(ctx, &mut inst)?;
+        inst.values.panel = PanelValues::new(&inst.primary, &inst.secondary);
 
         Ok(Rc::new(inst))
     }
 
+    /// Assembles one InstCharacter for each style of the character.
+    /// Returns all styles and the index of the style selected by `param.style`.
+    pub fn new_player_styles(
+        ctx: &mut ContextAssemble<'_>,
+        param: &ParamPlayer,
+    ) -> XResult<(Vec<Rc<InstCharacter>>, usize)> {
+        let chara = ctx.tmpl_db.find_as::<TmplCharacter>(param.character)?;
+        let Some(current) = chara.styles.iter().position(|id| *id == param.style)
+        else {
+            return xresf!(BadParameter; "character={}, style={}", param.character, param.style);
+        };
+
+        let mut styles = Vec::with_capacity(chara.styles.len());
+        for style_id in chara.styles.iter() {
+            if *style_id == param.style {
+                styles.push(Self::new_player(ctx, param)?);
+                continue;
+            }
+
+            let mut style_param = param.clone();
+            style_param.style = *style_id;
+            // Drop the perks that are not usable by this style.
+            let mut perks = Vec::with_capacity(param.perks.len());
+            for pair in param.perks.iter() {
+                let perk = ctx.tmpl_db.find_as::<TmplPerk>(pair.id)?;
+                if perk.style == *style_id || perk.usable_styles.contains(style_id) {
+                    perks.push(pair.clone());
+                }
+            }
+            style_param.perks = perks;
+            styles.push(Self::new_player(ctx, &style_param)?);
+        }
+        Ok((styles, current))
+    }
+
     fn collect_player_character_style(
         ctx: &mut ContextAssemble<'_>,
         param: &ParamPlayer,
@@ -100,6 +250,9 @@ impl InstCharacter {
         inst.skeleton_files = sb!(&chara.skeleton_files);
         inst.skeleton_toward = chara.skeleton_toward;
         inst.skeleton_rotation = quat_from_dir_xz(chara.skeleton_toward);
+        inst.foot_ik = chara.foot_ik.as_ref().map(InstFootIk::from_rkyv);
+        inst.look_at = chara.look_at.as_ref().map(InstLookAt::from_rkyv);
+        inst.joint_masks = chara.joint_masks.iter().map(InstJointMask::from_rkyv).collect();
 
         let idx = chara.level_to_index(param.level);
         for attr in style.attributes.iter() {
@@ -271,6 +424,7 @@ impl InstCharacter {
         Self::collect_npc_character(ctx, param, &mut inst)?;
         Self::collect_npc_actions(ctx, param, &mut inst)?;
         Self::collect_npc_ai_brain(ctx, param, &mut inst)?;
+        inst.values.panel = PanelValues::new(&inst.primary, &inst.secondary);
 
         Ok(Rc::new(inst))
     }
@@ -282,6 +436,9 @@ impl InstCharacter {
         inst.skeleton_files = sb!(&chara.skeleton_files);
         inst.skeleton_toward = chara.skeleton_toward;
         inst.skeleton_rotation = quat_from_dir_xz(chara.skeleton_toward);
+        inst.foot_ik = chara.foot_ik.as_ref().map(InstFootIk::from_rkyv);
+        inst.look_at = chara.look_at.as_ref().map(InstLookAt::from_rkyv);
+        inst.joint_masks = chara.joint_masks.iter().map(InstJointMask::from_rkyv).collect();
 
         let idx = chara.level_to_index(param.level);
         for attr in chara.attributes.iter() {
@@ -438,6 +595,29 @@ mod tests {
         assert_eq!(inst.skeleton_files, sb!("Girl/Girl.*"));
         assert_eq!(inst.skeleton_toward, Vec2xz::new(0.0, 1.0));
         assert_eq!(inst.skeleton_rotation, quat_from_dir_xz(Vec2xz::new(0.0, 1.0)));
+        assert_eq!(inst.joint_masks, vec![InstJointMask {
+            name: sb!("UpperBody"),
+            joints: vec![
+                InstJointMaskJoint {
+                    joint: sb!("Spine"),
+                    weight: 0.5,
+                },
+                InstJointMaskJoint {
+                    joint: sb!("Chest"),
+                    weight: 1.0,
+                },
+            ],
+        }]);
+        let foot_ik = inst.foot_ik.as_ref().unwrap();
+        assert_eq!(foot_ik.pelvis, sb!("Hips"));
+        assert_eq!(foot_ik.legs.len(), 2);
+        assert_eq!(foot_ik.legs[0].hip, sb!("LeftUpperLeg"));
+        assert_eq!(foot_ik.legs[0].knee, sb!("LeftLowerLeg"));
+        assert_eq!(foot_ik.legs[0].ankle, sb!("LeftFoot"));
+        assert_eq!(foot_ik.legs[1].ankle, sb!("RightFoot"));
+        assert_eq!(foot_ik.max_pelvis_offset, 0.3);
+        assert_eq!(foot_ik.max_foot_offset, 0.4);
+        assert_eq!(foot_ik.root_tilt, 0.0);
 
         assert_eq!(inst.primary.max_health, 1200.0);
         assert_eq!(inst.primary.max_posture, 180.0);
@@ -779,6 +959,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_inst_player_styles() {
+        let db = TmplDatabase::new(10240, 150).unwrap();
+        let mut ctx = ContextAssemble::new(&db);
+
+        let param = ParamPlayer {
+            character: id!("Character.Instance^1"),
+            style: id!("Style.Instance^1A"),
+            level: 4,
+            ..Default::default()
+        };
+        let (styles, current) = InstCharacter::new_player_styles(&mut ctx, &param).unwrap();
+        assert_eq!(styles.len(), 2);
+        assert_eq!(current, 0);
+        assert_eq!(styles[0].tmpl_style, id!("Style.Instance^1A"));
+        assert_eq!(styles[0].panel.max_health, 850.0);
+        assert!(styles[0].primary_keys.find_first(&VirtualKey::Switch).is_none());
+        assert_eq!(styles[1].tmpl_style, id!("Style.Instance^1B"));
+        assert_eq!(styles[1].panel.max_health, 1000.0);
+        assert_eq!(styles[1].panel.max_posture, 110.0);
+        assert_eq!(
+            styles[1].primary_keys.find_first(&VirtualKey::Switch),
+            Some(&id!("Action.Instance.Switch^1B"))
+        );
+
+        let param = ParamPlayer {
+            style: id!("Style.Instance^1B"),
+            ..param
+        };
+        let (styles, current) = InstCharacter::new_player_styles(&mut ctx, &param).unwrap();
+        assert_eq!(styles.len(), 2);
+        assert_eq!(current, 1);
+        assert_eq!(styles[current].tmpl_style, id!("Style.Instance^1B"));
+
+        let param = ParamPlayer {
+            style: id!("Style.One^1"),
+            ..param
+        };
+        assert!(InstCharacter::new_player_styles(&mut ctx, &param).is_err());
+    }
+
+    #[test]
+    fn test_inst_panel_with_buffs() {
+        let db = TmplDatabase::new(10240, 150).unwrap();
+        let mut ctx = ContextAssemble::new(&db);
+
+        let param = ParamPlayer {
+            character: id!("Character.Instance^1"),
+            style: id!("Style.Instance^1A"),
+            level: 4,
+            ..Default::default()
+        };
+        let inst = InstCharacter::new_player(&mut ctx, &param).unwrap();
+        let attack_up = InstBuff::new(&mut ctx, id!("Buff.Instance.AttackUp")).unwrap();
+
+        let panel = inst.panel_with_buffs([].into_iter());
+        assert_eq!(panel.max_health, inst.panel.max_health);
+        assert_eq!(panel.physical_attack, inst.panel.physical_attack);
+
+        let panel = inst.panel_with_buffs([(&attack_up, 2)].into_iter());
+        assert_eq!(panel.max_health, inst.panel.max_health);
+        assert!((panel.physical_attack_up - inst.panel.physical_attack_up - 0.15).abs() < 1e-6);
+        assert!(panel.physical_attack > inst.panel.physical_attack);
+    }
+
     #[test]
     fn test_inst_npc_new() {
         let db = TmplDatabase::new(10240, 150).unwrap();
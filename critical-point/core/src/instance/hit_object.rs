@@ -0,0 +1,67 @@
+use glam::Vec3A;
+
+use crate::instance::base::ContextAssemble;
+use crate::template::{TmplHitObject, TmplHitObjectShape};
+use crate::utils::{Symbol, TmplID, XResult};
+
+pub type InstHitObjectShape = TmplHitObjectShape;
+
+#[derive(Debug)]
+pub struct InstHitObject {
+    pub tmpl_id: TmplID,
+    pub group: Symbol,
+    pub shape: InstHitObjectShape,
+    pub spawn_offset: Vec3A,
+    pub speed: f32,
+    pub gravity: f32,
+    pub homing_speed: f32,
+    pub homing_radius: f32,
+    pub life_time: f32,
+    pub max_hits: u16,
+    pub chara_max_times: u16,
+    pub chara_min_interval: f32,
+    pub destroy_on_scenery: bool,
+}
+
+impl InstHitObject {
+    pub fn new(ctx: &mut ContextAssemble<'_>, tmpl_id: TmplID) -> XResult<InstHitObject> {
+        let tmpl = ctx.tmpl_db.find_as::<TmplHitObject>(tmpl_id)?;
+        Ok(InstHitObject {
+            tmpl_id,
+            group: Symbol::new(&tmpl.group)?,
+            shape: InstHitObjectShape::from_rkyv(&tmpl.shape),
+            spawn_offset: tmpl.spawn_offset,
+            speed: tmpl.speed.into(),
+            gravity: tmpl.gravity.into(),
+            homing_speed: tmpl.homing_speed.into(),
+            homing_radius: tmpl.homing_radius.into(),
+            life_time: tmpl.life_time.into(),
+            max_hits: tmpl.max_hits.into(),
+            chara_max_times: tmpl.chara_max_times.into(),
+            chara_min_interval: tmpl.chara_min_interval.into(),
+            destroy_on_scenery: tmpl.destroy_on_scenery,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::TmplDatabase;
+    use crate::utils::{ShapeCapsule, id};
+
+    #[test]
+    fn test_inst_hit_object() {
+        let db = TmplDatabase::new(10240, 150).unwrap();
+        let mut ctx = ContextAssemble::new(&db);
+        let arrow = InstHitObject::new(&mut ctx, id!("HitObject.Instance.Arrow")).unwrap();
+        assert_eq!(arrow.tmpl_id, id!("HitObject.Instance.Arrow"));
+        assert_eq!(arrow.group, "Arrow");
+        assert_eq!(arrow.shape, InstHitObjectShape::Capsule(ShapeCapsule::new(0.3, 0.05)));
+        assert_eq!(arrow.speed, 20.0);
+        assert_eq!(arrow.max_hits, 1);
+        assert!(arrow.destroy_on_scenery);
+
+        assert!(InstHitObject::new(&mut ctx, id!("Zone.Demo")).is_err());
+    }
+}
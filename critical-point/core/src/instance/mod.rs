@@ -3,7 +3,9 @@ mod ai_brain;
 mod ai_routine;
 mod ai_task;
 mod base;
+mod buff;
 mod character;
+mod hit_object;
 // mod script;
 mod values;
 mod zone;
@@ -13,6 +15,9 @@ pub use ai_brain::*;
 pub use ai_routine::*;
 pub use ai_task::*;
 pub use base::*;
+pub use buff::*;
 pub use character::*;
+pub use hit_object::*;
 // pub use script::*;
+pub use values::*;
 pub use zone::*;
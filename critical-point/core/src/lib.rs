@@ -29,6 +29,10 @@ pub mod input;
 #[cfg(not(feature = "for-turning-point"))]
 pub mod logic;
 #[cfg(not(feature = "for-turning-point"))]
+pub mod netcode;
+#[cfg(not(feature = "for-turning-point"))]
 pub mod save;
 #[cfg(not(feature = "for-turning-point"))]
 pub mod script;
+#[cfg(all(feature = "server-side", not(feature = "for-turning-point")))]
+pub mod server;
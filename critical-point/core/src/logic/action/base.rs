@@ -1,5 +1,6 @@
 use approx::abs_diff_eq;
 use critical_point_macros::{csharp_enum, csharp_out};
+use enum_iterator::Sequence;
 use glam::Vec3A;
 use glam_ext::Vec2xz;
 use std::alloc::Layout;
@@ -8,13 +9,15 @@ use std::fmt::Debug;
 use std::hint::unlikely;
 use std::rc::Rc;
 
-use crate::consts::{INVALID_ACTION_ID, MAX_ACTION_ANIMATION, SPF};
-use crate::instance::{InstActionAny, InstAnimation, InstCharacter};
+use crate::consts::{INVALID_ACTION_ID, LOOK_AT_BLEND_TIME, MAX_ACTION_ANIMATION};
+use crate::instance::{InstActionAny, InstAdditiveAnimation, InstAnimation, InstCharacter};
+use crate::logic::action::motion_warp::find_action_target;
 use crate::logic::ai_task::AiBrainThinking;
 use crate::logic::character::LogicCharaPhysics;
 use crate::logic::game::ContextUpdateEx;
 use crate::utils::{
-    ActionType, ArrayVec, CustomEvent, NumID, Symbol, TmplID, VirtualKey, XResult, interface, rkyv_self, xres,
+    ActionType, ArrayVec, Castable, CustomEvent, NumID, Symbol, TmplID, VirtualKey, XResult, interface, rkyv_self,
+    xres,
 };
 
 //
@@ -24,6 +27,7 @@ use crate::utils::{
 const SAA_FLAG_WEAPON_MOTION: u8 = 0x1;
 const SAA_FLAG_HIT_MOTION: u8 = 0x2;
 const SAA_FLAG_SHAPE_KEY: u8 = 0x4;
+const SAA_FLAG_NOTIFY: u8 = 0x8;
 
 #[repr(C)]
 #[csharp_out(Value, Partial)]
@@ -45,6 +49,7 @@ pub struct StateActionAnimation {
     pub flags: u8,
     pub ratio: f32,
     pub weight: f32,
+    pub joint_mask: Symbol, // Empty for full-body animations
 }
 
 impl Default for StateActionAnimation {
@@ -56,6 +61,7 @@ impl Default for StateActionAnimation {
             flags: 0,
             ratio: 0.0,
             weight: 1.0,
+            joint_mask: Symbol::default(),
         }
     }
 }
@@ -87,6 +93,7 @@ impl StateActionAnimation {
             flags,
             ratio,
             weight,
+            joint_mask: Symbol::default(),
         }
     }
 
@@ -98,6 +105,7 @@ impl StateActionAnimation {
             flags: 0,
             ratio,
             weight,
+            joint_mask: Symbol::default(),
         }
     }
 
@@ -113,12 +121,16 @@ impl StateActionAnimation {
         if inst.shape_key {
             flags |= SAA_FLAG_SHAPE_KEY;
         }
+        if inst.notify {
+            flags |= SAA_FLAG_NOTIFY;
+        }
         StateActionAnimation {
             files: inst.files,
             animation_id: inst.local_id,
             flags,
             ratio,
             weight,
+            joint_mask: inst.joint_mask,
         }
     }
 
@@ -171,6 +183,107 @@ impl StateActionAnimation {
             self.flags &= !SAA_FLAG_SHAPE_KEY;
         }
     }
+
+    #[inline]
+    pub fn notify(&self) -> bool {
+        self.flags & SAA_FLAG_NOTIFY != 0
+    }
+
+    #[inline]
+    pub fn set_notify(&mut self, enabled: bool) {
+        if enabled {
+            self.flags |= SAA_FLAG_NOTIFY;
+        }
+        else {
+            self.flags &= !SAA_FLAG_NOTIFY;
+        }
+    }
+}
+
+//
+// StateAdditiveAnimation
+//
+
+/// An additive animation playing over the actions of a character.
+#[repr(C)]
+#[csharp_out(Value)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct StateAdditiveAnimation {
+    pub files: Symbol,
+    pub action_id: u32, // The action playing the animation
+    pub start_frame: u32,
+    pub start_time: f32, // In game time
+    pub duration: f32,
+    pub weight: f32,
+    pub looping: bool,
+}
+
+impl StateAdditiveAnimation {
+    #[inline]
+    pub fn new(
+        inst: &InstAdditiveAnimation,
+        action_id: u32,
+        start_frame: u32,
+        start_time: f32,
+    ) -> StateAdditiveAnimation {
+        StateAdditiveAnimation {
+            files: inst.files,
+            action_id,
+            start_frame,
+            start_time,
+            duration: inst.duration,
+            weight: inst.weight,
+            looping: inst.looping,
+        }
+    }
+
+    #[inline]
+    pub fn elapsed_time(&self, time: f32) -> f32 {
+        (time - self.start_time).max(0.0)
+    }
+
+    /// Looping animations never finish by time, they end with the action playing them.
+    #[inline]
+    pub fn is_finished(&self, time: f32) -> bool {
+        !self.looping && self.elapsed_time(time) >= self.duration
+    }
+
+    /// The weight decays linearly to 0 in the duration, or keeps constant for looping animations.
+    #[inline]
+    pub fn current_weight(&self, time: f32) -> f32 {
+        if self.looping {
+            return self.weight;
+        }
+        match self.duration > 0.0 {
+            true => self.weight * (1.0 - self.elapsed_time(time) / self.duration).max(0.0),
+            false => 0.0,
+        }
+    }
+
+    /// The sampling ratio of an animation lasting `anim_duration` seconds.
+    #[inline]
+    pub fn ratio(&self, time: f32, anim_duration: f32) -> f32 {
+        if anim_duration <= 0.0 {
+            return 0.0;
+        }
+        let ratio = self.elapsed_time(time) / anim_duration;
+        match self.looping {
+            true => ratio.fract(),
+            false => ratio.min(1.0),
+        }
+    }
 }
 
 //
@@ -198,6 +311,8 @@ pub struct StateActionBase {
     pub fade_in_weight: f32,
     pub keep_level: u16,
     pub poise_level: u16,
+    pub look_at: Vec3A,      // Look-at target in world space
+    pub look_at_weight: f32, // 0 if the action doesn't look at anything
     pub animations: ArrayVec<StateActionAnimation, MAX_ACTION_ANIMATION>,
 }
 
@@ -223,6 +338,8 @@ impl StateActionBase {
             fade_in_weight: 1.0,
             keep_level: 0,
             poise_level: 0,
+            look_at: Vec3A::ZERO,
+            look_at_weight: 0.0,
             animations: Default::default(),
         }
     }
@@ -258,6 +375,43 @@ pub trait ArchivedStateActionAny: Debug + Any {
     fn typ(&self) -> ActionType;
 }
 
+const MAX_POOLED_ACTION_STATES: usize = 256;
+
+/// Recycled action states, grouped by ActionType.
+/// An action state is plain data, the box is overwritten by the next state of the same type.
+#[derive(Debug, Default)]
+pub struct StateActionPool {
+    states: Vec<Vec<Box<dyn StateActionAny>>>,
+    pub(crate) allocs: u64,
+    pub(crate) reuses: u64,
+}
+
+impl StateActionPool {
+    pub(crate) fn put(&mut self, state: Box<dyn StateActionAny>) {
+        let idx = u16::from(state.typ()) as usize;
+        if self.states.len() <= idx {
+            self.states.resize_with(idx + 1, Vec::new);
+        }
+        if self.states[idx].len() < MAX_POOLED_ACTION_STATES {
+            self.states[idx].push(state);
+        }
+    }
+
+    /// Moves the state into a recycled box of the same type, or allocates a new box.
+    pub fn boxed<T: StateActionAny + 'static>(&mut self, state: T) -> Box<T> {
+        let idx = u16::from(state.typ()) as usize;
+        if let Some(recycled) = self.states.get_mut(idx).and_then(|states| states.pop())
+            && let Ok(mut recycled) = recycled.cast::<T>()
+        {
+            self.reuses += 1;
+            *recycled = state;
+            return recycled;
+        }
+        self.allocs += 1;
+        Box::new(state)
+    }
+}
+
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, rkyv::Portable)]
 pub struct StateActionAnyMetadata(rkyv::primitive::ArchivedU16);
@@ -517,7 +671,15 @@ pub(crate) use impl_state_action;
 
 pub unsafe trait LogicActionAny: Debug + Any {
     fn typ(&self) -> ActionType;
-    fn save(&self) -> Box<dyn StateActionAny>;
+
+    /// Saves the state, reusing a recycled box from the pool.
+    fn save_pooled(&self, pool: &mut StateActionPool) -> Box<dyn StateActionAny>;
+
+    #[inline]
+    fn save(&self) -> Box<dyn StateActionAny> {
+        self.save_pooled(&mut StateActionPool::default())
+    }
+
     fn restore(&mut self, state: &(dyn StateActionAny + 'static)) -> XResult<()>;
 
     fn start(
@@ -565,7 +727,10 @@ pub struct ContextAction<'a> {
     pub(crate) inst_chara: Rc<InstCharacter>,
     pub(crate) chara_phy: &'a LogicCharaPhysics,
     pub(crate) ai_thinking: Option<&'a AiBrainThinking>,
+    /// The target character of the character control, possible NumID::INVALID.
+    pub(crate) target_chara: NumID,
 
+    pub(crate) spf: f32,
     pub(crate) time_speed: f32,
     pub(crate) time_step: f32,
     pub(crate) frac_1_time_step: f32,
@@ -577,16 +742,20 @@ impl<'a> ContextAction<'a> {
         inst_chara: Rc<InstCharacter>,
         chara_phy: &'a LogicCharaPhysics,
         ai_thinking: Option<&'a AiBrainThinking>,
+        target_chara: NumID,
+        spf: f32,
     ) -> ContextAction<'a> {
         ContextAction {
             chara_id,
             inst_chara,
             chara_phy,
             ai_thinking,
+            target_chara,
 
+            spf,
             time_speed: 1.0,
-            time_step: SPF,
-            frac_1_time_step: 1.0 / SPF,
+            time_step: spf,
+            frac_1_time_step: 1.0 / spf,
         }
     }
 
@@ -598,7 +767,7 @@ impl<'a> ContextAction<'a> {
         }
         else {
             self.time_speed = time_speed;
-            self.time_step = SPF * time_speed;
+            self.time_step = self.spf * time_speed;
             self.frac_1_time_step = 1.0 / self.time_step;
         }
     }
@@ -645,6 +814,8 @@ pub struct ActionStartReturn {
     pub prev_fade_update: bool,
     pub clear_preinput: bool,
     pub custom_events: Vec<CustomEvent>,
+    pub hit_objects: Vec<TmplID>,
+    pub additive_animations: Vec<InstAdditiveAnimation>,
 }
 
 impl ActionStartReturn {
@@ -661,6 +832,8 @@ pub struct ActionUpdateReturn {
     pub clear_preinput: bool,
     pub derive_keeping: DeriveKeeping,
     pub custom_events: Vec<CustomEvent>,
+    pub hit_objects: Vec<TmplID>,
+    pub additive_animations: Vec<InstAdditiveAnimation>,
 }
 
 impl ActionUpdateReturn {
@@ -687,7 +860,7 @@ impl ActionUpdateReturn {
 
 #[repr(u8)]
 #[csharp_enum]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum LogicActionStatus {
     Starting,
     Running,
@@ -696,7 +869,7 @@ pub enum LogicActionStatus {
     Finalized,
 }
 
-rkyv_self!(LogicActionStatus);
+rkyv_self!(LogicActionStatus: Sequence);
 
 const LA_FLAG_DERIVE_SELF: u8 = 0x1;
 
@@ -711,6 +884,8 @@ pub struct LogicActionBase {
     pub fade_in_weight: f32,
     pub keep_level: u16,
     pub poise_level: u16,
+    pub look_at: Vec3A,
+    pub look_at_weight: f32,
 }
 
 interface!(LogicActionAny, LogicActionBase);
@@ -727,6 +902,8 @@ impl LogicActionBase {
             fade_in_weight: 0.0,
             keep_level: 0,
             poise_level: 0,
+            look_at: Vec3A::ZERO,
+            look_at_weight: 0.0,
         }
     }
 
@@ -762,6 +939,8 @@ impl LogicActionBase {
             fade_in_weight: self.fade_in_weight,
             keep_level: self.keep_level,
             poise_level: self.poise_level,
+            look_at: self.look_at,
+            look_at_weight: self.look_at_weight,
             animations: Default::default(),
         }
     }
@@ -773,9 +952,11 @@ impl LogicActionBase {
         self.fade_in_weight = state.fade_in_weight;
         self.keep_level = state.keep_level;
         self.poise_level = state.poise_level;
+        self.look_at = state.look_at;
+        self.look_at_weight = state.look_at_weight;
     }
 
-    pub fn start(&mut self, ctx: &ContextUpdateEx, _ctxa: &mut ContextAction, args: &ActionStartArgs) -> XResult<()> {
+    pub fn start(&mut self, ctx: &ContextUpdateEx, ctxa: &mut ContextAction, args: &ActionStartArgs) -> XResult<()> {
         if unlikely(self.status != LogicActionStatus::Starting) {
             return xres!(Unexpected; "status != Starting");
         }
@@ -785,16 +966,34 @@ impl LogicActionBase {
         if args.prev_action.is_none() {
             self.fade_in_weight = 1.0;
         }
+        self.update_look_at(ctx, ctxa);
         Ok(())
     }
 
-    pub fn update(&mut self, _ctx: &ContextUpdateEx, _ctxa: &mut ContextAction) -> XResult<()> {
+    pub fn update(&mut self, ctx: &ContextUpdateEx, ctxa: &mut ContextAction) -> XResult<()> {
         if unlikely(self.status != LogicActionStatus::Running) {
             return xres!(Unexpected; "status != Running");
         }
+        self.update_look_at(ctx, ctxa);
         Ok(())
     }
 
+    /// Follows the look-at target of the action. Keeps the last target while the target is lost,
+    /// so that the spine and head turn back smoothly.
+    fn update_look_at(&mut self, ctx: &ContextUpdateEx, ctxa: &ContextAction) {
+        let Some(look_at) = &self.inst.look_at
+        else {
+            return;
+        };
+        match find_action_target(ctx, ctxa, look_at.target, look_at.search_radius) {
+            Some(pos) => {
+                self.look_at = pos + Vec3A::new(0.0, look_at.height, 0.0);
+                self.look_at_weight = (self.look_at_weight + ctxa.time_step / LOOK_AT_BLEND_TIME).min(1.0);
+            }
+            None => self.look_at_weight = (self.look_at_weight - ctxa.time_step / LOOK_AT_BLEND_TIME).max(0.0),
+        }
+    }
+
     pub fn fade_start(&mut self, _ctx: &ContextUpdateEx, _ctxa: &mut ContextAction) -> XResult<()> {
         if unlikely(self.status != LogicActionStatus::Running) {
             return xres!(Unexpected; "status != Running");
@@ -944,4 +1143,4 @@ impl DeriveKeeping {
     }
 }
 
-rkyv_self!(DeriveKeeping);
+rkyv_self!(DeriveKeeping { action_id });
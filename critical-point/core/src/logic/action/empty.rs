@@ -4,7 +4,7 @@ use std::rc::Rc;
 
 use crate::instance::InstActionEmpty;
 use crate::logic::action::base::{
-    ActionUpdateReturn, ContextAction, LogicActionAny, LogicActionBase, StateActionAny, StateActionBase,
+    ActionUpdateReturn, ContextAction, LogicActionAny, LogicActionBase, StateActionAny, StateActionBase, StateActionPool,
     impl_state_action,
 };
 use crate::logic::game::ContextUpdateEx;
@@ -57,8 +57,8 @@ unsafe impl LogicActionAny for LogicActionEmpty {
         ActionType::Empty
     }
 
-    fn save(&self) -> Box<dyn StateActionAny> {
-        Box::new(StateActionEmpty {
+    fn save_pooled(&self, pool: &mut StateActionPool) -> Box<dyn StateActionAny> {
+        pool.boxed(StateActionEmpty {
             _base: self._base.save(self.typ()),
         })
     }
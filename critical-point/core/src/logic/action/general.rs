@@ -9,8 +9,9 @@ use crate::input::{InputMoveSpeed, RefInputEventQueue};
 use crate::instance::{InstActionGeneral, InstActionGeneralMovement};
 use crate::logic::action::base::{
     ActionStartReturn, ActionUpdateReturn, ContextAction, LogicActionAny, LogicActionBase, StateActionAnimation,
-    StateActionAny, StateActionBase, impl_state_action,
+    StateActionAny, StateActionBase, StateActionPool, impl_state_action,
 };
+use crate::logic::action::motion_warp::{StateMotionWarp, find_motion_warp_target};
 use crate::logic::action::root_motion::{LogicRootMotion, StateRootMotion};
 use crate::logic::action::{ActionStartArgs, DeriveKeeping};
 use crate::logic::game::ContextUpdateEx;
@@ -32,6 +33,7 @@ pub struct StateActionGeneral {
     pub current_rotation: f32,
     pub rotation_time: TimeRange,
     pub root_motion: StateRootMotion,
+    pub motion_warp: StateMotionWarp,
 }
 
 extend!(StateActionGeneral, StateActionBase);
@@ -49,6 +51,7 @@ pub(crate) struct LogicActionGeneral {
     current_rotation: f32,
     rotation_time: TimeRange,
     root_motion: LogicRootMotion,
+    motion_warp: StateMotionWarp,
 }
 
 extend!(LogicActionGeneral, LogicActionBase);
@@ -72,6 +75,7 @@ impl LogicActionGeneral {
             to_rotation: 0.0,
             rotation_time: TimeRange::EMPTY,
             root_motion: LogicRootMotion::new(ctx, &inst_act.anim_main, 0.0)?,
+            motion_warp: StateMotionWarp::NONE,
         })
     }
 }
@@ -95,6 +99,7 @@ unsafe impl LogicActionAny for LogicActionGeneral {
         self.current_rotation = state.current_rotation;
         self.rotation_time = state.rotation_time;
         self.root_motion.restore(&state.root_motion);
+        self.motion_warp = state.motion_warp;
         Ok(())
     }
 
@@ -115,11 +120,13 @@ unsafe impl LogicActionAny for LogicActionGeneral {
         self.to_rotation = 0.0;
         self.current_rotation = ctxa.chara_phy.direction_xz().to_angle();
         self.rotation_time = TimeRange::EMPTY;
+        self.motion_warp = StateMotionWarp::NONE;
 
         let mut ret = ActionStartReturn::new();
         if self.handle_input_movement(ctxa, f32::NEG_INFINITY)? {
             ret.clear_preinput = true;
         }
+        self.handle_motion_warps(ctx, ctxa, f32::NEG_INFINITY)?;
 
         ret.custom_events = self
             .inst
@@ -127,6 +134,20 @@ unsafe impl LogicActionAny for LogicActionGeneral {
             .find_values((f32::NEG_INFINITY, self.current_time).into())
             .map(|ev| CustomEvent::new(self.inst.tmpl_id, *ev))
             .collect();
+        ret.hit_objects = self
+            .inst
+            .hit_objects
+            .find_values((f32::NEG_INFINITY, self.current_time).into())
+            .copied()
+            .collect();
+        ret.additive_animations = self
+            .inst
+            .additive_animations
+            .find_values((f32::NEG_INFINITY, self.current_time).into())
+            .copied()
+            .collect();
+        // A partial-body action plays over the previous action, which keeps updating (e.g. running legs).
+        ret.prev_fade_update = !self.inst.anim_main.joint_mask.is_empty();
         Ok(ret)
     }
 
@@ -151,12 +172,19 @@ unsafe impl LogicActionAny for LogicActionGeneral {
 
         let clear_preinput = self.handle_input_movement(ctxa, prev_time)?;
         self.update_rotation();
-        let direction = Vec2xz::from_angle(self.current_rotation);
-        let rotation = quat_from_dir_xz(direction);
+        self.handle_motion_warps(ctx, ctxa, prev_time)?;
 
         self.root_motion
             .update(self.inst.anim_main.ratio_saturating(self.current_time))?;
-        let delta_pos = self.root_motion.position_delta();
+        self.apply_root_rotation();
+        let warp_scale = self.apply_motion_warp(prev_time);
+        let direction = Vec2xz::from_angle(self.current_rotation);
+        let rotation = quat_from_dir_xz(direction);
+
+        // Position deltas are in the space of the first animation frame.
+        let mut delta_pos = self.root_motion.rotation().inverse() * self.root_motion.position_delta();
+        delta_pos.x *= warp_scale;
+        delta_pos.z *= warp_scale;
         let velocity = rotation * delta_pos * ctxa.frac_1_time_step;
         // velocity = rotation * (Vec3A::new(delta_pos.x, 0.0, delta_pos.z) / ctxa.time_step);
         // if abs_diff_ne!(delta_pos.y, 0.0) {
@@ -194,12 +222,24 @@ unsafe impl LogicActionAny for LogicActionGeneral {
             .find_values((prev_time, self.current_time).into())
             .map(|ev| CustomEvent::new(self.inst.tmpl_id, *ev))
             .collect();
+        ret.hit_objects = self
+            .inst
+            .hit_objects
+            .find_values((prev_time, self.current_time).into())
+            .copied()
+            .collect();
+        ret.additive_animations = self
+            .inst
+            .additive_animations
+            .find_values((prev_time, self.current_time).into())
+            .copied()
+            .collect();
         ret.clear_preinput = clear_preinput;
         Ok(ret)
     }
 
-    fn save(&self) -> Box<dyn StateActionAny> {
-        let mut state = Box::new(StateActionGeneral {
+    fn save_pooled(&self, pool: &mut StateActionPool) -> Box<dyn StateActionAny> {
+        let mut state = pool.boxed(StateActionGeneral {
             _base: self._base.save(self.typ()),
             current_time: self.current_time,
             current_rotation: self.current_rotation,
@@ -207,6 +247,7 @@ unsafe impl LogicActionAny for LogicActionGeneral {
             to_rotation: self.to_rotation,
             rotation_time: self.rotation_time,
             root_motion: self.root_motion.save(),
+            motion_warp: self.motion_warp,
         });
 
         let ratio = self.inst.anim_main.ratio_saturating(self.current_time);
@@ -281,6 +322,55 @@ impl LogicActionGeneral {
         Ok(clear_preinput)
     }
 
+    /// Plans the motion warps beginning in `(prev_time, current_time]`, a later warp replaces the running one.
+    /// Must be called before the root motion update, the warp window begins at `prev_time`.
+    fn handle_motion_warps(&mut self, ctx: &ContextUpdateEx, ctxa: &ContextAction, prev_time: f32) -> XResult<()> {
+        let begin_time = prev_time.max(0.0);
+        for warp in self
+            .inst
+            .motion_warps
+            .find_values((prev_time, self.current_time).into())
+        {
+            let target_pos = ok_or!(find_motion_warp_target(ctx, ctxa, warp); continue);
+            let direction = Vec2xz::from_angle(self.current_rotation);
+            let end_ratio = self.inst.anim_main.ratio_saturating(begin_time + warp.duration);
+            let displacement = quat_from_dir_xz(direction) * self.root_motion.displacement_to(end_ratio)?;
+            self.motion_warp = StateMotionWarp::plan(
+                warp,
+                begin_time,
+                ctxa.chara_phy.position(),
+                direction,
+                target_pos,
+                displacement,
+            );
+        }
+        Ok(())
+    }
+
+    /// Turns the character with the root rotation, weighted by the fade in weight.
+    fn apply_root_rotation(&mut self) {
+        if !self.root_motion.has_rotation() {
+            return;
+        }
+        self.turn(self.root_motion.yaw_delta() * self.fade_in_weight);
+    }
+
+    /// Turns the character with the running motion warp, returns the displacement scale.
+    fn apply_motion_warp(&mut self, prev_time: f32) -> f32 {
+        let (scale, yaw) = self.motion_warp.step(prev_time, self.current_time);
+        self.turn(yaw);
+        scale
+    }
+
+    /// The rotation in progress (from input) is turned together, so that the two are added.
+    fn turn(&mut self, yaw: f32) {
+        self.current_rotation += yaw;
+        if !self.rotation_time.is_empty() {
+            self.from_rotation += yaw;
+            self.to_rotation += yaw;
+        }
+    }
+
     fn update_rotation(&mut self) {
         if self.rotation_time.is_empty() {
             return;
@@ -306,13 +396,16 @@ impl LogicActionGeneral {
 
 #[cfg(test)]
 mod tests {
-    use approx::assert_ulps_eq;
+    use approx::{assert_abs_diff_eq, assert_relative_eq, assert_ulps_eq};
+    use glam::Vec3A;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_4};
 
     use super::*;
     use crate::logic::action::base::LogicActionStatus;
+    use crate::logic::ai_task::AiBrainThinking;
     use crate::logic::action::test_utils::*;
     use crate::utils::tests::FrameTicker;
-    use crate::utils::{LEVEL_ACTION, LEVEL_ATTACK, id, ratio_saturating, s2f, sb};
+    use crate::utils::{LEVEL_ACTION, LEVEL_ATTACK, NumID, id, ratio_saturating, sb};
 
     #[test]
     fn test_state_rkyv() {
@@ -320,6 +413,7 @@ mod tests {
             _base: StateActionBase::new(ActionType::General),
             current_time: 4.0,
             root_motion: StateRootMotion::default(),
+            motion_warp: StateMotionWarp::NONE,
             current_rotation: 0.0,
             from_rotation: 0.5,
             to_rotation: 0.5,
@@ -388,7 +482,7 @@ mod tests {
         let (mut ctx, mut ctxa, sargs) = tenv.contexts(true);
 
         logic_gen.start(&mut ctx, &mut ctxa, &sargs).unwrap();
-        for ft in FrameTicker::new(1..s2f(4.0) + 1) {
+        for ft in FrameTicker::new(1..ctx.time.s2f(4.0) + 1) {
             ctx.time_mut().time = ft.time;
             let ret = logic_gen.update(&mut ctx, &mut ctxa).unwrap();
             if !ft.last {
@@ -442,6 +536,7 @@ mod tests {
             current_rotation: 0.15,
             rotation_time: TimeRange::new(1.0, 2.0),
             root_motion: StateRootMotion::default(),
+            motion_warp: StateMotionWarp::NONE,
         };
 
         logic_gen.restore(&state).unwrap();
@@ -452,4 +547,128 @@ mod tests {
         assert_eq!(logic_gen.current_rotation, 0.15);
         assert_eq!(logic_gen.rotation_time, TimeRange::new(1.0, 2.0));
     }
+
+    #[test]
+    fn test_logic_general_root_rotation() {
+        let mut tenv = TestEnv::new().unwrap();
+        // Girl/Attack_03A turns 90 degrees in the first second of its root rotation track.
+        let inst_gen: Rc<InstActionGeneral> = tenv.assemble_action(id!("Action.Instance.AttackDerive^1A"), &[(
+            id!("#.Action.Instance.AttackDerive^1A"),
+            2,
+        )]);
+        let mut logic_gen = LogicActionGeneral::new(&mut tenv.context_update(), inst_gen.clone()).unwrap();
+        let (mut ctx, mut ctxa, sargs) = tenv.contexts(false);
+
+        logic_gen.start(&mut ctx, &mut ctxa, &sargs).unwrap();
+        let start_rotation = logic_gen.current_rotation;
+        let mut rotations = Vec::new();
+        let mut saved = None;
+        for ft in FrameTicker::new(1..ctx.time.s2f(1.5) + 1) {
+            ctx.time_mut().time = ft.time;
+            let prev_rotation = logic_gen.current_rotation;
+            logic_gen.update(&mut ctx, &mut ctxa).unwrap();
+            assert!(logic_gen.root_motion.has_rotation());
+            assert_abs_diff_eq!(
+                logic_gen.current_rotation,
+                prev_rotation + logic_gen.root_motion.yaw_delta() * logic_gen.fade_in_weight,
+                epsilon = 1e-5
+            );
+            rotations.push(logic_gen.current_rotation);
+            if ft.frame == 10 {
+                saved = Some(logic_gen.save());
+            }
+        }
+        let turned = logic_gen.current_rotation - start_rotation;
+        assert!(turned.abs() > FRAC_PI_4 && turned.abs() < FRAC_PI_2 + 1e-4);
+
+        logic_gen.restore(saved.as_deref().unwrap()).unwrap();
+        for ft in FrameTicker::new(11..ctx.time.s2f(1.5) + 1) {
+            ctx.time_mut().time = ft.time;
+            logic_gen.update(&mut ctx, &mut ctxa).unwrap();
+            assert_eq!(logic_gen.current_rotation, rotations[ft.frame as usize - 1]);
+        }
+    }
+
+    #[test]
+    fn test_logic_general_motion_warp() {
+        let mut tenv = TestEnv::new().unwrap();
+        let inst_gen: Rc<InstActionGeneral> = tenv.assemble_action(id!("Action.Instance.AttackDerive^1A"), &[(
+            id!("#.Action.Instance.AttackDerive^1A"),
+            2,
+        )]);
+        let warp = inst_gen.motion_warps.find_iter((0.0, 1.0).into()).next().unwrap();
+        let (warp_time, warp) = (warp.time, warp.value);
+        let mut logic_warp = LogicActionGeneral::new(&mut tenv.context_update(), inst_gen.clone()).unwrap();
+        let mut logic_plain = LogicActionGeneral::new(&mut tenv.context_update(), inst_gen.clone()).unwrap();
+
+        let thinking = AiBrainThinking {
+            target_chara: NumID::MIN_AUTO_GEN,
+            target_chara_pos: Vec3A::new(3.0, 0.0, 3.0),
+            ..Default::default()
+        };
+        let (mut ctx, mut ctxa, sargs) = tenv.contexts(false);
+        ctxa.ai_thinking = Some(&thinking);
+        logic_warp.start(&mut ctx, &mut ctxa, &sargs).unwrap();
+        ctxa.ai_thinking = None;
+        logic_plain.start(&mut ctx, &mut ctxa, &sargs).unwrap();
+
+        let mut planned = StateMotionWarp::NONE;
+        let mut warp_angle = 0.0;
+        let mut rotations = Vec::new();
+        let mut saved = None;
+        let mut prev_time = 0.0;
+        for ft in FrameTicker::new(1..ctx.time.s2f(2.0) + 1) {
+            ctx.time_mut().time = ft.time;
+            let prev_rotation = logic_warp.current_rotation;
+            ctxa.ai_thinking = Some(&thinking);
+            let ret_warp = logic_warp.update(&mut ctx, &mut ctxa).unwrap();
+            ctxa.ai_thinking = None;
+            let ret_plain = logic_plain.update(&mut ctx, &mut ctxa).unwrap();
+            rotations.push(logic_warp.current_rotation);
+
+            if !planned.is_running() && logic_warp.motion_warp.is_running() {
+                planned = logic_warp.motion_warp;
+                assert_abs_diff_eq!(planned.time.duration(), warp.duration, epsilon = 1e-5);
+                assert!(planned.scale >= warp.min_scale && planned.scale <= warp.max_scale);
+                assert!(planned.angle != 0.0 && planned.angle.abs() <= FRAC_PI_3 + 1e-5);
+            }
+            if ft.time <= warp_time {
+                assert!(!planned.is_running());
+            }
+            assert!(!logic_plain.motion_warp.is_running());
+
+            // Besides the root rotation, the warp turns the character.
+            let root_yaw = logic_warp.root_motion.yaw_delta() * logic_warp.fade_in_weight;
+            warp_angle += logic_warp.current_rotation - prev_rotation - root_yaw;
+
+            // The warp scales the horizontal root displacement.
+            let len_warp = Vec2xz::from_vec3a(ret_warp.new_velocity.unwrap()).length();
+            let len_plain = Vec2xz::from_vec3a(ret_plain.new_velocity.unwrap()).length();
+            if planned.is_running() && ft.time > planned.time.begin && prev_time < planned.time.end {
+                assert_relative_eq!(len_warp, len_plain * planned.scale, max_relative = 1e-3);
+            }
+            else {
+                assert_relative_eq!(len_warp, len_plain, max_relative = 1e-3);
+            }
+
+            if ft.time > warp_time && saved.is_none() {
+                saved = Some((ft.frame, logic_warp.save()));
+            }
+            prev_time = ft.time;
+        }
+        assert!(planned.is_running());
+        assert!(!logic_warp.motion_warp.is_running());
+        assert_abs_diff_eq!(warp_angle, planned.angle, epsilon = 1e-4);
+
+        // The running warp is restored and replayed.
+        let (saved_frame, saved_state) = saved.unwrap();
+        logic_warp.restore(saved_state.as_ref()).unwrap();
+        assert_eq!(logic_warp.motion_warp, planned);
+        ctxa.ai_thinking = Some(&thinking);
+        for ft in FrameTicker::new(saved_frame + 1..ctx.time.s2f(2.0) + 1) {
+            ctx.time_mut().time = ft.time;
+            logic_warp.update(&mut ctx, &mut ctxa).unwrap();
+            assert_eq!(logic_warp.current_rotation, rotations[ft.frame as usize - 1]);
+        }
+    }
 }
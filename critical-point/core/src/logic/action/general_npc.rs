@@ -8,8 +8,9 @@ use crate::instance::{InstActionGeneralNpc, InstActionGeneralNpcMovement};
 use crate::logic::action::ActionStartArgs;
 use crate::logic::action::base::{
     ActionStartReturn, ActionUpdateReturn, ContextAction, LogicActionAny, LogicActionBase, StateActionAnimation,
-    StateActionAny, StateActionBase, impl_state_action,
+    StateActionAny, StateActionBase, StateActionPool, impl_state_action,
 };
+use crate::logic::action::motion_warp::{StateMotionWarp, find_motion_warp_target};
 use crate::logic::action::root_motion::{LogicRootMotion, StateRootMotion};
 use crate::logic::game::ContextUpdateEx;
 use crate::utils::{
@@ -34,6 +35,7 @@ pub struct StateActionGeneralNpc {
     pub translation_time: TimeRange,
 
     pub root_motion: StateRootMotion,
+    pub motion_warp: StateMotionWarp,
 }
 
 extend!(StateActionGeneralNpc, StateActionBase);
@@ -56,6 +58,7 @@ pub(crate) struct LogicActionGeneralNpc {
     translation_time: TimeRange,
 
     root_motion: LogicRootMotion,
+    motion_warp: StateMotionWarp,
 }
 
 extend!(LogicActionGeneralNpc, LogicActionBase);
@@ -84,6 +87,7 @@ impl LogicActionGeneralNpc {
             translation_time: TimeRange::EMPTY,
 
             root_motion: LogicRootMotion::new(ctx, &inst_act.anim_main, 0.0)?,
+            motion_warp: StateMotionWarp::NONE,
         })
     }
 }
@@ -113,6 +117,7 @@ unsafe impl LogicActionAny for LogicActionGeneralNpc {
         self.translation_time = state.translation_time;
 
         self.root_motion.restore(&state.root_motion);
+        self.motion_warp = state.motion_warp;
         Ok(())
     }
 
@@ -130,6 +135,7 @@ unsafe impl LogicActionAny for LogicActionGeneralNpc {
         self.to_rotation = 0.0;
         self.current_rotation = ctxa.chara_phy.direction_xz().to_angle();
         self.rotation_time = TimeRange::EMPTY;
+        self.motion_warp = StateMotionWarp::NONE;
 
         self.translation_speed_ratio = 1.0;
         self.translation_fade_ratio = 0.0;
@@ -137,6 +143,7 @@ unsafe impl LogicActionAny for LogicActionGeneralNpc {
 
         let mut ret = ActionStartReturn::new();
         self.handle_ai_movement(ctx, ctxa, f32::NEG_INFINITY)?;
+        self.handle_motion_warps(ctx, ctxa, f32::NEG_INFINITY)?;
 
         ret.custom_events = self
             .inst
@@ -144,6 +151,12 @@ unsafe impl LogicActionAny for LogicActionGeneralNpc {
             .find_values((f32::NEG_INFINITY, self.current_time).into())
             .map(|ev| CustomEvent::new(self.inst.tmpl_id, *ev))
             .collect();
+        ret.additive_animations = self
+            .inst
+            .additive_animations
+            .find_values((f32::NEG_INFINITY, self.current_time).into())
+            .copied()
+            .collect();
         Ok(ret)
     }
 
@@ -163,18 +176,22 @@ unsafe impl LogicActionAny for LogicActionGeneralNpc {
 
         self.handle_ai_movement(ctx, ctxa, prev_time)?;
         self.update_rotation();
-
-        let direction = Vec2xz::from_angle(self.current_rotation);
-        let rotation = quat_from_dir_xz(direction);
+        self.handle_motion_warps(ctx, ctxa, prev_time)?;
 
         self.root_motion
             .update(self.inst.anim_main.ratio_saturating(self.current_time))?;
+        self.apply_root_rotation();
+        let warp_scale = self.apply_motion_warp(prev_time);
+
+        let direction = Vec2xz::from_angle(self.current_rotation);
+        let rotation = quat_from_dir_xz(direction);
 
-        let mut delta_pos = self.root_motion.position_delta();
+        // Position deltas are in the space of the first animation frame.
+        let mut delta_pos = self.root_motion.rotation().inverse() * self.root_motion.position_delta();
         let real_speed_ratio = self.update_translation(ctxa);
 
-        delta_pos.x *= real_speed_ratio;
-        delta_pos.z *= real_speed_ratio;
+        delta_pos.x *= real_speed_ratio * warp_scale;
+        delta_pos.z *= real_speed_ratio * warp_scale;
 
         let velocity = rotation * delta_pos * ctxa.frac_1_time_step;
 
@@ -195,11 +212,17 @@ unsafe impl LogicActionAny for LogicActionGeneralNpc {
             .find_values((prev_time, self.current_time).into())
             .map(|ev| CustomEvent::new(self.inst.tmpl_id, *ev))
             .collect();
+        ret.additive_animations = self
+            .inst
+            .additive_animations
+            .find_values((prev_time, self.current_time).into())
+            .copied()
+            .collect();
         Ok(ret)
     }
 
-    fn save(&self) -> Box<dyn StateActionAny> {
-        let mut state = Box::new(StateActionGeneralNpc {
+    fn save_pooled(&self, pool: &mut StateActionPool) -> Box<dyn StateActionAny> {
+        let mut state = pool.boxed(StateActionGeneralNpc {
             _base: self._base.save(self.typ()),
             current_time: self.current_time,
 
@@ -213,6 +236,7 @@ unsafe impl LogicActionAny for LogicActionGeneralNpc {
             translation_time: self.translation_time,
 
             root_motion: self.root_motion.save(),
+            motion_warp: self.motion_warp,
         });
 
         let ratio = self.inst.anim_main.ratio_saturating(self.current_time);
@@ -291,6 +315,55 @@ impl LogicActionGeneralNpc {
         Ok(())
     }
 
+    /// Plans the motion warps beginning in `(prev_time, current_time]`, a later warp replaces the running one.
+    /// Must be called before the root motion update, the warp window begins at `prev_time`.
+    fn handle_motion_warps(&mut self, ctx: &ContextUpdateEx, ctxa: &ContextAction, prev_time: f32) -> XResult<()> {
+        let begin_time = prev_time.max(0.0);
+        for warp in self
+            .inst
+            .motion_warps
+            .find_values((prev_time, self.current_time).into())
+        {
+            let target_pos = ok_or!(find_motion_warp_target(ctx, ctxa, warp); continue);
+            let direction = Vec2xz::from_angle(self.current_rotation);
+            let end_ratio = self.inst.anim_main.ratio_saturating(begin_time + warp.duration);
+            let displacement = quat_from_dir_xz(direction) * self.root_motion.displacement_to(end_ratio)?;
+            self.motion_warp = StateMotionWarp::plan(
+                warp,
+                begin_time,
+                ctxa.chara_phy.position(),
+                direction,
+                target_pos,
+                displacement,
+            );
+        }
+        Ok(())
+    }
+
+    /// Turns the character with the root rotation, weighted by the fade in weight.
+    fn apply_root_rotation(&mut self) {
+        if !self.root_motion.has_rotation() {
+            return;
+        }
+        self.turn(self.root_motion.yaw_delta() * self.fade_in_weight);
+    }
+
+    /// Turns the character with the running motion warp, returns the displacement scale.
+    fn apply_motion_warp(&mut self, prev_time: f32) -> f32 {
+        let (scale, yaw) = self.motion_warp.step(prev_time, self.current_time);
+        self.turn(yaw);
+        scale
+    }
+
+    /// The rotation in progress (from AI) is turned together, so that the two are added.
+    fn turn(&mut self, yaw: f32) {
+        self.current_rotation += yaw;
+        if !self.rotation_time.is_empty() {
+            self.from_rotation += yaw;
+            self.to_rotation += yaw;
+        }
+    }
+
     fn update_rotation(&mut self) {
         if self.rotation_time.is_empty() {
             return;
@@ -333,3 +406,56 @@ impl LogicActionGeneralNpc {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::instance::InstActionGeneral;
+    use crate::logic::action::test_utils::*;
+    use crate::utils::tests::FrameTicker;
+    use crate::utils::id;
+
+    #[test]
+    fn test_logic_general_npc_root_rotation() {
+        let mut tenv = TestEnv::new().unwrap();
+        let inst_npc: Rc<InstActionGeneralNpc> = tenv.assemble_action(id!("Action.InstanceNpc.Attack^1A"), &[]);
+        let inst_turn: Rc<InstActionGeneral> = tenv.assemble_action(id!("Action.Instance.AttackDerive^1A"), &[(
+            id!("#.Action.Instance.AttackDerive^1A"),
+            2,
+        )]);
+        let mut logic_npc = LogicActionGeneralNpc::new(&mut tenv.context_update(), inst_npc).unwrap();
+        // Drives the NPC action with the turning root motion of Girl/Attack_03A.
+        logic_npc.root_motion = LogicRootMotion::new(&mut tenv.context_update(), &inst_turn.anim_main, 0.0).unwrap();
+        let (mut ctx, mut ctxa, sargs) = tenv.contexts(true);
+
+        logic_npc.start(&mut ctx, &mut ctxa, &sargs).unwrap();
+        let start_rotation = logic_npc.current_rotation;
+        let mut rotations = Vec::new();
+        let mut saved = None;
+        for ft in FrameTicker::new(1..ctx.time.s2f(2.0) + 1) {
+            ctx.time_mut().time = ft.time;
+            let prev_rotation = logic_npc.current_rotation;
+            logic_npc.update(&mut ctx, &mut ctxa).unwrap();
+            assert!(logic_npc.root_motion.has_rotation());
+            assert_abs_diff_eq!(
+                logic_npc.current_rotation,
+                prev_rotation + logic_npc.root_motion.yaw_delta() * logic_npc.fade_in_weight,
+                epsilon = 1e-5
+            );
+            rotations.push(logic_npc.current_rotation);
+            if ft.frame == 10 {
+                saved = Some(logic_npc.save());
+            }
+        }
+        assert!((logic_npc.current_rotation - start_rotation).abs() > 0.5);
+
+        logic_npc.restore(saved.as_deref().unwrap()).unwrap();
+        for ft in FrameTicker::new(11..ctx.time.s2f(2.0) + 1) {
+            ctx.time_mut().time = ft.time;
+            logic_npc.update(&mut ctx, &mut ctxa).unwrap();
+            assert_eq!(logic_npc.current_rotation, rotations[ft.frame as usize - 1]);
+        }
+    }
+}
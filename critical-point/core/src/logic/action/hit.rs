@@ -7,7 +7,7 @@ use std::rc::Rc;
 use crate::instance::InstActionHit;
 use crate::logic::action::base::{
     ActionStartArgs, ActionStartReturn, ActionUpdateReturn, ContextAction, LogicActionAny, LogicActionBase,
-    StateActionAnimation, StateActionAny, StateActionBase, impl_state_action,
+    StateActionAnimation, StateActionAny, StateActionBase, StateActionPool, impl_state_action,
 };
 use crate::logic::action::root_motion::{LogicMultiRootMotion, StateMultiRootMotion};
 use crate::logic::game::ContextUpdateEx;
@@ -218,8 +218,8 @@ unsafe impl LogicActionAny for LogicActionHit {
         Ok(ret)
     }
 
-    fn save(&self) -> Box<dyn StateActionAny> {
-        let mut state = Box::new(StateActionHit {
+    fn save_pooled(&self, pool: &mut StateActionPool) -> Box<dyn StateActionAny> {
+        let mut state = pool.boxed(StateActionHit {
             _base: self._base.save(self.typ()),
             mode: self.mode,
             be_hit_index0: self.be_hit_index0,
@@ -5,7 +5,7 @@ use std::rc::Rc;
 use crate::instance::InstActionIdle;
 use crate::logic::action::base::{
     ActionStartArgs, ActionStartReturn, ActionUpdateReturn, ContextAction, LogicActionAny, LogicActionBase,
-    StateActionAnimation, StateActionAny, StateActionBase, impl_state_action,
+    StateActionAnimation, StateActionAny, StateActionBase, StateActionPool, impl_state_action,
 };
 use crate::logic::game::ContextUpdateEx;
 use crate::utils::{ActionType, Castable, XResult, extend, loose_ge, ratio_saturating, ratio_warpping, xresf};
@@ -203,11 +203,11 @@ unsafe impl LogicActionAny for LogicActionIdle {
         Ok(ActionUpdateReturn::new())
     }
 
-    fn save(&self) -> Box<dyn StateActionAny> {
+    fn save_pooled(&self, pool: &mut StateActionPool) -> Box<dyn StateActionAny> {
         let anim_idle = &self.inst.anim_idle;
         let anim_ready = self.inst.anim_ready.as_ref().unwrap_or(&anim_idle);
 
-        let mut state = Box::new(StateActionIdle {
+        let mut state = pool.boxed(StateActionIdle {
             _base: self._base.save(self.typ()),
             mode: self.mode,
             idle_time: self.idle_time,
@@ -273,7 +273,7 @@ mod tests {
     use crate::logic::action::base::LogicActionStatus;
     use crate::logic::action::test_utils::*;
     use crate::utils::tests::FrameTicker;
-    use crate::utils::{id, s2f, sb};
+    use crate::utils::{id, sb};
     use approx::assert_ulps_eq;
 
     const ANIME_IDLE_ID: u16 = 0;
@@ -427,7 +427,7 @@ mod tests {
             assert!(logic_idle.is_running());
             assert_eq!(logic_idle.first_frame, TestEnv::FRAME);
             assert_eq!(logic_idle.last_frame, u32::MAX);
-            assert_eq!(logic_idle.fade_in_weight, 1.0 / s2f(0.4) as f32);
+            assert_eq!(logic_idle.fade_in_weight, 1.0 / ctx.time.s2f(0.4) as f32);
             assert_eq!(logic_idle.mode, ActionIdleMode::Ready);
             assert_eq!(logic_idle.idle_time, 0.0);
             assert_eq!(logic_idle.ready_time, SPF);
@@ -457,7 +457,7 @@ mod tests {
         ctxa.chara_phy.set_idle(true);
 
         logic_idle.start(&mut ctx, &mut ctxa, &sargs).unwrap();
-        for ft in FrameTicker::new(1..s2f(7.0)) {
+        for ft in FrameTicker::new(1..ctx.time.s2f(7.0)) {
             logic_idle.update(&mut ctx, &mut ctxa).unwrap();
             assert_eq!(logic_idle.mode, ActionIdleMode::Idle);
             assert_ulps_eq!(logic_idle.idle_time, ft.time);
@@ -479,7 +479,7 @@ mod tests {
         ctxa.chara_phy.set_idle(false);
 
         logic_idle.start(&mut ctx, &mut ctxa, &sargs).unwrap();
-        for ft in FrameTicker::new(1..s2f(7.0)) {
+        for ft in FrameTicker::new(1..ctx.time.s2f(7.0)) {
             logic_idle.update(&mut ctx, &mut ctxa).unwrap();
             assert_eq!(logic_idle.mode, ActionIdleMode::Ready);
             assert_ulps_eq!(logic_idle.ready_time, ft.time);
@@ -507,7 +507,7 @@ mod tests {
         logic_idle.update(&mut ctx, &mut ctxa).unwrap();
         ctxa.chara_phy.set_idle(false);
 
-        for ft in FrameTicker::new(0..s2f(0.4)) {
+        for ft in FrameTicker::new(0..ctx.time.s2f(0.4)) {
             logic_idle.update(&mut ctx, &mut ctxa).unwrap();
             let state = logic_idle.save();
 
@@ -558,7 +558,7 @@ mod tests {
             assert_ulps_eq!(logic_idle.auto_idle_time, inst_idle.auto_idle_delay - n / FPS);
         }
 
-        for ft in FrameTicker::new(0..s2f(0.2)) {
+        for ft in FrameTicker::new(0..ctx.time.s2f(0.2)) {
             logic_idle.update(&mut ctx, &mut ctxa).unwrap();
             let state = logic_idle.save();
             assert_eq!(logic_idle.auto_idle_time, 0.0);
@@ -610,7 +610,7 @@ mod tests {
         ctxa.chara_phy.set_idle(true);
 
         logic_idle.auto_idle_time = inst_idle.auto_idle_delay;
-        for ft in FrameTicker::new(0..s2f(0.133333)) {
+        for ft in FrameTicker::new(0..ctx.time.s2f(0.133333)) {
             logic_idle.update(&mut ctx, &mut ctxa).unwrap();
             assert_eq!(logic_idle.mode, ActionIdleMode::ReadyToIdle);
             assert_eq!(logic_idle.switch_time, ft.time(1));
@@ -618,7 +618,7 @@ mod tests {
 
         ctxa.chara_phy.set_idle(false);
         let switch_base = anim_ready.fade_in * (1.0 / 3.0);
-        for ft in FrameTicker::new(0..s2f(0.266667)) {
+        for ft in FrameTicker::new(0..ctx.time.s2f(0.266667)) {
             logic_idle.update(&mut ctx, &mut ctxa).unwrap();
             let state = logic_idle.save();
 
@@ -5,6 +5,7 @@ mod general_npc;
 mod hit;
 mod idle;
 mod r#move;
+mod motion_warp;
 mod move_npc;
 mod root_motion;
 #[cfg(test)]
@@ -17,6 +18,7 @@ pub use general_npc::*;
 pub use hit::*;
 pub use idle::*;
 pub use r#move::*;
+pub use motion_warp::*;
 pub use move_npc::*;
 pub use root_motion::*;
 
@@ -0,0 +1,268 @@
+use critical_point_macros::csharp_out;
+use glam::Vec3A;
+use glam_ext::Vec2xz;
+
+use crate::instance::{InstMotionWarp, InstMotionWarpTarget};
+use crate::logic::action::base::ContextAction;
+use crate::logic::game::ContextUpdateEx;
+use crate::utils::{ShapeSphere, TimeRange};
+
+const LEN_THRESHOLD: f32 = 1e-3;
+
+/// A running motion warp.
+///
+/// The warp is planned once when its time window begins. Then every frame in the window, the horizontal
+/// root displacement is scaled by `scale`, and the character turns a part of `angle`.
+#[repr(C)]
+#[csharp_out(Value)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct StateMotionWarp {
+    pub time: TimeRange, // In action time, empty if no warp is running
+    pub scale: f32,
+    pub angle: f32,
+}
+
+impl Default for StateMotionWarp {
+    fn default() -> StateMotionWarp {
+        StateMotionWarp::NONE
+    }
+}
+
+impl StateMotionWarp {
+    pub const NONE: StateMotionWarp = StateMotionWarp {
+        time: TimeRange::EMPTY,
+        scale: 1.0,
+        angle: 0.0,
+    };
+
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        !self.time.is_empty()
+    }
+
+    /// Plans a warp starting at `current_time`.
+    ///
+    /// `displacement` is the root displacement (in world space) the animation would do in the window.
+    /// Returns NONE if the target is too close to tell a direction.
+    pub(crate) fn plan(
+        warp: &InstMotionWarp,
+        current_time: f32,
+        chara_pos: Vec3A,
+        chara_dir: Vec2xz,
+        target_pos: Vec3A,
+        displacement: Vec3A,
+    ) -> StateMotionWarp {
+        let to_target = Vec2xz::from_vec3a(target_pos - chara_pos);
+        let target_dist = to_target.length();
+        if target_dist < LEN_THRESHOLD || warp.duration <= 0.0 {
+            return StateMotionWarp::NONE;
+        }
+
+        let motion = Vec2xz::from_vec3a(displacement);
+        let motion_len = motion.length();
+        let wanted_len = (target_dist - warp.distance).max(0.0);
+        let scale = match motion_len < LEN_THRESHOLD {
+            true => 1.0,
+            false => (wanted_len / motion_len).clamp(warp.min_scale, warp.max_scale),
+        };
+
+        let motion_dir = match motion_len < LEN_THRESHOLD {
+            true => chara_dir,
+            false => motion / motion_len,
+        };
+        let max_angle = warp.max_angle.abs();
+        let angle = motion_dir
+            .angle_to(to_target / target_dist)
+            .clamp(-max_angle, max_angle);
+
+        StateMotionWarp {
+            time: TimeRange::new(current_time, current_time + warp.duration),
+            scale,
+            angle,
+        }
+    }
+
+    /// Returns the displacement scale and the turning angle of the frame `(prev_time, current_time]`.
+    /// The warp stops after its window.
+    pub(crate) fn step(&mut self, prev_time: f32, current_time: f32) -> (f32, f32) {
+        if !self.is_running() || current_time <= self.time.begin {
+            return (1.0, 0.0);
+        }
+
+        let overlap = current_time.min(self.time.end) - prev_time.max(self.time.begin);
+        let ret = match overlap > 0.0 {
+            true => (self.scale, self.angle * overlap / self.time.duration()),
+            false => (1.0, 0.0),
+        };
+        if current_time >= self.time.end {
+            *self = StateMotionWarp::NONE;
+        }
+        ret
+    }
+}
+
+/// Finds the position the warp moves the character to.
+#[inline]
+pub(crate) fn find_motion_warp_target(
+    ctx: &ContextUpdateEx,
+    ctxa: &ContextAction,
+    warp: &InstMotionWarp,
+) -> Option<Vec3A> {
+    find_action_target(ctx, ctxa, warp.target, warp.search_radius)
+}
+
+/// Finds the position (in world space) of an action target. Dead characters are never targets.
+pub(crate) fn find_action_target(
+    ctx: &ContextUpdateEx,
+    ctxa: &ContextAction,
+    target: InstMotionWarpTarget,
+    search_radius: f32,
+) -> Option<Vec3A> {
+    match target {
+        InstMotionWarpTarget::LockedTarget => {
+            if ctxa.target_chara.is_invalid() {
+                return None;
+            }
+            let chara = ctx.characters.iter().find(|chara| chara.id() == ctxa.target_chara)?;
+            match chara.value().health() > 0.0 {
+                true => Some(chara.physics().position()),
+                false => None,
+            }
+        }
+        InstMotionWarpTarget::NearestEnemy => {
+            let idx = ctx.chara_grid.find_nearest_chara_in_sphere(
+                &ctx.characters,
+                !ctxa.inst_chara.is_player,
+                &ShapeSphere::new(search_radius),
+                ctxa.chara_phy.position(),
+                |chara| chara.value().health() > 0.0,
+            )?;
+            Some(ctx.characters[idx as usize].physics().position())
+        }
+        InstMotionWarpTarget::AiTarget => ctxa.ai_thinking?.target_chara_pos(),
+        InstMotionWarpTarget::NavPoint => {
+            let ai_thinking = ctxa.ai_thinking?;
+            match ai_thinking.move_dir == Vec2xz::ZERO {
+                true => None,
+                false => Some(ai_thinking.move_dst_pos),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn warp() -> InstMotionWarp {
+        InstMotionWarp {
+            duration: 0.4,
+            target: InstMotionWarpTarget::AiTarget,
+            distance: 1.0,
+            min_scale: 0.5,
+            max_scale: 2.0,
+            max_angle: 0.5,
+            search_radius: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_motion_warp_plan() {
+        let displacement = Vec3A::new(0.0, 0.0, 2.0);
+
+        // Stretch
+        let mw = StateMotionWarp::plan(
+            &warp(),
+            1.0,
+            Vec3A::ZERO,
+            Vec2xz::Z,
+            Vec3A::new(0.0, 0.0, 4.0),
+            displacement,
+        );
+        assert_eq!(mw.time, TimeRange::new(1.0, 1.4));
+        assert_abs_diff_eq!(mw.scale, 1.5);
+        assert_abs_diff_eq!(mw.angle, 0.0);
+
+        // Clamped by max_scale & min_scale
+        let mw = StateMotionWarp::plan(
+            &warp(),
+            1.0,
+            Vec3A::ZERO,
+            Vec2xz::Z,
+            Vec3A::new(0.0, 0.0, 20.0),
+            displacement,
+        );
+        assert_abs_diff_eq!(mw.scale, 2.0);
+        let mw = StateMotionWarp::plan(
+            &warp(),
+            1.0,
+            Vec3A::ZERO,
+            Vec2xz::Z,
+            Vec3A::new(0.0, 0.0, 1.2),
+            displacement,
+        );
+        assert_abs_diff_eq!(mw.scale, 0.5);
+
+        // Clamped by max_angle
+        let mw = StateMotionWarp::plan(
+            &warp(),
+            1.0,
+            Vec3A::ZERO,
+            Vec2xz::Z,
+            Vec3A::new(3.0, 0.0, 0.0),
+            displacement,
+        );
+        assert_abs_diff_eq!(mw.angle.abs(), 0.5);
+        assert_abs_diff_eq!(Vec2xz::Z.angle_to(Vec2xz::X).abs(), FRAC_PI_2);
+        assert_eq!(mw.angle.signum(), Vec2xz::Z.angle_to(Vec2xz::X).signum());
+
+        // Target too close
+        let mw = StateMotionWarp::plan(
+            &warp(),
+            1.0,
+            Vec3A::ZERO,
+            Vec2xz::Z,
+            Vec3A::new(0.0, 1.0, 0.0),
+            displacement,
+        );
+        assert_eq!(mw, StateMotionWarp::NONE);
+    }
+
+    #[test]
+    fn test_motion_warp_step() {
+        let mut mw = StateMotionWarp {
+            time: TimeRange::new(1.0, 1.4),
+            scale: 1.5,
+            angle: 0.4,
+        };
+        assert_eq!(mw.step(0.9, 1.0), (1.0, 0.0));
+
+        let (scale, angle) = mw.step(1.0, 1.1);
+        assert_eq!(scale, 1.5);
+        assert_abs_diff_eq!(angle, 0.1, epsilon = 1e-5);
+
+        let (_, angle) = mw.step(1.1, 1.3);
+        assert_abs_diff_eq!(angle, 0.2, epsilon = 1e-5);
+        assert!(mw.is_running());
+
+        // Partial overlap at the end
+        let (scale, angle) = mw.step(1.3, 1.5);
+        assert_eq!(scale, 1.5);
+        assert_abs_diff_eq!(angle, 0.1, epsilon = 1e-5);
+        assert!(!mw.is_running());
+        assert_eq!(mw.step(1.5, 1.6), (1.0, 0.0));
+    }
+}
@@ -13,13 +13,13 @@ use crate::input::{RefInputEventQueue, WorldMoveState};
 use crate::instance::{InstActionMove, InstAnimation};
 use crate::logic::action::base::{
     ActionStartArgs, ActionStartReturn, ActionUpdateReturn, ContextAction, LogicActionAny, LogicActionBase,
-    StateActionAnimation, StateActionAny, StateActionBase, impl_state_action,
+    StateActionAnimation, StateActionAny, StateActionBase, StateActionPool, impl_state_action,
 };
 use crate::logic::action::root_motion::{LogicMultiRootMotion, StateMultiRootMotion};
 use crate::logic::game::ContextUpdateEx;
 use crate::utils::{
     ActionType, Castable, XResult, calc_fade_in, extend, ifelse, lerp, loose_ge, loose_le, ok_or, ratio_warpping,
-    s2ff_round, strict_gt, xres, xresf,
+    strict_gt, xres, xresf,
 };
 
 #[csharp_enum]
@@ -107,8 +107,8 @@ impl LogicActionMove {
         let root_motion =
             LogicMultiRootMotion::new_with_capacity(ctx, inst_act.animations(), inst_act.animations_count())?;
 
-        let turn_angle_step = Vec2xz::from_angle(PI / s2ff_round(inst_act.turn_time).max(1.0));
-        let turn_cos_step = libm::cosf(PI / s2ff_round(inst_act.turn_time).max(1.0));
+        let turn_angle_step = Vec2xz::from_angle(PI / ctx.time.s2ff_round(inst_act.turn_time).max(1.0));
+        let turn_cos_step = libm::cosf(PI / ctx.time.s2ff_round(inst_act.turn_time).max(1.0));
 
         Ok(LogicActionMove {
             _base: LogicActionBase {
@@ -174,8 +174,8 @@ unsafe impl LogicActionAny for LogicActionMove {
         Ok(())
     }
 
-    fn save(&self) -> Box<dyn StateActionAny> {
-        let mut state = Box::new(StateActionMove {
+    fn save_pooled(&self, pool: &mut StateActionPool) -> Box<dyn StateActionAny> {
+        let mut state = pool.boxed(StateActionMove {
             _base: self._base.save(self.typ()),
             mode: self.mode,
             smooth_move_switch: self.smooth_move_switch,
@@ -390,7 +390,8 @@ impl LogicActionMove {
         if let Some((start_idx, start)) = inst_act.find_start_by_angle(angle) {
             self.init_anim(ActionMoveMode::Start, start_idx as u16);
             self.keep_level = inst_act.keep_level_special;
-            self.start_turn_angle_step = Vec2xz::from_angle(angle / s2ff_round(start.turn_in_place_end + CFG_SPF));
+            self.start_turn_angle_step =
+                Vec2xz::from_angle(angle / ((start.turn_in_place_end + CFG_SPF) / ctxa.spf).round());
             self.root_motion.set_local_id(start.anim.local_id, 0.0)?;
         }
         else {
@@ -423,7 +424,10 @@ impl LogicActionMove {
             // Turn inplace
             if loose_le!(adjusted_time, start.turn_in_place_end + CFG_SPF) {
                 let chara_dir = ctxa.chara_phy.direction_xz();
-                let new_direction = self.start_turn_angle_step.rotate(chara_dir);
+                let new_direction = match self.root_motion.has_rotation() {
+                    true => self.root_yaw_step().rotate(chara_dir),
+                    false => self.start_turn_angle_step.rotate(chara_dir),
+                };
                 res.set_dir_speed(new_direction, speed);
                 // println!(
                 //     "update_start(trun) => adjusted_time:{} chara_dir:{:?} new_direction:{:?}",
@@ -595,7 +599,7 @@ impl LogicActionMove {
         self.root_motion.update(stop.anim.ratio_saturating(adjusted_time))?;
         let speed: f32 =
             self.root_motion.position_delta().xz().length() * ctxa.frac_1_time_step * self.inst.speed_ratio;
-        res.set_dir_speed(chara_dir, speed);
+        res.set_dir_speed(self.root_yaw_step().rotate(chara_dir), speed);
 
         if loose_ge!(adjusted_time, stop.anim.duration) {
             res.exit();
@@ -603,6 +607,13 @@ impl LogicActionMove {
         return Ok(res);
     }
 
+    /// The root rotation of the last update, weighted by the local fade in weight.
+    /// The previous local animation is fading out, and it doesn't turn the character.
+    #[inline]
+    fn root_yaw_step(&self) -> Vec2xz {
+        Vec2xz::from_angle(self.root_motion.yaw_delta() * self.local_fade_in_weight)
+    }
+
     #[inline(always)]
     fn handle_fade_in(&mut self, anim: &InstAnimation, time_step: f32) {
         if self.local_fade_in_weight < 1.0 {
@@ -749,6 +760,61 @@ impl LogicActionMove {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use std::iter;
+
+    use super::*;
+    use crate::instance::InstActionGeneral;
+    use crate::logic::action::test_utils::*;
+    use crate::utils::tests::FrameTicker;
+    use crate::utils::id;
+
+    #[test]
+    fn test_logic_move_root_yaw_step() {
+        let mut tenv = TestEnv::new().unwrap();
+        let inst_move: Rc<InstActionMove> = tenv.inst_chara.find_action_by_id(id!("Action.Instance.Run^1A")).unwrap();
+        let inst_turn: Rc<InstActionGeneral> = tenv.assemble_action(id!("Action.Instance.AttackDerive^1A"), &[(
+            id!("#.Action.Instance.AttackDerive^1A"),
+            2,
+        )]);
+        let mut logic_move = LogicActionMove::new(&mut tenv.context_update(), inst_move).unwrap();
+        // The move clips don't turn, steps the move action with the turning root motion of Girl/Attack_03A.
+        logic_move.root_motion =
+            LogicMultiRootMotion::new(&mut tenv.context_update(), iter::once(&inst_turn.anim_main)).unwrap();
+        logic_move.root_motion.set_local_id(0, 0.0).unwrap();
+        logic_move.local_fade_in_weight = 0.5;
+
+        let mut steps = Vec::new();
+        let mut saved = None;
+        for ft in FrameTicker::new(1..tenv.time.s2f(2.0) + 1) {
+            logic_move
+                .root_motion
+                .update(inst_turn.anim_main.ratio_saturating(ft.time))
+                .unwrap();
+            assert!(logic_move.root_motion.has_rotation());
+            let step = logic_move.root_yaw_step().to_angle();
+            assert_abs_diff_eq!(step, logic_move.root_motion.yaw_delta() * 0.5, epsilon = 1e-5);
+            steps.push(step);
+            if ft.frame == 10 {
+                saved = Some(logic_move.save());
+            }
+        }
+        assert!(steps.iter().sum::<f32>().abs() > 0.5);
+
+        logic_move.restore(saved.as_deref().unwrap()).unwrap();
+        assert_eq!(logic_move.local_fade_in_weight, 0.5);
+        for ft in FrameTicker::new(11..tenv.time.s2f(2.0) + 1) {
+            logic_move
+                .root_motion
+                .update(inst_turn.anim_main.ratio_saturating(ft.time))
+                .unwrap();
+            assert_eq!(logic_move.root_yaw_step().to_angle(), steps[ft.frame as usize - 1]);
+        }
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use super::*;
@@ -10,13 +10,11 @@ use crate::consts::MAX_ACTION_ANIMATION;
 use crate::instance::{InstActionMoveNpc, InstAnimation};
 use crate::logic::action::base::{
     ActionStartArgs, ActionStartReturn, ActionUpdateReturn, ContextAction, LogicActionAny, LogicActionBase,
-    StateActionAnimation, StateActionAny, StateActionBase, impl_state_action,
+    StateActionAnimation, StateActionAny, StateActionBase, StateActionPool, impl_state_action,
 };
 use crate::logic::action::root_motion::{LogicMultiRootMotion, StateMultiRootMotion};
 use crate::logic::game::ContextUpdateEx;
-use crate::utils::{
-    ActionType, Castable, LEVEL_MOVE, XResult, extend, ifelse, loose_ge, loose_le, s2ff_round, xres, xresf,
-};
+use crate::utils::{ActionType, Castable, LEVEL_MOVE, XResult, extend, ifelse, loose_ge, loose_le, xres, xresf};
 
 #[csharp_enum]
 #[repr(u8)]
@@ -92,8 +90,8 @@ impl LogicActionMoveNpc {
         let root_motion =
             LogicMultiRootMotion::new_with_capacity(ctx, inst_act.animations(), inst_act.animations_count())?;
 
-        let turn_angle_step = Vec2xz::from_angle(PI / s2ff_round(inst_act.turn_time).max(1.0));
-        let turn_cos_step = libm::cosf(PI / s2ff_round(inst_act.turn_time).max(1.0));
+        let turn_angle_step = Vec2xz::from_angle(PI / ctx.time.s2ff_round(inst_act.turn_time).max(1.0));
+        let turn_cos_step = libm::cosf(PI / ctx.time.s2ff_round(inst_act.turn_time).max(1.0));
 
         Ok(LogicActionMoveNpc {
             _base: LogicActionBase {
@@ -146,8 +144,8 @@ unsafe impl LogicActionAny for LogicActionMoveNpc {
         Ok(())
     }
 
-    fn save(&self) -> Box<dyn StateActionAny> {
-        let mut state = Box::new(StateActionMoveNpc {
+    fn save_pooled(&self, pool: &mut StateActionPool) -> Box<dyn StateActionAny> {
+        let mut state = pool.boxed(StateActionMoveNpc {
             _base: self._base.save(self.typ()),
             mode: self.mode,
             current_time: self.current_time,
@@ -1,12 +1,13 @@
 use critical_point_macros::csharp_out;
-use glam::Vec3A;
+use glam::{Quat, Vec3A};
 use ozz_animation_rs::TrackSamplingJobRef;
 use std::rc::Rc;
 
 use crate::animation::{RootMotion, RootTrackName};
+use crate::consts::DEFAULT_TOWARD_DIR_2D;
 use crate::instance::InstAnimation;
 use crate::logic::ContextUpdateEx;
-use crate::utils::{XResult, xresf};
+use crate::utils::{XResult, dir_xz_from_quat, xresf};
 
 //
 // LogicRootMotion
@@ -24,9 +25,10 @@ pub struct StateRootMotion {
     pub current_pos: Vec3A,
     pub previous_pos: Vec3A,
     pub pos_delta: Vec3A,
-    // pub rotation_cursor: Quat,
-    // pub rotation: Quat,
-    // pub rotation_delta: Quat,
+    // Rotations are relative to the first key of the rotation track.
+    pub current_rot: Quat,
+    pub previous_rot: Quat,
+    pub rot_delta: Quat,
 }
 
 impl Default for StateRootMotion {
@@ -37,6 +39,9 @@ impl Default for StateRootMotion {
             current_pos: Vec3A::ZERO,
             previous_pos: Vec3A::ZERO,
             pos_delta: Vec3A::ZERO,
+            current_rot: Quat::IDENTITY,
+            previous_rot: Quat::IDENTITY,
+            rot_delta: Quat::IDENTITY,
         }
     }
 }
@@ -62,11 +67,10 @@ impl LogicRootMotion {
                 zelf.state.previous_pos = zelf.state.current_pos;
             }
 
-            // if zelf.root_motion.has_rotation() {
-            //     zelf.state.rotation_cursor = zelf.root_motion.first_rotation();
-            //     zelf.state.rotation =
-            //         update_rotation_job(&zelf.root_motion, &mut zelf.state.rotation_cursor, 0.0, start_ratio)?;
-            // }
+            if zelf.root_motion.has_rotation() {
+                zelf.state.current_rot = run_rotation_job(&zelf.root_motion, start_ratio)?;
+                zelf.state.previous_rot = zelf.state.current_rot;
+            }
 
             zelf.state.ratio = start_ratio;
         }
@@ -102,15 +106,11 @@ impl LogicRootMotion {
             self.state.pos_delta = self.state.current_pos - self.state.previous_pos;
         }
 
-        // if self.root_motion.has_rotation() {
-        //     self.state.rotation_delta = update_rotation_job(
-        //         &self.root_motion,
-        //         &mut self.state.rotation_cursor,
-        //         self.state.ratio,
-        //         ratio,
-        //     )?;
-        //     self.state.rotation *= self.state.rotation_delta;
-        // }
+        if self.root_motion.has_rotation() {
+            self.state.previous_rot = self.state.current_rot;
+            self.state.current_rot = run_rotation_job(&self.root_motion, ratio)?;
+            self.state.rot_delta = self.state.current_rot * self.state.previous_rot.inverse();
+        }
 
         self.state.ratio = ratio;
         Ok(())
@@ -135,6 +135,37 @@ impl LogicRootMotion {
     pub fn velocity(&self, step: f32) -> Vec3A {
         self.state.pos_delta / step
     }
+
+    /// The position displacement from current ratio to `ratio`, in the space of the current root rotation.
+    pub fn displacement_to(&self, ratio: f32) -> XResult<Vec3A> {
+        if !self.root_motion.has_position(self.state.pos_track) {
+            return Ok(Vec3A::ZERO);
+        }
+        let pos = run_position_job(&self.root_motion, self.state.pos_track, ratio)?;
+        Ok(self.state.current_rot.inverse() * (pos - self.state.current_pos))
+    }
+
+    #[inline]
+    pub fn has_rotation(&self) -> bool {
+        self.root_motion.has_rotation()
+    }
+
+    /// The root rotation at current ratio, relative to the first key.
+    #[inline]
+    pub fn rotation(&self) -> Quat {
+        self.state.current_rot
+    }
+
+    #[inline]
+    pub fn rotation_delta(&self) -> Quat {
+        self.state.rot_delta
+    }
+
+    /// The signed angle turned by the root on XZ plane in the last update.
+    #[inline]
+    pub fn yaw_delta(&self) -> f32 {
+        yaw_of(self.state.rot_delta)
+    }
 }
 
 //
@@ -154,9 +185,10 @@ pub struct StateMultiRootMotion {
     pub current_pos: Vec3A,
     pub previous_pos: Vec3A,
     pub pos_delta: Vec3A,
-    // pub rotation_cursor: Quat,
-    // pub rotation: Quat,
-    // pub rotation_delta: Quat,
+    // Rotations are relative to the first key of the rotation track.
+    pub current_rot: Quat,
+    pub previous_rot: Quat,
+    pub rot_delta: Quat,
 }
 
 impl Default for StateMultiRootMotion {
@@ -168,6 +200,9 @@ impl Default for StateMultiRootMotion {
             current_pos: Vec3A::ZERO,
             previous_pos: Vec3A::ZERO,
             pos_delta: Vec3A::ZERO,
+            current_rot: Quat::IDENTITY,
+            previous_rot: Quat::IDENTITY,
+            rot_delta: Quat::IDENTITY,
         }
     }
 }
@@ -225,11 +260,10 @@ impl LogicMultiRootMotion {
                     self.state.previous_pos = self.state.current_pos;
                 }
 
-                // if root_motion.has_rotation() {
-                //     self.state.rotation_cursor = root_motion.first_rotation();
-                //     self.state.rotation =
-                //         update_rotation_job(root_motion, &mut self.state.rotation_cursor, 0.0, start_ratio)?;
-                // }
+                if root_motion.has_rotation() {
+                    self.state.current_rot = run_rotation_job(root_motion, start_ratio)?;
+                    self.state.previous_rot = self.state.current_rot;
+                }
 
                 self.state.ratio = start_ratio;
             }
@@ -265,11 +299,11 @@ impl LogicMultiRootMotion {
                 self.state.pos_delta = self.state.current_pos - old_pos;
             }
 
-            // if track.has_rotation() {
-            //     self.state.rotation_delta =
-            //         update_rotation_job(track, &mut self.state.rotation_cursor, self.state.ratio, ratio)?;
-            //     self.state.rotation *= self.state.rotation_delta;
-            // }
+            if track.has_rotation() {
+                self.state.previous_rot = self.state.current_rot;
+                self.state.current_rot = run_rotation_job(track, ratio)?;
+                self.state.rot_delta = self.state.current_rot * self.state.previous_rot.inverse();
+            }
 
             self.state.ratio = ratio;
         }
@@ -295,6 +329,32 @@ impl LogicMultiRootMotion {
     pub fn velocity(&self, step: f32) -> Vec3A {
         self.state.pos_delta / step
     }
+
+    /// Whether the animation of current local id has a rotation track.
+    #[inline]
+    pub fn has_rotation(&self) -> bool {
+        match self.root_motions.get(self.state.local_id as usize) {
+            Some(root_motion) => root_motion.has_rotation(),
+            None => false,
+        }
+    }
+
+    /// The root rotation at current ratio, relative to the first key.
+    #[inline]
+    pub fn rotation(&self) -> Quat {
+        self.state.current_rot
+    }
+
+    #[inline]
+    pub fn rotation_delta(&self) -> Quat {
+        self.state.rot_delta
+    }
+
+    /// The signed angle turned by the root on XZ plane in the last update.
+    #[inline]
+    pub fn yaw_delta(&self) -> f32 {
+        yaw_of(self.state.rot_delta)
+    }
 }
 
 //
@@ -317,62 +377,46 @@ fn run_position_job(root_motion: &RootMotion, pos_track: RootTrackName, ratio: f
     Ok(trunc_pos + frac_pos)
 }
 
-// fn update_rotation_job(
-//     root_motion: &RootMotion,
-//     cursor: &mut Quat,
-//     from_ratio: f32,
-//     to_ratio: f32,
-// ) -> XResult<Quat> {
-//     if (to_ratio - from_ratio).abs() > 10.0 {
-//         return xres!(BadArgument; "from_ratio - to_ratio > 10.0");
-//     }
-
-//     let mut diff;
-//     let mut job = TrackSamplingJobRef::default();
-//     job.set_track(&root_motion.rotation);
-
-//     if from_ratio <= to_ratio {
-//         if from_ratio.ceil() >= to_ratio {
-//             job.set_ratio(to_ratio % 1.0);
-//             job.run()?;
-//             diff = job.result() * cursor.inverse();
-//             *cursor = job.result();
-//         }
-//         else {
-//             let last_rot = root_motion.last_rotation();
-//             diff = last_rot * cursor.inverse();
-
-//             for _ in (from_ratio.ceil() as i64)..=(to_ratio.floor() as i64) {
-//                 diff *= last_rot;
-//             }
-
-//             job.set_ratio(to_ratio % 1.0);
-//             job.run()?;
-//             diff *= job.result();
-//             *cursor = job.result();
-//         }
-//     }
-//     else {
-//         if from_ratio.floor() <= to_ratio {
-//             job.set_ratio(to_ratio % 1.0 + 1.0);
-//             job.run()?;
-//             diff = cursor.inverse() * job.result();
-//             *cursor = job.result();
-//         }
-//         else {
-//             let last_rot = root_motion.last_rotation();
-//             diff = cursor.inverse() * last_rot;
-
-//             for _ in (to_ratio.ceil() as i64)..=(from_ratio.floor() as i64) {
-//                 diff *= last_rot.inverse();
-//             }
-
-//             job.set_ratio(to_ratio % 1.0 + 1.0);
-//             job.run()?;
-//             diff *= job.result().inverse();
-//             *cursor = job.result();
-//         }
-//     }
-
-//     Ok(diff)
-// }
+// Same as positions, each whole loop (the integer part of ratio) adds a whole rotation.
+fn run_rotation_job(root_motion: &RootMotion, ratio: f32) -> XResult<Quat> {
+    let trunc = ratio.floor();
+    let frac = ratio - trunc;
+
+    let mut job = TrackSamplingJobRef::default();
+    job.set_track(&root_motion.rotation);
+    job.set_ratio(frac);
+    job.run()?;
+    let frac_rot = job.result() * root_motion.first_rotation().inverse();
+
+    if trunc == 0.0 {
+        return Ok(frac_rot.normalize());
+    }
+    let (axis, angle) = root_motion.whole_rotation().to_axis_angle();
+    let trunc_rot = Quat::from_axis_angle(axis, angle * trunc);
+    Ok((frac_rot * trunc_rot).normalize())
+}
+
+#[inline]
+fn yaw_of(rotation: Quat) -> f32 {
+    DEFAULT_TOWARD_DIR_2D.angle_to(dir_xz_from_quat(rotation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use glam_ext::Vec2xz;
+
+    #[test]
+    fn test_yaw_of() {
+        assert_eq!(yaw_of(Quat::IDENTITY), 0.0);
+        for angle in [0.3, 1.2, -0.7, 2.9] {
+            let rotation = Quat::from_rotation_y(angle);
+            let dir = Vec2xz::from_angle(yaw_of(rotation)).rotate(DEFAULT_TOWARD_DIR_2D);
+            let expected = dir_xz_from_quat(rotation);
+            assert_abs_diff_eq!(dir.x, expected.x, epsilon = 1e-5);
+            assert_abs_diff_eq!(dir.z, expected.z, epsilon = 1e-5);
+            assert_abs_diff_eq!(yaw_of(rotation).abs(), angle.abs(), epsilon = 1e-5);
+        }
+    }
+}
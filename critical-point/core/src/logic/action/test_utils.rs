@@ -2,9 +2,9 @@ use glam::Vec3A;
 use glam_ext::Vec2xz;
 use std::rc::Rc;
 
-use crate::consts::{DEFAULT_TOWARD_DIR_2D, TEST_ASSET_PATH};
+use crate::consts::{DEFAULT_TICK_RATE, DEFAULT_TOWARD_DIR_2D, TEST_ASSET_PATH};
 use crate::input::InputPlayerInputs;
-use crate::instance::{InstActionEmpty, InstCharacter};
+use crate::instance::{ContextActionAssemble, InstActionAny, InstActionEmpty, InstCharacter, assemble_action};
 use crate::logic::LogicActionEmpty;
 use crate::logic::action::base::{ActionStartArgs, ContextAction, StateActionAny};
 use crate::logic::character::LogicCharaPhysics;
@@ -12,7 +12,7 @@ use crate::logic::game::{ContextUpdate, ContextUpdateEx, GameTime, LogicSystems}
 use crate::logic::zone::LogicZone;
 use crate::parameter::{ParamPlayer, ParamZone};
 use crate::template::TmplDatabase;
-use crate::utils::{ActionType, NumID, VirtualKey, XResult, id, ifelse};
+use crate::utils::{ActionType, Castable, DtHashMap, NumID, TmplID, VirtualKey, XResult, id, ifelse};
 
 pub(super) fn test_state_action_rkyv(
     state: Box<dyn StateActionAny>,
@@ -45,7 +45,7 @@ impl TestEnv {
 
     pub fn new() -> XResult<TestEnv> {
         let db = TmplDatabase::new(10240, 150)?;
-        let mut systems = LogicSystems::new(db, TEST_ASSET_PATH, None)?;
+        let mut systems = LogicSystems::new(db, TEST_ASSET_PATH, None, 0, DEFAULT_TICK_RATE)?;
         systems.input.init(1)?;
 
         let time_init = GameTime::new(Self::FRAME, 0);
@@ -90,13 +90,31 @@ impl TestEnv {
         })
     }
 
+    /// Assembles an action outside the test character, e.g. an action disabled at the default variable levels.
+    pub fn assemble_action<T: InstActionAny + 'static>(&self, id: TmplID, vars: &[(TmplID, u32)]) -> Rc<T> {
+        let var_indexes: DtHashMap<TmplID, u32> = vars.iter().copied().collect();
+        let ctx = ContextActionAssemble {
+            var_indexes: &var_indexes,
+        };
+        let tmpl = self.systems.tmpl_db.find(id).unwrap();
+        let inst_act = assemble_action(&ctx, tmpl).unwrap().unwrap();
+        inst_act.cast::<T>().unwrap()
+    }
+
     pub fn context_update(&mut self) -> ContextUpdateEx<'_> {
         ContextUpdateEx::new(&mut self.systems, &self.time, &self.zone)
     }
 
     pub fn contexts(&mut self, prev_action: bool) -> (ContextUpdateEx<'_>, ContextAction<'_>, ActionStartArgs<'_>) {
         let ctx = ContextUpdateEx::new(&mut self.systems, &self.time, &self.zone);
-        let mut ctxa = ContextAction::new(Self::PLAYER_ID, self.inst_chara.clone(), &self.chara_phy, None);
+        let mut ctxa = ContextAction::new(
+            Self::PLAYER_ID,
+            self.inst_chara.clone(),
+            &self.chara_phy,
+            None,
+            NumID::INVALID,
+            self.time.spf,
+        );
         ctxa.set_time_normalized(1.0);
         let sargs = ActionStartArgs::new(
             ifelse!(prev_action, Some(&self.logic_empty), None),
@@ -1,5 +1,6 @@
 use approx::abs_diff_eq;
 use critical_point_macros::{csharp_enum, csharp_out, wasm_impl, wasm_struct};
+use enum_iterator::Sequence;
 use glam::Vec3A;
 use glam_ext::Vec2xz;
 use std::alloc::Layout;
@@ -7,7 +8,7 @@ use std::any::Any;
 use std::fmt::Debug;
 use std::rc::Rc;
 
-use crate::consts::{INVALID_AI_TASK_ID, SPF};
+use crate::consts::INVALID_AI_TASK_ID;
 use crate::instance::{InstActionAny, InstAiTaskAny, InstCharacter};
 use crate::logic::character::{LogicCharaControl, LogicCharaPhysics};
 use crate::logic::game::ContextUpdateEx;
@@ -343,6 +344,7 @@ pub struct ContextAiTask<'a> {
     pub(crate) ai_thinking: &'a AiBrainThinking,
     pub(crate) zone: &'a LogicZone,
 
+    pub(crate) spf: f32,
     pub(crate) time_speed: f32,
     pub(crate) time_step: f32,
     pub(crate) frac_1_time_step: f32,
@@ -355,6 +357,7 @@ impl<'a> ContextAiTask<'a> {
         chara_phy: &'a LogicCharaPhysics,
         ai_thinking: &'a AiBrainThinking,
         zone: &'a LogicZone,
+        spf: f32,
     ) -> ContextAiTask<'a> {
         ContextAiTask {
             inst_chara,
@@ -363,9 +366,10 @@ impl<'a> ContextAiTask<'a> {
             ai_thinking,
             zone,
 
+            spf,
             time_speed: 1.0,
-            time_step: SPF,
-            frac_1_time_step: 1.0 / SPF,
+            time_step: spf,
+            frac_1_time_step: 1.0 / spf,
         }
     }
 
@@ -377,7 +381,7 @@ impl<'a> ContextAiTask<'a> {
         }
         else {
             self.time_speed = time_speed;
-            self.time_step = SPF * time_speed;
+            self.time_step = self.spf * time_speed;
             self.frac_1_time_step = 1.0 / self.time_step;
         }
     }
@@ -405,7 +409,7 @@ pub struct AiTaskReturn {
 
 #[repr(u8)]
 #[csharp_enum]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum LogicAiTaskStatus {
     Starting,
     Running,
@@ -413,7 +417,7 @@ pub enum LogicAiTaskStatus {
     Finalized,
 }
 
-rkyv_self!(LogicAiTaskStatus);
+rkyv_self!(LogicAiTaskStatus: Sequence);
 
 #[derive(Debug)]
 pub struct LogicAiTaskBase {
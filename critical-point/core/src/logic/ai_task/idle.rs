@@ -7,6 +7,7 @@ use crate::logic::ai_task::base::{
     AiTaskReturn, ContextAiTask, LogicAiTaskAny, LogicAiTaskBase, StateAiTaskAny, StateAiTaskBase, impl_state_ai_task,
 };
 use crate::logic::game::ContextUpdateEx;
+use crate::logic::system::RandomStream;
 use crate::utils::{AiTaskType, Castable, TmplID, XResult, extend, lerp, xres};
 
 #[repr(C)]
@@ -94,7 +95,7 @@ unsafe impl LogicAiTaskAny for LogicAiTaskIdle {
         // decide duration randomly from inst.duration; None => infinite
         match &self.inst.duration {
             Some(range) => {
-                self.duration = lerp(range.min, range.max, ctx.rand.rand_f32());
+                self.duration = lerp(range.min, range.max, ctx.rand.stream(RandomStream::Ai).rand_f32());
                 self.timer = 0.0;
             }
             None => {
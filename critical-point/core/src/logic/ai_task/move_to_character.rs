@@ -8,7 +8,6 @@ use std::hint::likely;
 use std::rc::Rc;
 use std::sync::Arc;
 
-use crate::consts::SPF;
 use crate::instance::{InstActionMoveNpc, InstAiTaskMoveToCharacter, InstCharacter};
 use crate::logic::ai_task::base::{
     AiTaskReturn, ContextAiTask, LogicAiTaskAny, LogicAiTaskBase, StateAiTaskAny, StateAiTaskBase, impl_state_ai_task,
@@ -253,7 +252,7 @@ impl LogicAiTaskMoveToCharacter {
         }
 
         self.path_refresh_timer += ctxt.time_step;
-        if loose_ge!(self.path_refresh_timer, 5.0 * SPF) {
+        if loose_ge!(self.path_refresh_timer, 5.0 * ctxt.spf) {
             self.path_refresh_timer = 0.0;
 
             let dst_pos = if rel == Ordering::Greater {
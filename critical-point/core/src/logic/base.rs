@@ -18,9 +18,10 @@ pub enum LogicType {
     Game,
     Zone,
     Character,
+    HitObject,
 }
 
-rkyv_self!(LogicType);
+rkyv_self!(LogicType: Sequence);
 
 impl From<LogicType> for u16 {
     #[inline]
@@ -70,9 +71,11 @@ pub enum StateType {
     ZoneUpdate,
     CharacterInit,
     CharacterUpdate,
+    HitObjectInit,
+    HitObjectUpdate,
 }
 
-rkyv_self!(StateType);
+rkyv_self!(StateType: Sequence);
 
 impl StateType {
     #[inline]
@@ -81,6 +84,7 @@ impl StateType {
             StateType::GameInit | StateType::GameUpdate => LogicType::Game,
             StateType::ZoneInit | StateType::ZoneUpdate => LogicType::Zone,
             StateType::CharacterInit | StateType::CharacterUpdate => LogicType::Character,
+            StateType::HitObjectInit | StateType::HitObjectUpdate => LogicType::HitObject,
         }
     }
 }
@@ -193,6 +197,9 @@ const _: () = {
         ArchivedStateCharacterInit, ArchivedStateCharacterUpdate, StateCharacterInit, StateCharacterUpdate,
     };
     use crate::logic::game::{ArchivedStateGameInit, ArchivedStateGameUpdate, StateGameInit, StateGameUpdate};
+    use crate::logic::hit_object::{
+        ArchivedStateHitObjectInit, ArchivedStateHitObjectUpdate, StateHitObjectInit, StateHitObjectUpdate,
+    };
     use crate::logic::zone::{ArchivedStateZoneInit, ArchivedStateZoneUpdate, StateZoneInit, StateZoneUpdate};
     use crate::utils::Castable;
     use StateType::*;
@@ -218,6 +225,12 @@ const _: () = {
                 (CharacterUpdate, CharacterUpdate) => unsafe {
                     self.cast_unchecked::<StateCharacterUpdate>() == other.cast_unchecked::<StateCharacterUpdate>()
                 },
+                (HitObjectInit, HitObjectInit) => unsafe {
+                    self.cast_unchecked::<StateHitObjectInit>() == other.cast_unchecked::<StateHitObjectInit>()
+                },
+                (HitObjectUpdate, HitObjectUpdate) => unsafe {
+                    self.cast_unchecked::<StateHitObjectUpdate>() == other.cast_unchecked::<StateHitObjectUpdate>()
+                },
                 _ => false,
             }
         }
@@ -257,6 +270,8 @@ const _: () = {
                     ZoneUpdate => mem::transmute_copy::<usize, &ArchivedStateZoneUpdate>(&0),
                     CharacterInit => mem::transmute_copy::<usize, &ArchivedStateCharacterInit>(&0),
                     CharacterUpdate => mem::transmute_copy::<usize, &ArchivedStateCharacterUpdate>(&0),
+                    HitObjectInit => mem::transmute_copy::<usize, &ArchivedStateHitObjectInit>(&0),
+                    HitObjectUpdate => mem::transmute_copy::<usize, &ArchivedStateHitObjectUpdate>(&0),
                     _ => unreachable!("pointer_metadata() Invalid StateType"),
                 }
             };
@@ -299,6 +314,8 @@ const _: () = {
                 ZoneUpdate => serialize::<StateZoneUpdate, _>(self, serializer),
                 CharacterInit => serialize::<StateCharacterInit, _>(self, serializer),
                 CharacterUpdate => serialize::<StateCharacterUpdate, _>(self, serializer),
+                HitObjectInit => serialize::<StateHitObjectInit, _>(self, serializer),
+                HitObjectUpdate => serialize::<StateHitObjectUpdate, _>(self, serializer),
                 _ => unreachable!("serialize_unsized() Invalid StateType"),
             }
         }
@@ -335,6 +352,8 @@ const _: () = {
                 ZoneUpdate => deserialize::<StateZoneUpdate, _>(self, deserializer, out),
                 CharacterInit => deserialize::<StateCharacterInit, _>(self, deserializer, out),
                 CharacterUpdate => deserialize::<StateCharacterUpdate, _>(self, deserializer, out),
+                HitObjectInit => deserialize::<StateHitObjectInit, _>(self, deserializer, out),
+                HitObjectUpdate => deserialize::<StateHitObjectUpdate, _>(self, deserializer, out),
                 _ => unreachable!("deserialize_unsized() Invalid StateType"),
             }
         }
@@ -348,6 +367,8 @@ const _: () = {
                     ZoneUpdate => mem::transmute_copy::<usize, &StateZoneUpdate>(&0),
                     CharacterInit => mem::transmute_copy::<usize, &StateCharacterInit>(&0),
                     CharacterUpdate => mem::transmute_copy::<usize, &StateCharacterUpdate>(&0),
+                    HitObjectInit => mem::transmute_copy::<usize, &StateHitObjectInit>(&0),
+                    HitObjectUpdate => mem::transmute_copy::<usize, &StateHitObjectUpdate>(&0),
                     _ => unreachable!("deserialize_metadata() Invalid StateType"),
                 }
             };
@@ -413,17 +434,17 @@ mod tests {
     use crate::animation::AnimationFileMeta;
     use crate::logic::action::DeriveKeeping;
     use crate::logic::character::{
-        StateCharaControl, StateCharaHitBoxPair, StateCharaHitGroupPair, StateCharaPhysics, StateCharaValue,
-        StateCharacterInit, StateCharacterUpdate,
+        StateCharaBuff, StateCharaControl, StateCharaHitBox, StateCharaHitBoxPair, StateCharaHitGroupPair,
+        StateCharaPhysics, StateCharaValue, StateCharacterInit, StateCharacterUpdate,
     };
     use crate::logic::game::{HitCharacterEvent, StateGameInit, StateGameUpdate};
+    use crate::logic::hit_object::{StateHitObjectInit, StateHitObjectPair, StateHitObjectUpdate};
     use crate::logic::system::{StateIdentity, StateRandom};
-    use crate::logic::zone::{StateZoneInit, StateZoneUpdate};
-    use crate::utils::{Castable, TmplID, sb, smallvec};
+    use crate::logic::zone::{StateZoneInit, StateZoneOverlap, StateZoneUpdate, ZoneEvent, ZoneEventType};
+    use crate::utils::{ArrayVec, Castable, HitInteraction, TmplID, id, sb, smallvec};
     use anyhow::Result;
-    use glam::Vec3A;
+    use glam::{Quat, Vec3A};
     use glam_ext::Vec2xz;
-    use jolt_physics_rs::BodyID;
 
     fn test_rkyv(state: Box<dyn StateAny>, typ: StateType, logic_typ: LogicType) -> Result<Box<dyn StateAny>> {
         use rkyv::Archived;
@@ -470,6 +491,7 @@ mod tests {
                     src_chara_id: NumID(100),
                     dst_chara_id: NumID(101),
                     group: sb!("group-name"),
+                    interaction: HitInteraction::Counter,
                     ..Default::default()
                 }],
             }),
@@ -496,6 +518,7 @@ mod tests {
             src_chara_id: NumID(100),
             dst_chara_id: NumID(101),
             group: sb!("group-name"),
+            interaction: HitInteraction::Counter,
             ..Default::default()
         });
     }
@@ -521,6 +544,17 @@ mod tests {
         let state_zone_update = test_rkyv(
             Box::new(StateZoneUpdate {
                 _base: StateBase::new(NumID(8765), StateType::ZoneUpdate, LogicType::Zone),
+                overlaps: vec![StateZoneOverlap {
+                    trigger_index: 1,
+                    chara_id: NumID(100),
+                }],
+                disabled_triggers: vec![2],
+                events: vec![ZoneEvent {
+                    typ: ZoneEventType::Interact,
+                    trigger_index: 1,
+                    trigger: sb!("trigger-name"),
+                    chara_id: NumID(100),
+                }],
             }),
             StateType::ZoneUpdate,
             LogicType::Zone,
@@ -531,6 +565,64 @@ mod tests {
         assert_eq!(state_zone_update.id, 8765);
         assert_eq!(state_zone_update.typ, StateType::ZoneUpdate);
         assert_eq!(state_zone_update.logic_typ, LogicType::Zone);
+        assert_eq!(state_zone_update.overlaps, vec![StateZoneOverlap {
+            trigger_index: 1,
+            chara_id: NumID(100),
+        }]);
+        assert_eq!(state_zone_update.disabled_triggers, vec![2]);
+        assert_eq!(state_zone_update.events.len(), 1);
+        assert_eq!(state_zone_update.events[0].typ, ZoneEventType::Interact);
+        assert_eq!(state_zone_update.events[0].trigger, "trigger-name");
+    }
+
+    #[test]
+    fn test_rkyv_state_hit_object() {
+        let state_obj_new = test_rkyv(
+            Box::new(StateHitObjectInit {
+                _base: StateBase::new(NumID(2468), StateType::HitObjectInit, LogicType::HitObject),
+                tmpl_id: id!("HitObject.Instance.Arrow"),
+                shooter_id: NumID(100),
+                spawn_frame: 30,
+            }),
+            StateType::HitObjectInit,
+            LogicType::HitObject,
+        )
+        .unwrap();
+        assert_eq!(state_obj_new.id(), 2468);
+        let state_obj_new = state_obj_new.cast::<StateHitObjectInit>().unwrap();
+        assert_eq!(state_obj_new.typ, StateType::HitObjectInit);
+        assert_eq!(state_obj_new.logic_typ, LogicType::HitObject);
+        assert_eq!(state_obj_new.tmpl_id, id!("HitObject.Instance.Arrow"));
+        assert_eq!(state_obj_new.shooter_id, NumID(100));
+        assert_eq!(state_obj_new.spawn_frame, 30);
+
+        let state_obj_update = test_rkyv(
+            Box::new(StateHitObjectUpdate {
+                _base: StateBase::new(NumID(1357), StateType::HitObjectUpdate, LogicType::HitObject),
+                position: Vec3A::new(1.0, 2.0, 3.0),
+                velocity: Vec3A::new(0.0, -1.0, 20.0),
+                hit_times: 2,
+                death_frame: 45,
+                chara_pairs: vec![StateHitObjectPair {
+                    dst_chara_id: NumID(200),
+                    last_hit_time: 1.5,
+                    hit_times: 2,
+                }],
+            }),
+            StateType::HitObjectUpdate,
+            LogicType::HitObject,
+        )
+        .unwrap();
+        assert_eq!(state_obj_update.id(), 1357);
+        let state_obj_update = state_obj_update.cast::<StateHitObjectUpdate>().unwrap();
+        assert_eq!(state_obj_update.typ, StateType::HitObjectUpdate);
+        assert_eq!(state_obj_update.logic_typ, LogicType::HitObject);
+        assert_eq!(state_obj_update.position, Vec3A::new(1.0, 2.0, 3.0));
+        assert_eq!(state_obj_update.velocity, Vec3A::new(0.0, -1.0, 20.0));
+        assert_eq!(state_obj_update.hit_times, 2);
+        assert_eq!(state_obj_update.death_frame, 45);
+        assert_eq!(state_obj_update.chara_pairs.len(), 1);
+        assert_eq!(state_obj_update.chara_pairs[0].dst_chara_id, NumID(200));
     }
 
     #[test]
@@ -576,12 +668,22 @@ mod tests {
                     current_routine: TmplID::INVALID,
                     current_routine_exec: 0,
                     target_chara: NumID::INVALID,
+                    aggro_last_time: 0.0,
+                    style_index: 1,
+                    additive_animations: ArrayVec::new(),
                 },
                 physics: StateCharaPhysics {
                     velocity: Vec3A::ONE.into(),
                     position: Vec3A::new(1.0, 2.0, 3.0).into(),
                     direction: Vec2xz::X,
-                    body_ids: smallvec![BodyID(22)],
+                    hit_boxes: vec![StateCharaHitBox {
+                        box_index: 1,
+                        swept: true,
+                        position: Vec3A::new(0.0, 1.0, 1.0),
+                        rotation: Quat::IDENTITY,
+                        prev_position: Vec3A::new(1.0, 1.0, 0.0),
+                        prev_rotation: Quat::IDENTITY,
+                    }],
                     box_pairs: smallvec![StateCharaHitBoxPair {
                         box_index: 10,
                         dst_chara_id: NumID(101),
@@ -595,8 +697,18 @@ mod tests {
                     }],
                 },
                 value: StateCharaValue::default(),
+                buffs: vec![StateCharaBuff {
+                    tmpl_id: id!("Buff.Instance.Burning"),
+                    src_chara_id: NumID(101),
+                    stacks: 1,
+                    start_time: 1.0,
+                    end_time: 6.0,
+                    next_tick_time: 2.0,
+                }],
                 actions: Vec::new(),
+                ai_tasks: Vec::new(),
                 custom_events: Vec::new(),
+                shape_keys: Vec::new(),
             }),
             StateType::CharacterUpdate,
             LogicType::Character,
@@ -611,7 +723,10 @@ mod tests {
         assert_eq!(state_player_update.physics.velocity, Vec3A::ONE);
         assert_eq!(state_player_update.physics.position, Vec3A::new(1.0, 2.0, 3.0));
         assert_eq!(state_player_update.physics.direction, Vec2xz::X);
-        assert_eq!(state_player_update.physics.body_ids.as_slice(), &[BodyID(22)]);
+        assert_eq!(state_player_update.physics.hit_boxes.len(), 1);
+        assert_eq!(state_player_update.physics.hit_boxes[0].box_index, 1);
+        assert!(state_player_update.physics.hit_boxes[0].swept);
+        assert_eq!(state_player_update.physics.hit_boxes[0].position, Vec3A::new(0.0, 1.0, 1.0));
         assert_eq!(state_player_update.physics.box_pairs.as_slice(), &[
             StateCharaHitBoxPair {
                 box_index: 10,
@@ -635,8 +750,14 @@ mod tests {
             current_routine: TmplID::INVALID,
             current_routine_exec: 0,
             target_chara: NumID::INVALID,
+            aggro_last_time: 0.0,
+            style_index: 1,
+            additive_animations: ArrayVec::new(),
         });
         assert_eq!(state_player_update.value, StateCharaValue::default());
+        assert_eq!(state_player_update.buffs.len(), 1);
+        assert_eq!(state_player_update.buffs[0].tmpl_id, id!("Buff.Instance.Burning"));
+        assert_eq!(state_player_update.buffs[0].end_time, 6.0);
         assert_eq!(state_player_update.actions.len(), 0);
     }
 }
@@ -4,19 +4,21 @@ use glam_ext::Vec2xz;
 use std::rc::Rc;
 use std::sync::Arc;
 
-use crate::animation::AnimationFileMeta;
+use crate::animation::{AnimationFileMeta, Animator, ShapeKeyValue};
 use crate::consts::DEFAULT_TOWARD_DIR_2D;
 use crate::instance::InstCharacter;
 use crate::logic::action::StateActionAny;
+use crate::logic::ai_task::StateAiTaskAny;
 use crate::logic::base::{LogicAny, LogicType, StateBase, StateType, impl_state};
 use crate::logic::character::{
-    LogicCharaControl, LogicCharaPhysics, LogicCharaValue, StateCharaControl, StateCharaPhysics, StateCharaValue,
+    AiPerceiver, LogicCharaControl, LogicCharaPhysics, LogicCharaValue, StateCharaBuff, StateCharaControl,
+    StateCharaPhysics, StateCharaValue,
 };
 use crate::logic::game::{ContextHitGenerate, ContextRestore, ContextUpdateEx, HitCharacterEvent};
 use crate::logic::physics::PhyHitCharacterEvent;
 use crate::parameter::{ParamNpc, ParamPlayer};
 use crate::template::{TmplCharacterNpc, TmplStyle};
-use crate::utils::{CustomEvent, NumID, Symbol, XResult, extend};
+use crate::utils::{CustomEvent, HitInteraction, NumID, Symbol, XResult, extend, find_offset_by};
 
 #[repr(C)]
 #[csharp_out(Ref)]
@@ -45,8 +47,11 @@ pub struct StateCharacterUpdate {
     pub control: StateCharaControl,
     pub physics: StateCharaPhysics,
     pub value: StateCharaValue,
+    pub buffs: Vec<StateCharaBuff>,
     pub actions: Vec<Box<dyn StateActionAny>>,
+    pub ai_tasks: Vec<Box<dyn StateAiTaskAny>>, // The running AI task, at most one
     pub custom_events: Vec<CustomEvent>,
+    pub shape_keys: Vec<ShapeKeyValue>,
 }
 
 extend!(StateCharacterUpdate, StateBase);
@@ -99,11 +104,12 @@ impl LogicCharacter {
         ctx: &mut ContextUpdateEx,
         param_player: &ParamPlayer,
     ) -> XResult<(Box<LogicCharacter>, Arc<StateCharacterInit>)> {
-        let inst_chara = InstCharacter::new_player(&mut ctx.context_assemble(), param_player)?;
+        let (inst_styles, style_index) = InstCharacter::new_player_styles(&mut ctx.context_assemble(), param_player)?;
         let tmpl_style = ctx.tmpl_db.find_as::<TmplStyle>(param_player.style)?;
         Self::new_impl(
             ctx,
-            inst_chara,
+            inst_styles,
+            style_index,
             &tmpl_style.view_model,
             param_player.position,
             DEFAULT_TOWARD_DIR_2D,
@@ -118,7 +124,8 @@ impl LogicCharacter {
         let tmpl_chara = ctx.tmpl_db.find_as::<TmplCharacterNpc>(param.character)?;
         Self::new_impl(
             ctx,
-            inst_npc,
+            vec![inst_npc],
+            0,
             &tmpl_chara.view_model,
             param.position,
             DEFAULT_TOWARD_DIR_2D,
@@ -127,11 +134,13 @@ impl LogicCharacter {
 
     fn new_impl(
         ctx: &mut ContextUpdateEx,
-        inst_chara: Rc<InstCharacter>,
+        inst_styles: Vec<Rc<InstCharacter>>,
+        style_index: usize,
         view_model: &str,
         init_position: Vec3A,
         init_direction: Vec2xz,
     ) -> XResult<(Box<LogicCharacter>, Arc<StateCharacterInit>)> {
+        let inst_chara = inst_styles[style_index].clone();
         let id = if inst_chara.is_player {
             ctx.identity.gen_player_id()?
         }
@@ -143,7 +152,7 @@ impl LogicCharacter {
             spawn_frame: ctx.time.frame,
             death_frame: u32::MAX,
             inst: inst_chara.clone(),
-            control: LogicCharaControl::new(ctx, id, inst_chara.clone(), inst_chara.ai_brain.clone())?,
+            control: LogicCharaControl::new(ctx, id, inst_styles, style_index, inst_chara.ai_brain.clone())?,
             physics: LogicCharaPhysics::new(ctx, id, inst_chara.clone(), init_position, init_direction)?,
             value: LogicCharaValue::new(ctx, id, inst_chara.clone()),
         });
@@ -160,7 +169,7 @@ impl LogicCharacter {
         });
 
         chara.control.init(ctx, &chara.physics, &chara.value)?;
-        chara.control.apply_animations(ctx)?;
+        chara.control.apply_animations(ctx, &chara.physics)?;
         chara.physics.init(ctx, &chara.control)?;
         chara.value.init(ctx)?;
         Ok((chara, state_init))
@@ -168,32 +177,118 @@ impl LogicCharacter {
 
     pub fn state(&mut self) -> XResult<Box<StateCharacterUpdate>> {
         let (action, actions, custom_events) = self.control.take_states()?;
+        let mut ai_tasks = Vec::new();
+        self.control.ai_task_states_into(&mut ai_tasks);
         Ok(Box::new(StateCharacterUpdate {
             _base: StateBase::new(self.id, StateType::CharacterUpdate, LogicType::Character),
             control: action,
             physics: self.physics.state(),
             value: self.value.state(),
+            buffs: self.value.buff_states(),
             actions,
+            ai_tasks,
             custom_events,
+            shape_keys: self.control.take_shape_keys(),
         }))
     }
 
+    /// Same as `state()`, but fills a pooled state instead of allocating a new one.
+    pub(crate) fn state_into(&mut self, mut state: Box<StateCharacterUpdate>) -> XResult<Box<StateCharacterUpdate>> {
+        state.control = self
+            .control
+            .take_states_into(&mut state.actions, &mut state.custom_events)?;
+        state._base = StateBase::new(self.id, StateType::CharacterUpdate, LogicType::Character);
+        state.physics = self.physics.state();
+        state.value = self.value.state();
+        self.value.buff_states_into(&mut state.buffs);
+        self.control.ai_task_states_into(&mut state.ai_tasks);
+        self.control.take_shape_keys_into(&mut state.shape_keys);
+        Ok(state)
+    }
+
     pub fn restore(&mut self, ctx: &ContextRestore) -> XResult<()> {
         let state = ctx.find_as::<StateCharacterUpdate>(self.id)?;
         self.control.restore(ctx, &state.control, &state.actions)?;
+        if !Rc::ptr_eq(&self.inst, self.control.inst_chara()) {
+            self.switch_style();
+        }
         self.physics.restore(ctx, &state.physics)?;
-        self.value.restore(ctx, &state.value)?;
+        self.value.restore(ctx, &state.value, &state.buffs)?;
         Ok(())
     }
 
-    #[inline]
+    /// Restores the parts not kept as plain data, called after `restore()`.
+    /// Moves (or rebuilds) the Jolt bodies, and recreates the AI task if it was changed after the frame.
+    pub(crate) fn restore_ex(&mut self, ctx: &mut ContextUpdateEx, ctx_restore: &ContextRestore) -> XResult<()> {
+        let state = ctx_restore.find_as::<StateCharacterUpdate>(self.id)?;
+        self.control.restore_ai_task(ctx, &state.ai_tasks)?;
+        let hit_motion = self.control.hit_motion_sampler().map(|sampler| sampler.hit_motion.as_ref());
+        self.physics.restore_bodies(&mut ctx.physics, &state.physics, hit_motion)
+    }
+
+    /// Restores a character that has no history before the keyframe, used by late-join.
+    pub fn rebuild(&mut self, ctx: &mut ContextUpdateEx, ctx_restore: &ContextRestore) -> XResult<()> {
+        let state = ctx_restore.find_as::<StateCharacterUpdate>(self.id)?;
+        self.control.rebuild_actions(ctx, &state.control, &state.actions)?;
+        self.value.rebuild_buffs(ctx, &state.buffs)?;
+        self.restore(ctx_restore)?;
+        self.restore_ex(ctx, ctx_restore)
+    }
+
     pub fn update_control(&mut self, ctx: &mut ContextUpdateEx) -> XResult<()> {
-        self.control.update(ctx, &self.physics, &self.value)
+        self.control.update(ctx, &self.physics, &self.value)?;
+        if self.control.style_switched() {
+            self.switch_style();
+        }
+        Ok(())
+    }
+
+    fn switch_style(&mut self) {
+        self.inst = self.control.inst_chara().clone();
+        self.physics.switch_style(self.inst.clone());
+        self.value.switch_style(self.inst.clone());
     }
 
     #[inline]
     pub fn update_physics(&mut self, ctx: &mut ContextUpdateEx) -> XResult<()> {
-        self.control.apply_animations(ctx)?;
+        self.control.apply_animations(ctx, &self.physics)?;
+        self.physics.update(ctx, &self.control)
+    }
+
+    // The parallel update splits `update_control()` and `update_physics()` into sequential steps and
+    // steps over `Send` data. The results are the same as the single-threaded update.
+
+    /// See `LogicCharaControl::ai_perceiver()`.
+    #[inline]
+    pub(crate) fn ai_perceiver(&self) -> Option<AiPerceiver> {
+        match self.is_player() {
+            true => None,
+            false => self.control.ai_perceiver(&self.physics),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_perceived_targets(&mut self, indexes: &mut Vec<u32>) {
+        if !self.is_player() {
+            self.control.set_perceived_targets(indexes);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn prepare_animations(&mut self, ctx: &mut ContextUpdateEx) -> XResult<()> {
+        self.control.prepare_animations(ctx, &self.physics)
+    }
+
+    #[inline]
+    pub(crate) fn animator_mut(&mut self) -> &mut Animator {
+        self.control.animator_mut()
+    }
+
+    /// `update_physics()` without the animation part, after `Animator::animate()`.
+    #[inline]
+    pub(crate) fn update_physics_sampled(&mut self, ctx: &mut ContextUpdateEx) -> XResult<()> {
+        self.control.finish_sampling();
+        self.control.apply_foot_ik(ctx, &self.physics)?;
         self.physics.update(ctx, &self.control)
     }
 
@@ -207,10 +302,11 @@ impl LogicCharacter {
         dst_chara: &mut LogicCharacter,
         ctx: &mut ContextHitGenerate<HitCharacterEvent>,
         phy_event: &PhyHitCharacterEvent,
+        interaction: HitInteraction,
     ) -> XResult<()> {
-        let event_count = self
-            .physics
-            .detect_hits(&mut dst_chara.physics, ctx, &self.control, phy_event)?;
+        let event_count =
+            self.physics
+                .detect_hits(&mut dst_chara.physics, ctx, &self.control, phy_event, interaction)?;
         if event_count == 0 {
             return Ok(());
         }
@@ -224,6 +320,31 @@ impl LogicCharacter {
             self.value
                 .before_hit(&mut dst_chara.value, &mut ctx.context_update(idx), phy_event)?;
         }
+
+        // Apply the buffs and elemental build-up of the hit to the dst character, unless blocked
+        if interaction == HitInteraction::Blocked {
+            return Ok(());
+        }
+        if let Some(curr_act) = self.control.current_action() {
+            let event = &ctx.events[ctx.events.len() - 1];
+            let inst_hit = find_offset_by(&curr_act.inst.hits, event.group_index as usize, |hit| {
+                hit.group == event.group
+            });
+            if let Some(inst_hit) = inst_hit {
+                for _ in 0..event_count {
+                    for buff in inst_hit.buffs.iter() {
+                        dst_chara.value.request_buff(*buff, self.id);
+                    }
+                }
+                let count = event_count as f32;
+                dst_chara.value.add_buildup(
+                    ctx.time,
+                    inst_hit.fire_buildup * count,
+                    inst_hit.ice_buildup * count,
+                    inst_hit.thunder_buildup * count,
+                );
+            }
+        }
         Ok(())
     }
 
@@ -246,18 +367,38 @@ impl LogicCharacter {
         &self.physics
     }
 
+    #[inline]
+    pub(crate) fn physics_mut(&mut self) -> &mut LogicCharaPhysics {
+        &mut self.physics
+    }
+
     #[inline]
     pub(crate) fn value(&self) -> &LogicCharaValue {
         &self.value
     }
+
+    #[cfg(test)]
+    #[inline]
+    pub(crate) fn value_mut(&mut self) -> &mut LogicCharaValue {
+        &mut self.value
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use approx::assert_abs_diff_eq;
+
+    use std::ops::RangeInclusive;
+
+    use crate::consts::{BURN_DAMAGE_RATIO, FPS, FREEZE_TIME_SPEED};
+    use crate::input::InputPlayerInputs;
+    use crate::instance::InstBuff;
     use crate::logic::action::StateActionIdle;
+    use crate::logic::game::GameTime;
+    use crate::logic::system::StateSet;
     use crate::logic::test_utils::*;
-    use crate::utils::{Castable, id, sb};
+    use crate::utils::{Castable, RawInput, RawKey, TimeRange, cf2s, id, sb};
 
     fn prepare_player(tenv: &mut TestEnv) -> (Box<LogicCharacter>, Arc<StateCharacterInit>) {
         let param_player = ParamPlayer {
@@ -295,11 +436,490 @@ mod tests {
 
         let state_update = logic_player.state().unwrap();
         assert_eq!(state_update.id, 100);
+        assert_eq!(state_update.control.style_index, 0);
+        assert_eq!(state_update.value.health, 850.0);
+        assert_eq!(state_update.value.posture, 145.0);
         assert_eq!(state_update.actions.len(), 1);
         let state_act = state_update.actions[0].as_ref().cast::<StateActionIdle>().unwrap();
         assert_eq!(state_act.tmpl_id, id!("Action.Instance.Idle^1A"));
     }
 
+    #[test]
+    fn test_logic_player_buffs() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        let max_health = logic_player.value.panel().max_health;
+        let fire_defense = logic_player.value.panel().fire_defense;
+        let burning = InstBuff::new(
+            &mut tenv.context_update().context_assemble(),
+            id!("Buff.Instance.Burning"),
+        )
+        .unwrap();
+        let tick_frames = tenv.time.s2ff_round(burning.tick_interval) as u32;
+        let duration_frames = tenv.time.s2ff_round(burning.duration) as u32;
+        // Ticks strictly inside the duration, none at the expiry
+        let ticks = (burning.duration / burning.tick_interval).ceil() as u32 - 1;
+
+        logic_player
+            .value
+            .request_buff(id!("Buff.Instance.Burning"), NumID(200));
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        let buffs = logic_player.value.buff_states();
+        assert_eq!(buffs.len(), 1);
+        assert_eq!(buffs[0].tmpl_id, id!("Buff.Instance.Burning"));
+        assert_eq!(buffs[0].src_chara_id, NumID(200));
+        assert_eq!(buffs[0].stacks, 1);
+        assert_eq!(logic_player.value.health(), max_health);
+        assert!(logic_player.value.panel().fire_defense < fire_defense);
+        let value_state = logic_player.value.state();
+
+        // One tick interval later, ticks once
+        tenv.time = tenv.time.at(TestEnv::FRAME + tick_frames, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.value.health(), max_health + burning.tick_health);
+
+        // Refresh keeps one stack
+        logic_player
+            .value
+            .request_buff(id!("Buff.Instance.Burning"), NumID(200));
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        let buffs2 = logic_player.value.buff_states();
+        assert_eq!(buffs2[0].stacks, 1);
+        assert_eq!(buffs2[0].end_time, tenv.time.time + burning.duration);
+        assert_eq!(buffs2[0].next_tick_time, tenv.time.time + burning.tick_interval);
+        assert_eq!(logic_player.value.health(), max_health + burning.tick_health);
+
+        // Expired, after the refreshed duration
+        tenv.time = tenv.time.at(TestEnv::FRAME + tick_frames + duration_frames + tick_frames, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert!(logic_player.value.buff_states().is_empty());
+        assert_eq!(logic_player.value.health(), max_health + burning.tick_health * (ticks + 1) as f32);
+        assert_eq!(logic_player.value.panel().fire_defense, fire_defense);
+
+        // Restore to the first frame
+        let ctx = ContextRestore::new(Arc::new(StateSet::new(TestEnv::FRAME)));
+        logic_player.value.restore(&ctx, &value_state, &buffs).unwrap();
+        assert_eq!(logic_player.value.buff_states(), buffs);
+        assert_eq!(logic_player.value.health(), max_health);
+        assert!(logic_player.value.panel().fire_defense < fire_defense);
+    }
+
+    #[test]
+    fn test_logic_player_buffs_expiry() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        let max_health = logic_player.value.panel().max_health;
+        let burning = InstBuff::new(
+            &mut tenv.context_update().context_assemble(),
+            id!("Buff.Instance.Burning"),
+        )
+        .unwrap();
+        let duration_frames = tenv.time.s2ff_round(burning.duration) as u32;
+
+        // The duration is a multiple of the tick interval, 1s ~ 4s ticks and 5s expires
+        logic_player
+            .value
+            .request_buff(id!("Buff.Instance.Burning"), NumID(200));
+        let mut ticks = 0;
+        let mut health = max_health;
+        for frame in 0..=duration_frames + 2 {
+            tenv.time = tenv.time.at(TestEnv::FRAME + frame, 95);
+            logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+            if logic_player.value.health() != health {
+                health = logic_player.value.health();
+                ticks += 1;
+            }
+        }
+        assert!(logic_player.value.buff_states().is_empty());
+        assert_eq!(ticks, 4);
+        assert_eq!(logic_player.value.health(), max_health + burning.tick_health * 4.0);
+    }
+
+    #[test]
+    fn test_logic_player_buffs_stack() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        let max_health = logic_player.value.panel().max_health;
+        let poison = InstBuff::new(&mut tenv.context_update().context_assemble(), id!("Buff.Instance.Poison")).unwrap();
+        let tick_frames = tenv.time.s2ff_round(poison.tick_interval) as u32;
+        let time0 = tenv.time.time;
+
+        logic_player
+            .value
+            .request_buff(id!("Buff.Instance.Poison"), NumID(200));
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.value.buff_states()[0].stacks, 1);
+
+        // Ticks with one stack, then stacks up and restarts the duration and the tick interval
+        tenv.time = tenv.time.at(TestEnv::FRAME + tick_frames, 95);
+        let time1 = tenv.time.time;
+        logic_player
+            .value
+            .request_buff(id!("Buff.Instance.Poison"), NumID(200));
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.value.health(), max_health + poison.tick_health);
+        let buffs = logic_player.value.buff_states();
+        assert_eq!(buffs[0].stacks, 2);
+        assert_eq!(buffs[0].start_time, time0);
+        assert_eq!(buffs[0].end_time, time1 + poison.duration);
+        assert_eq!(buffs[0].next_tick_time, time1 + poison.tick_interval);
+
+        // Stacks up to max_stacks
+        for _ in 0..3 {
+            logic_player
+                .value
+                .request_buff(id!("Buff.Instance.Poison"), NumID(200));
+        }
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.value.buff_states()[0].stacks, poison.max_stacks);
+        assert_eq!(logic_player.value.health(), max_health + poison.tick_health);
+
+        // Each tick scales with the stacks, 2 ticks before the expiry
+        tenv.time = tenv.time.at(TestEnv::FRAME + tick_frames * 5, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert!(logic_player.value.buff_states().is_empty());
+        let health = max_health + poison.tick_health * (1.0 + 2.0 * poison.max_stacks as f32);
+        assert_eq!(logic_player.value.health(), health);
+    }
+
+    #[test]
+    fn test_logic_player_buffs_restore() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        let value_state = logic_player.value.state();
+        let buffs = vec![StateCharaBuff {
+            tmpl_id: id!("Buff.Instance.Poison"),
+            src_chara_id: NumID(200),
+            stacks: 2,
+            start_time: 0.0,
+            end_time: 3.0,
+            next_tick_time: 1.0,
+        }];
+
+        // A buff never applied to the character, the state is not from its history
+        let ctx = ContextRestore::new(Arc::new(StateSet::new(TestEnv::FRAME)));
+        assert!(logic_player.value.restore(&ctx, &value_state, &buffs).is_err());
+
+        // Late-join rebuilds the buffs before restoring
+        logic_player
+            .value
+            .rebuild_buffs(&mut tenv.context_update_ex(), &buffs)
+            .unwrap();
+        logic_player.value.restore(&ctx, &value_state, &buffs).unwrap();
+        assert_eq!(logic_player.value.buff_states(), buffs);
+    }
+
+    #[test]
+    fn test_logic_player_elements() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        let max_health = logic_player.value.panel().max_health;
+        let max_posture = logic_player.value.panel().max_posture;
+
+        // Decays over time
+        logic_player.value.add_buildup(tenv.time.time, 60.0, 0.0, 0.0);
+        assert_eq!(logic_player.value.state().fire_buildup, 60.0);
+        tenv.time = GameTime::new(TestEnv::FRAME + 30, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert!(logic_player.value.state().fire_buildup < 60.0);
+
+        // FireDefenseDown from Burning increases the build-up
+        logic_player
+            .value
+            .request_buff(id!("Buff.Instance.Burning"), NumID(200));
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        let fire_buildup = logic_player.value.state().fire_buildup;
+        logic_player.value.add_buildup(tenv.time.time, 10.0, 0.0, 0.0);
+        assert!(logic_player.value.state().fire_buildup > fire_buildup + 10.0);
+
+        // Burn
+        logic_player.value.add_buildup(tenv.time.time, 100.0, 0.0, 0.0);
+        let state = logic_player.value.state();
+        assert_eq!(state.fire_buildup, 0.0);
+        assert!(state.burn_time.contains(tenv.time.time));
+        let health = logic_player.value.health();
+        tenv.time = GameTime::new(TestEnv::FRAME + 31, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert!(logic_player.value.health() < health);
+        assert!(logic_player.value.health() > max_health * 0.9);
+
+        // Freeze
+        logic_player.value.add_buildup(tenv.time.time, 0.0, 100.0, 0.0);
+        tenv.time = GameTime::new(TestEnv::FRAME + 32, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.value.time_speed(), 0.5);
+
+        // Shock
+        logic_player.value.add_buildup(tenv.time.time, 0.0, 0.0, 100.0);
+        assert!(logic_player.value.state().shock_time.contains(tenv.time.time));
+        assert_eq!(logic_player.value.posture(), max_posture - max_posture * 0.3);
+
+        // Expired
+        tenv.time = GameTime::new(TestEnv::FRAME + 30 * 8, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        let state = logic_player.value.state();
+        assert_eq!(state.burn_time, TimeRange::EMPTY);
+        assert_eq!(state.freeze_time, TimeRange::EMPTY);
+        assert_eq!(state.shock_time, TimeRange::EMPTY);
+        assert_eq!(logic_player.value.time_speed(), 1.0);
+    }
+
+    #[test]
+    fn test_logic_player_elements_time_speed() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        let max_health = logic_player.value.panel().max_health;
+        let burn_damage = max_health * BURN_DAMAGE_RATIO * tenv.time.spf;
+
+        // Burn
+        logic_player.value.add_buildup(tenv.time.time, 100.0, 0.0, 0.0);
+        tenv.time = tenv.time.at(TestEnv::FRAME + 1, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.value.time_speed(), 1.0);
+        assert_abs_diff_eq!(logic_player.value.health(), max_health - burn_damage, epsilon = 1e-3);
+
+        // Freeze slows down the burn
+        logic_player.value.add_buildup(tenv.time.time, 0.0, 100.0, 0.0);
+        tenv.time = tenv.time.at(TestEnv::FRAME + 2, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.value.time_speed(), FREEZE_TIME_SPEED);
+        let health = max_health - burn_damage * (1.0 + FREEZE_TIME_SPEED);
+        assert_abs_diff_eq!(logic_player.value.health(), health, epsilon = 1e-3);
+
+        // The hit lag pauses the frozen character and the burn
+        logic_player.value.hit_lag_time = TimeRange::new(tenv.time.time, tenv.time.time + cf2s(10));
+        for frame in 3..=8 {
+            tenv.time = tenv.time.at(TestEnv::FRAME + frame, 95);
+            logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+            assert_eq!(logic_player.value.time_speed(), 0.0);
+            assert_abs_diff_eq!(logic_player.value.health(), health, epsilon = 1e-3);
+        }
+
+        // The freeze outlasts the hit lag
+        tenv.time = tenv.time.at(TestEnv::FRAME + 14, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.value.hit_lag_time(), TimeRange::EMPTY);
+        assert_eq!(logic_player.value.time_speed(), FREEZE_TIME_SPEED);
+        let health = health - burn_damage * FREEZE_TIME_SPEED;
+        assert_abs_diff_eq!(logic_player.value.health(), health, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_logic_player_shock_started() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+
+        // Starts in the update after the hit step, only once
+        logic_player.value.add_buildup(tenv.time.time, 0.0, 0.0, 100.0);
+        assert!(!logic_player.value.shock_started());
+        let value_state = logic_player.value.state();
+        assert!(value_state.shock_pending);
+        for frame in 1..=3 {
+            tenv.time = tenv.time.at(TestEnv::FRAME + frame, 95);
+            logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+            assert_eq!(logic_player.value.shock_started(), frame == 1);
+            assert!(logic_player.value.state().shock_time.contains(tenv.time.time));
+        }
+
+        // Rollback to the hit step, starts again in the replay
+        let ctx = ContextRestore::new(Arc::new(StateSet::new(TestEnv::FRAME)));
+        logic_player.value.restore(&ctx, &value_state, &[]).unwrap();
+        assert!(!logic_player.value.shock_started());
+        tenv.time = tenv.time.at(TestEnv::FRAME + 1, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        assert!(logic_player.value.shock_started());
+    }
+
+    #[test]
+    fn test_logic_npc_shock_stagger() {
+        let mut tenv = TestEnv::new().unwrap();
+        let param_npc = ParamNpc {
+            character: id!("CharacterNpc.InstanceNpc^1"),
+            level: 2,
+            ai_brain: id!("AiBrain.InstanceNpc^1"),
+            ..Default::default()
+        };
+        let (mut logic_npc, _) = LogicCharacter::new_npc(&mut tenv.context_update_ex(), &param_npc).unwrap();
+        let current_action = |chara: &LogicCharacter| chara.control.current_action().map(|act| act.tmpl_id());
+        for frame in 1..=3 {
+            tenv.time = tenv.time.at(TestEnv::FRAME + frame, 95);
+            logic_npc.update_value(&mut tenv.context_update_ex()).unwrap();
+            logic_npc.update_control(&mut tenv.context_update_ex()).unwrap();
+        }
+        assert_eq!(current_action(&logic_npc), Some(id!("Action.InstanceNpc.Idle^1A")));
+
+        // Shock in the hit step, staggers in the next frame
+        logic_npc.value.add_buildup(tenv.time.time, 0.0, 0.0, 100.0);
+        tenv.time = tenv.time.at(TestEnv::FRAME + 4, 95);
+        logic_npc.update_value(&mut tenv.context_update_ex()).unwrap();
+        logic_npc.update_control(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(current_action(&logic_npc), Some(id!("Action.InstanceNpc.Hit1^1A")));
+        let hit_action_id = logic_npc.control.current_action().unwrap().id;
+
+        // Staggers only once in a shock status
+        tenv.time = tenv.time.at(TestEnv::FRAME + 5, 95);
+        logic_npc.update_value(&mut tenv.context_update_ex()).unwrap();
+        logic_npc.update_control(&mut tenv.context_update_ex()).unwrap();
+        assert!(logic_npc.value.state().shock_time.contains(tenv.time.time));
+        assert_eq!(logic_npc.control.current_action().unwrap().id, hit_action_id);
+    }
+
+    #[test]
+    fn test_logic_player_foot_ik() {
+        let mut tenv = TestEnv::new().unwrap();
+        // Between two stair steps in Zone.Demo, the tops are at 0.3 (x < 6.17) and 0.4 (x > 6.17)
+        let param_player = ParamPlayer {
+            character: id!("Character.Instance^1"),
+            style: id!("Style.Instance^1A"),
+            level: 4,
+            position: Vec3A::new(6.17, 0.3, -0.57),
+            ..Default::default()
+        };
+        let mut ctx = tenv.context_update_ex();
+        ctx.input.init(1).unwrap();
+        let (logic_player, _) = LogicCharacter::new_player(&mut ctx, &param_player).unwrap();
+
+        // The grounds are found by ray casts, the left foot (+X in the model space) is on the higher step
+        let grounds = logic_player.control.foot_ik().unwrap().grounds();
+        assert_eq!(grounds.len(), 2);
+        assert_abs_diff_eq!(grounds[0].offset, 0.1, epsilon = 1e-3);
+        assert_abs_diff_eq!(grounds[1].offset, 0.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_logic_player_hit_additive() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        for frame in 1..=TestEnv::FRAME + 2 {
+            let inputs = match frame {
+                _ if frame == TestEnv::FRAME + 1 => vec![RawInput::new_button(RawKey::Attack1, true)],
+                _ if frame == TestEnv::FRAME + 2 => vec![RawInput::new_button(RawKey::Attack1, false)],
+                _ => vec![],
+            };
+            let player_inputs = [InputPlayerInputs::new(NumID::MIN_PLAYER, frame, inputs)];
+            tenv.systems.input.produce(&player_inputs).unwrap();
+        }
+        for frame in 1..=2 {
+            tenv.time = tenv.time.at(TestEnv::FRAME + frame, 95);
+            logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+            logic_player.update_control(&mut tenv.context_update_ex()).unwrap();
+        }
+        let attack = logic_player.control.current_action().unwrap();
+        assert_eq!(attack.tmpl_id(), id!("Action.Instance.Attack^1A"));
+        let attack_id = attack.id;
+
+        // Style 1A has no hit action, the shock plays the hit additive in the attack (super armor)
+        logic_player.value.add_buildup(tenv.time.time, 0.0, 0.0, 100.0);
+        tenv.time = tenv.time.at(TestEnv::FRAME + 3, 95);
+        logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+        logic_player.update_control(&mut tenv.context_update_ex()).unwrap();
+        assert_eq!(logic_player.control.current_action().unwrap().id, attack_id);
+        let state = logic_player.state().unwrap();
+        assert_eq!(state.control.additive_animations.len(), 1);
+        let additive = &state.control.additive_animations[0];
+        assert_eq!(additive.files, sb!("Girl/Idle_Empty.*"));
+        assert_eq!(additive.action_id, attack_id);
+        assert_eq!(additive.start_frame, TestEnv::FRAME + 3);
+        assert_eq!(additive.start_time, tenv.time.time);
+        assert_eq!(additive.weight, 0.5);
+        assert!(!additive.looping);
+        let additive = *additive;
+
+        // Finished after the duration
+        for frame in 4..=20 {
+            tenv.time = tenv.time.at(TestEnv::FRAME + frame, 95);
+            logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+            logic_player.update_control(&mut tenv.context_update_ex()).unwrap();
+        }
+        assert_eq!(logic_player.control.current_action().unwrap().id, attack_id);
+        assert!(logic_player.state().unwrap().control.additive_animations.is_empty());
+
+        // Restore to the hit frame
+        let mut state_set = StateSet::new(TestEnv::FRAME + 3);
+        state_set.chara_updates.push(state);
+        logic_player.restore(&ContextRestore::new(Arc::new(state_set))).unwrap();
+        let state = logic_player.state().unwrap();
+        assert_eq!(state.control.additive_animations.as_slice(), &[additive]);
+    }
+
+    #[test]
+    fn test_logic_player_animation_notify() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        let last_frame = TestEnv::FRAME + 90;
+        for frame in 1..=last_frame {
+            let inputs = match frame {
+                _ if frame == TestEnv::FRAME + 1 => vec![RawInput::new_button(RawKey::Attack1, true)],
+                _ if frame == TestEnv::FRAME + 2 => vec![RawInput::new_button(RawKey::Attack1, false)],
+                _ => vec![],
+            };
+            let player_inputs = [InputPlayerInputs::new(NumID::MIN_PLAYER, frame, inputs)];
+            tenv.systems.input.produce(&player_inputs).unwrap();
+        }
+
+        let mut update_frames = |logic_player: &mut LogicCharacter, frames: RangeInclusive<u32>| {
+            let mut notifies = Vec::new();
+            let mut states = Vec::new();
+            for frame in frames {
+                tenv.time = tenv.time.at(frame, 95);
+                logic_player.update_value(&mut tenv.context_update_ex()).unwrap();
+                logic_player.update_control(&mut tenv.context_update_ex()).unwrap();
+                let state = logic_player.state().unwrap();
+                for event in state.custom_events.iter() {
+                    if event.name == "Swing" || event.name == "Hit" {
+                        assert_eq!(event.source, id!("Action.Instance.Attack^1A"));
+                        notifies.push((frame, event.name));
+                    }
+                }
+                states.push(state);
+            }
+            (notifies, states)
+        };
+
+        // Each event is emitted once, at the time of the clip (1.4s) scaled to the action (4s)
+        let (notifies, mut states) = update_frames(logic_player.as_mut(), TestEnv::FRAME + 1..=last_frame);
+        assert_eq!(notifies.len(), 2);
+        let (swing_frame, swing) = notifies[0];
+        let (hit_frame, hit) = notifies[1];
+        assert_eq!(swing, sb!("Swing"));
+        assert_eq!(hit, sb!("Hit"));
+        assert!(swing_frame <= TestEnv::FRAME + 2);
+        assert_abs_diff_eq!((hit_frame - swing_frame) as f32, 2.0 * FPS, epsilon = 1.0);
+
+        // Rollback between the events, the replay emits the same events
+        let restore_frame = swing_frame + 10;
+        let mut state_set = StateSet::new(restore_frame);
+        state_set.chara_updates.push(states.swap_remove((restore_frame - TestEnv::FRAME - 1) as usize));
+        logic_player.restore(&ContextRestore::new(Arc::new(state_set))).unwrap();
+        let (replay_notifies, _) = update_frames(logic_player.as_mut(), restore_frame + 1..=last_frame);
+        assert_eq!(replay_notifies, vec![(hit_frame, hit)]);
+    }
+
+    #[test]
+    fn test_logic_player_switch_style() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (mut logic_player, _) = prepare_player(&mut tenv);
+        assert_eq!(logic_player.value.panel().max_posture, 145.0);
+
+        // Shock takes 30% posture
+        logic_player.value.add_buildup(tenv.time.time, 0.0, 0.0, 100.0);
+        assert_abs_diff_eq!(logic_player.value.posture(), 145.0 * 0.7, epsilon = 1e-3);
+
+        let param_player = ParamPlayer {
+            character: id!("Character.Instance^1"),
+            style: id!("Style.Instance^1B"),
+            level: 4,
+            ..Default::default()
+        };
+        let inst_b =
+            InstCharacter::new_player(&mut tenv.context_update_ex().context_assemble(), &param_player).unwrap();
+        logic_player.value.switch_style(inst_b);
+        assert_eq!(logic_player.value.panel().max_health, 1000.0);
+        assert_eq!(logic_player.value.panel().max_posture, 110.0);
+        assert_eq!(logic_player.value.health(), 1000.0);
+        assert_abs_diff_eq!(logic_player.value.posture(), 110.0 * 0.7, epsilon = 1e-3);
+    }
+
     // #[test]
     // fn test_logic_player_update() {
     //     let mut tenv = TestEnv::new().unwrap();
@@ -3,17 +3,17 @@ use glam::Vec3A;
 use glam_ext::Vec2xz;
 use std::rc::Rc;
 
-use crate::consts::DEFAULT_TOWARD_DIR_2D;
+use crate::consts::{ACTION_WEIGHT_THRESHOLD, DEFAULT_TOWARD_DIR_2D, MAX_ADDITIVE_ANIMATION};
 use crate::input::InputVariables;
-use crate::instance::InstActionAny;
+use crate::instance::{InstActionAny, InstAdditiveAnimation};
 use crate::logic::action::{
-    ActionStartArgs, ContextAction, DeriveKeeping, LogicActionAny, StateActionAny, new_logic_action,
-    try_reuse_logic_action,
+    ActionStartArgs, ContextAction, DeriveKeeping, LogicActionAny, StateActionAny, StateAdditiveAnimation,
+    new_logic_action, try_reuse_logic_action,
 };
 use crate::logic::character::physics::LogicCharaPhysics;
 use crate::logic::character::value::LogicCharaValue;
 use crate::logic::game::ContextUpdateEx;
-use crate::utils::{InputDir, VirtualInput, VirtualKey, XResult, ok_or};
+use crate::utils::{ArrayVec, CustomEvent, InputDir, LEVEL_MOVE, Symbol, VirtualInput, VirtualKey, XResult, ok_or};
 
 use super::control::*;
 
@@ -22,6 +22,7 @@ impl LogicCharaControl {
         &mut self,
         ctx: &mut ContextUpdateEx,
         chara_phy: &LogicCharaPhysics,
+        chara_val: &LogicCharaValue,
     ) -> XResult<Option<NextAction>> {
         let current_act = self.action_queue.last().unwrap(); // verified
         let player_dir = chara_phy.direction_xz();
@@ -39,9 +40,65 @@ impl LogicCharaControl {
                 next_act = Some(NextAction::new(act, VirtualKey::Hit1, hit_dir));
             }
         }
+
+        // The shock status staggers the character from the front, interrupting the current action.
+        let shock_started = chara_val.shock_started();
+        if next_act.is_none() && shock_started {
+            let hit_dir = -player_dir;
+            if let Some(act) = self.find_next_action_impl(current_act, None, player_dir, VirtualKey::Hit1, hit_dir) {
+                next_act = Some(NextAction::new(act, VirtualKey::Hit1, hit_dir));
+            }
+        }
+
+        // The hit doesn't switch the action (such as super armor), flinch with an additive animation.
+        if next_act.is_none()
+            && (!chara_phy.be_hit_events().is_empty() || shock_started)
+            && let Some(hit_additive) = current_act.inst.hit_additive
+        {
+            play_additive_animations(&mut self.additive_animations, &[hit_additive], current_act.id, ctx);
+        }
         Ok(next_act)
     }
 
+    /// Switches to the next style on the Switch key, and enters the switch action of the new style.
+    /// Falls back to the idle action of the new style, if it has no switch action.
+    pub(super) fn handle_player_switch(&mut self, ctx: &mut ContextUpdateEx) -> XResult<Option<NextAction>> {
+        if self.inst_styles.len() <= 1 {
+            return Ok(None);
+        }
+
+        let switch = {
+            let player_inputs = ok_or!(self.player_inputs.as_ref(); return Ok(None)).borrow();
+            let mut switch = false;
+            for input in player_inputs.iter_current(ctx.time.frame)? {
+                switch |= !input.pressed && input.key == VirtualKey::Switch;
+            }
+            switch
+        };
+        if !switch {
+            return Ok(None);
+        }
+
+        // Only switch style in idle/move actions.
+        let current_act = self.action_queue.last().unwrap(); // verified
+        if current_act.is_running() && current_act.keep_level > LEVEL_MOVE {
+            return Ok(None);
+        }
+
+        let next_index = (self.style_index as usize + 1) % self.inst_styles.len();
+        self.switch_style(next_index)?;
+        self.style_switched = true;
+
+        let switch_action = self
+            .inst_chara
+            .primary_keys
+            .find_first(&VirtualKey::Switch)
+            .and_then(|id| self.inst_chara.actions.get(id))
+            .cloned();
+        let next_action = switch_action.unwrap_or_else(|| self.inst_idle_action.clone() as Rc<dyn InstActionAny>);
+        Ok(Some(NextAction::new(next_action, VirtualKey::Switch, Vec2xz::ZERO)))
+    }
+
     pub(super) fn handle_player_inputs(
         &mut self,
         ctx: &mut ContextUpdateEx,
@@ -70,6 +127,9 @@ impl LogicCharaControl {
             if input.pressed {
                 continue;
             }
+            if input.key == VirtualKey::Interact {
+                self.interact_requested = true;
+            }
             next_act = self.find_next_action(current_act, next_act, player_dir, &input);
         }
 
@@ -92,6 +152,11 @@ impl LogicCharaControl {
         player_dir: Vec2xz,
         input: &VirtualInput,
     ) -> Option<NextAction> {
+        // With multiple styles, the Switch key is consumed by `handle_player_switch()`.
+        if input.key == VirtualKey::Switch && self.inst_styles.len() > 1 {
+            return candidate_act;
+        }
+
         let candidate_action = candidate_act.as_ref().map(|act| act.action.clone());
         let next_action = self.find_next_action_impl(
             current_act,
@@ -238,6 +303,8 @@ impl LogicCharaControl {
             self.inst_chara.clone(),
             chara_phy,
             Some(&self.ai_thinking),
+            self.target_chara,
+            ctx.time.spf,
         );
         ctxa.set_time_normalized(chara_val.time_speed());
 
@@ -257,6 +324,8 @@ impl LogicCharaControl {
         }
 
         self.action_events = ret.custom_events;
+        self.hit_object_requests.extend(ret.hit_objects);
+        play_additive_animations(&mut self.additive_animations, &ret.additive_animations, current_act.id, ctx);
 
         if current_act.is_stopping() {
             // Trigger derive keeping, when current action actively stops.
@@ -304,6 +373,8 @@ impl LogicCharaControl {
             self.inst_chara.clone(),
             chara_phy,
             Some(&self.ai_thinking),
+            self.target_chara,
+            ctx.time.spf,
         );
         ctxa.set_time_normalized(chara_val.time_speed());
 
@@ -313,7 +384,7 @@ impl LogicCharaControl {
             current_act.start(ctx, &mut ctxa, &args)?
         };
 
-        let mut previous_frame_state = current_act.save();
+        let mut previous_frame_state = current_act.save_pooled(ctx.state.action_pool());
         previous_frame_state.set_previous_frame(true);
 
         if ret.clear_preinput {
@@ -323,6 +394,8 @@ impl LogicCharaControl {
         }
 
         self.action_events.extend(ret.custom_events);
+        self.hit_object_requests.extend(ret.hit_objects);
+        play_additive_animations(&mut self.additive_animations, &ret.additive_animations, current_act.id, ctx);
 
         // Clear derive keeping, if current action not supported.
         if !current_act.inst.derive_keeping {
@@ -375,7 +448,7 @@ impl LogicCharaControl {
                 zero_count += 1;
             }
 
-            let mut act_state = logic_act.save();
+            let mut act_state = logic_act.save_pooled(ctx.state.action_pool());
             #[cfg(debug_assertions)]
             {
                 debug_assert!(
@@ -402,7 +475,12 @@ impl LogicCharaControl {
             }
 
             act_state.fade_in_weight = (unused_weight * act_state.fade_in_weight).clamp(0.0, 1.0);
-            unused_weight = (unused_weight - act_state.fade_in_weight).max(0.0);
+            // Partial-body actions play over the actions below them, which keep their weights.
+            let mut anims = act_state.animations.iter().filter(|anim| !anim.is_empty()).peekable();
+            let partial = anims.peek().is_some() && anims.all(|anim| !anim.joint_mask.is_empty());
+            if !partial {
+                unused_weight = (unused_weight - act_state.fade_in_weight).max(0.0);
+            }
             self.cache_action_states.push(act_state);
         }
         self.cache_action_states.reverse();
@@ -414,12 +492,16 @@ impl LogicCharaControl {
             self.cache_action_states.swap(len - 1, len - 2);
         }
 
+        self.emit_animation_notifies(ctx)?;
+
         // Finalize actions
         let mut ctxa = ContextAction::new(
             self.chara_id,
             self.inst_chara.clone(),
             chara_phy,
             Some(&self.ai_thinking),
+            self.target_chara,
+            ctx.time.spf,
         );
         for idx in 0..zero_count {
             if self.action_queue[idx].is_fading() {
@@ -433,4 +515,93 @@ impl LogicCharaControl {
         self.action_queue.discard(|act| act.last_frame <= ctx.time.synced_frame);
         Ok(())
     }
+
+    /// Emits the notify events of the animations in the collected states, fading actions included.
+    /// The events passed since the ratios of the previous frame are emitted.
+    ///
+    /// An animation without cursor just started in this frame, maybe in the middle of the clip. Its events
+    /// are emitted from the start ratio (saved in the previous frame state), not from the clip beginning.
+    fn emit_animation_notifies(&mut self, ctx: &mut ContextUpdateEx) -> XResult<()> {
+        for state in self.cache_action_states.iter().filter(|s| !s.previous_frame()) {
+            for anim in state.animations.iter() {
+                if !anim.notify() || anim.is_empty() || anim.weight < ACTION_WEIGHT_THRESHOLD {
+                    continue;
+                }
+                let notify = ctx.asset.load_animation_notify(anim.files)?;
+                let cursor = self
+                    .notify_cursors
+                    .iter()
+                    .find(|cursor| cursor.is_same(state.id, anim.files, anim.animation_id));
+                if let Some(cursor) = cursor {
+                    self.action_events.extend(
+                        notify
+                            .find_events(cursor.ratio, anim.ratio)
+                            .map(|event| CustomEvent::new(state.tmpl_id, event.name)),
+                    );
+                }
+                else {
+                    let start_ratio = self
+                        .cache_action_states
+                        .iter()
+                        .filter(|s| s.previous_frame() && s.id == state.id)
+                        .flat_map(|s| s.animations.iter())
+                        .find(|a| a.files == anim.files && a.animation_id == anim.animation_id && !a.is_empty())
+                        .map_or(anim.ratio, |a| a.ratio);
+                    self.action_events.extend(
+                        notify
+                            .find_started_events(start_ratio, anim.ratio)
+                            .map(|event| CustomEvent::new(state.tmpl_id, event.name)),
+                    );
+                }
+            }
+        }
+        collect_notify_cursors(&self.cache_action_states, &mut self.notify_cursors);
+        Ok(())
+    }
+}
+
+/// The ratio of an animation with notify track in the last frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct NotifyCursor {
+    action_id: u32,
+    files: Symbol,
+    animation_id: u16,
+    ratio: f32,
+}
+
+impl NotifyCursor {
+    #[inline]
+    fn is_same(&self, action_id: u32, files: Symbol, animation_id: u16) -> bool {
+        self.action_id == action_id && self.files == files && self.animation_id == animation_id
+    }
+}
+
+/// Rebuilds the notify cursors from the action states, also used to restore the cursors.
+pub(super) fn collect_notify_cursors(states: &[Box<dyn StateActionAny>], cursors: &mut Vec<NotifyCursor>) {
+    cursors.clear();
+    for state in states.iter().filter(|s| !s.previous_frame()) {
+        for anim in state.animations.iter().filter(|anim| anim.notify() && !anim.is_empty()) {
+            cursors.push(NotifyCursor {
+                action_id: state.id,
+                files: anim.files,
+                animation_id: anim.animation_id,
+                ratio: anim.ratio,
+            });
+        }
+    }
+}
+
+/// Plays the additive animations of an action from the current frame, replaces the oldest ones when full.
+fn play_additive_animations(
+    additive_animations: &mut ArrayVec<StateAdditiveAnimation, MAX_ADDITIVE_ANIMATION>,
+    insts: &[InstAdditiveAnimation],
+    action_id: u32,
+    ctx: &ContextUpdateEx,
+) {
+    for inst in insts {
+        if additive_animations.is_full() {
+            additive_animations.remove(0);
+        }
+        additive_animations.push(StateAdditiveAnimation::new(inst, action_id, ctx.time.frame, ctx.time.time));
+    }
 }
@@ -1,19 +1,76 @@
+use glam::Vec3A;
+use glam_ext::Vec2xz;
 use std::rc::Rc;
-use std::usize;
+use std::{mem, usize};
 
-use crate::instance::{InstAiRoutine, InstAiRoutineItem, InstAiTaskAny};
-use crate::logic::ai_task::{AiTaskReturn, ContextAiTask, new_logic_ai_task};
+use crate::instance::{InstAiBrain, InstAiRoutine, InstAiRoutineItem, InstAiTaskAny};
+use crate::logic::ai_task::{AiTaskReturn, ContextAiTask, StateAiTaskAny, new_logic_ai_task};
 use crate::logic::base::LogicAny;
 use crate::logic::character::physics::LogicCharaPhysics;
 use crate::logic::character::value::LogicCharaValue;
-use crate::logic::game::ContextUpdateEx;
-use crate::utils::{NumID, TmplID, XResult, ok_or};
+use crate::logic::game::{CharaGrid, CharaSpots, ContextUpdateEx};
+use crate::logic::system::RandomStream;
+use crate::utils::{AiIntention, NumID, ShapeSphere, ShapeSphericalCone, TmplID, XResult, ok_or, xerrf};
 use crate::xresf;
 
 use super::control::*;
 
+/// A copy of what the AI target searching reads from the searching character.
+/// Plain data, so the searches of all characters can run on worker threads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct AiPerceiver {
+    alert_cone: ShapeSphericalCone,
+    aggro_sphere: ShapeSphere,
+    position: Vec3A,
+    direction: Vec2xz,
+}
+
+impl AiPerceiver {
+    #[inline]
+    fn new(inst_ai_brain: &InstAiBrain, chara_phy: &LogicCharaPhysics) -> AiPerceiver {
+        AiPerceiver {
+            alert_cone: inst_ai_brain.alert_cone,
+            aggro_sphere: inst_ai_brain.aggro_sphere,
+            position: chara_phy.position(),
+            direction: chara_phy.direction_xz(),
+        }
+    }
+
+    /// Appends the indexes of the target candidates.
+    pub(crate) fn search<C: CharaSpots>(&self, characters: &C, chara_grid: &CharaGrid, indexes: &mut Vec<u32>) {
+        // Find new target in alert cone first.
+        chara_grid.search_chara_in_spherical_cone(
+            characters,
+            true,
+            &self.alert_cone,
+            self.position,
+            self.direction,
+            indexes,
+        );
+        if indexes.is_empty() {
+            // Find new target in aggro sphere.
+            chara_grid.search_chara_in_sphere(characters, true, &self.aggro_sphere, self.position, indexes);
+        }
+    }
+}
+
 impl LogicCharaControl {
+    /// Copies the data for searching the target candidates ahead of the control pass.
+    /// The result is handed over by `set_perceived_targets()`.
+    #[inline]
+    pub(crate) fn ai_perceiver(&self, chara_phy: &LogicCharaPhysics) -> Option<AiPerceiver> {
+        let inst_ai_brain = self.inst_ai_brain.as_ref()?;
+        Some(AiPerceiver::new(inst_ai_brain, chara_phy))
+    }
+
+    #[inline]
+    pub(crate) fn set_perceived_targets(&mut self, indexes: &mut Vec<u32>) {
+        mem::swap(&mut self.tmp_target_indexes, indexes);
+        self.targets_perceived = true;
+    }
+
     pub(super) fn update_ai_target(&mut self, ctx: &mut ContextUpdateEx, chara_phy: &LogicCharaPhysics) {
+        let perceived = mem::take(&mut self.targets_perceived);
         let inst_ai_brain = ok_or!(self.inst_ai_brain.as_ref(); return);
 
         self.ai_thinking.reset();
@@ -48,27 +105,18 @@ impl LogicCharaControl {
             }
         }
 
-        self.tmp_target_indexes.clear();
-        // Find new target in alert cone first.
-        ctx.characters.search_chara_in_spherical_cone(
-            true,
-            &inst_ai_brain.alert_cone,
-            chara_phy.position(),
-            chara_phy.direction_xz(),
-            &mut self.tmp_target_indexes,
-        );
-        if self.tmp_target_indexes.is_empty() {
-            // Find new target in aggro sphere.
-            ctx.characters.search_chara_in_sphere(
-                true,
-                &inst_ai_brain.aggro_sphere,
-                chara_phy.position(),
+        // Positions don't change in the control pass, so the perceived candidates are the same.
+        if !perceived {
+            self.tmp_target_indexes.clear();
+            AiPerceiver::new(inst_ai_brain, chara_phy).search(
+                &ctx.characters,
+                ctx.chara_grid,
                 &mut self.tmp_target_indexes,
             );
         }
 
         if !self.tmp_target_indexes.is_empty() {
-            let rand = ctx.systems.rand.rand_u32() as usize % self.tmp_target_indexes.len();
+            let rand = ctx.systems.rand.stream(RandomStream::Ai).rand_u32() as usize % self.tmp_target_indexes.len();
             let idx = self.tmp_target_indexes[rand] as usize;
             let target = ctx.characters[idx].as_ref();
 
@@ -133,7 +181,14 @@ impl LogicCharaControl {
     ) -> XResult<AiTaskReturn> {
         let mut task = ok_or!(self.current_task.take(); return Ok(AiTaskReturn::default()));
 
-        let mut ctxt = ContextAiTask::new(self.inst_chara.clone(), self, chara_phy, &self.ai_thinking, ctx.zone);
+        let mut ctxt = ContextAiTask::new(
+            self.inst_chara.clone(),
+            self,
+            chara_phy,
+            &self.ai_thinking,
+            ctx.zone,
+            ctx.time.spf,
+        );
         ctxt.set_time_normalized(chara_val.time_speed());
 
         let res = task.update(ctx, &mut ctxt)?;
@@ -210,6 +265,47 @@ impl LogicCharaControl {
         Ok(ExecuteResult::None)
     }
 
+    /// Saves the running AI task, at most one.
+    pub(crate) fn ai_task_states_into(&self, states: &mut Vec<Box<dyn StateAiTaskAny>>) {
+        states.clear();
+        if let Some(task) = self.current_task.as_ref() {
+            states.push(task.save());
+        }
+    }
+
+    /// Restores the running AI task. A task not running now is recreated from the AI brain, the id
+    /// it generates is dropped when the identity system is restored afterwards.
+    pub(crate) fn restore_ai_task(
+        &mut self,
+        ctx: &mut ContextUpdateEx,
+        states: &[Box<dyn StateAiTaskAny>],
+    ) -> XResult<()> {
+        let Some(state) = states.first()
+        else {
+            self.current_task = None;
+            self.ws.current_task = TmplID::INVALID;
+            self.ws.ai_intention = AiIntention::Idle;
+            return Ok(());
+        };
+
+        if self.current_task.as_ref().is_none_or(|task| task.id != state.id()) {
+            let inst_task = self
+                .inst_ai_brain
+                .as_ref()
+                .and_then(|brain| brain.tasks.get(&state.tmpl_id).cloned())
+                .ok_or_else(|| xerrf!(LogicNotFound; "chara_id={}, task={}", self.chara_id, state.tmpl_id))?;
+            let mut task = new_logic_ai_task(ctx, inst_task, self.inst_chara.clone())?;
+            task.id = state.id();
+            self.current_task = Some(task);
+        }
+
+        let task = self.current_task.as_mut().unwrap();
+        task.restore(state.as_ref())?;
+        self.ws.current_task = task.inst.tmpl_id;
+        self.ws.ai_intention = task.intention;
+        Ok(())
+    }
+
     fn start_ai_task(
         &mut self,
         ctx: &mut ContextUpdateEx,
@@ -218,7 +314,14 @@ impl LogicCharaControl {
         inst_task: Rc<dyn InstAiTaskAny>,
     ) -> XResult<AiTaskReturn> {
         if let Some(mut old_task) = self.current_task.take() {
-            let mut ctxt = ContextAiTask::new(self.inst_chara.clone(), self, chara_phy, &self.ai_thinking, ctx.zone);
+            let mut ctxt = ContextAiTask::new(
+                self.inst_chara.clone(),
+                self,
+                chara_phy,
+                &self.ai_thinking,
+                ctx.zone,
+                ctx.time.spf,
+            );
             ctxt.set_time_normalized(chara_val.time_speed());
             old_task.stop(ctx, &mut ctxt)?;
             old_task.finalize(ctx, &mut ctxt)?;
@@ -226,7 +329,14 @@ impl LogicCharaControl {
 
         let mut task = new_logic_ai_task(ctx, inst_task, self.inst_chara.clone())?;
 
-        let mut ctxt = ContextAiTask::new(self.inst_chara.clone(), self, chara_phy, &self.ai_thinking, ctx.zone);
+        let mut ctxt = ContextAiTask::new(
+            self.inst_chara.clone(),
+            self,
+            chara_phy,
+            &self.ai_thinking,
+            ctx.zone,
+            ctx.time.spf,
+        );
         ctxt.set_time_normalized(chara_val.time_speed());
         let ret = task.start(ctx, &mut ctxt)?;
 
@@ -6,21 +6,28 @@ use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
-use crate::animation::{AnimationFileMeta, Animator, HitMotionSampler};
-use crate::consts::{DEFAULT_TOWARD_DIR_2D, MAX_ACTION_ANIMATION};
+use crate::animation::{AnimationFileMeta, Animator, FootIk, HitMotionSampler, JointMask, LookAt, ShapeKeyValue};
+use crate::consts::{
+    DEFAULT_TOWARD_DIR_2D, DEFAULT_TOWARD_DIR_3D, FOOT_IK_RAY_DOWN, FOOT_IK_RAY_UP, INVALID_ACTION_ID,
+    INVALID_ANIMATION_ID, MAX_ACTION_ANIMATION, MAX_ADDITIVE_ANIMATION,
+};
 use crate::input::RefInputEventQueue;
 use crate::instance::{InstActionAny, InstActionIdle, InstAiBrain, InstAiRoutine, InstCharacter};
-use crate::logic::action::{DeriveKeeping, LogicActionAny, StateActionAny};
+use crate::logic::action::{DeriveKeeping, LogicActionAny, StateActionAny, StateAdditiveAnimation, new_logic_action};
 use crate::logic::ai_task::{AiBrainThinking, AiTaskReturn, LogicAiTaskAny, WsAiDo};
 use crate::logic::character::physics::LogicCharaPhysics;
 use crate::logic::character::value::LogicCharaValue;
 use crate::logic::game::{ContextRestore, ContextUpdateEx};
+use crate::logic::physics::phy_cast_ray_scenery;
 use crate::logic::script::WsFuncAiBrainExecute;
 use crate::script::{WsBox, WsVec};
 use crate::utils::{
-    AiIntention, CustomEvent, DtHashMap, HistoryQueue, NumID, TmplID, VirtualInput, VirtualKey, XResult, xerr, xres,
+    AiIntention, ArrayVec, CustomEvent, DtHashMap, HistoryQueue, HitInteraction, NumID, TmplID, VirtualInput,
+    VirtualKey, XResult, xerr, xerrf, xres,
 };
 
+use super::action::{NotifyCursor, collect_notify_cursors};
+
 const DEFAULT_ACTION_QUEUE_CAP: usize = 8;
 
 #[repr(C)]
@@ -37,6 +44,9 @@ pub struct StateCharaControl {
     pub current_routine: TmplID,
     pub current_routine_exec: u32,
     pub target_chara: NumID,
+    pub aggro_last_time: f32,
+    pub style_index: u16,
+    pub additive_animations: ArrayVec<StateAdditiveAnimation, MAX_ADDITIVE_ANIMATION>,
 }
 
 #[repr(C)]
@@ -55,6 +65,8 @@ pub(crate) struct WsCharaControl {
 pub(crate) struct LogicCharaControl {
     pub(super) chara_id: NumID,
     pub(super) inst_chara: Rc<InstCharacter>,
+    pub(super) inst_styles: Vec<Rc<InstCharacter>>,
+    pub(super) style_index: u16,
     pub(super) inst_idle_action: Rc<InstActionIdle>,
     pub(super) inst_ai_brain: Option<Rc<InstAiBrain>>,
     pub(super) player_inputs: Option<RefInputEventQueue>,
@@ -64,6 +76,8 @@ pub(crate) struct LogicCharaControl {
     pub(super) derive_keeping: DeriveKeeping,
     pub(super) action_changed: bool,
     pub(super) animation_changed: bool,
+    pub(super) interact_requested: bool,
+    pub(super) style_switched: bool,
 
     #[educe(Debug(ignore))]
     pub(super) ai_brain_execute: Option<WsFuncAiBrainExecute>,
@@ -74,6 +88,7 @@ pub(crate) struct LogicCharaControl {
     pub(super) aggro_last_time: f32,
     pub(super) ai_thinking: AiBrainThinking,
     pub(super) tmp_target_indexes: Vec<u32>,
+    pub(super) targets_perceived: bool,
     pub(super) tmp_ai_do_list: WsVec<WsAiDo>,
 
     pub(super) ws: WsBox<WsCharaControl>,
@@ -81,8 +96,12 @@ pub(crate) struct LogicCharaControl {
     pub(super) new_direction: Vec2xz,
     pub(super) cache_action_states: Vec<Box<dyn StateActionAny>>,
     pub(super) action_events: Vec<CustomEvent>,
+    pub(super) hit_object_requests: Vec<TmplID>,
+    pub(super) notify_cursors: Vec<NotifyCursor>,
+    pub(super) additive_animations: ArrayVec<StateAdditiveAnimation, MAX_ADDITIVE_ANIMATION>,
 
     pub(super) animator: Animator,
+    pub(super) prev_animation_ids: (u32, u16),
 }
 
 impl Deref for LogicCharaControl {
@@ -105,15 +124,31 @@ impl LogicCharaControl {
     pub(crate) fn new(
         ctx: &mut ContextUpdateEx,
         chara_id: NumID,
-        inst_chara: Rc<InstCharacter>,
+        inst_styles: Vec<Rc<InstCharacter>>,
+        style_index: usize,
         inst_ai_brain: Option<Rc<InstAiBrain>>,
     ) -> XResult<LogicCharaControl> {
+        let inst_chara = inst_styles
+            .get(style_index)
+            .cloned()
+            .ok_or_else(|| xerrf!(BadArgument; "style_index={}", style_index))?;
         let skeleton = ctx.asset.load_skeleton(inst_chara.skeleton_files)?;
 
         let inst_idle_action: Rc<InstActionIdle> = inst_chara
             .find_first_primary_action(&VirtualKey::Idle)
             .ok_or_else(|| xerr!(NotFound; "No idle action"))?;
 
+        let mut animator = Animator::new(skeleton.clone(), DEFAULT_ACTION_QUEUE_CAP, MAX_ACTION_ANIMATION * 3)?;
+        if let Some(foot_ik) = inst_chara.foot_ik.as_ref() {
+            animator.set_foot_ik(Some(FootIk::new(foot_ik, &skeleton)?));
+        }
+        if let Some(look_at) = inst_chara.look_at.as_ref() {
+            let forward = inst_chara.skeleton_rotation.inverse() * DEFAULT_TOWARD_DIR_3D;
+            animator.set_look_at(Some(LookAt::new(look_at, &skeleton, forward)?));
+        }
+        let joint_masks = inst_chara.joint_masks.iter().map(|mask| JointMask::new(mask, &skeleton));
+        animator.set_joint_masks(joint_masks.collect::<XResult<Vec<_>>>()?);
+
         let ai_brain_execute = match inst_ai_brain.as_ref() {
             Some(brain) if brain.execute => Some(ctx.script.get_ai_brain_execute(brain.tmpl_id)?),
             _ => None,
@@ -122,6 +157,8 @@ impl LogicCharaControl {
         Ok(LogicCharaControl {
             chara_id,
             inst_chara,
+            inst_styles,
+            style_index: style_index as u16,
             inst_idle_action,
             inst_ai_brain,
             player_inputs: None,
@@ -131,6 +168,8 @@ impl LogicCharaControl {
             derive_keeping: DeriveKeeping::default(),
             action_changed: false,
             animation_changed: false,
+            interact_requested: false,
+            style_switched: false,
 
             ai_brain_execute,
             current_task: None,
@@ -140,6 +179,7 @@ impl LogicCharaControl {
             aggro_last_time: 0.0,
             ai_thinking: AiBrainThinking::default(),
             tmp_target_indexes: Vec::with_capacity(16),
+            targets_perceived: false,
             tmp_ai_do_list: WsVec::with_capacity_in(64, ctx.script.alloc()),
 
             ws: WsBox::new_in(WsCharaControl::default(), ctx.script.alloc()),
@@ -147,8 +187,12 @@ impl LogicCharaControl {
             new_direction: DEFAULT_TOWARD_DIR_2D,
             cache_action_states: Vec::with_capacity(16),
             action_events: Vec::new(),
+            hit_object_requests: Vec::new(),
+            notify_cursors: Vec::new(),
+            additive_animations: ArrayVec::new(),
 
-            animator: Animator::new(skeleton, DEFAULT_ACTION_QUEUE_CAP, MAX_ACTION_ANIMATION * 3)?,
+            animator,
+            prev_animation_ids: (INVALID_ACTION_ID, INVALID_ANIMATION_ID),
         })
     }
 
@@ -157,7 +201,7 @@ impl LogicCharaControl {
         let mut animations = Vec::with_capacity(16);
         let mut animation_files = DtHashMap::default();
 
-        for action in self.inst_chara.actions.values() {
+        for action in self.inst_styles.iter().flat_map(|inst| inst.actions.values()) {
             action.animations(&mut animations);
             for anime in animations.iter() {
                 ctx.asset.load_animation(anime.files)?;
@@ -167,6 +211,12 @@ impl LogicCharaControl {
                 if anime.weapon_motion {
                     ctx.asset.load_weapon_motion(anime.files)?;
                 }
+                if anime.shape_key {
+                    ctx.asset.load_shape_key(anime.files)?;
+                }
+                if anime.notify {
+                    ctx.asset.load_animation_notify(anime.files)?;
+                }
                 match animation_files.entry(anime.files) {
                     Entry::Vacant(e) => {
                         e.insert(anime.file_meta());
@@ -174,6 +224,7 @@ impl LogicCharaControl {
                     Entry::Occupied(mut e) => {
                         e.get_mut().root_motion |= anime.root_motion;
                         e.get_mut().weapon_motion |= anime.weapon_motion;
+                        e.get_mut().shape_key |= anime.shape_key;
                     }
                 }
             }
@@ -215,8 +266,16 @@ impl LogicCharaControl {
             return xres!(Unexpected; "action queue empty");
         }
 
-        let mut next_action = self.handle_hit_events(ctx, chara_phy)?;
+        self.interact_requested = false;
+        self.style_switched = false;
+        self.hit_object_requests.clear();
+        let time = ctx.time.time;
+        self.additive_animations.retain(|additive| !additive.is_finished(time));
+        let mut next_action = self.handle_hit_events(ctx, chara_phy, chara_val)?;
         if self.inst_chara.is_player {
+            if next_action.is_none() {
+                next_action = self.handle_player_switch(ctx)?;
+            }
             next_action = self.handle_player_inputs(ctx, chara_phy, next_action)?;
         }
         else {
@@ -229,6 +288,10 @@ impl LogicCharaControl {
 
         self.update_current_actions(ctx, chara_phy, chara_val)?;
 
+        // Looping additive animations end with the action playing them.
+        let current_id = self.action_queue.last().map(|act| act.id);
+        self.additive_animations.retain(|additive| !additive.looping || Some(additive.action_id) == current_id);
+
         self.collect_states_and_cleanup(ctx, chara_phy, previous_frame_state)?;
         Ok(())
     }
@@ -243,6 +306,10 @@ impl LogicCharaControl {
         self.derive_keeping = state.derive_keeping;
         self.action_changed = state.action_changed;
         self.animation_changed = state.animation_changed;
+        self.style_switched = false;
+        if state.style_index != self.style_index {
+            self.switch_style(state.style_index as usize)?;
+        }
 
         let mut state_iter = states.iter();
         self.action_queue.restore_when(|act| {
@@ -261,8 +328,10 @@ impl LogicCharaControl {
             }
         })?;
         self.cache_action_states.clear();
+        collect_notify_cursors(states, &mut self.notify_cursors);
 
         self.target_chara = state.target_chara;
+        self.aggro_last_time = state.aggro_last_time;
 
         if state.current_routine.is_valid() {
             if self.current_routine.is_none() || self.current_routine.as_ref().unwrap().tmpl_id != state.current_routine
@@ -286,23 +355,15 @@ impl LogicCharaControl {
             self.current_routine = None;
         }
         self.current_routine_exec = state.current_routine_exec;
+        self.additive_animations = state.additive_animations.clone();
 
         if let Some(action) = self.action_queue.last() {
-            self.ws.current_task = action.tmpl_id();
+            self.ws.current_action = action.tmpl_id();
             self.ws.action_keep_level = action.keep_level;
         }
-        else {
-            self.ws.current_task = TmplID::INVALID;
-            self.ws.action_keep_level = 0;
-        }
-
-        if let Some(task) = self.current_task.as_ref() {
-            self.ws.current_action = task.inst.tmpl_id;
-            self.ws.ai_intention = task.intention;
-        }
         else {
             self.ws.current_action = TmplID::INVALID;
-            self.ws.ai_intention = AiIntention::Idle;
+            self.ws.action_keep_level = 0;
         }
 
         self.ws.current_routine = state.current_routine;
@@ -312,18 +373,108 @@ impl LogicCharaControl {
         Ok(())
     }
 
-    pub(crate) fn apply_animations(&mut self, ctx: &mut ContextUpdateEx) -> XResult<()> {
-        let prev_ids = self.animator.action_animation_id();
+    /// Recreates the actions listed in the states, then `restore()` can be used as usual.
+    /// Used by late-join, where the character has no action history.
+    pub(crate) fn rebuild_actions(
+        &mut self,
+        ctx: &mut ContextUpdateEx,
+        state: &StateCharaControl,
+        states: &[Box<dyn StateActionAny>],
+    ) -> XResult<()> {
+        if state.style_index != self.style_index {
+            self.switch_style(state.style_index as usize)?;
+        }
+
+        self.action_queue.clear();
+        for act_state in states {
+            let inst_act = self
+                .inst_styles
+                .iter()
+                .find_map(|inst| inst.actions.get(&act_state.tmpl_id))
+                .cloned()
+                .ok_or_else(|| xerrf!(NotFound; "chara_id={}, action={}", self.chara_id, act_state.tmpl_id))?;
+            let mut logic_act = new_logic_action(ctx, inst_act)?;
+            logic_act.id = act_state.id;
+            self.action_queue.enqueue_new(logic_act);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn apply_animations(&mut self, ctx: &mut ContextUpdateEx, chara_phy: &LogicCharaPhysics) -> XResult<()> {
+        self.prepare_animations(ctx, chara_phy)?;
+        self.sample_animations()?;
+        self.apply_foot_ik(ctx, chara_phy)
+    }
 
+    /// Updates the animator queue from the action states. Touches the asset loader, must run in order.
+    pub(crate) fn prepare_animations(
+        &mut self,
+        ctx: &mut ContextUpdateEx,
+        chara_phy: &LogicCharaPhysics,
+    ) -> XResult<()> {
+        self.prev_animation_ids = self.animator.action_animation_id();
         self.animator.discard(ctx.time.synced_frame);
         self.animator
             .update(ctx.time.frame, &self.cache_action_states, &mut ctx.asset)?;
+        self.animator
+            .update_additives(ctx.time.time, &self.additive_animations, &mut ctx.asset)?;
+        self.prepare_look_at(chara_phy);
+        Ok(())
+    }
+
+    /// Blends the look-at targets of the actions by their blending weights (`fade_in_weight` of
+    /// the collected states), and converts the target into model space.
+    fn prepare_look_at(&mut self, chara_phy: &LogicCharaPhysics) {
+        let mut target = Vec3A::ZERO;
+        let mut weight = 0.0;
+        for state in self.cache_action_states.iter().filter(|s| !s.previous_frame()) {
+            let look_at_weight = state.fade_in_weight * state.look_at_weight;
+            target += state.look_at * look_at_weight;
+            weight += look_at_weight;
+        }
+
+        if weight <= 0.0 {
+            self.animator.set_look_at_target(Vec3A::ZERO, 0.0);
+            return;
+        }
+        let rotation = chara_phy.rotation() * self.inst_chara.skeleton_rotation;
+        let model_target = rotation.inverse() * (target / weight - chara_phy.position());
+        self.animator.set_look_at_target(model_target, weight);
+    }
+
+    /// Samples and blends the animations (and hit motions), after `prepare_animations()`.
+    fn sample_animations(&mut self) -> XResult<()> {
         self.animator.animate()?;
+        self.finish_sampling();
+        Ok(())
+    }
 
+    /// The animator is `Send`, its `animate()` can run on a worker thread after `prepare_animations()`.
+    /// Then `finish_sampling()` must be called in order.
+    #[inline]
+    pub(crate) fn animator_mut(&mut self) -> &mut Animator {
+        &mut self.animator
+    }
+
+    pub(crate) fn finish_sampling(&mut self) {
         let current_ids = self.animator.action_animation_id();
-        self.action_changed = prev_ids.0 != current_ids.0;
-        self.animation_changed = prev_ids != current_ids;
-        Ok(())
+        self.action_changed = self.prev_animation_ids.0 != current_ids.0;
+        self.animation_changed = self.prev_animation_ids != current_ids;
+    }
+
+    /// Places the feet on the scenery, after `sample_animations()`. Queries the physics, must run in order.
+    pub(crate) fn apply_foot_ik(&mut self, ctx: &ContextUpdateEx, chara_phy: &LogicCharaPhysics) -> XResult<()> {
+        let physics = &ctx.physics;
+        self.animator.apply_foot_ik(
+            chara_phy.position(),
+            chara_phy.rotation() * self.inst_chara.skeleton_rotation,
+            |point| {
+                let origin = point + Vec3A::Y * FOOT_IK_RAY_UP;
+                let direction = Vec3A::NEG_Y * (FOOT_IK_RAY_UP + FOOT_IK_RAY_DOWN);
+                Ok(phy_cast_ray_scenery(physics, origin, direction).map(|hit| hit.point))
+            },
+        )
     }
 
     pub(crate) fn action_states(&self) -> XResult<&[Box<dyn StateActionAny>]> {
@@ -340,24 +491,47 @@ impl LogicCharaControl {
             return xres!(LogicBadState; "states already taken");
         }
         Ok((
-            StateCharaControl {
-                input_cursor_id: self.input_cursor_id,
-                current_routine: self
-                    .current_routine
-                    .as_ref()
-                    .map(|r| r.tmpl_id)
-                    .unwrap_or(TmplID::INVALID),
-                current_routine_exec: self.current_routine_exec,
-                derive_keeping: self.derive_keeping,
-                action_changed: self.action_changed,
-                animation_changed: self.animation_changed,
-                target_chara: self.target_chara,
-            },
+            self.control_state(),
             mem::take(&mut self.cache_action_states),
             mem::take(&mut self.action_events),
         ))
     }
 
+    /// Same as `take_states()`, but swaps with the given empty vectors to keep their capacity.
+    pub(crate) fn take_states_into(
+        &mut self,
+        action_states: &mut Vec<Box<dyn StateActionAny>>,
+        custom_events: &mut Vec<CustomEvent>,
+    ) -> XResult<StateCharaControl> {
+        if self.cache_action_states.is_empty() {
+            return xres!(LogicBadState; "states already taken");
+        }
+        action_states.clear();
+        custom_events.clear();
+        mem::swap(&mut self.cache_action_states, action_states);
+        mem::swap(&mut self.action_events, custom_events);
+        Ok(self.control_state())
+    }
+
+    fn control_state(&self) -> StateCharaControl {
+        StateCharaControl {
+            input_cursor_id: self.input_cursor_id,
+            current_routine: self
+                .current_routine
+                .as_ref()
+                .map(|r| r.tmpl_id)
+                .unwrap_or(TmplID::INVALID),
+            current_routine_exec: self.current_routine_exec,
+            derive_keeping: self.derive_keeping,
+            action_changed: self.action_changed,
+            animation_changed: self.animation_changed,
+            target_chara: self.target_chara,
+            aggro_last_time: self.aggro_last_time,
+            style_index: self.style_index,
+            additive_animations: self.additive_animations.clone(),
+        }
+    }
+
     #[inline]
     pub(crate) fn id(&self) -> NumID {
         self.chara_id
@@ -368,6 +542,26 @@ impl LogicCharaControl {
         self.animator.model_transforms()
     }
 
+    #[inline]
+    pub(crate) fn take_shape_keys(&mut self) -> Vec<ShapeKeyValue> {
+        self.animator.take_shape_keys()
+    }
+
+    #[inline]
+    pub(crate) fn take_shape_keys_into(&mut self, shape_keys: &mut Vec<ShapeKeyValue>) {
+        self.animator.take_shape_keys_into(shape_keys);
+    }
+
+    #[inline]
+    pub(crate) fn look_at(&self) -> Option<&LookAt> {
+        self.animator.look_at()
+    }
+
+    #[inline]
+    pub(crate) fn foot_ik(&self) -> Option<&FootIk> {
+        self.animator.foot_ik()
+    }
+
     #[inline]
     pub(crate) fn new_velocity(&self) -> Vec3A {
         self.new_velocity
@@ -388,6 +582,49 @@ impl LogicCharaControl {
         self.animation_changed
     }
 
+    /// Whether the player pressed the Interact key in current frame.
+    #[inline]
+    pub(crate) fn interact_requested(&self) -> bool {
+        self.interact_requested
+    }
+
+    /// Hit objects requested by the action timelines in current frame.
+    #[inline]
+    pub(crate) fn hit_object_requests(&self) -> &[TmplID] {
+        &self.hit_object_requests
+    }
+
+    /// Whether the player switched style in current frame.
+    #[inline]
+    pub(crate) fn style_switched(&self) -> bool {
+        self.style_switched
+    }
+
+    #[inline]
+    pub(crate) fn style_index(&self) -> u16 {
+        self.style_index
+    }
+
+    #[inline]
+    pub(crate) fn inst_chara(&self) -> &Rc<InstCharacter> {
+        &self.inst_chara
+    }
+
+    pub(super) fn switch_style(&mut self, style_index: usize) -> XResult<()> {
+        let inst_chara = self
+            .inst_styles
+            .get(style_index)
+            .cloned()
+            .ok_or_else(|| xerrf!(BadArgument; "style_index={}", style_index))?;
+        self.inst_idle_action = inst_chara
+            .find_first_primary_action(&VirtualKey::Idle)
+            .ok_or_else(|| xerr!(NotFound; "No idle action"))?;
+        self.inst_chara = inst_chara;
+        self.style_index = style_index as u16;
+        self.derive_keeping.clear();
+        Ok(())
+    }
+
     #[inline]
     pub(crate) fn current_action(&self) -> Option<&dyn LogicActionAny> {
         self.action_queue.last().map(|act| act.as_ref())
@@ -429,6 +666,15 @@ impl LogicCharaControl {
         }
     }
 
+    /// How an attack meets this character, by the hit boxes of this character it touches.
+    #[inline]
+    pub(crate) fn hit_interaction<I: Iterator<Item = u16>>(&self, touched_boxes: I) -> HitInteraction {
+        match self.hit_motion_sampler() {
+            Some(sampler) => sampler.hit_interaction(touched_boxes),
+            None => HitInteraction::Normal,
+        }
+    }
+
     #[inline]
     pub(crate) fn ai_thinking(&self) -> &AiBrainThinking {
         &self.ai_thinking
@@ -2,4 +2,5 @@ mod action;
 mod ai_brain;
 mod control;
 
+pub(crate) use ai_brain::AiPerceiver;
 pub use control::*;
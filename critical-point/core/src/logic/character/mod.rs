@@ -10,5 +10,5 @@ pub(crate) use control::*;
 // pub use hit::{StateCharaHit, StateCharaHitBoxPair, StateCharaHitGroupPair};
 pub use physics::StateCharaPhysics;
 pub(crate) use physics::*;
-pub use value::StateCharaValue;
+pub use value::{StateCharaBuff, StateCharaValue};
 pub(crate) use value::*;
@@ -11,7 +11,6 @@ use jolt_physics_rs::{
 use ozz_animation_rs::SKELETON_NO_PARENT;
 
 use crate::animation::rest_poses_to_model_transforms;
-use crate::consts::SPF;
 use crate::instance::InstCharacter;
 use crate::logic::character::control::LogicCharaControl;
 use crate::logic::character::physics::physics::{CharacterHandle, CharacterLocation, JointBinding, LogicCharaPhysics};
@@ -86,6 +85,7 @@ impl LogicCharaPhysics {
         ctx: &mut ContextUpdateEx,
         chara_ctrl: &LogicCharaControl,
     ) -> XResult<CharacterLocation> {
+        let spf = ctx.time.spf;
         let new_rotation = quat_from_dir_xz(chara_ctrl.new_direction());
         if new_rotation != character.get_rotation() {
             character.set_rotation(new_rotation);
@@ -108,7 +108,7 @@ impl LogicCharaPhysics {
                 new_velocity = Vec3A::new(0.0, linear_velocity.y, 0.0);
             }
 
-            new_velocity += gravity * SPF; // Gravity
+            new_velocity += gravity * spf; // Gravity
 
             if character.is_supported() {
                 new_velocity += chara_ctrl.new_velocity();
@@ -125,7 +125,7 @@ impl LogicCharaPhysics {
 
         character.extended_update(
             phy_layer!(Bounding, Player),
-            SPF,
+            spf,
             gravity.into(),
             &ExtendedUpdateSettings::default(),
         );
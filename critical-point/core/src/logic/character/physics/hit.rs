@@ -1,13 +1,16 @@
+use glam::Vec3A;
 use glam_ext::Isometry3A;
-use jolt_physics_rs::{BodyCreationSettings, BodyID, BodyInterface, MotionType};
+use jolt_physics_rs::{BodyCreationSettings, BodyID, BodyInterface, JRef, MotionType, Shape};
 
 use crate::animation::{HitMotion, HitSampler};
 use crate::consts::MAX_HIT_TIMES_PER_FRAME;
 use crate::logic::character::control::LogicCharaControl;
-use crate::logic::character::physics::physics::{LogicCharaPhysics, StateCharaHitBoxPair, StateCharaHitGroupPair};
+use crate::logic::character::physics::physics::{
+    HitSweep, LogicCharaPhysics, StateCharaHitBoxPair, StateCharaHitGroupPair, StateCharaPhysics,
+};
 use crate::logic::game::{ContextHitGenerate, ContextUpdateEx, HitCharacterEvent};
 use crate::logic::physics::{PhyBodyUserData, PhyHitCharacterEvent, phy_layer};
-use crate::utils::{XResult, find_offset_by, ok_or, strict_lt, xfrom};
+use crate::utils::{HitInteraction, HitType, NumID, XResult, find_offset_by, ok_or, strict_lt, xfrom};
 
 impl LogicCharaPhysics {
     pub(super) fn handle_action_changed(
@@ -16,18 +19,27 @@ impl LogicCharaPhysics {
         chara_act: &LogicCharaControl,
     ) -> XResult<()> {
         let body_itf = ctx.physics.body_itf();
-        for body_id in self.body_ids.drain(..) {
+        for body_id in self.body_ids.drain(..).chain(self.target_body_ids.drain(..)) {
             if body_id.is_valid() {
                 body_itf.remove_body(body_id);
                 body_itf.destroy_body(body_id);
             }
         }
 
+        for sweep in self.sweeps.iter_mut() {
+            clear_sweep(body_itf, sweep);
+        }
+
         self.box_pairs.clear();
         self.group_pairs.clear();
+        self.sweeps.clear();
 
         if let Some(sampler) = chara_act.hit_motion_sampler() {
             self.body_ids.resize(sampler.hit_motion.count_boxes(), BodyID::INVALID);
+            self.target_body_ids
+                .resize(sampler.hit_motion.count_boxes(), BodyID::INVALID);
+            self.sweeps
+                .resize_with(sampler.hit_motion.count_boxes(), HitSweep::default);
         }
 
         Ok(())
@@ -85,32 +97,87 @@ impl LogicCharaPhysics {
                 return Ok(());
             };
 
+            let asset_group = &hit_motion.groups()[asset_box.group_index as usize];
+
             // If hit box is active, the sampler will return its position information.
+            // All types of boxes can be hit, only the attack boxes hit others.
             if let Some(isometry) = sampler.isometry() {
                 let hit_isometry = chara_isometry * *isometry;
 
-                if zelf.body_ids[box_idx] == BodyID::INVALID {
-                    let mut settings = BodyCreationSettings::new_sensor(
-                        asset_box.shape.clone(),
-                        phy_layer!(Hit, zelf.inst_chara.is_player => Enemy | Player),
-                        MotionType::Static,
-                        hit_isometry.translation,
-                        hit_isometry.rotation,
-                    );
-                    settings.user_data = PhyBodyUserData::new_hit(zelf.chara_id, asset_box.box_index).into();
-                    zelf.body_ids[box_idx] = body_itf.create_add_body(&settings, true).map_err(xfrom!())?;
+                if zelf.target_body_ids[box_idx] == BodyID::INVALID {
+                    zelf.target_body_ids[box_idx] = create_hit_target_body(
+                        body_itf,
+                        &asset_box.shape,
+                        zelf.inst_chara.is_player,
+                        zelf.chara_id,
+                        asset_box.box_index,
+                        hit_isometry,
+                    )?;
                 }
                 else {
                     body_itf.set_position_rotation(
-                        zelf.body_ids[box_idx],
+                        zelf.target_body_ids[box_idx],
                         hit_isometry.translation,
                         hit_isometry.rotation,
                         true,
                     );
                 }
+
+                let is_attack = asset_box.typ == HitType::Attack;
+                if is_attack {
+                    if zelf.body_ids[box_idx] == BodyID::INVALID {
+                        zelf.body_ids[box_idx] = create_hit_body(
+                            body_itf,
+                            &asset_box.shape,
+                            zelf.inst_chara.is_player,
+                            zelf.chara_id,
+                            asset_box.box_index,
+                            hit_isometry,
+                        )?;
+                    }
+                    else {
+                        body_itf.set_position_rotation(
+                            zelf.body_ids[box_idx],
+                            hit_isometry.translation,
+                            hit_isometry.rotation,
+                            true,
+                        );
+                    }
+                }
+
+                if let Some(sweep) = zelf.sweeps.get_mut(box_idx) {
+                    // No previous isometry (first active frame) or not swept, nothing to sweep.
+                    // Only the attack boxes sweep, the others are hit at the current isometry.
+                    let prev_isometry = sweep.prev_isometry.replace(hit_isometry);
+                    sweep.sweep_from = prev_isometry.filter(|_| is_attack && asset_group.is_swept());
+                    match sweep.sweep_from {
+                        Some(from) => place_sweep_bodies(
+                            body_itf,
+                            sweep,
+                            (&asset_box.shape, asset_box.box_index),
+                            asset_group.sweep_steps,
+                            zelf.inst_chara.is_player,
+                            zelf.chara_id,
+                            (from, hit_isometry),
+                        )?,
+                        None => clear_sweep(body_itf, sweep),
+                    }
+                }
             }
             // No position information, hit box inactive, clean up if needed.
             else {
+                if let Some(sweep) = zelf.sweeps.get_mut(box_idx) {
+                    clear_sweep(body_itf, sweep);
+                    sweep.prev_isometry = None;
+                    sweep.sweep_from = None;
+                }
+
+                if zelf.target_body_ids[box_idx] != BodyID::INVALID {
+                    body_itf.remove_body(zelf.target_body_ids[box_idx]);
+                    body_itf.destroy_body(zelf.target_body_ids[box_idx]);
+                    zelf.target_body_ids[box_idx] = BodyID::INVALID;
+                }
+
                 if zelf.body_ids[box_idx] != BodyID::INVALID {
                     body_itf.remove_body(zelf.body_ids[box_idx]);
                     body_itf.destroy_body(zelf.body_ids[box_idx]);
@@ -118,7 +185,6 @@ impl LogicCharaPhysics {
 
                     zelf.box_pairs.retain(|p| p.box_index != asset_box.box_index);
 
-                    let asset_group = &hit_motion.groups()[asset_box.group_index as usize];
                     if !asset_group.in_time_loose(sampler_time) {
                         zelf.group_pairs.retain(|p| p.group != asset_group.name);
                     }
@@ -130,12 +196,92 @@ impl LogicCharaPhysics {
         Ok(())
     }
 
+    /// Drops the hit bodies of the frames after the restored one, and creates the ones of the restored frame.
+    pub(super) fn restore_hit_bodies(
+        &mut self,
+        body_itf: &mut BodyInterface,
+        state: &StateCharaPhysics,
+        hit_motion: Option<&HitMotion>,
+    ) -> XResult<()> {
+        for body_id in self.body_ids.drain(..).chain(self.target_body_ids.drain(..)) {
+            if body_id.is_valid() {
+                body_itf.remove_body(body_id);
+                body_itf.destroy_body(body_id);
+            }
+        }
+        for sweep in self.sweeps.iter_mut() {
+            clear_sweep(body_itf, sweep);
+        }
+        self.sweeps.clear();
+
+        let Some(hit_motion) = hit_motion
+        else {
+            return Ok(());
+        };
+        self.body_ids.resize(hit_motion.count_boxes(), BodyID::INVALID);
+        self.target_body_ids.resize(hit_motion.count_boxes(), BodyID::INVALID);
+        self.sweeps.resize_with(hit_motion.count_boxes(), HitSweep::default);
+
+        for hit_box in state.hit_boxes.iter() {
+            let box_idx = hit_box.box_index as usize;
+            let Some(asset_box) = hit_motion.find_box(hit_box.box_index)
+            else {
+                log::warn!(
+                    "character={}, hit_motion={}, box_index={}, hit box not in the current animation",
+                    self.chara_id,
+                    hit_motion.name(),
+                    box_idx
+                );
+                continue;
+            };
+
+            let isometry = Isometry3A::new_3a(hit_box.position, hit_box.rotation);
+            self.target_body_ids[box_idx] = create_hit_target_body(
+                body_itf,
+                &asset_box.shape,
+                self.inst_chara.is_player,
+                self.chara_id,
+                asset_box.box_index,
+                isometry,
+            )?;
+            if asset_box.typ == HitType::Attack {
+                self.body_ids[box_idx] = create_hit_body(
+                    body_itf,
+                    &asset_box.shape,
+                    self.inst_chara.is_player,
+                    self.chara_id,
+                    asset_box.box_index,
+                    isometry,
+                )?;
+            }
+
+            let sweep = &mut self.sweeps[box_idx];
+            sweep.prev_isometry = Some(isometry);
+            if hit_box.swept {
+                let from = Isometry3A::new_3a(hit_box.prev_position, hit_box.prev_rotation);
+                sweep.sweep_from = Some(from);
+                let steps = hit_motion.groups()[asset_box.group_index as usize].sweep_steps;
+                place_sweep_bodies(
+                    body_itf,
+                    sweep,
+                    (&asset_box.shape, asset_box.box_index),
+                    steps,
+                    self.inst_chara.is_player,
+                    self.chara_id,
+                    (from, isometry),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn detect_hits(
         &mut self,
         dst_chara_phy: &mut LogicCharaPhysics,
         ctx: &mut ContextHitGenerate<HitCharacterEvent>,
         chara_act: &LogicCharaControl,
         phy_event: &PhyHitCharacterEvent,
+        interaction: HitInteraction,
     ) -> XResult<usize> {
         let curr_act = ok_or!(chara_act.current_action_with_log(); return Ok(0));
         let sampler = ok_or!(chara_act.hit_motion_sampler_with_log(); return Ok(0));
@@ -150,6 +296,15 @@ impl LogicCharaPhysics {
             return Ok(0);
         };
 
+        // A swept box may touch the same character with several bodies, only the first one counts.
+        if sampler.hit_motion.groups()[asset_box.group_index as usize].is_swept() {
+            let pair = (asset_box.box_index, phy_event.dst_chara_id);
+            if self.swept_pairs.contains(&pair) {
+                return Ok(0);
+            }
+            self.swept_pairs.push(pair);
+        }
+
         let Some(inst_hit) = find_offset_by(&curr_act.inst.hits, asset_box.group_index as usize, |hit| {
             hit.group == asset_box.group
         })
@@ -225,6 +380,7 @@ impl LogicCharaPhysics {
                 group_index: asset_box.group_index,
                 box_hit_times: box_pair.hit_times,
                 group_hit_times: group_pair.hit_times,
+                interaction,
                 collision_normal: phy_event.world_space_normal,
                 collision_point_average: phy_event.collision_point_average,
                 character_vector: dst_chara_phy.position - self.ws.position,
@@ -247,4 +403,248 @@ impl LogicCharaPhysics {
     pub(crate) fn be_hit_events(&self) -> &Vec<usize> {
         &self.be_hit_events
     }
+
+    /// Records a hit event not generated by the hit boxes of this character, such as a hit object.
+    #[inline]
+    pub(crate) fn append_hit_event(&mut self, event_idx: usize) {
+        self.hit_events.push(event_idx);
+    }
+
+    #[inline]
+    pub(crate) fn append_be_hit_event(&mut self, event_idx: usize) {
+        self.be_hit_events.push(event_idx);
+    }
+}
+
+fn create_hit_body(
+    body_itf: &mut BodyInterface,
+    shape: &JRef<Shape>,
+    is_player: bool,
+    chara_id: NumID,
+    box_index: u16,
+    isometry: Isometry3A,
+) -> XResult<BodyID> {
+    let mut settings = BodyCreationSettings::new_sensor(
+        shape.clone(),
+        phy_layer!(Hit, is_player => Enemy | Player),
+        MotionType::Static,
+        isometry.translation,
+        isometry.rotation,
+    );
+    settings.user_data = PhyBodyUserData::new_hit(chara_id, box_index).into();
+    body_itf.create_add_body(&settings, true).map_err(xfrom!())
+}
+
+/// The sensor body of a hit box of this character, for the attacks of the others.
+fn create_hit_target_body(
+    body_itf: &mut BodyInterface,
+    shape: &JRef<Shape>,
+    is_player: bool,
+    chara_id: NumID,
+    box_index: u16,
+    isometry: Isometry3A,
+) -> XResult<BodyID> {
+    let mut settings = BodyCreationSettings::new_sensor(
+        shape.clone(),
+        phy_layer!(Target, is_player => Player | Enemy),
+        MotionType::Static,
+        isometry.translation,
+        isometry.rotation,
+    );
+    settings.user_data = PhyBodyUserData::new_hit_target(chara_id, box_index).into();
+    body_itf.create_add_body(&settings, true).map_err(xfrom!())
+}
+
+/// Places the sub-frame bodies between the isometries `from` and `to`, creates the missing ones.
+fn place_sweep_bodies(
+    body_itf: &mut BodyInterface,
+    sweep: &mut HitSweep,
+    (shape, box_index): (&JRef<Shape>, u16),
+    steps: u8,
+    is_player: bool,
+    chara_id: NumID,
+    (from, to): (Isometry3A, Isometry3A),
+) -> XResult<()> {
+    let steps = steps as usize;
+    for step in 1..steps {
+        let isometry = sweep_isometry(from, to, step, steps);
+        match sweep.body_ids.get(step - 1) {
+            Some(body_id) => {
+                body_itf.set_position_rotation(*body_id, isometry.translation, isometry.rotation, true);
+            }
+            None => {
+                let body_id = create_hit_body(body_itf, shape, is_player, chara_id, box_index, isometry)?;
+                sweep.body_ids.push(body_id);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn clear_sweep(body_itf: &mut BodyInterface, sweep: &mut HitSweep) {
+    for body_id in sweep.body_ids.drain(..) {
+        body_itf.remove_body(body_id);
+        body_itf.destroy_body(body_id);
+    }
+}
+
+/// The isometry of the sub-frame `step` in `steps`, between the previous frame and current frame.
+#[inline]
+fn sweep_isometry(prev: Isometry3A, curr: Isometry3A, step: usize, steps: usize) -> Isometry3A {
+    let t = step as f32 / steps as f32;
+    Isometry3A::new_3a(
+        Vec3A::lerp(prev.translation, curr.translation, t),
+        prev.rotation.slerp(curr.rotation, t),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_ulps_eq;
+    use glam::Quat;
+    use jolt_physics_rs::{
+        self as jolt, Body, BoxShapeSettings, CollideShapeResult, ContactListener, ContactListenerVTable,
+        ContactManifold, ContactSettings, JVec3, SubShapeIDPair, ValidateResult, vdata,
+    };
+    use std::f32::consts::FRAC_PI_2;
+
+    use crate::logic::test_utils::*;
+
+    #[test]
+    fn test_sweep_isometry() {
+        let prev = Isometry3A::new_3a(Vec3A::new(0.0, 1.0, 0.0), Quat::IDENTITY);
+        let curr = Isometry3A::new_3a(Vec3A::new(4.0, 1.0, 0.0), Quat::from_rotation_y(FRAC_PI_2));
+
+        let iso = sweep_isometry(prev, curr, 1, 4);
+        assert_ulps_eq!(iso.translation, Vec3A::new(1.0, 1.0, 0.0));
+        assert_ulps_eq!(iso.rotation, Quat::from_rotation_y(FRAC_PI_2 * 0.25));
+
+        let iso = sweep_isometry(prev, curr, 2, 4);
+        assert_ulps_eq!(iso.translation, Vec3A::new(2.0, 1.0, 0.0));
+        assert_ulps_eq!(iso.rotation, Quat::from_rotation_y(FRAC_PI_2 * 0.5));
+
+        let iso = sweep_isometry(prev, curr, 4, 4);
+        assert_ulps_eq!(iso.translation, curr.translation);
+        assert_ulps_eq!(iso.rotation, curr.rotation);
+    }
+
+    #[vdata(ContactListenerVTable)]
+    struct HitBoxCollector<'t> {
+        hits: &'t mut Vec<u16>,
+    }
+
+    impl<'t> HitBoxCollector<'t> {
+        fn collect(&mut self, body1: &Body, body2: &Body) {
+            let ud1 = PhyBodyUserData::from(body1.get_user_data());
+            let ud2 = PhyBodyUserData::from(body2.get_user_data());
+            match (ud1, ud2) {
+                (PhyBodyUserData::Hit { hit, .. }, PhyBodyUserData::Character { .. })
+                | (PhyBodyUserData::Character { .. }, PhyBodyUserData::Hit { hit, .. }) => self.hits.push(hit),
+                _ => {}
+            }
+        }
+    }
+
+    impl<'t> ContactListener for HitBoxCollector<'t> {
+        fn on_contact_validate(
+            &mut self,
+            _body1: &Body,
+            _body2: &Body,
+            _base_offset: JVec3,
+            _collision_result: &CollideShapeResult,
+        ) -> ValidateResult {
+            ValidateResult::AcceptAllContactsForThisBodyPair
+        }
+
+        fn on_contact_added(
+            &mut self,
+            body1: &Body,
+            body2: &Body,
+            _manifold: &ContactManifold,
+            _settings: &mut ContactSettings,
+        ) {
+            self.collect(body1, body2);
+        }
+
+        fn on_contact_persisted(
+            &mut self,
+            body1: &Body,
+            body2: &Body,
+            _manifold: &ContactManifold,
+            _settings: &mut ContactSettings,
+        ) {
+            self.collect(body1, body2);
+        }
+
+        fn on_contact_removed(&mut self, _pair: &SubShapeIDPair) {}
+    }
+
+    fn create_box(half_x: f32, half_y: f32, half_z: f32) -> JRef<Shape> {
+        let mut settings = BoxShapeSettings::new(half_x, half_y, half_z);
+        settings.convex_radius = 0.0;
+        jolt::create_box_shape(&settings).unwrap()
+    }
+
+    // A thin enemy, a kinematic sensor like the character bodies.
+    fn create_thin_target(tenv: &mut TestEnv) -> BodyID {
+        let mut settings = BodyCreationSettings::new_sensor(
+            create_box(0.02, 0.5, 0.5),
+            phy_layer!(Target, Enemy),
+            MotionType::Kinematic,
+            Vec3A::new(0.0, 1.0, 0.0),
+            Quat::IDENTITY,
+        );
+        settings.collide_kinematic_vs_non_dynamic = true;
+        settings.user_data = PhyBodyUserData::new_character(2).into();
+        tenv.systems.physics.body_itf().create_add_body(&settings, true).unwrap()
+    }
+
+    fn step_hit_boxes(tenv: &mut TestEnv) -> Vec<u16> {
+        let mut hits = Vec::new();
+        let mut cl = HitBoxCollector::new_vpair(HitBoxCollector { hits: &mut hits });
+        tenv.systems
+            .physics
+            .update_with_listeners::<_, ()>(tenv.time.spf, 1, Some(&mut cl), None)
+            .unwrap();
+        drop(cl);
+        hits
+    }
+
+    #[test]
+    fn test_sweep_fast_swing() {
+        let mut tenv = TestEnv::new().unwrap();
+        create_thin_target(&mut tenv);
+
+        // A player's weapon crossing the thin target within one frame.
+        let shape = create_box(0.1, 0.1, 0.1);
+        let from = Isometry3A::new_3a(Vec3A::new(-1.0, 1.0, 0.0), Quat::IDENTITY);
+        let to = Isometry3A::new_3a(Vec3A::new(1.0, 1.0, 0.0), Quat::IDENTITY);
+
+        // Without sweep, the hit box at the current pose misses.
+        let body_itf = tenv.systems.physics.body_itf();
+        let body_id = create_hit_body(body_itf, &shape, true, 1, 0, to).unwrap();
+        tenv.systems.physics.optimize_broad_phase();
+        assert!(step_hit_boxes(&mut tenv).is_empty());
+
+        // With sweep, the sub-frame bodies between the poses catch it.
+        let mut sweep = HitSweep::default();
+        let body_itf = tenv.systems.physics.body_itf();
+        place_sweep_bodies(body_itf, &mut sweep, (&shape, 0), 4, true, 1, (from, to)).unwrap();
+        assert_eq!(sweep.body_ids.len(), 3);
+        tenv.systems.physics.optimize_broad_phase();
+        assert_eq!(step_hit_boxes(&mut tenv), vec![0]);
+
+        // Swept away from the target, nothing is hit.
+        let away = Isometry3A::new_3a(Vec3A::new(3.0, 1.0, 0.0), Quat::IDENTITY);
+        let body_itf = tenv.systems.physics.body_itf();
+        body_itf.set_position_rotation(body_id, away.translation, away.rotation, true);
+        place_sweep_bodies(body_itf, &mut sweep, (&shape, 0), 4, true, 1, (to, away)).unwrap();
+        assert_eq!(sweep.body_ids.len(), 3);
+        assert!(step_hit_boxes(&mut tenv).is_empty());
+
+        let body_itf = tenv.systems.physics.body_itf();
+        clear_sweep(body_itf, &mut sweep);
+        assert!(sweep.body_ids.is_empty());
+    }
 }
@@ -2,11 +2,12 @@ use critical_point_macros::{csharp_out, wasm_impl, wasm_struct};
 use educe::Educe;
 use glam::{Quat, Vec3A, Vec3Swizzles};
 use glam_ext::{Isometry3A, Vec2xz};
-use jolt_physics_rs::{BodyID, Character, CharacterVirtual, JMut, MutableCompoundShape};
+use jolt_physics_rs::{BodyID, Character, CharacterVirtual, JMut, MutableCompoundShape, PhysicsSystem};
 use std::cell::Cell;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
+use crate::animation::HitMotion;
 use crate::instance::InstCharacter;
 use crate::logic::character::control::LogicCharaControl;
 use crate::logic::character::physics::body::CharacterContactListenerImpl;
@@ -59,10 +60,41 @@ pub struct StateCharaHitGroupPair {
     pub hit_times: u16,
 }
 
+/// The world isometry of an active hit box, used to put its Jolt bodies back on restore.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct StateCharaHitBox {
+    pub box_index: u16,
+    pub swept: bool,
+    pub position: Vec3A,
+    pub rotation: Quat,
+    // The isometry of the previous frame, the sweep bodies lie in between. Only used if swept.
+    pub prev_position: Vec3A,
+    pub prev_rotation: Quat,
+}
+
 #[repr(C)]
 #[csharp_out(Value)]
 #[derive(
-    Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
 )]
 #[rkyv(derive(Debug))]
 pub struct StateCharaPhysics {
@@ -71,7 +103,7 @@ pub struct StateCharaPhysics {
     pub direction: Vec2xz,
 
     #[csharp_hide(24, 8)]
-    pub body_ids: SmallVec<[BodyID; 4]>,
+    pub hit_boxes: Vec<StateCharaHitBox>,
     #[csharp_hide(56, 8)]
     pub box_pairs: SmallVec<[StateCharaHitBoxPair; 4]>,
     #[csharp_hide(56, 8)]
@@ -117,6 +149,16 @@ pub(super) struct JointBinding {
     pub(super) joint2: i16,
 }
 
+/// The isometries and the sub-frame sensor bodies of a hit box.
+///
+/// The isometries are kept in the state (see `StateCharaHitBox`), the bodies are rebuilt on restore.
+#[derive(Debug, Default)]
+pub(super) struct HitSweep {
+    pub(super) prev_isometry: Option<Isometry3A>, // Isometry of the last update, None if inactive
+    pub(super) sweep_from: Option<Isometry3A>,    // Start isometry of the sweep bodies, None if not swept
+    pub(super) body_ids: Vec<BodyID>,
+}
+
 #[derive(Educe)]
 #[educe(Debug)]
 pub(crate) struct LogicCharaPhysics {
@@ -132,9 +174,12 @@ pub(crate) struct LogicCharaPhysics {
     pub(super) target_shape: JMut<MutableCompoundShape>,
     pub(super) joint_bindings: Vec<JointBinding>,
 
-    pub(super) body_ids: Vec<BodyID>,
+    pub(super) body_ids: Vec<BodyID>,        // Attack boxes, hit the others
+    pub(super) target_body_ids: Vec<BodyID>, // All boxes, hit by the others (resolves the hit interaction)
     pub(super) box_pairs: Vec<StateCharaHitBoxPair>,
     pub(super) group_pairs: Vec<StateCharaHitGroupPair>,
+    pub(super) sweeps: Vec<HitSweep>,
+    pub(super) swept_pairs: Vec<(u16, NumID)>, // (box_index, dst_chara_id) hit by swept boxes in this frame
 
     pub(super) cache_isometries: Vec<Isometry3A>,
     pub(super) hit_events: Vec<usize>,
@@ -192,8 +237,11 @@ impl LogicCharaPhysics {
             joint_bindings,
 
             body_ids: Vec::with_capacity(32),
+            target_body_ids: Vec::with_capacity(32),
             box_pairs: Vec::with_capacity(32),
             group_pairs: Vec::with_capacity(16),
+            sweeps: Vec::new(),
+            swept_pairs: Vec::new(),
 
             cache_isometries: Vec::with_capacity(target_bindings_len),
             hit_events: Vec::with_capacity(32),
@@ -217,6 +265,7 @@ impl LogicCharaPhysics {
 
         self.hit_events.clear();
         self.be_hit_events.clear();
+        self.swept_pairs.clear();
         Ok(())
     }
 
@@ -226,7 +275,23 @@ impl LogicCharaPhysics {
             position: self.position,
             direction: self.direction,
 
-            body_ids: SmallVec::from_slice(&self.body_ids),
+            hit_boxes: self
+                .sweeps
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, sweep)| {
+                    let isometry = sweep.prev_isometry?;
+                    let prev = sweep.sweep_from.unwrap_or(isometry);
+                    Some(StateCharaHitBox {
+                        box_index: idx as u16,
+                        swept: sweep.sweep_from.is_some(),
+                        position: isometry.translation,
+                        rotation: isometry.rotation,
+                        prev_position: prev.translation,
+                        prev_rotation: prev.rotation,
+                    })
+                })
+                .collect(),
             box_pairs: SmallVec::from_slice(&self.box_pairs),
             group_pairs: SmallVec::from_slice(&self.group_pairs),
         }
@@ -238,8 +303,6 @@ impl LogicCharaPhysics {
         self.direction = state.direction;
         self.rotation = quat_from_dir_xz(self.direction);
 
-        self.body_ids.clear();
-        self.body_ids.extend_from_slice(&state.body_ids);
         self.box_pairs.clear();
         self.box_pairs.extend_from_slice(&state.box_pairs);
         self.group_pairs.clear();
@@ -247,9 +310,48 @@ impl LogicCharaPhysics {
         Ok(())
     }
 
+    /// Moves the bounding and the target body back to the restored location, and rebuilds the hit bodies
+    /// of the restored frame. Called after `restore()`.
+    ///
+    /// The sub-shapes of the target body keep the latest sampled pose, until the next update.
+    /// `hit_motion` is the one of the current animation, no hit body is rebuilt if it doesn't match the state.
+    pub(crate) fn restore_bodies(
+        &mut self,
+        physics: &mut PhysicsSystem,
+        state: &StateCharaPhysics,
+        hit_motion: Option<&HitMotion>,
+    ) -> XResult<()> {
+        match &mut self.character {
+            CharacterHandle::Player(character) => {
+                character.set_position(self.position);
+                character.set_rotation(self.rotation);
+                character.set_linear_velocity(self.velocity);
+            }
+            CharacterHandle::Npc(character) => {
+                character.set_position(self.position, false);
+                character.set_rotation(self.rotation, false);
+                character.set_linear_velocity(self.velocity, false);
+            }
+        }
+        physics.body_itf().set_position_rotation(
+            self.target_body,
+            self.position,
+            self.rotation * self.inst_chara.skeleton_rotation,
+            true,
+        );
+        self.restore_hit_bodies(physics.body_itf(), state, hit_motion)
+    }
+
+    /// Styles of a character share the skeleton and the bounding, only the instance is swapped.
+    pub(crate) fn switch_style(&mut self, inst_chara: Rc<InstCharacter>) {
+        debug_assert_eq!(inst_chara.skeleton_files, self.inst_chara.skeleton_files);
+        self.inst_chara = inst_chara;
+    }
+
     pub(crate) fn clean_up(&mut self) {
         self.hit_events.clear();
         self.be_hit_events.clear();
+        self.swept_pairs.clear();
         self.cache_isometries.clear();
     }
 
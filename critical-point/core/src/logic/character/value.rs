@@ -1,14 +1,19 @@
 use critical_point_macros::{csharp_out, wasm_struct};
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
-use crate::instance::InstCharacter;
+use crate::consts::{
+    BURN_DAMAGE_RATIO, BURN_DURATION, COUNTER_POSTURE_RATIO, ELEMENTAL_BUILDUP_DECAY, ELEMENTAL_BUILDUP_THRESHOLD,
+    FREEZE_DURATION, FREEZE_TIME_SPEED, SHOCK_DURATION, SHOCK_POSTURE_RATIO,
+};
+use crate::instance::{InstBuff, InstBuffStacking, InstCharacter, PanelValues};
 use crate::logic::action::LogicActionAny;
 use crate::logic::ai_task::LogicAiTaskAny;
 use crate::logic::game::{ContextHitUpdate, ContextRestore, ContextUpdateEx, HitCharacterEvent};
 use crate::logic::physics::PhyHitCharacterEvent;
 use crate::script::WsBox;
-use crate::utils::{NumID, TimeRange, XResult, cf2s, ifelse};
+use crate::utils::{HitInteraction, NumID, TimeRange, TmplID, XResult, cf2s, ifelse, strict_lt, xresf};
 
 #[repr(C)]
 #[wasm_struct(24, 4)]
@@ -32,12 +37,90 @@ pub(crate) struct WsCharaValue {
 pub struct StateCharaValue {
     pub time_speed: f32,
     pub hit_lag_time: TimeRange,
+    pub health: f32,
+    pub posture: f32,
+    pub fire_buildup: f32,
+    pub ice_buildup: f32,
+    pub thunder_buildup: f32,
+    pub burn_time: TimeRange,
+    pub freeze_time: TimeRange,
+    pub shock_time: TimeRange,
+    pub shock_pending: bool,
+}
+
+#[repr(C)]
+#[csharp_out(Value)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct StateCharaBuff {
+    pub tmpl_id: TmplID,
+    pub src_chara_id: NumID,
+    pub stacks: u16,
+    pub start_time: f32,
+    pub end_time: f32,
+    pub next_tick_time: f32,
+}
+
+#[derive(Debug)]
+struct LogicCharaBuff {
+    inst: Rc<InstBuff>,
+    src_chara_id: NumID,
+    stacks: u16,
+    start_time: f32,
+    end_time: f32,
+    next_tick_time: f32,
+}
+
+impl LogicCharaBuff {
+    #[inline]
+    fn state(&self) -> StateCharaBuff {
+        StateCharaBuff {
+            tmpl_id: self.inst.tmpl_id,
+            src_chara_id: self.src_chara_id,
+            stacks: self.stacks,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            next_tick_time: self.next_tick_time,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct LogicCharaValue {
     chara_id: NumID,
     inst_chara: Rc<InstCharacter>,
+    panel: PanelValues,
+    health: f32,
+    posture: f32,
+
+    // Elemental build-up meters, trigger the status when reaching ELEMENTAL_BUILDUP_THRESHOLD.
+    fire_buildup: f32,
+    ice_buildup: f32,
+    thunder_buildup: f32,
+    burn_time: TimeRange,
+    freeze_time: TimeRange,
+    shock_time: TimeRange,
+    // The shock status started in the hit step, consumed by the next update.
+    shock_pending: bool,
+    // The shock status started since the last update, staggers the character once.
+    shock_started: bool,
+
+    buffs: Vec<LogicCharaBuff>,
+    // Buff requests from hits, items and scripts, applied in next update.
+    buff_requests: Vec<(TmplID, NumID)>,
+    // All InstBuff ever applied to this character, for restoring buffs from states.
+    buff_cache: Vec<Rc<InstBuff>>,
 
     ws: WsBox<WsCharaValue>,
 }
@@ -62,7 +145,21 @@ impl LogicCharaValue {
     pub(crate) fn new(ctx: &mut ContextUpdateEx, chara_id: NumID, inst_chara: Rc<InstCharacter>) -> LogicCharaValue {
         LogicCharaValue {
             chara_id,
+            panel: inst_chara.panel,
+            health: inst_chara.panel.max_health,
+            posture: inst_chara.panel.max_posture,
+            fire_buildup: 0.0,
+            ice_buildup: 0.0,
+            thunder_buildup: 0.0,
+            burn_time: TimeRange::EMPTY,
+            freeze_time: TimeRange::EMPTY,
+            shock_time: TimeRange::EMPTY,
+            shock_pending: false,
+            shock_started: false,
             inst_chara,
+            buffs: Vec::new(),
+            buff_requests: Vec::new(),
+            buff_cache: Vec::new(),
             ws: WsBox::new_in(
                 WsCharaValue {
                     chara_id,
@@ -82,27 +179,244 @@ impl LogicCharaValue {
         StateCharaValue {
             time_speed: self.time_speed,
             hit_lag_time: self.hit_lag_time,
+            health: self.health,
+            posture: self.posture,
+            fire_buildup: self.fire_buildup,
+            ice_buildup: self.ice_buildup,
+            thunder_buildup: self.thunder_buildup,
+            burn_time: self.burn_time,
+            freeze_time: self.freeze_time,
+            shock_time: self.shock_time,
+            shock_pending: self.shock_pending,
         }
     }
 
-    pub(crate) fn restore(&mut self, _ctx: &ContextRestore, state: &StateCharaValue) -> XResult<()> {
+    pub(crate) fn buff_states(&self) -> Vec<StateCharaBuff> {
+        self.buffs.iter().map(|buff| buff.state()).collect()
+    }
+
+    #[inline]
+    pub(crate) fn buff_states_into(&self, buff_states: &mut Vec<StateCharaBuff>) {
+        buff_states.clear();
+        buff_states.extend(self.buffs.iter().map(|buff| buff.state()));
+    }
+
+    /// Loads the instances of the buffs in the states, used by late-join. `restore()` only finds the cached ones.
+    pub(crate) fn rebuild_buffs(&mut self, ctx: &mut ContextUpdateEx, buff_states: &[StateCharaBuff]) -> XResult<()> {
+        for state in buff_states {
+            if !self.buff_cache.iter().any(|inst| inst.tmpl_id == state.tmpl_id) {
+                let inst = Rc::new(InstBuff::new(&mut ctx.context_assemble(), state.tmpl_id)?);
+                self.buff_cache.push(inst);
+            }
+        }
+        Ok(())
+    }
+
+    /// Restores from a state of this character, its buffs went through `apply_buff()` and are cached.
+    /// A state from elsewhere (late-join keyframe) needs `rebuild_buffs()` first, or fails with NotFound.
+    pub(crate) fn restore(
+        &mut self,
+        _ctx: &ContextRestore,
+        state: &StateCharaValue,
+        buff_states: &[StateCharaBuff],
+    ) -> XResult<()> {
         self.hit_lag_time = state.hit_lag_time;
+        self.health = state.health;
+        self.posture = state.posture;
+        self.fire_buildup = state.fire_buildup;
+        self.ice_buildup = state.ice_buildup;
+        self.thunder_buildup = state.thunder_buildup;
+        self.burn_time = state.burn_time;
+        self.freeze_time = state.freeze_time;
+        self.shock_time = state.shock_time;
+        self.shock_pending = state.shock_pending;
+        self.shock_started = false;
+
+        self.buffs.clear();
+        self.buff_requests.clear();
+        for state in buff_states {
+            let Some(inst) = self.buff_cache.iter().find(|inst| inst.tmpl_id == state.tmpl_id)
+            else {
+                return xresf!(NotFound; "chara_id={}, buff={}", self.chara_id, state.tmpl_id);
+            };
+            self.buffs.push(LogicCharaBuff {
+                inst: inst.clone(),
+                src_chara_id: state.src_chara_id,
+                stacks: state.stacks,
+                start_time: state.start_time,
+                end_time: state.end_time,
+                next_tick_time: state.next_tick_time,
+            });
+        }
+        self.update_panel();
         Ok(())
     }
 
+    /// Swaps the panel values to another style, keeps the health/posture ratio.
+    pub(crate) fn switch_style(&mut self, inst_chara: Rc<InstCharacter>) {
+        let health_ratio = ifelse!(self.panel.max_health > 0.0, self.health / self.panel.max_health, 1.0);
+        let posture_ratio = ifelse!(self.panel.max_posture > 0.0, self.posture / self.panel.max_posture, 1.0);
+        self.inst_chara = inst_chara;
+        self.update_panel();
+        self.health = health_ratio * self.panel.max_health;
+        self.posture = posture_ratio * self.panel.max_posture;
+    }
+
     pub(crate) fn init(&mut self, _ctx: &mut ContextUpdateEx) -> XResult<()> {
         Ok(())
     }
 
     pub(crate) fn update(&mut self, ctx: &mut ContextUpdateEx) -> XResult<()> {
-        if !self.hit_lag_time.contains(ctx.time.time) {
+        let time = ctx.time.time;
+        if !self.hit_lag_time.contains(time) {
             self.hit_lag_time = TimeRange::EMPTY;
         }
+        if !self.freeze_time.contains(time) {
+            self.freeze_time = TimeRange::EMPTY;
+        }
+
+        // The hit lag pauses the character, and the freeze status slows it down.
+        let hit_lag_speed = ifelse!(self.hit_lag_time.is_empty(), 1.0, 0.0);
+        let freeze_speed = ifelse!(self.freeze_time.is_empty(), 1.0, FREEZE_TIME_SPEED);
+        self.time_speed = hit_lag_speed * freeze_speed;
+
+        self.update_elements(ctx);
+        self.update_buffs(ctx)?;
+        Ok(())
+    }
+
+    fn update_elements(&mut self, ctx: &mut ContextUpdateEx) {
+        let time = ctx.time.time;
+        let spf = ctx.time.spf;
+
+        let decay = ELEMENTAL_BUILDUP_DECAY * spf;
+        self.fire_buildup = (self.fire_buildup - decay).max(0.0);
+        self.ice_buildup = (self.ice_buildup - decay).max(0.0);
+        self.thunder_buildup = (self.thunder_buildup - decay).max(0.0);
+
+        // Burns in the character's time, paused by the hit lag and slowed down by the freeze status.
+        if !self.burn_time.contains(time) {
+            self.burn_time = TimeRange::EMPTY;
+        }
+        else {
+            let damage = self.panel.max_health * BURN_DAMAGE_RATIO * spf * self.time_speed;
+            self.health = (self.health - damage).max(0.0);
+        }
+
+        if !self.shock_time.contains(time) {
+            self.shock_time = TimeRange::EMPTY;
+        }
+        self.shock_started = mem::take(&mut self.shock_pending);
+    }
+
+    /// Fills the elemental build-up meters, scaled by the fire/ice/thunder defense up/down.
+    /// Reaching ELEMENTAL_BUILDUP_THRESHOLD empties the meter and triggers the status.
+    pub(crate) fn add_buildup(&mut self, time: f32, fire: f32, ice: f32, thunder: f32) {
+        let panel = &self.panel;
+        let fire = fire * (1.0 + panel.fire_defense_down - panel.fire_defense_up).max(0.0);
+        let ice = ice * (1.0 + panel.ice_defense_down - panel.ice_defense_up).max(0.0);
+        let thunder = thunder * (1.0 + panel.thunder_defense_down - panel.thunder_defense_up).max(0.0);
+
+        self.fire_buildup += fire;
+        if self.fire_buildup >= ELEMENTAL_BUILDUP_THRESHOLD {
+            self.fire_buildup = 0.0;
+            self.burn_time = TimeRange::new(time, time + BURN_DURATION);
+        }
+
+        self.ice_buildup += ice;
+        if self.ice_buildup >= ELEMENTAL_BUILDUP_THRESHOLD {
+            self.ice_buildup = 0.0;
+            self.freeze_time = TimeRange::new(time, time + FREEZE_DURATION);
+        }
+
+        self.thunder_buildup += thunder;
+        if self.thunder_buildup >= ELEMENTAL_BUILDUP_THRESHOLD {
+            self.thunder_buildup = 0.0;
+            self.shock_time = TimeRange::new(time, time + SHOCK_DURATION);
+            self.shock_pending = true;
+            self.posture = (self.posture - self.panel.max_posture * SHOCK_POSTURE_RATIO).max(0.0);
+        }
+    }
+
+    /// Requests a buff on this character, from a hit, an item or a script.
+    /// The buff is applied in the next value update.
+    #[inline]
+    pub(crate) fn request_buff(&mut self, tmpl_id: TmplID, src_chara_id: NumID) {
+        self.buff_requests.push((tmpl_id, src_chara_id));
+    }
+
+    fn update_buffs(&mut self, ctx: &mut ContextUpdateEx) -> XResult<()> {
+        let time = ctx.time.time;
+
+        // Ticks before applying the requests, a refresh doesn't skip the tick due in this frame.
+        // The ticks are strictly inside the duration, there's no tick at the expiry.
+        for buff in self.buffs.iter_mut() {
+            if buff.inst.tick_interval <= 0.0 {
+                continue;
+            }
+            while !strict_lt!(time, buff.next_tick_time) && strict_lt!(buff.next_tick_time, buff.end_time) {
+                let stacks = buff.stacks as f32;
+                self.health = (self.health + buff.inst.tick_health * stacks).clamp(0.0, self.panel.max_health);
+                self.posture = (self.posture + buff.inst.tick_posture * stacks).clamp(0.0, self.panel.max_posture);
+                buff.next_tick_time += buff.inst.tick_interval;
+            }
+        }
+
+        let count = self.buffs.len();
+        self.buffs.retain(|buff| buff.end_time > time);
+        let mut changed = count != self.buffs.len();
+
+        for (tmpl_id, src_chara_id) in mem::take(&mut self.buff_requests) {
+            changed |= self.apply_buff(ctx, tmpl_id, src_chara_id)?;
+        }
 
-        self.time_speed = ifelse!(self.hit_lag_time().contains(ctx.time.time), 0.0, 1.0);
+        if changed {
+            self.update_panel();
+        }
         Ok(())
     }
 
+    fn apply_buff(&mut self, ctx: &mut ContextUpdateEx, tmpl_id: TmplID, src_chara_id: NumID) -> XResult<bool> {
+        let time = ctx.time.time;
+        if let Some(buff) = self.buffs.iter_mut().find(|buff| buff.inst.tmpl_id == tmpl_id) {
+            match buff.inst.stacking {
+                InstBuffStacking::Ignore => return Ok(false),
+                InstBuffStacking::Refresh => {}
+                InstBuffStacking::Stack => buff.stacks = (buff.stacks + 1).min(buff.inst.max_stacks),
+            }
+            buff.src_chara_id = src_chara_id;
+            buff.end_time = time + buff.inst.duration;
+            buff.next_tick_time = time + buff.inst.tick_interval;
+            return Ok(true);
+        }
+
+        let inst = match self.buff_cache.iter().find(|inst| inst.tmpl_id == tmpl_id) {
+            Some(inst) => inst.clone(),
+            None => {
+                let inst = Rc::new(InstBuff::new(&mut ctx.context_assemble(), tmpl_id)?);
+                self.buff_cache.push(inst.clone());
+                inst
+            }
+        };
+        self.buffs.push(LogicCharaBuff {
+            src_chara_id,
+            stacks: 1,
+            start_time: time,
+            end_time: time + inst.duration,
+            next_tick_time: time + inst.tick_interval,
+            inst,
+        });
+        Ok(true)
+    }
+
+    fn update_panel(&mut self) {
+        self.panel = self
+            .inst_chara
+            .panel_with_buffs(self.buffs.iter().map(|buff| (buff.inst.as_ref(), buff.stacks)));
+        self.health = self.health.min(self.panel.max_health);
+        self.posture = self.posture.min(self.panel.max_posture);
+    }
+
     pub(crate) fn before_hit(
         &mut self,
         dst_chara_val: &mut LogicCharaValue,
@@ -110,6 +424,14 @@ impl LogicCharaValue {
         phy_event: &PhyHitCharacterEvent,
     ) -> XResult<()> {
         self.hit_lag_time = TimeRange::new(ctx.time, ctx.time + cf2s(10));
+        match ctx.event.interaction {
+            HitInteraction::Clash => dst_chara_val.hit_lag_time = self.hit_lag_time,
+            HitInteraction::Counter => {
+                let deposture = dst_chara_val.panel.max_posture * COUNTER_POSTURE_RATIO;
+                dst_chara_val.posture = (dst_chara_val.posture - deposture).max(0.0);
+            }
+            HitInteraction::Normal | HitInteraction::Blocked => {}
+        }
         Ok(())
     }
 
@@ -139,6 +461,21 @@ impl LogicCharaValue {
         &self.ws
     }
 
+    #[inline]
+    pub(crate) fn panel(&self) -> &PanelValues {
+        &self.panel
+    }
+
+    #[inline]
+    pub(crate) fn health(&self) -> f32 {
+        self.health
+    }
+
+    #[inline]
+    pub(crate) fn posture(&self) -> f32 {
+        self.posture
+    }
+
     #[inline]
     pub(crate) fn time_speed(&self) -> f32 {
         self.time_speed
@@ -148,4 +485,10 @@ impl LogicCharaValue {
     pub(crate) fn hit_lag_time(&self) -> TimeRange {
         self.hit_lag_time
     }
+
+    /// Whether the shock status started since the previous update, true in one frame only.
+    #[inline]
+    pub(crate) fn shock_started(&self) -> bool {
+        self.shock_started
+    }
 }
@@ -11,6 +11,79 @@ unsafe impl Send for StaticWrapper {}
 
 static STATIC_WRAPPER: StaticWrapper = StaticWrapper(HistoryVec::new());
 
+static EMPTY_GRID: CharaGrid = CharaGrid::new(CharaGrid::DEFAULT_CELL_SIZE);
+
+const LEN_THRESHOLD_SQ: f32 = 1e-6;
+
+/// What the area queries read from a character, its side and its position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CharaSpot {
+    pub is_player: bool,
+    pub position: Vec3A,
+}
+
+impl CharaSpot {
+    #[inline]
+    pub(crate) fn of(chara: &LogicCharacter) -> CharaSpot {
+        CharaSpot {
+            is_player: chara.is_player(),
+            position: chara.physics().position(),
+        }
+    }
+}
+
+/// The characters searched by the area queries, by character index.
+pub(crate) trait CharaSpots {
+    fn spot(&self, idx: usize) -> Option<CharaSpot>;
+
+    fn for_each_spot<F: FnMut(usize, CharaSpot)>(&self, func: F);
+}
+
+impl CharaSpots for HistoryVecRest<'_, Box<LogicCharacter>> {
+    #[inline]
+    fn spot(&self, idx: usize) -> Option<CharaSpot> {
+        self.get(idx).map(|chara| CharaSpot::of(chara))
+    }
+
+    #[inline]
+    fn for_each_spot<F: FnMut(usize, CharaSpot)>(&self, mut func: F) {
+        self.index_iter().for_each(|(idx, chara)| func(idx, CharaSpot::of(chara)));
+    }
+}
+
+/// Copied spots of all characters except the searching one. Plain data, so it can be searched on worker threads.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CharaSpotsRest<'t> {
+    spots: &'t [CharaSpot],
+    taken_idx: usize,
+}
+
+impl<'t> CharaSpotsRest<'t> {
+    #[inline]
+    pub(crate) fn new(spots: &'t [CharaSpot], taken_idx: usize) -> CharaSpotsRest<'t> {
+        CharaSpotsRest { spots, taken_idx }
+    }
+}
+
+impl CharaSpots for CharaSpotsRest<'_> {
+    #[inline]
+    fn spot(&self, idx: usize) -> Option<CharaSpot> {
+        if idx == self.taken_idx {
+            return None;
+        }
+        self.spots.get(idx).copied()
+    }
+
+    #[inline]
+    fn for_each_spot<F: FnMut(usize, CharaSpot)>(&self, mut func: F) {
+        for (idx, spot) in self.spots.iter().enumerate() {
+            if idx != self.taken_idx {
+                func(idx, *spot);
+            }
+        }
+    }
+}
+
 impl<'t> HistoryVecRest<'t, Box<LogicCharacter>> {
     pub(super) const fn empty() -> HistoryVecRest<'static, Box<LogicCharacter>> {
         unsafe { HistoryVecRest::new(&STATIC_WRAPPER.0, usize::MAX) }
@@ -23,16 +96,10 @@ impl<'t> HistoryVecRest<'t, Box<LogicCharacter>> {
         center: Vec3A,
         indexes: &mut Vec<u32>,
     ) {
-        // TODO: use octree to optimize search
         // TODO: use team instead of is_player
 
         for (idx, chara) in self.index_iter() {
-            if is_player != chara.is_player() {
-                continue;
-            }
-
-            let dist_sq = (chara.physics().position() - center).length_squared();
-            if dist_sq <= sphere.radius_sq() {
+            if in_sphere(CharaSpot::of(chara), is_player, sphere, center) {
                 indexes.push(idx as u32);
             }
         }
@@ -46,37 +113,291 @@ impl<'t> HistoryVecRest<'t, Box<LogicCharacter>> {
         direction: Vec2xz,
         indexes: &mut Vec<u32>,
     ) {
-        const LEN_THRESHOLD_SQ: f32 = 1e-6;
-        // TODO: use octree to optimize search
         // TODO: use team instead of is_player
 
         let direction = direction.as_vec3a();
         if unlikely(direction.length_squared() < LEN_THRESHOLD_SQ) {
             return;
         }
-        let dir_len = direction.length();
 
         let cos_half_angle = cone.half_angle.cos();
         for (idx, chara) in self.index_iter() {
-            if is_player != chara.is_player() {
-                continue;
+            if in_spherical_cone(CharaSpot::of(chara), is_player, cone, center, direction, cos_half_angle) {
+                indexes.push(idx as u32);
             }
+        }
+    }
+}
 
-            let diff = chara.physics().position() - center;
-            let dist_sq = diff.length_squared();
-            if dist_sq > cone.radius_sq() {
-                continue;
+#[inline]
+fn in_sphere(spot: CharaSpot, is_player: bool, sphere: &ShapeSphere, center: Vec3A) -> bool {
+    if is_player != spot.is_player {
+        return false;
+    }
+    let dist_sq = (spot.position - center).length_squared();
+    dist_sq <= sphere.radius_sq()
+}
+
+#[inline]
+fn in_spherical_cone(
+    spot: CharaSpot,
+    is_player: bool,
+    cone: &ShapeSphericalCone,
+    center: Vec3A,
+    direction: Vec3A,
+    cos_half_angle: f32,
+) -> bool {
+    if is_player != spot.is_player {
+        return false;
+    }
+
+    let diff = spot.position - center;
+    let dist_sq = diff.length_squared();
+    if dist_sq > cone.radius_sq() {
+        return false;
+    }
+    if dist_sq < LEN_THRESHOLD_SQ {
+        return true;
+    }
+
+    let dot = diff.dot(direction) / (dist_sq.sqrt() * direction.length());
+    dot >= cos_half_angle
+}
+
+/// A uniform grid over the character positions (on XZ plane), rebuilt once per frame after the physics update.
+///
+/// Serves all character area queries (AI targeting, lock-on, ...). Results are always in ascending character
+/// index order, the same as a linear scan, so switching between them never changes the logic.
+/// Queries fall back to a linear scan if the grid is not built.
+#[derive(Debug)]
+pub(crate) struct CharaGrid {
+    cell_size: f32,
+    built: bool,
+    entries: Vec<((i32, i32), u32)>, // Sorted by (cell, character index)
+}
+
+impl CharaGrid {
+    pub(crate) const DEFAULT_CELL_SIZE: f32 = 8.0;
+
+    #[inline]
+    pub(crate) const fn new(cell_size: f32) -> CharaGrid {
+        CharaGrid {
+            cell_size,
+            built: false,
+            entries: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn empty() -> &'static CharaGrid {
+        &EMPTY_GRID
+    }
+
+    #[inline]
+    pub(crate) fn is_built(&self) -> bool {
+        self.built
+    }
+
+    #[inline]
+    pub(crate) fn rebuild(&mut self, characters: &HistoryVec<Box<LogicCharacter>>) {
+        self.rebuild_by_positions(characters.iter().map(|chara| chara.physics().position()));
+    }
+
+    pub(crate) fn rebuild_by_positions<I: Iterator<Item = Vec3A>>(&mut self, positions: I) {
+        self.entries.clear();
+        for (idx, position) in positions.enumerate() {
+            self.entries.push((self.cell_of(position.x, position.z), idx as u32));
+        }
+        self.entries.sort_unstable();
+        self.built = true;
+    }
+
+    #[inline]
+    fn cell_of(&self, x: f32, z: f32) -> (i32, i32) {
+        // Saturating float to int casts, NaN goes to 0.
+        ((x / self.cell_size).floor() as i32, (z / self.cell_size).floor() as i32)
+    }
+
+    /// Appends the indexes of characters in the cells overlapping the square [center - radius, center + radius],
+    /// in ascending order.
+    pub(crate) fn candidates(&self, center: Vec3A, radius: f32, indexes: &mut Vec<u32>) {
+        let start = indexes.len();
+        self.for_each_candidate(center, radius, |idx| indexes.push(idx));
+        indexes[start..].sort_unstable();
+    }
+
+    /// Visits the same indexes as `candidates()`, but in cell order.
+    fn for_each_candidate<F: FnMut(u32)>(&self, center: Vec3A, radius: f32, mut func: F) {
+        let (min_x, min_z) = self.cell_of(center.x - radius, center.z - radius);
+        let (max_x, max_z) = self.cell_of(center.x + radius, center.z + radius);
+
+        let cell_count = (max_x as i64 - min_x as i64 + 1) * (max_z as i64 - min_z as i64 + 1);
+        if cell_count >= self.entries.len() as i64 {
+            self.entries.iter().for_each(|(_, idx)| func(*idx));
+        }
+        else {
+            for x in min_x..=max_x {
+                let begin = self.entries.partition_point(|(cell, _)| *cell < (x, min_z));
+                let end = self.entries.partition_point(|(cell, _)| *cell <= (x, max_z));
+                self.entries[begin..end].iter().for_each(|(_, idx)| func(*idx));
             }
+        }
+    }
 
-            if dist_sq < LEN_THRESHOLD_SQ {
-                indexes.push(idx as u32);
-                continue;
+    pub(crate) fn search_chara_in_sphere<C: CharaSpots>(
+        &self,
+        characters: &C,
+        is_player: bool,
+        sphere: &ShapeSphere,
+        center: Vec3A,
+        indexes: &mut Vec<u32>,
+    ) {
+        if !self.is_built() {
+            return characters.for_each_spot(|idx, spot| {
+                if in_sphere(spot, is_player, sphere, center) {
+                    indexes.push(idx as u32);
+                }
+            });
+        }
+
+        let start = indexes.len();
+        self.candidates(center, sphere.radius, indexes);
+        retain_from(indexes, start, |idx| match characters.spot(idx as usize) {
+            Some(spot) => in_sphere(spot, is_player, sphere, center),
+            None => false,
+        });
+    }
+
+    /// Finds the nearest character in the sphere accepted by `filter`, without a temporary index buffer.
+    /// Ties go to the lower index, the same as the first nearest one of a search in ascending order.
+    pub(crate) fn find_nearest_chara_in_sphere<F: Fn(&LogicCharacter) -> bool>(
+        &self,
+        characters: &HistoryVecRest<Box<LogicCharacter>>,
+        is_player: bool,
+        sphere: &ShapeSphere,
+        center: Vec3A,
+        filter: F,
+    ) -> Option<u32> {
+        let mut nearest: Option<(f32, u32)> = None;
+        let mut visit = |idx: u32| {
+            let Some(chara) = characters.get(idx as usize)
+            else {
+                return;
+            };
+            if !in_sphere(CharaSpot::of(chara), is_player, sphere, center) || !filter(chara) {
+                return;
+            }
+            let dist_sq = (chara.physics().position() - center).length_squared();
+            if nearest.is_none_or(|nearest| (dist_sq, idx) < nearest) {
+                nearest = Some((dist_sq, idx));
             }
+        };
 
-            let dot = diff.dot(direction) / (dist_sq.sqrt() * dir_len);
-            if dot >= cos_half_angle {
-                indexes.push(idx as u32);
+        match self.is_built() {
+            true => self.for_each_candidate(center, sphere.radius, &mut visit),
+            false => characters.index_iter().for_each(|(idx, _)| visit(idx as u32)),
+        }
+        nearest.map(|(_, idx)| idx)
+    }
+
+    pub(crate) fn search_chara_in_spherical_cone<C: CharaSpots>(
+        &self,
+        characters: &C,
+        is_player: bool,
+        cone: &ShapeSphericalCone,
+        center: Vec3A,
+        direction: Vec2xz,
+        indexes: &mut Vec<u32>,
+    ) {
+        let direction = direction.as_vec3a();
+        if unlikely(direction.length_squared() < LEN_THRESHOLD_SQ) {
+            return;
+        }
+        let cos_half_angle = cone.half_angle.cos();
+
+        if !self.is_built() {
+            return characters.for_each_spot(|idx, spot| {
+                if in_spherical_cone(spot, is_player, cone, center, direction, cos_half_angle) {
+                    indexes.push(idx as u32);
+                }
+            });
+        }
+
+        let start = indexes.len();
+        self.candidates(center, cone.radius, indexes);
+        retain_from(indexes, start, |idx| match characters.spot(idx as usize) {
+            Some(spot) => in_spherical_cone(spot, is_player, cone, center, direction, cos_half_angle),
+            None => false,
+        });
+    }
+}
+
+#[inline]
+fn retain_from<F: FnMut(u32) -> bool>(indexes: &mut Vec<u32>, start: usize, mut func: F) {
+    let mut write = start;
+    for read in start..indexes.len() {
+        let idx = indexes[read];
+        if func(idx) {
+            indexes[write] = idx;
+            write += 1;
+        }
+    }
+    indexes.truncate(write);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chara_grid_candidates() {
+        let positions = [
+            Vec3A::new(0.0, 0.0, 0.0),
+            Vec3A::new(30.0, 0.0, 30.0),
+            Vec3A::new(-5.0, 2.0, 3.0),
+            Vec3A::new(7.9, 0.0, -7.9),
+            Vec3A::new(-100.0, 0.0, 50.0),
+            Vec3A::new(1.0, 0.0, 1.0),
+        ];
+
+        let mut grid = CharaGrid::new(4.0);
+        assert!(!grid.is_built());
+        grid.rebuild_by_positions(positions.iter().cloned());
+        assert!(grid.is_built());
+
+        let mut indexes = vec![99];
+        grid.candidates(Vec3A::ZERO, 3.0, &mut indexes);
+        assert_eq!(indexes, vec![99, 0, 5]);
+
+        indexes.clear();
+        grid.candidates(Vec3A::new(30.0, 0.0, 30.0), 1.0, &mut indexes);
+        assert_eq!(indexes, vec![1]);
+
+        indexes.clear();
+        grid.candidates(Vec3A::new(200.0, 0.0, 200.0), 1.0, &mut indexes);
+        assert!(indexes.is_empty());
+
+        // Large radius, scans all entries.
+        indexes.clear();
+        grid.candidates(Vec3A::ZERO, 1000.0, &mut indexes);
+        assert_eq!(indexes, vec![0, 1, 2, 3, 4, 5]);
+
+        // Every character within radius must be a candidate.
+        for center in positions.iter() {
+            for radius in [0.5, 3.0, 9.0, 40.0] {
+                indexes.clear();
+                grid.candidates(*center, radius, &mut indexes);
+                assert!(indexes.windows(2).all(|w| w[0] < w[1]));
+                for (idx, pos) in positions.iter().enumerate() {
+                    if (*pos - *center).length() <= radius {
+                        assert!(indexes.contains(&(idx as u32)), "{} {} {}", center, radius, idx);
+                    }
+                }
             }
         }
+
+        let mut indexes = vec![1, 2, 3, 4, 5, 6];
+        retain_from(&mut indexes, 2, |idx| idx % 2 == 0);
+        assert_eq!(indexes, vec![1, 2, 4, 6]);
     }
 }
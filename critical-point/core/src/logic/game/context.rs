@@ -3,14 +3,15 @@ use glam::Vec3A;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
-use crate::consts::FPS;
+use crate::consts::DEFAULT_TICK_RATE;
 use crate::instance::ContextAssemble;
 use crate::logic::base::StateAny;
 use crate::logic::character::LogicCharacter;
+use crate::logic::game::characters::CharaGrid;
 use crate::logic::game::game::LogicSystems;
 use crate::logic::system::StateSet;
 use crate::logic::zone::LogicZone;
-use crate::utils::{HistoryVecRest, NumID, Symbol, XResult, force_mut};
+use crate::utils::{HistoryVecRest, HitInteraction, NumID, Symbol, XResult, force_mut};
 
 //
 // Context Update
@@ -55,6 +56,7 @@ pub struct ContextUpdateEx<'t> {
     pub(crate) time: &'t GameTime,
     pub(crate) zone: &'t LogicZone,
     pub(crate) characters: HistoryVecRest<'t, Box<LogicCharacter>>,
+    pub(crate) chara_grid: &'t CharaGrid,
     pub(crate) hit_events: &'t [HitCharacterEvent],
 }
 
@@ -80,6 +82,7 @@ impl<'t> ContextUpdateEx<'t> {
             time,
             zone,
             characters: HistoryVecRest::empty(),
+            chara_grid: CharaGrid::empty(),
             hit_events: &[],
         }
     }
@@ -103,24 +106,60 @@ impl<'t> ContextUpdateEx<'t> {
 // Game Time
 //
 
-#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct GameTime {
     pub(crate) frame: u32,
     pub(crate) synced_frame: u32,
     pub(crate) time: f32,
     pub(crate) synced_time: f32,
+    pub(crate) tick_rate: u32,
+    pub(crate) fps: f32, // frames per second
+    pub(crate) spf: f32, // seconds per frame
+}
+
+impl Default for GameTime {
+    fn default() -> GameTime {
+        GameTime::new(0, 0)
+    }
 }
 
 impl GameTime {
     #[inline]
     pub(crate) fn new(frame: u32, synced_frame: u32) -> GameTime {
+        GameTime::with_tick_rate(frame, synced_frame, DEFAULT_TICK_RATE)
+    }
+
+    #[inline]
+    pub(crate) fn with_tick_rate(frame: u32, synced_frame: u32, tick_rate: u32) -> GameTime {
+        let fps = tick_rate as f32;
         GameTime {
             frame,
             synced_frame,
-            time: frame as f32 / FPS,
-            synced_time: synced_frame as f32 / FPS,
+            time: frame as f32 / fps,
+            synced_time: synced_frame as f32 / fps,
+            tick_rate,
+            fps,
+            spf: 1.0 / fps,
         }
     }
+
+    /// Same tick rate, another frame.
+    #[inline]
+    pub(crate) fn at(&self, frame: u32, synced_frame: u32) -> GameTime {
+        GameTime::with_tick_rate(frame, synced_frame, self.tick_rate)
+    }
+
+    /// Seconds to frames at this tick rate, rounded.
+    #[inline]
+    pub(crate) fn s2f(&self, second: f32) -> u32 {
+        (second * self.fps).round() as u32
+    }
+
+    /// Seconds to frames at this tick rate, rounded.
+    #[inline]
+    pub(crate) fn s2ff_round(&self, second: f32) -> f32 {
+        (second * self.fps).round()
+    }
 }
 
 //
@@ -159,37 +198,43 @@ impl ContextRestore {
 pub struct ContextHitGenerate<'t, E> {
     pub(crate) frame: u32,
     pub(crate) time: f32,
+    pub(crate) fps: f32,
+    pub(crate) spf: f32,
     pub(crate) events: &'t mut Vec<E>,
 }
 
 impl<'t, E> ContextHitGenerate<'t, E> {
     #[inline]
-    pub(crate) fn new(frame: u32, events: &'t mut Vec<E>) -> ContextHitGenerate<'t, E> {
+    pub(crate) fn new(frame: u32, fps: f32, events: &'t mut Vec<E>) -> ContextHitGenerate<'t, E> {
         ContextHitGenerate {
             frame,
-            time: frame as f32 / FPS,
+            time: frame as f32 / fps,
+            fps,
+            spf: 1.0 / fps,
             events,
         }
     }
 
     #[inline]
     pub(crate) fn context_update(&mut self, idx: usize) -> ContextHitUpdate<'_, E> {
-        ContextHitUpdate::new(self.frame, &mut self.events[idx])
+        ContextHitUpdate::new(self.frame, self.fps, &mut self.events[idx])
     }
 }
 
 pub struct ContextHitUpdate<'t, E> {
     pub(crate) frame: u32,
     pub(crate) time: f32,
+    pub(crate) spf: f32,
     pub(crate) event: &'t mut E,
 }
 
 impl<'t, E> ContextHitUpdate<'t, E> {
     #[inline]
-    pub(crate) fn new(frame: u32, event: &'t mut E) -> ContextHitUpdate<'t, E> {
+    pub(crate) fn new(frame: u32, fps: f32, event: &'t mut E) -> ContextHitUpdate<'t, E> {
         ContextHitUpdate {
             frame,
-            time: frame as f32 / FPS,
+            time: frame as f32 / fps,
+            spf: 1.0 / fps,
             event,
         }
     }
@@ -221,6 +266,7 @@ pub struct HitCharacterEvent {
     pub group_index: u16,
     pub box_hit_times: u16,
     pub group_hit_times: u16,
+    pub interaction: HitInteraction,
     // Normal for this collision, direction along which to move dst_chara out of collision along the shortest path.
     pub collision_normal: Vec3A,
     // The average position of all collision points
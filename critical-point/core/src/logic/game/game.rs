@@ -4,25 +4,30 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::asset::AssetLoader;
-use crate::consts::{MAX_INPUT_WINDOW, SPF};
-use crate::input::{InputFrameInputs, InputManager, InputPlayerInputs};
-use crate::logic::base::{LogicAny, LogicType, StateAny, StateBase, StateType, impl_state};
-use crate::logic::character::{LogicCharacter, StateCharacterUpdate};
+use crate::consts::{DEFAULT_TICK_RATE, MAX_INPUT_WINDOW_SECS, TICK_RATE_30, TICK_RATE_60};
+use crate::input::{InputFrameInputs, InputManager, InputPlayerInputs, InputQueueSnapshot};
+use crate::logic::base::{LogicAny, LogicType, StateBase, StateType, impl_state};
+use crate::animation::Animator;
+use crate::logic::character::{AiPerceiver, LogicCharacter};
+use crate::logic::game::characters::{CharaGrid, CharaSpot, CharaSpotsRest};
 use crate::logic::game::context::{
     ContextHitGenerate, ContextRestore, ContextUpdate, ContextUpdateEx, GameTime, HitCharacterEvent,
 };
+use crate::logic::hit_object::{LogicHitObject, StateHitObjectInit};
 use crate::logic::physics::{
-    PhyBroadPhaseLayerInterface, PhyContactCollector, PhyHitCharacterEvent, PhyObjectLayerPairFilter,
-    PhyObjectVsBroadPhaseLayerFilter,
+    PhyBroadPhaseLayerInterface, PhyContactCollector, PhyHitCharacterEvent, PhyHitObjectEvent,
+    PhyObjectLayerPairFilter, PhyObjectVsBroadPhaseLayerFilter,
 };
 use crate::logic::script::LogicScriptEngine;
-use crate::logic::system::{StateIdentity, StateRandom, StateSet, SystemIdentity, SystemRandom, SystemState};
+use crate::logic::system::{
+    StateChecksum, StateIdentity, StatePoolCounters, StateRandom, StateSet, SystemIdentity, SystemRandom, SystemState,
+};
 use crate::logic::zone::LogicZone;
 use crate::parameter::ParamGame;
 use crate::save::SaveManager;
 use crate::script::ScriptEngineConfig;
 use crate::template::TmplDatabase;
-use crate::utils::{HistoryVec, NumID, XResult, extend, force_mut, xres};
+use crate::utils::{HistoryVec, NumID, XResult, extend, force_mut, parallel_for_each, xerr, xres, xresf};
 
 pub struct LogicSystems {
     stopped: bool,
@@ -49,6 +54,8 @@ impl LogicSystems {
         tmpl_db: TmplDatabase,
         asset_path: P,
         save_path: Option<PathBuf>,
+        seed: u64,
+        tick_rate: u32,
     ) -> XResult<LogicSystems> {
         let physics = PhysicsSystem::new(
             PhyBroadPhaseLayerInterface::new_vbox(PhyBroadPhaseLayerInterface),
@@ -62,10 +69,10 @@ impl LogicSystems {
             asset: AssetLoader::new(asset_path.as_ref())?,
             physics,
             // executor: ScriptExecutor::new(),
-            identity: SystemIdentity::new(),
-            input: InputManager::new(MAX_INPUT_WINDOW),
-            rand: SystemRandom::new(12345, 98765),
-            state: SystemState::new(),
+            identity: SystemIdentity::new(tick_rate),
+            input: InputManager::new(MAX_INPUT_WINDOW_SECS * tick_rate),
+            rand: SystemRandom::new(seed, tick_rate),
+            state: SystemState::with_tick_rate(tick_rate),
             save: match save_path {
                 Some(save_path) => Some(SaveManager::new(save_path)?),
                 None => None,
@@ -91,11 +98,42 @@ impl LogicSystems {
 // LogicLoop
 //
 
+fn resolve_tick_rate(param: &ParamGame) -> XResult<u32> {
+    match param.tick_rate {
+        0 => Ok(DEFAULT_TICK_RATE),
+        TICK_RATE_30 | TICK_RATE_60 => Ok(param.tick_rate),
+        _ => xresf!(BadArgument; "tick_rate={}", param.tick_rate),
+    }
+}
+
+/// Everything needed to rebuild a running match at a confirmed frame.
+/// Used by spectators joining late, and by players reconnecting after a drop.
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct LogicJoinSnapshot {
+    pub param: ParamGame,
+    /// The confirmed StateSet at the join frame.
+    pub keyframe: Arc<StateSet>,
+    /// Init states of the hit objects alive at the join frame.
+    pub hit_objects: Vec<Arc<StateHitObjectInit>>,
+    /// The confirmed input history of all players, within the pre-input window.
+    pub inputs: Vec<InputQueueSnapshot>,
+}
+
+impl LogicJoinSnapshot {
+    #[inline]
+    pub fn frame(&self) -> u32 {
+        self.keyframe.frame
+    }
+}
+
 pub struct LogicLoop {
     systems: LogicSystems,
     game: Option<Box<LogicGame>>,
     frame: u32, // The current game frame for library user's side
     local_mode: bool,
+    following: bool, // Rebuilt from a LogicJoinSnapshot, only follows confirmed inputs
+    tick_rate: u32,
+    param: ParamGame,
 }
 
 impl Drop for LogicLoop {
@@ -119,13 +157,14 @@ impl LogicLoop {
             return xres!(BadArgument; "local mode only supports one player");
         }
 
-        let mut systems = LogicSystems::new(tmpl_db, asset_path, save_path)?;
+        let tick_rate = resolve_tick_rate(&param)?;
+        let mut systems = LogicSystems::new(tmpl_db, asset_path, save_path, param.seed, tick_rate)?;
         systems.input.init(param.players.len())?;
 
-        let time = GameTime::new(0, 0);
+        let time = GameTime::with_tick_rate(0, 0, tick_rate);
         systems.script.update_global(&time);
 
-        let (game, state_set) = LogicGame::new(&mut systems, &time, param)?;
+        let (game, state_set) = LogicGame::new(&mut systems, &time, param.clone())?;
         systems.state.init(state_set.clone())?;
 
         systems.physics.optimize_broad_phase();
@@ -135,18 +174,100 @@ impl LogicLoop {
             game: Some(game),
             frame: 0,
             local_mode,
+            following: false,
+            tick_rate,
+            param,
         };
         Ok((logic_loop, state_set))
     }
 
+    /// Rebuilds a running match at the snapshot frame.
+    ///
+    /// The returned loop only follows confirmed inputs, one InputPlayerInputs per player per frame,
+    /// so it never rolls back. The returned StateSet contains the init states of all living objects.
+    pub fn new_join<P: AsRef<Path>>(
+        tmpl_db: TmplDatabase,
+        asset_path: P,
+        join: LogicJoinSnapshot,
+    ) -> XResult<(LogicLoop, Arc<StateSet>)> {
+        let frame = join.frame();
+        let tick_rate = resolve_tick_rate(&join.param)?;
+        let mut systems = LogicSystems::new(tmpl_db, asset_path, None, join.param.seed, tick_rate)?;
+        systems.input.init_from_snapshots(&join.inputs)?;
+        if systems.input.synced_frame() != frame {
+            return xresf!(BadArgument; "inputs.synced_frame={}, frame={}", systems.input.synced_frame(), frame);
+        }
+
+        // Spawns the initial objects with the same ids, then restores them to the keyframe.
+        let time = GameTime::with_tick_rate(0, 0, tick_rate);
+        systems.script.update_global(&time);
+        let (mut game, init_set) = LogicGame::new(&mut systems, &time, join.param.clone())?;
+
+        let time = time.at(frame, frame);
+        systems.script.update_global(&time);
+        let ctx_restore = ContextRestore::new(join.keyframe.clone());
+        game.rebuild(&mut systems, &time, &ctx_restore, &join.hit_objects)?;
+
+        let game_state = join.keyframe.find_as::<StateGameUpdate>(NumID::GAME)?;
+        systems.identity.reset(frame, &game_state.identity);
+        systems.rand.reset(frame, &game_state.rand);
+        systems.state.reset(join.keyframe.clone());
+
+        systems.physics.optimize_broad_phase();
+
+        let mut state_set = StateSet::new(frame);
+        state_set.inits = init_set.inits.clone();
+        for init in join.hit_objects.iter() {
+            state_set.inits.push(init.clone());
+        }
+        game.collect_states_updates(&mut systems, &mut state_set)?;
+
+        let logic_loop = LogicLoop {
+            systems,
+            game: Some(game),
+            frame,
+            local_mode: join.param.local_mode,
+            following: true,
+            tick_rate,
+            param: join.param,
+        };
+        Ok((logic_loop, Arc::new(state_set)))
+    }
+
+    /// Takes a snapshot at the synced frame, for a spectator or a reconnecting player to join.
+    pub fn join_snapshot(&self) -> XResult<LogicJoinSnapshot> {
+        let synced_frame = self.systems.state.synced_frame();
+        let keyframe = match self.systems.state.get(synced_frame) {
+            Some(state_set) => state_set.clone(),
+            None => return xresf!(NotFound; "synced_frame={}", synced_frame),
+        };
+        let game = self
+            .game
+            .as_ref()
+            .ok_or_else(|| xerr!(Unexpected; "game not running"))?;
+        let hit_objects = game
+            .hit_objects
+            .iter()
+            .filter(|obj| obj.spawn_frame() <= synced_frame && obj.death_frame() >= synced_frame)
+            .map(|obj| obj.init_state())
+            .collect();
+        Ok(LogicJoinSnapshot {
+            param: self.param.clone(),
+            keyframe,
+            hit_objects,
+            inputs: self.systems.input.snapshots()?,
+        })
+    }
+
     pub fn update(&mut self, player_events: Vec<InputPlayerInputs>) -> XResult<Arc<StateSet>> {
         if self.systems.stopped {
             return xres!(Unexpected; "system stopped");
         }
 
-        println!("--------------------{}--------------------", self.frame);
+        #[cfg(feature = "debug-print")]
+        log::debug!("LogicLoop::update() frame={}", self.frame);
 
-        if self.local_mode {
+        if self.local_mode || self.following {
             self.update_local(player_events)
         }
         else {
@@ -155,8 +276,8 @@ impl LogicLoop {
     }
 
     fn update_local(&mut self, player_events: Vec<InputPlayerInputs>) -> XResult<Arc<StateSet>> {
-        if player_events.len() != 1 {
-            return xres!(BadArgument; "local mode must have one InputPlayerInputs per frame");
+        if player_events.len() != self.systems.input.player_count() as usize {
+            return xres!(BadArgument; "must have one InputPlayerInputs per player per frame");
         }
 
         let systems = &mut self.systems;
@@ -180,12 +301,13 @@ impl LogicLoop {
 
         // Update game logic.
 
+        let time = GameTime::with_tick_rate(game.frame + 1, synced_frame, self.tick_rate);
+
         let mut cl = PhyContactCollector::new_vpair(PhyContactCollector::new(game));
         systems
             .physics
-            .update_with_listeners::<_, ()>(SPF, 1, Some(&mut cl), None)?;
+            .update_with_listeners::<_, ()>(time.spf, 1, Some(&mut cl), None)?;
 
-        let time = GameTime::new(game.frame + 1, synced_frame);
         systems.script.update_global(&time);
 
         let state_set = game.update(systems, &time)?;
@@ -197,65 +319,89 @@ impl LogicLoop {
         let ret_state = systems.state[game.frame].clone();
 
         systems.identity.update(game.frame);
+        systems.rand.update(game.frame);
 
         systems.input.confirm()?;
         let state_sets = systems.state.confirm(systems.input.synced_frame())?;
         if let Some(save) = self.systems.save.as_mut() {
-            save.save_states(state_sets)?;
+            save.save_states(state_sets.clone())?;
         }
+        self.systems.state.recycle(state_sets);
 
         Ok(ret_state)
     }
 
-    fn update_online(&mut self, mut _player_events: Vec<InputPlayerInputs>) -> XResult<Arc<StateSet>> {
-        unimplemented!()
+    fn update_online(&mut self, mut player_events: Vec<InputPlayerInputs>) -> XResult<Arc<StateSet>> {
+        let systems = &mut self.systems;
+        let game = self.game.as_mut().unwrap();
+        self.frame += 1;
+
+        // Save inputs.
+
+        if let Some(save) = systems.save.as_mut() {
+            let player_events = InputFrameInputs::new(self.frame, &player_events);
+            save.save_input(player_events)?;
+        }
+
+        // Handle new inputs.
+        // The remote inputs may arrive late. The missing frames of a player are predicted by repeating
+        // its last InputVariables, and rolled back once the real inputs arrive.
+
+        if let Some(inputs) = player_events.iter().find(|inputs| inputs.frame > self.frame) {
+            return xresf!(BadArgument; "player_id={}, frame={}", inputs.player_id, inputs.frame);
+        }
+        player_events.sort_by_key(|inputs| (inputs.player_id.0, inputs.frame));
+        let base_frame = systems.input.produce(&player_events)?.min(game.frame);
+        let synced_frame = systems.input.synced_frame();
+
+        // Restore to base frame.
+
+        if base_frame < game.frame {
+            systems.state.restore(base_frame)?;
+
+            let time = GameTime::with_tick_rate(base_frame, synced_frame.min(base_frame), self.tick_rate);
+            let ctx = ContextRestore::new(systems.state[base_frame].clone());
+            game.restore(systems, &time, &ctx)?;
+            debug_assert_eq!(game.frame, base_frame);
+
+            // After the game, drops the ids and the random numbers generated in restoring.
+            systems.identity.restore(base_frame);
+            systems.rand.restore(base_frame);
+        }
+
+        // Update game logic to the current frame.
 
-        // let systems = &mut self.systems;
-        // let game = self.game.as_mut().unwrap();
-        // self.frame += 1;
+        while game.frame < self.frame {
+            let frame = game.frame + 1;
+            let time = GameTime::with_tick_rate(frame, synced_frame.min(frame), self.tick_rate);
 
-        // if let Some(save) = systems.save.as_mut() {
-        //     let player_events = InputFrameInputs::new(self.frame, &player_events);
-        //     save.save_input(player_events)?;
-        // }
+            let mut cl = PhyContactCollector::new_vpair(PhyContactCollector::new(game));
+            systems
+                .physics
+                .update_with_listeners::<_, ()>(time.spf, 1, Some(&mut cl), None)?;
 
-        // // Insert new input events
-        // bubble_sort_by(&mut player_events, |a, b| {
-        //     a.player_id < b.player_id && a.frame < b.frame
-        // });
-        // let base_frame = systems.input.produce(&player_events)?.min(game.frame);
+            systems.script.update_global(&time);
 
-        // // Restore to base_frame
-        // if base_frame < game.frame {
-        //     systems.state.restore(base_frame)?;
-        //     systems.identity.restore(base_frame);
-        //     // TODO: restore physics.
+            let state_set = game.update(systems, &time)?;
+            debug_assert_eq!(frame, game.frame);
 
-        //     let ctx = ContextRestore::new(systems.state[base_frame].clone());
-        //     game.restore(&ctx)?;
-        //     debug_assert_eq!(game.frame, base_frame);
-        // }
+            systems.state.append(state_set)?;
+            systems.identity.update(game.frame);
+            systems.rand.update(game.frame);
+        }
 
-        // // Update frame to current
-        // while game.frame < self.frame {
-        //     let frame = game.frame + 1;
-        //     let synced_frame = systems.input.synced_frame();
-        //     let mut ctx = ContextUpdate::new(systems, frame, synced_frame);
-        //     let state_set = game.update(&mut ctx)?;
-        //     // systems.physics.update(delta);
-        //     systems.state.append(state_set.clone())?;
+        // Handle states.
 
-        //     systems.identity.update(frame);
-        // }
-        // let ret_state = systems.state[game.frame].clone();
+        let ret_state = systems.state[game.frame].clone();
 
-        // systems.input.confirm()?;
-        // let state_sets = systems.state.confirm(systems.input.synced_frame())?;
-        // if let Some(save) = self.systems.save.as_mut() {
-        //     save.save_states(state_sets)?;
-        // }
+        systems.input.confirm()?;
+        let state_sets = systems.state.confirm(systems.input.synced_frame())?;
+        if let Some(save) = self.systems.save.as_mut() {
+            save.save_states(state_sets.clone())?;
+        }
+        self.systems.state.recycle(state_sets);
 
-        // Ok(ret_state)
+        Ok(ret_state)
     }
 
     pub fn stop(&mut self) -> XResult<()> {
@@ -273,10 +419,43 @@ impl LogicLoop {
         self.frame + 1
     }
 
+    /// Simulation frames per second of the running game.
+    #[inline]
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
     #[inline]
     pub fn phy_system(&self) -> &PhysicsSystem {
         &self.systems.physics
     }
+
+    /// Gets the checksum of a frame, the frame must be in [synced_frame, current_frame].
+    pub fn checksum(&self, frame: u32) -> XResult<StateChecksum> {
+        match self.systems.state.get(frame) {
+            Some(state_set) => state_set.checksum(),
+            None => xresf!(NotFound; "frame={}", frame),
+        }
+    }
+
+    /// Compares a remote checksum with the local one of the synced frame.
+    /// Returns a LogicDesync error naming the first diverging object.
+    ///
+    /// Only the synced frame is both confirmed and kept in the history. The later frames may still
+    /// be rolled back, and the earlier ones are dropped on confirming. So the peers should exchange
+    /// the checksum right after confirming a frame, and other frames are rejected.
+    pub fn verify_checksum(&self, remote: &StateChecksum) -> XResult<()> {
+        let synced_frame = self.systems.state.synced_frame();
+        if remote.frame != synced_frame {
+            return xresf!(BadArgument; "remote.frame={}, synced_frame={}", remote.frame, synced_frame);
+        }
+        self.checksum(remote.frame)?.verify(remote)
+    }
+
+    #[inline]
+    pub fn state_pool_counters(&self) -> StatePoolCounters {
+        self.systems.state.pool_counters()
+    }
 }
 
 //
@@ -304,7 +483,7 @@ pub struct StateGameUpdate {
     pub frame: u32,
     #[csharp_hide(16, 4)]
     pub identity: StateIdentity,
-    #[csharp_hide(48, 16)]
+    #[csharp_hide(192, 16)]
     pub rand: StateRandom,
     pub hit_events: Vec<HitCharacterEvent>,
 }
@@ -319,7 +498,18 @@ pub struct LogicGame {
     frame: u32, // Internal logical restorable frame, always equal to ctx.time.frame
     zone: Box<LogicZone>,
     characters: HistoryVec<Box<LogicCharacter>>,
+    hit_objects: HistoryVec<Box<LogicHitObject>>,
     hit_events: Vec<HitCharacterEvent>,
+    fps: f32, // Used by hit events generated in the physics update, before GameTime of the frame is created
+    // Contacts collected in the physics update, resolved once the whole step is known
+    phy_chara_hits: Vec<PhyHitCharacterEvent>,
+    phy_object_hits: Vec<PhyHitObjectEvent>,
+    phy_scenery_objects: Vec<NumID>,
+    update_threads: usize,
+    tmp_chara_spots: Vec<CharaSpot>,
+    tmp_ai_perceivers: Vec<Option<AiPerceiver>>,
+    tmp_perceived_targets: Vec<Vec<u32>>,
+    chara_grid: CharaGrid,
 }
 
 impl LogicAny for LogicGame {
@@ -382,17 +572,26 @@ impl LogicGame {
             frame: 0,
             zone,
             characters: logic_characters,
+            hit_objects: HistoryVec::with_capacity(16),
             hit_events: Vec::with_capacity(32),
+            fps: time.fps,
+            phy_chara_hits: Vec::with_capacity(32),
+            phy_object_hits: Vec::with_capacity(16),
+            phy_scenery_objects: Vec::new(),
+            update_threads: param.update_threads as usize,
+            tmp_chara_spots: Vec::new(),
+            tmp_ai_perceivers: Vec::new(),
+            tmp_perceived_targets: Vec::new(),
+            chara_grid: CharaGrid::new(CharaGrid::DEFAULT_CELL_SIZE),
         });
+        game.chara_grid.rebuild(&game.characters);
 
-        let (updates, chara_updates) = game.collect_states_updates(systems)?;
-        state_set.updates = updates;
-        state_set.chara_updates = chara_updates;
+        game.collect_states_updates(systems, &mut state_set)?;
 
         Ok((game, Arc::new(state_set)))
     }
 
-    fn restore(&mut self, ctx: &ContextRestore) -> XResult<()> {
+    fn restore(&mut self, systems: &mut LogicSystems, time: &GameTime, ctx: &ContextRestore) -> XResult<()> {
         self.frame = ctx.frame;
         self.zone.restore(ctx)?;
 
@@ -408,14 +607,97 @@ impl LogicGame {
                 return Ok(0);
             }
         })?;
+
+        self.hit_objects.restore_when(|obj| {
+            if obj.death_frame() < self.frame {
+                Ok(-1)
+            }
+            else if obj.spawn_frame() > self.frame {
+                return Ok(1);
+            }
+            else {
+                obj.restore(ctx)?;
+                return Ok(0);
+            }
+        })?;
+
+        self.hit_events.clear();
+        self.phy_chara_hits.clear();
+        self.phy_object_hits.clear();
+        self.phy_scenery_objects.clear();
+        self.chara_grid.rebuild(&self.characters);
+
+        // The Jolt bodies and the AI tasks are not plain data in the states, restores them before the physics
+        // update of the next frame. The hit objects spawned after the frame are removed, not to be hit there.
+        {
+            let mut ctx = ContextUpdate::new(systems, time);
+            for mut obj in self.hit_objects.drain_future() {
+                obj.cleanup(&mut ctx);
+            }
+            for obj in self.hit_objects.iter_mut() {
+                obj.restore_body(&mut ctx)?;
+            }
+        }
+
+        for idx in 0..self.characters.len() {
+            if !self.characters[idx].is_alive() {
+                continue;
+            }
+            let (chara, rest) = self.characters.taken_rest(idx);
+            let chara = unsafe { chara.unwrap_unchecked() };
+            let mut ctx_ex = ContextUpdateEx::new(systems, time, &self.zone);
+            ctx_ex.characters = rest;
+            ctx_ex.chara_grid = &self.chara_grid;
+            chara.restore_ex(&mut ctx_ex, ctx)?;
+        }
+        Ok(())
+    }
+
+    fn rebuild(
+        &mut self,
+        systems: &mut LogicSystems,
+        time: &GameTime,
+        ctx_restore: &ContextRestore,
+        hit_objects: &[Arc<StateHitObjectInit>],
+    ) -> XResult<()> {
+        self.frame = ctx_restore.frame;
+        self.zone.restore(ctx_restore)?;
+
+        for idx in 0..self.characters.len() {
+            let (chara, rest) = self.characters.taken_rest(idx);
+            let chara = unsafe { chara.unwrap_unchecked() };
+            let mut ctx_ex = ContextUpdateEx::new(systems, time, &self.zone);
+            ctx_ex.characters = rest;
+            chara.rebuild(&mut ctx_ex, ctx_restore)?;
+        }
+
+        let mut ctx = ContextUpdate::new(systems, time);
+        for init in hit_objects {
+            let obj = LogicHitObject::rebuild(&mut ctx, init, ctx_restore)?;
+            self.hit_objects.append_new(obj);
+        }
+
+        self.chara_grid.rebuild(&self.characters);
         Ok(())
     }
 
     fn update(&mut self, systems: &mut LogicSystems, time: &GameTime) -> XResult<Arc<StateSet>> {
+        // Still in the time of the physics update
+        self.handle_phy_hits()?;
         self.frame = time.frame;
 
         self.zone.update(&mut ContextUpdate::new(systems, time))?;
 
+        // Clear hit objects spawned in the rolled back frames, and the ones dead before synced frame
+        {
+            let mut ctx = ContextUpdate::new(systems, time);
+            for mut obj in self.hit_objects.drain_future() {
+                obj.cleanup(&mut ctx);
+            }
+            self.hit_objects.discard(|obj| obj.death_frame() < time.synced_frame);
+        }
+        systems.rand.discard(time.synced_frame);
+
         // Update value
         for idx in 0..self.characters.len() {
             if !self.characters[idx].is_alive() {
@@ -426,12 +708,17 @@ impl LogicGame {
             let mut ctx_ex = ContextUpdateEx::new(systems, time, &self.zone);
             ctx_ex.hit_events = &self.hit_events;
             ctx_ex.characters = rest;
+            ctx_ex.chara_grid = &self.chara_grid;
             chara.update_value(&mut ctx_ex)?;
         }
 
         // TODO: Create new objects
         // TODO: Clear dead objects
 
+        if self.update_threads > 1 {
+            self.perceive_ai_targets()?;
+        }
+
         // Update character control
         for idx in 0..self.characters.len() {
             if !self.characters[idx].is_alive() {
@@ -442,10 +729,81 @@ impl LogicGame {
             let mut ctx_ex = ContextUpdateEx::new(systems, time, &self.zone);
             ctx_ex.hit_events = &self.hit_events;
             ctx_ex.characters = rest;
+            ctx_ex.chara_grid = &self.chara_grid;
             chara.update_control(&mut ctx_ex)?;
         }
 
-        // Update character control
+        // Update character physics
+        if self.update_threads > 1 {
+            self.update_physics_parallel(systems, time)?;
+        }
+        else {
+            for idx in 0..self.characters.len() {
+                if !self.characters[idx].is_alive() {
+                    continue;
+                }
+                let (chara, rest) = self.characters.taken_rest(idx);
+                let chara = unsafe { chara.unwrap_unchecked() };
+                let mut ctx_ex = ContextUpdateEx::new(systems, time, &self.zone);
+                ctx_ex.hit_events = &self.hit_events;
+                ctx_ex.characters = rest;
+                ctx_ex.chara_grid = &self.chara_grid;
+                chara.update_physics(&mut ctx_ex)?;
+            }
+        }
+
+        // Character positions are final in this frame, rebuild the spatial index for queries
+        self.chara_grid.rebuild(&self.characters);
+
+        // Update hit objects
+        let mut state_set = systems.state.alloc_state_set(self.frame);
+        let state_set_mut = Arc::get_mut(&mut state_set).ok_or_else(|| xerr!(Unexpected; "shared StateSet"))?;
+        self.update_hit_objects(systems, time, state_set_mut)?;
+
+        // Update zone triggers
+        self.zone.update_triggers(&self.characters)?;
+
+        // Collect states
+        self.collect_states_updates(systems, state_set_mut)?;
+        self.hit_events.clear();
+
+        Ok(state_set)
+    }
+
+    // Character positions don't change before the physics pass, so AI target searching can be done
+    // ahead of the control pass, on worker threads. The searches only see copied plain data.
+    fn perceive_ai_targets(&mut self) -> XResult<()> {
+        self.tmp_chara_spots.clear();
+        self.tmp_chara_spots.extend(self.characters.iter().map(|chara| CharaSpot::of(chara)));
+        self.tmp_ai_perceivers.clear();
+        self.tmp_ai_perceivers.extend(self.characters.iter().map(|chara| match chara.is_alive() {
+            true => chara.ai_perceiver(),
+            false => None,
+        }));
+
+        let spots = &self.tmp_chara_spots;
+        let perceivers = &self.tmp_ai_perceivers;
+        let chara_grid = &self.chara_grid;
+        self.tmp_perceived_targets.resize_with(spots.len(), Vec::new);
+        let targets = &mut self.tmp_perceived_targets[..spots.len()];
+        parallel_for_each(self.update_threads, targets, |idx, indexes| {
+            indexes.clear();
+            if let Some(perceiver) = &perceivers[idx] {
+                perceiver.search(&CharaSpotsRest::new(spots, idx), chara_grid, indexes);
+            }
+            Ok(())
+        })?;
+
+        for (chara, indexes) in self.characters.iter_mut().zip(self.tmp_perceived_targets.iter_mut()) {
+            if chara.is_alive() {
+                chara.set_perceived_targets(indexes);
+            }
+        }
+        Ok(())
+    }
+
+    // Same results as `LogicCharacter::update_physics()` in order, animations are sampled on worker threads.
+    fn update_physics_parallel(&mut self, systems: &mut LogicSystems, time: &GameTime) -> XResult<()> {
         for idx in 0..self.characters.len() {
             if !self.characters[idx].is_alive() {
                 continue;
@@ -455,42 +813,172 @@ impl LogicGame {
             let mut ctx_ex = ContextUpdateEx::new(systems, time, &self.zone);
             ctx_ex.hit_events = &self.hit_events;
             ctx_ex.characters = rest;
-            chara.update_physics(&mut ctx_ex)?;
+            ctx_ex.chara_grid = &self.chara_grid;
+            chara.prepare_animations(&mut ctx_ex)?;
         }
 
-        // Collect states
-        let mut state_set = StateSet::new(self.frame);
-        let (updates, chara_updates) = self.collect_states_updates(systems)?;
-        state_set.updates = updates;
-        state_set.chara_updates = chara_updates;
-        self.hit_events.clear();
+        let mut animators: Vec<&mut Animator> = self
+            .characters
+            .iter_mut()
+            .filter(|chara| chara.is_alive())
+            .map(|chara| chara.animator_mut())
+            .collect();
+        parallel_for_each(self.update_threads, &mut animators, |_, animator| animator.animate())?;
 
-        Ok(Arc::new(state_set))
+        for idx in 0..self.characters.len() {
+            if !self.characters[idx].is_alive() {
+                continue;
+            }
+            let (chara, rest) = self.characters.taken_rest(idx);
+            let chara = unsafe { chara.unwrap_unchecked() };
+            let mut ctx_ex = ContextUpdateEx::new(systems, time, &self.zone);
+            ctx_ex.hit_events = &self.hit_events;
+            ctx_ex.characters = rest;
+            ctx_ex.chara_grid = &self.chara_grid;
+            chara.update_physics_sampled(&mut ctx_ex)?;
+        }
+        Ok(())
     }
 
-    fn collect_states_updates(
+    fn update_hit_objects(
         &mut self,
         systems: &mut LogicSystems,
-    ) -> XResult<(Vec<Box<dyn StateAny>>, Vec<Box<StateCharacterUpdate>>)> {
-        let mut updates: Vec<Box<dyn StateAny>> = Vec::with_capacity(2);
-        updates.push(Box::new(StateGameUpdate {
-            _base: StateBase::new(self.id, StateType::GameUpdate, LogicType::Game),
-            frame: self.frame,
-            identity: systems.identity.state(),
-            rand: systems.rand.state(),
-            hit_events: self.hit_events.drain(..).collect(),
-        }));
+        time: &GameTime,
+        state_set: &mut StateSet,
+    ) -> XResult<()> {
+        let mut ctx = ContextUpdate::new(systems, time);
+        for obj in self.hit_objects.iter_mut() {
+            obj.update(&mut ctx, &self.characters)?;
+        }
 
-        updates.push(self.zone.state());
+        for chara in self.characters.iter() {
+            if !chara.is_alive() {
+                continue;
+            }
+            for tmpl_id in chara.control().hit_object_requests() {
+                let (obj, obj_init) = LogicHitObject::new(&mut ctx, *tmpl_id, chara)?;
+                self.hit_objects.append_new(obj);
+                state_set.inits.push(obj_init);
+            }
+        }
+        Ok(())
+    }
 
-        let mut chara_updates = Vec::with_capacity(self.characters.len());
+    fn collect_states_updates(&mut self, systems: &mut LogicSystems, state_set: &mut StateSet) -> XResult<()> {
+        let updates = &mut state_set.updates;
+        updates.reserve(2 + self.hit_objects.len());
+        match systems.state.alloc_game_update() {
+            Some(mut state) => {
+                state._base = StateBase::new(self.id, StateType::GameUpdate, LogicType::Game);
+                state.frame = self.frame;
+                state.identity = systems.identity.state();
+                state.rand = systems.rand.state();
+                state.hit_events.clear();
+                state.hit_events.extend(self.hit_events.drain(..));
+                updates.push(state);
+            }
+            None => updates.push(Box::new(StateGameUpdate {
+                _base: StateBase::new(self.id, StateType::GameUpdate, LogicType::Game),
+                frame: self.frame,
+                identity: systems.identity.state(),
+                rand: systems.rand.state(),
+                hit_events: self.hit_events.drain(..).collect(),
+            })),
+        }
+
+        match systems.state.alloc_zone_update() {
+            Some(state) => updates.push(self.zone.state_into(state)),
+            None => updates.push(self.zone.state()),
+        }
+
+        for obj in self.hit_objects.iter() {
+            if obj.death_frame() >= self.frame {
+                match systems.state.alloc_hit_object_update() {
+                    Some(state) => updates.push(obj.state_into(state)),
+                    None => updates.push(obj.state()),
+                }
+            }
+        }
+
+        let chara_updates = &mut state_set.chara_updates;
+        chara_updates.reserve(self.characters.len());
         for chara in self.characters.iter_mut() {
-            chara_updates.push(chara.state()?);
+            match systems.state.alloc_chara_update() {
+                Some(state) => chara_updates.push(chara.state_into(state)?),
+                None => chara_updates.push(chara.state()?),
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn on_hit_character(&mut self, phy_event: &PhyHitCharacterEvent) -> XResult<()> {
+        self.phy_chara_hits.push(*phy_event);
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn on_hit_object_character(&mut self, phy_event: &PhyHitObjectEvent) -> XResult<()> {
+        self.phy_object_hits.push(*phy_event);
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn on_hit_object_scenery(&mut self, object_id: NumID) -> XResult<()> {
+        self.phy_scenery_objects.push(object_id);
+        Ok(())
+    }
+
+    /// An attack may touch the target body and several hit boxes of a character in one step. Each pair of
+    /// the attack and the character is hit once, with the interaction resolved from all the boxes touched.
+    fn handle_phy_hits(&mut self) -> XResult<()> {
+        let chara_hits = std::mem::take(&mut self.phy_chara_hits);
+        for (idx, phy_event) in chara_hits.iter().enumerate() {
+            let same_pair = |e: &&PhyHitCharacterEvent| {
+                e.src_chara_id == phy_event.src_chara_id
+                    && e.src_box_index == phy_event.src_box_index
+                    && e.dst_chara_id == phy_event.dst_chara_id
+            };
+            if chara_hits[..idx].iter().any(|e| same_pair(&e)) {
+                continue;
+            }
+            let contacts = chara_hits[idx..].iter().filter(same_pair);
+            // The contact with the target body gives the hit position, if any.
+            let hit_event = contacts.clone().find(|e| e.dst_box_index.is_none()).unwrap_or(phy_event);
+            self.hit_character(hit_event, contacts.filter_map(|e| e.dst_box_index))?;
         }
-        Ok((updates, chara_updates))
+        self.phy_chara_hits = chara_hits;
+        self.phy_chara_hits.clear();
+
+        let object_hits = std::mem::take(&mut self.phy_object_hits);
+        for (idx, phy_event) in object_hits.iter().enumerate() {
+            let same_pair = |e: &&PhyHitObjectEvent| {
+                e.object_id == phy_event.object_id && e.dst_chara_id == phy_event.dst_chara_id
+            };
+            if object_hits[..idx].iter().any(|e| same_pair(&e)) {
+                continue;
+            }
+            let contacts = object_hits[idx..].iter().filter(same_pair);
+            let hit_event = contacts.clone().find(|e| e.dst_box_index.is_none()).unwrap_or(phy_event);
+            self.hit_object_character(hit_event, contacts.filter_map(|e| e.dst_box_index))?;
+        }
+        self.phy_object_hits = object_hits;
+        self.phy_object_hits.clear();
+
+        // After the character hits, a hit object touching both in the same step still hits.
+        for object_id in self.phy_scenery_objects.drain(..) {
+            if let Some(obj) = self.hit_objects.iter_mut().find(|o| o.id() == object_id) {
+                obj.on_hit_scenery();
+            }
+        }
+        Ok(())
     }
 
-    pub(crate) fn on_hit_character<'t>(&mut self, phy_event: &PhyHitCharacterEvent<'t>) -> XResult<()> {
+    fn hit_character<I: Iterator<Item = u16>>(
+        &mut self,
+        phy_event: &PhyHitCharacterEvent,
+        dst_boxes: I,
+    ) -> XResult<()> {
         let Some(src) = self.characters.iter().position(|c| c.id() == phy_event.src_chara_id)
         else {
             log::warn!("Src Character not found ({})", phy_event.src_chara_id);
@@ -502,11 +990,53 @@ impl LogicGame {
             return Ok(());
         };
 
-        let mut ctx = ContextHitGenerate::new(self.frame, &mut self.hit_events);
+        let mut ctx = ContextHitGenerate::new(self.frame, self.fps, &mut self.hit_events);
         let src_chara = unsafe { force_mut(&self.characters[src]) };
         let dst_chara = unsafe { force_mut(&self.characters[dst]) };
-        src_chara.before_hit(dst_chara, &mut ctx, phy_event)?;
+        let interaction = dst_chara.control().hit_interaction(dst_boxes);
+        src_chara.before_hit(dst_chara, &mut ctx, phy_event, interaction)?;
+
+        Ok(())
+    }
+
+    fn hit_object_character<I: Iterator<Item = u16>>(
+        &mut self,
+        phy_event: &PhyHitObjectEvent,
+        dst_boxes: I,
+    ) -> XResult<()> {
+        let Some(obj) = self.hit_objects.iter().position(|o| o.id() == phy_event.object_id)
+        else {
+            log::warn!("HitObject not found ({})", phy_event.object_id);
+            return Ok(());
+        };
+        let Some(dst) = self.characters.iter().position(|c| c.id() == phy_event.dst_chara_id)
+        else {
+            log::warn!("Dst Character not found ({})", phy_event.dst_chara_id);
+            return Ok(());
+        };
+
+        let event_start = self.hit_events.len();
+        let mut ctx = ContextHitGenerate::new(self.frame, self.fps, &mut self.hit_events);
+        let obj = unsafe { force_mut(&self.hit_objects[obj]) };
+        let dst_chara = unsafe { force_mut(&self.characters[dst]) };
+        let interaction = dst_chara.control().hit_interaction(dst_boxes);
+        let event_count = obj.detect_hits(&mut ctx, dst_chara, phy_event, interaction)?;
+
+        for event_idx in event_start..(event_start + event_count) {
+            dst_chara.physics_mut().append_be_hit_event(event_idx);
+        }
+        // The shooter may be dead, the hit events still work.
+        if let Some(src) = self.characters.iter().position(|c| c.id() == obj.shooter_id()) {
+            let src_chara = unsafe { force_mut(&self.characters[src]) };
+            for event_idx in event_start..(event_start + event_count) {
+                src_chara.physics_mut().append_hit_event(event_idx);
+            }
+        }
+        Ok(())
+    }
 
+    pub(crate) fn on_trigger_character(&mut self, trigger_index: u16, chara_id: NumID) -> XResult<()> {
+        self.zone.on_trigger_character(trigger_index, chara_id);
         Ok(())
     }
 }
@@ -516,7 +1046,7 @@ mod tests {
     use super::*;
     use crate::consts::TEST_ASSET_PATH;
     use crate::parameter::{ParamNpc, ParamPlayer, ParamZone};
-    use crate::utils::{RawInput, RawKey, id};
+    use crate::utils::{RawInput, RawKey, XError, id};
 
     #[ctor::ctor]
     fn test_init_jolt_physics() {
@@ -541,6 +1071,9 @@ mod tests {
                 ..Default::default()
             }],
             local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
         };
         let (mut ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
         ll.update(vec![InputPlayerInputs {
@@ -554,4 +1087,697 @@ mod tests {
         // // ll.update(vec![]).unwrap();
         // ll.stop().unwrap();
     }
+
+    fn join_inputs(frame: u32) -> Vec<InputPlayerInputs> {
+        let inputs = match frame % 8 {
+            1 => vec![RawInput::new_button(RawKey::Attack1, true)],
+            2 => vec![RawInput::new_button(RawKey::Attack1, false)],
+            5 => vec![RawInput::new_move(glam::Vec2::new(0.0, 1.0))],
+            7 => vec![RawInput::new_move(glam::Vec2::ZERO)],
+            _ => vec![],
+        };
+        vec![InputPlayerInputs::new(NumID::MIN_PLAYER, frame, inputs)]
+    }
+
+    #[test]
+    fn test_logic_loop_join() {
+        let param = ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.One"),
+                style: id!("Style.One^1"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs: vec![],
+            local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        };
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut host, init_set) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
+        for frame in 1..=20 {
+            host.update(join_inputs(frame)).unwrap();
+        }
+
+        let join = host.join_snapshot().unwrap();
+        assert_eq!(join.frame(), 20);
+        assert_eq!(join.inputs.len(), 1);
+        assert_eq!(join.inputs[0].synced_frame, 20);
+
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut guest, join_set) = LogicLoop::new_join(tmpl_db, TEST_ASSET_PATH, join).unwrap();
+        assert_eq!(guest.current_frame(), 20);
+        assert_eq!(join_set.frame, 20);
+        assert_eq!(join_set.inits.len(), init_set.inits.len());
+        guest.verify_checksum(&host.checksum(20).unwrap()).unwrap();
+
+        assert!(guest.update(vec![]).is_err());
+        for frame in 21..=40 {
+            let host_set = host.update(join_inputs(frame)).unwrap();
+            let guest_set = guest.update(join_inputs(frame)).unwrap();
+            guest_set
+                .checksum()
+                .unwrap()
+                .verify(&host_set.checksum().unwrap())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_logic_loop_join_npc_buff() {
+        let param = ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.One"),
+                style: id!("Style.One^1"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs: vec![ParamNpc {
+                character: id!("CharacterNpc.InstanceNpc^1"),
+                level: 2,
+                ai_brain: id!("AiBrain.InstanceNpc^1"),
+                ..Default::default()
+            }],
+            local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        };
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut host, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
+        for frame in 1..=10 {
+            host.update(join_inputs(frame)).unwrap();
+        }
+        let game = host.game.as_mut().unwrap();
+        let player_id = game.characters[0].id();
+        let npc = game.characters.iter_mut().find(|chara| !chara.is_player()).unwrap();
+        let npc_id = npc.id();
+        npc.value_mut().request_buff(id!("Buff.Instance.Burning"), player_id);
+        for frame in 11..=20 {
+            host.update(join_inputs(frame)).unwrap();
+        }
+
+        let join = host.join_snapshot().unwrap();
+        let npc_state = join.keyframe.chara_updates.iter().find(|state| state.id == npc_id).unwrap();
+        assert_eq!(npc_state.buffs.len(), 1);
+        assert_eq!(npc_state.buffs[0].tmpl_id, id!("Buff.Instance.Burning"));
+
+        // The buff and the AI task of the NPC are rebuilt, the guest keeps in sync.
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut guest, _) = LogicLoop::new_join(tmpl_db, TEST_ASSET_PATH, join).unwrap();
+        guest.verify_checksum(&host.checksum(20).unwrap()).unwrap();
+        for frame in 21..=60 {
+            let host_set = host.update(join_inputs(frame)).unwrap();
+            let guest_set = guest.update(join_inputs(frame)).unwrap();
+            guest_set
+                .checksum()
+                .unwrap()
+                .verify(&host_set.checksum().unwrap())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_logic_loop_tick_rate() {
+        let param = |tick_rate: u32| ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.One"),
+                style: id!("Style.One^1"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs: vec![],
+            local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate,
+        };
+
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let res = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param(45), None);
+        assert!(matches!(res, Err(XError::BadArgument(_))));
+
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param(0), None).unwrap();
+        assert_eq!(ll.tick_rate(), DEFAULT_TICK_RATE);
+        assert_eq!(ll.systems.input.input_window(), DEFAULT_TICK_RATE);
+
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut host, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param(TICK_RATE_60), None).unwrap();
+        assert_eq!(host.tick_rate(), TICK_RATE_60);
+        assert_eq!(host.systems.input.input_window(), TICK_RATE_60);
+        for frame in 1..=20 {
+            host.update(join_inputs(frame)).unwrap();
+        }
+
+        // The joined game runs at the tick rate of the host.
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut guest, _) = LogicLoop::new_join(tmpl_db, TEST_ASSET_PATH, host.join_snapshot().unwrap()).unwrap();
+        assert_eq!(guest.tick_rate(), TICK_RATE_60);
+        for frame in 21..=40 {
+            let host_set = host.update(join_inputs(frame)).unwrap();
+            let guest_set = guest.update(join_inputs(frame)).unwrap();
+            guest_set
+                .checksum()
+                .unwrap()
+                .verify(&host_set.checksum().unwrap())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_logic_loop_parallel_update() {
+        let param = |update_threads: u32| ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.One"),
+                style: id!("Style.One^1"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs: (0..6)
+                .map(|idx| ParamNpc {
+                    character: id!("CharacterNpc.InstanceNpc^1"),
+                    level: 2,
+                    ai_brain: id!("AiBrain.InstanceNpc^1"),
+                    position: glam::Vec3A::new(idx as f32 * 1.5 - 4.0, 0.0, 3.0),
+                })
+                .collect(),
+            local_mode: true,
+            seed: 12345,
+            update_threads,
+            tick_rate: 0,
+        };
+
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut single, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param(0), None).unwrap();
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut parallel, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param(4), None).unwrap();
+        for frame in 1..=40 {
+            let single_set = single.update(join_inputs(frame)).unwrap();
+            let parallel_set = parallel.update(join_inputs(frame)).unwrap();
+            parallel_set
+                .checksum()
+                .unwrap()
+                .verify(&single_set.checksum().unwrap())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_logic_loop_state_pool() {
+        let param = ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.One"),
+                style: id!("Style.One^1"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs: vec![ParamNpc {
+                character: id!("CharacterNpc.InstanceNpc^1"),
+                level: 2,
+                ai_brain: id!("AiBrain.InstanceNpc^1"),
+                ..Default::default()
+            }],
+            local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        };
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
+        for frame in 1..=10 {
+            ll.update(join_inputs(frame)).unwrap();
+        }
+        let counters = ll.state_pool_counters();
+        assert!(counters.state_set_reuses > 0);
+        assert!(counters.chara_update_reuses > 0);
+        assert!(counters.update_reuses > 0);
+        assert!(counters.action_reuses > 0);
+
+        for frame in 11..=40 {
+            ll.update(join_inputs(frame)).unwrap();
+        }
+        let steady = ll.state_pool_counters();
+        assert_eq!(steady.state_set_allocs, counters.state_set_allocs);
+        assert_eq!(steady.chara_update_allocs, counters.chara_update_allocs);
+        assert_eq!(steady.state_set_reuses, counters.state_set_reuses + 30);
+        // One game update and one zone update per frame
+        assert_eq!(steady.update_allocs, counters.update_allocs);
+        assert_eq!(steady.update_reuses, counters.update_reuses + 60);
+        // At least one action per character per frame
+        assert!(steady.action_reuses >= counters.action_reuses + 60);
+        assert_eq!(steady.recycle_misses, 0);
+    }
+
+    #[test]
+    fn test_logic_loop_switch_style() {
+        use crate::logic::character::StateCharacterUpdate;
+
+        let param = ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.Instance^1"),
+                style: id!("Style.Instance^1A"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs: vec![],
+            local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        };
+        let switch_inputs = |frame: u32, pressed: bool| {
+            let inputs = vec![RawInput::new_button(RawKey::Switch, pressed)];
+            vec![InputPlayerInputs::new(NumID::MIN_PLAYER, frame, inputs)]
+        };
+        let current_action = |state: &StateCharacterUpdate| state.actions.last().map(|act| act.tmpl_id);
+
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut host, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
+        let state_set = host.update(switch_inputs(1, true)).unwrap();
+        let chara = state_set.find_as::<StateCharacterUpdate>(NumID::MIN_PLAYER).unwrap();
+        assert_eq!(chara.control.style_index, 0);
+        assert_eq!(chara.value.health, 850.0);
+
+        // 1A => 1B, enters the switch action of 1B
+        let state_set = host.update(switch_inputs(2, false)).unwrap();
+        let chara = state_set.find_as::<StateCharacterUpdate>(NumID::MIN_PLAYER).unwrap();
+        assert_eq!(chara.control.style_index, 1);
+        assert_eq!(chara.value.health, 1000.0);
+        assert_eq!(chara.value.posture, 110.0);
+        assert_eq!(current_action(chara), Some(id!("Action.Instance.Switch^1B")));
+
+        // The switch action keeps a high level, the Switch key is ignored
+        host.update(switch_inputs(3, true)).unwrap();
+        let state_set = host.update(switch_inputs(4, false)).unwrap();
+        let chara = state_set.find_as::<StateCharacterUpdate>(NumID::MIN_PLAYER).unwrap();
+        assert_eq!(chara.control.style_index, 1);
+
+        // A joined game restores the switched style
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut guest, _) = LogicLoop::new_join(tmpl_db, TEST_ASSET_PATH, host.join_snapshot().unwrap()).unwrap();
+        for frame in 5..=45 {
+            let host_set = host
+                .update(vec![InputPlayerInputs::new(NumID::MIN_PLAYER, frame, vec![])])
+                .unwrap();
+            let guest_set = guest
+                .update(vec![InputPlayerInputs::new(NumID::MIN_PLAYER, frame, vec![])])
+                .unwrap();
+            guest_set
+                .checksum()
+                .unwrap()
+                .verify(&host_set.checksum().unwrap())
+                .unwrap();
+        }
+
+        // 1B => 1A after the switch action finished, no switch action in 1A
+        host.update(switch_inputs(46, true)).unwrap();
+        let state_set = host.update(switch_inputs(47, false)).unwrap();
+        let chara = state_set.find_as::<StateCharacterUpdate>(NumID::MIN_PLAYER).unwrap();
+        assert_eq!(chara.control.style_index, 0);
+        assert_eq!(chara.value.health, 850.0);
+        assert_eq!(current_action(chara), Some(id!("Action.Instance.Idle^1A")));
+    }
+
+    #[test]
+    fn test_logic_loop_look_at() {
+        use crate::logic::character::StateCharacterUpdate;
+        use crate::utils::sb;
+        use glam::Vec3A;
+
+        let param = |npcs: Vec<ParamNpc>| ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.Instance^1"),
+                style: id!("Style.Instance^1A"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs,
+            local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        };
+        let attack_inputs = |frame: u32| {
+            let inputs = match frame {
+                1 => vec![RawInput::new_button(RawKey::Attack1, true)],
+                2 => vec![RawInput::new_button(RawKey::Attack1, false)],
+                _ => vec![],
+            };
+            vec![InputPlayerInputs::new(NumID::MIN_PLAYER, frame, inputs)]
+        };
+
+        // The same attack with and without an enemy nearby
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut base, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param(vec![]), None).unwrap();
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let npcs = vec![ParamNpc {
+            character: id!("CharacterNpc.InstanceNpc^1"),
+            level: 2,
+            ai_brain: id!("AiBrain.InstanceNpc^1"),
+            position: Vec3A::new(3.0, 0.0, 3.0),
+        }];
+        let (mut ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param(npcs), None).unwrap();
+        let mut prev_weight = 0.0;
+        let mut look_at = Vec3A::ZERO;
+        for frame in 1..=12 {
+            base.update(attack_inputs(frame)).unwrap();
+            let state_set = ll.update(attack_inputs(frame)).unwrap();
+            let chara = state_set.find_as::<StateCharacterUpdate>(NumID::MIN_PLAYER).unwrap();
+            let action = chara.actions.last().unwrap();
+            if frame >= 2 {
+                assert_eq!(action.tmpl_id, id!("Action.Instance.Attack^1A"));
+                assert!(action.look_at_weight > prev_weight);
+                prev_weight = action.look_at_weight;
+                look_at = action.look_at;
+            }
+        }
+
+        // The action looks at the head of the NPC
+        let game = ll.game.as_ref().unwrap();
+        let player = &game.characters[0];
+        let npc = game.characters.iter().find(|chara| !chara.is_player()).unwrap();
+        let expected = npc.physics().position() + Vec3A::new(0.0, 1.5, 0.0);
+        assert!(look_at.abs_diff_eq(expected, 0.1));
+
+        let (target, weight) = player.control().look_at().unwrap().target();
+        assert!(weight > 0.0);
+        assert!(target.x > 0.0 && target.z > 0.0);
+
+        // The head turns towards the enemy (+X in the model space), compared to the same pose without look-at
+        let base_player = &base.game.as_ref().unwrap().characters[0];
+        assert_eq!(base_player.control().look_at().unwrap().target().1, 0.0);
+        let skeleton = ll.systems.asset.load_skeleton(sb!("Girl/Girl.*")).unwrap();
+        let head = skeleton.joint_by_name("Head").unwrap() as usize;
+        let rotation = player.control().model_transforms()[head].rotation;
+        let base_rotation = base_player.control().model_transforms()[head].rotation;
+        let turned = (rotation * base_rotation.inverse()) * Vec3A::Z;
+        assert!(turned.x > 0.05);
+    }
+
+    #[test]
+    fn test_logic_loop_rollback_hits() {
+        use crate::logic::character::StateCharacterUpdate;
+        use glam::Vec3A;
+
+        let player_2 = NumID(NumID::MIN_PLAYER.0 + 1);
+        let param = ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![
+                ParamPlayer {
+                    character: id!("Character.One"),
+                    style: id!("Style.One^1"),
+                    level: 4,
+                    ..Default::default()
+                },
+                ParamPlayer {
+                    character: id!("Character.One"),
+                    style: id!("Style.One^1"),
+                    level: 4,
+                    position: Vec3A::new(0.0, 0.0, -20.0),
+                    ..Default::default()
+                },
+            ],
+            npcs: vec![ParamNpc {
+                character: id!("CharacterNpc.InstanceNpc^1"),
+                level: 2,
+                ai_brain: id!("AiBrain.InstanceNpc^1"),
+                position: Vec3A::new(0.0, 0.0, 1.5),
+            }],
+            local_mode: false,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        };
+        let attack_inputs = |frame: u32| {
+            let inputs = match frame {
+                1 => vec![RawInput::new_button(RawKey::Attack1, true)],
+                2 => vec![RawInput::new_button(RawKey::Attack1, false)],
+                _ => vec![],
+            };
+            InputPlayerInputs::new(NumID::MIN_PLAYER, frame, inputs)
+        };
+        let hit_events = |state_set: &StateSet| {
+            let game = state_set.find_as::<StateGameUpdate>(NumID::GAME).unwrap();
+            game.hit_events.clone()
+        };
+        let chara_physics = |state_set: &StateSet| {
+            let chara = state_set.find_as::<StateCharacterUpdate>(NumID::MIN_PLAYER).unwrap();
+            chara.physics.clone()
+        };
+
+        // All inputs in time, never rolls back
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param.clone(), None).unwrap();
+        let mut expected = vec![];
+        for frame in 1..=60 {
+            let inputs = vec![attack_inputs(frame), InputPlayerInputs::new(player_2, frame, vec![])];
+            let state_set = ll.update(inputs).unwrap();
+            expected.push((hit_events(&state_set), chara_physics(&state_set)));
+        }
+        assert!(expected.iter().any(|(events, _)| !events.is_empty()));
+        assert!(expected.iter().any(|(_, phy)| phy.hit_boxes.iter().any(|hit_box| hit_box.swept)));
+
+        // The inputs of player 2 arrive 2 frames late, every frame is restored and updated again, mid-swing too
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
+        for frame in 1..=62 {
+            let mut inputs = vec![];
+            if frame <= 60 {
+                inputs.push(attack_inputs(frame));
+            }
+            if frame > 2 {
+                inputs.push(InputPlayerInputs::new(player_2, frame - 2, vec![]));
+            }
+            ll.update(inputs).unwrap();
+            if frame > 2 {
+                let state_set = ll.systems.state.get(frame - 2).unwrap();
+                let (events, phy) = &expected[frame as usize - 3];
+                assert_eq!(&hit_events(state_set), events, "frame={}", frame - 2);
+                assert_eq!(&chara_physics(state_set), phy, "frame={}", frame - 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_logic_game_hit_interactions() {
+        use crate::consts::COUNTER_POSTURE_RATIO;
+        use crate::utils::{HitInteraction, HitType};
+        use glam::Vec3A;
+
+        // Both players swing Attack_Test, player 2 is far away, the contacts are made up.
+        let player_2 = NumID(NumID::MIN_PLAYER.0 + 1);
+        let new_loop = || {
+            let param = ParamGame {
+                zone: ParamZone { zone: id!("Zone.Demo") },
+                players: vec![
+                    ParamPlayer {
+                        character: id!("Character.One"),
+                        style: id!("Style.One^1"),
+                        level: 4,
+                        ..Default::default()
+                    },
+                    ParamPlayer {
+                        character: id!("Character.One"),
+                        style: id!("Style.One^1"),
+                        level: 4,
+                        position: Vec3A::new(0.0, 0.0, -20.0),
+                        ..Default::default()
+                    },
+                ],
+                npcs: vec![],
+                local_mode: true,
+                seed: 12345,
+                update_threads: 0,
+                tick_rate: 0,
+            };
+            let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+            let (mut ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
+            for frame in 1..=120 {
+                let inputs = match frame {
+                    1 => vec![RawInput::new_button(RawKey::Attack1, true)],
+                    2 => vec![RawInput::new_button(RawKey::Attack1, false)],
+                    _ => vec![],
+                };
+                ll.update(vec![
+                    InputPlayerInputs::new(NumID::MIN_PLAYER, frame, inputs.clone()),
+                    InputPlayerInputs::new(player_2, frame, inputs),
+                ])
+                .unwrap();
+
+                let sampler = ll.game.characters[0].control().hit_motion_sampler().unwrap();
+                let attack_box = sampler.weapons().iter().find(|s| {
+                    s.active && sampler.hit_motion.find_box(s.box_index).unwrap().typ == HitType::Attack
+                });
+                if let Some(attack_box) = attack_box {
+                    let src_box = attack_box.box_index;
+                    return (ll, src_box);
+                }
+            }
+            panic!("no active attack box");
+        };
+        let dst_box = |ll: &LogicLoop, typ: HitType| {
+            let sampler = ll.game.characters[1].control().hit_motion_sampler().unwrap();
+            sampler.hit_motion.iter_boxes().find(|b| b.typ == typ).unwrap().box_index
+        };
+        let hit = |ll: &mut LogicLoop, src_box: u16, dst_boxes: &[u16]| {
+            let phy_event = PhyHitCharacterEvent {
+                src_chara_id: NumID::MIN_PLAYER,
+                src_box_index: src_box,
+                dst_chara_id: player_2,
+                dst_box_index: None,
+                world_space_normal: Vec3A::Z,
+                penetration_depth: 0.1,
+                collision_point_average: Vec3A::ZERO,
+            };
+            for dst_box in dst_boxes {
+                let box_event = PhyHitCharacterEvent {
+                    dst_box_index: Some(*dst_box),
+                    ..phy_event
+                };
+                ll.game.on_hit_character(&box_event).unwrap();
+            }
+            ll.game.on_hit_character(&phy_event).unwrap();
+            ll.game.handle_phy_hits().unwrap();
+            // One hit for all the contacts of the pair
+            assert_eq!(ll.game.hit_events.len(), 1);
+            ll.game.hit_events[0].interaction
+        };
+
+        // The target body and a health box
+        let (mut ll, src_box) = new_loop();
+        let health = dst_box(&ll, HitType::Health);
+        let posture = ll.game.characters[1].value().posture();
+        assert_eq!(hit(&mut ll, src_box, &[health]), HitInteraction::Normal);
+        assert_eq!(ll.game.characters[1].value().posture(), posture);
+        assert!(ll.game.characters[1].value().hit_lag_time().is_empty());
+
+        // Counter wins over health
+        let (mut ll, src_box) = new_loop();
+        let (health, counter) = (dst_box(&ll, HitType::Health), dst_box(&ll, HitType::Counter));
+        let dst_value = ll.game.characters[1].value();
+        let posture = dst_value.posture() - dst_value.panel().max_posture * COUNTER_POSTURE_RATIO;
+        assert_eq!(hit(&mut ll, src_box, &[health, counter]), HitInteraction::Counter);
+        assert_eq!(ll.game.characters[1].value().posture(), posture);
+
+        // Attack boxes clash, both are in hit lag
+        let (mut ll, src_box) = new_loop();
+        let attack = dst_box(&ll, HitType::Attack);
+        assert_eq!(hit(&mut ll, src_box, &[attack]), HitInteraction::Clash);
+        let src_hit_lag = ll.game.characters[0].value().hit_lag_time();
+        assert!(!src_hit_lag.is_empty());
+        assert_eq!(ll.game.characters[1].value().hit_lag_time(), src_hit_lag);
+    }
+
+    #[test]
+    fn test_logic_loop_zone_trigger() {
+        use crate::logic::zone::{StateZoneUpdate, ZoneEventType};
+
+        let param = ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.One"),
+                style: id!("Style.One^1"),
+                level: 4,
+                // Inside the "Checkpoint" trigger of the test zone
+                position: glam::Vec3A::new(0.0, 0.0, 5.0),
+                ..Default::default()
+            }],
+            npcs: vec![],
+            local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        };
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
+
+        // The overlap is reported by the physics step, not by hand.
+        let mut events = Vec::new();
+        for frame in 1..=3 {
+            let state_set = ll
+                .update(vec![InputPlayerInputs::new(NumID::MIN_PLAYER, frame, vec![])])
+                .unwrap();
+            let zone = state_set.find_as::<StateZoneUpdate>(NumID::STAGE).unwrap();
+            events.extend(zone.events.iter().map(|e| e.typ));
+        }
+        assert_eq!(events, vec![ZoneEventType::Enter]);
+
+        let inputs = vec![RawInput::new_button(RawKey::Interact, true)];
+        let state_set = ll
+            .update(vec![InputPlayerInputs::new(NumID::MIN_PLAYER, 4, inputs)])
+            .unwrap();
+        let zone = state_set.find_as::<StateZoneUpdate>(NumID::STAGE).unwrap();
+        assert_eq!(zone.overlaps.len(), 1);
+        assert_eq!(zone.events.iter().map(|e| e.typ).collect::<Vec<_>>(), vec![
+            ZoneEventType::Interact
+        ]);
+    }
+
+    #[test]
+    fn test_logic_game_chara_grid() {
+        use crate::utils::{ShapeSphere, ShapeSphericalCone};
+
+        let param = ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.One"),
+                style: id!("Style.One^1"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs: (0..8)
+                .map(|idx| ParamNpc {
+                    character: id!("CharacterNpc.InstanceNpc^1"),
+                    level: 2,
+                    ai_brain: id!("AiBrain.InstanceNpc^1"),
+                    position: glam::Vec3A::new((idx % 4) as f32 * 7.0 - 10.0, 0.0, (idx / 4) as f32 * 9.0 - 4.0),
+                })
+                .collect(),
+            local_mode: true,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        };
+        let tmpl_db = TmplDatabase::new(10240, 150).unwrap();
+        let (mut ll, _) = LogicLoop::new(tmpl_db, TEST_ASSET_PATH, param, None).unwrap();
+        for frame in 1..=10 {
+            ll.update(join_inputs(frame)).unwrap();
+        }
+
+        let game = ll.game.as_ref().unwrap();
+        assert!(game.chara_grid.is_built());
+        for idx in 0..game.characters.len() {
+            let rest = game.characters.rest(idx);
+            let center = game.characters[idx].physics().position();
+            for radius in [1.0, 6.0, 15.0, 100.0] {
+                for is_player in [true, false] {
+                    let mut expected = Vec::new();
+                    let mut actual = Vec::new();
+                    let sphere = ShapeSphere::new(radius);
+                    rest.search_chara_in_sphere(is_player, &sphere, center, &mut expected);
+                    game.chara_grid
+                        .search_chara_in_sphere(&rest, is_player, &sphere, center, &mut actual);
+                    assert_eq!(expected, actual);
+
+                    expected.clear();
+                    actual.clear();
+                    let cone = ShapeSphericalCone::new(radius, 1.0);
+                    let dir = glam_ext::Vec2xz::new(0.6, 0.8);
+                    rest.search_chara_in_spherical_cone(is_player, &cone, center, dir, &mut expected);
+                    game.chara_grid
+                        .search_chara_in_spherical_cone(&rest, is_player, &cone, center, dir, &mut actual);
+                    assert_eq!(expected, actual);
+                }
+            }
+        }
+    }
 }
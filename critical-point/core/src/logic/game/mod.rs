@@ -2,5 +2,6 @@ mod characters;
 mod context;
 mod game;
 
+pub(crate) use characters::{CharaGrid, CharaSpot, CharaSpots, CharaSpotsRest};
 pub use context::*;
 pub use game::*;
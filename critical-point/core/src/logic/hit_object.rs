@@ -0,0 +1,546 @@
+use critical_point_macros::csharp_out;
+use glam::{Quat, Vec3, Vec3A};
+use glam_ext::Vec2xz;
+use jolt_physics_rs::{BodyCreationSettings, BodyID, JRef, MotionType, Shape};
+use std::f32::consts::{PI, TAU};
+use std::sync::Arc;
+
+use crate::asset::{AssetShapeBox, AssetShapeCapsule, AssetShapeCylinder, AssetShapeSphere};
+use crate::consts::MAX_HIT_TIMES_PER_FRAME;
+use crate::instance::{InstHitObject, InstHitObjectShape};
+use crate::logic::base::{LogicAny, LogicType, StateBase, StateType, impl_state};
+use crate::logic::character::LogicCharacter;
+use crate::logic::game::{ContextHitGenerate, ContextRestore, ContextUpdate, HitCharacterEvent};
+use crate::logic::physics::{PhyBodyUserData, PhyHitObjectEvent, phy_layer};
+use crate::utils::{HistoryVec, HitInteraction, NumID, TmplID, XResult, extend, strict_lt, xfrom};
+
+#[repr(C)]
+#[csharp_out(Ref)]
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct StateHitObjectInit {
+    pub _base: StateBase,
+    pub tmpl_id: TmplID,
+    pub shooter_id: NumID,
+    pub spawn_frame: u32,
+}
+
+extend!(StateHitObjectInit, StateBase);
+
+impl_state!(StateHitObjectInit, HitObject, HitObjectInit, "HitObjectInit");
+
+#[repr(C)]
+#[csharp_out(Value)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct StateHitObjectPair {
+    pub dst_chara_id: NumID,
+    pub last_hit_time: f32,
+    pub hit_times: u16,
+}
+
+#[repr(C)]
+#[csharp_out(Ref)]
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct StateHitObjectUpdate {
+    pub _base: StateBase,
+    pub position: Vec3A,
+    pub velocity: Vec3A,
+    pub hit_times: u16,
+    pub death_frame: u32,
+    pub chara_pairs: Vec<StateHitObjectPair>,
+}
+
+extend!(StateHitObjectUpdate, StateBase);
+
+impl_state!(StateHitObjectUpdate, HitObject, HitObjectUpdate, "HitObjectUpdate");
+
+/// A projectile or an area-of-effect spawned by a character action.
+/// The hits are reported as HitCharacterEvent, with the shooter as the source character.
+#[derive(Debug)]
+pub struct LogicHitObject {
+    id: NumID,
+    spawn_frame: u32,
+    death_frame: u32,
+    inst: InstHitObject,
+    shooter_id: NumID,
+    shooter_is_player: bool,
+    spawn_time: f32,
+
+    position: Vec3A,
+    velocity: Vec3A,
+    body_id: BodyID,
+    hit_times: u16,
+    chara_pairs: Vec<StateHitObjectPair>,
+    destroy_requested: bool,
+}
+
+impl LogicAny for LogicHitObject {
+    #[inline]
+    fn typ(&self) -> LogicType {
+        LogicType::HitObject
+    }
+
+    #[inline]
+    fn id(&self) -> NumID {
+        self.id
+    }
+
+    #[inline]
+    fn spawn_frame(&self) -> u32 {
+        self.spawn_frame
+    }
+
+    #[inline]
+    fn death_frame(&self) -> u32 {
+        self.death_frame
+    }
+}
+
+impl LogicHitObject {
+    pub fn new(
+        ctx: &mut ContextUpdate,
+        tmpl_id: TmplID,
+        shooter: &LogicCharacter,
+    ) -> XResult<(Box<LogicHitObject>, Arc<StateHitObjectInit>)> {
+        let inst = InstHitObject::new(&mut ctx.context_assemble(), tmpl_id)?;
+        let shooter_phy = shooter.physics();
+
+        let mut obj = Box::new(LogicHitObject {
+            id: ctx.identity.gen_num_id(),
+            spawn_frame: ctx.time.frame,
+            death_frame: u32::MAX,
+            shooter_id: shooter.id(),
+            shooter_is_player: shooter.is_player(),
+            spawn_time: ctx.time.time,
+
+            position: shooter_phy.position() + shooter_phy.rotation() * inst.spawn_offset,
+            velocity: shooter_phy.direction() * inst.speed,
+            body_id: BodyID::INVALID,
+            hit_times: 0,
+            chara_pairs: Vec::with_capacity(4),
+            destroy_requested: false,
+            inst,
+        });
+        obj.create_body(ctx)?;
+
+        let state = Arc::new(StateHitObjectInit {
+            _base: StateBase::new(obj.id, StateType::HitObjectInit, LogicType::HitObject),
+            tmpl_id,
+            shooter_id: obj.shooter_id,
+            spawn_frame: obj.spawn_frame,
+        });
+        Ok((obj, state))
+    }
+
+    /// Rebuilds a hit object alive in a keyframe, used by late-join.
+    /// The shooter is not required, it may be dead at the keyframe.
+    pub fn rebuild(
+        ctx: &mut ContextUpdate,
+        init: &StateHitObjectInit,
+        ctx_restore: &ContextRestore,
+    ) -> XResult<Box<LogicHitObject>> {
+        let inst = InstHitObject::new(&mut ctx.context_assemble(), init.tmpl_id)?;
+        let mut obj = Box::new(LogicHitObject {
+            id: init.id,
+            spawn_frame: init.spawn_frame,
+            death_frame: u32::MAX,
+            shooter_id: init.shooter_id,
+            shooter_is_player: init.shooter_id.is_player(),
+            spawn_time: init.spawn_frame as f32 / ctx.time.fps,
+
+            position: Vec3A::ZERO,
+            velocity: Vec3A::ZERO,
+            body_id: BodyID::INVALID,
+            hit_times: 0,
+            chara_pairs: Vec::with_capacity(4),
+            destroy_requested: false,
+            inst,
+        });
+        obj.restore(ctx_restore)?;
+        if obj.is_alive() {
+            obj.create_body(ctx)?;
+        }
+        Ok(obj)
+    }
+
+    pub fn init_state(&self) -> Arc<StateHitObjectInit> {
+        Arc::new(StateHitObjectInit {
+            _base: StateBase::new(self.id, StateType::HitObjectInit, LogicType::HitObject),
+            tmpl_id: self.inst.tmpl_id,
+            shooter_id: self.shooter_id,
+            spawn_frame: self.spawn_frame,
+        })
+    }
+
+    fn create_body(&mut self, ctx: &mut ContextUpdate) -> XResult<()> {
+        let shape = Self::create_shape(&self.inst.shape)?;
+        let mut settings = BodyCreationSettings::new_sensor(
+            shape,
+            phy_layer!(HitEx, self.shooter_is_player => Enemy | Player),
+            MotionType::Kinematic,
+            self.position,
+            self.rotation(),
+        );
+        // Sensor needs to detect the static scenery and the kinematic targets.
+        settings.collide_kinematic_vs_non_dynamic = true;
+        settings.user_data = PhyBodyUserData::new_hit_object(self.id).into();
+        self.body_id = ctx
+            .physics
+            .body_itf()
+            .create_add_body(&settings, true)
+            .map_err(xfrom!())?;
+        Ok(())
+    }
+
+    fn create_shape(shape: &InstHitObjectShape) -> XResult<JRef<Shape>> {
+        match shape {
+            InstHitObjectShape::Sphere(shape) => AssetShapeSphere::from(*shape).create_physics(),
+            InstHitObjectShape::Capsule(shape) => AssetShapeCapsule::from(*shape).create_physics(),
+            InstHitObjectShape::Cylinder(shape) => AssetShapeCylinder::from(*shape).create_physics(),
+            InstHitObjectShape::Box(shape) => AssetShapeBox::from(*shape).create_physics(),
+        }
+    }
+
+    pub fn cleanup(&mut self, ctx: &mut ContextUpdate) {
+        if self.body_id.is_valid() {
+            let body_itf = ctx.physics.body_itf();
+            body_itf.remove_body(self.body_id);
+            body_itf.destroy_body(self.body_id);
+            self.body_id = BodyID::INVALID;
+        }
+    }
+
+    pub fn state(&self) -> Box<StateHitObjectUpdate> {
+        Box::new(StateHitObjectUpdate {
+            _base: StateBase::new(self.id, StateType::HitObjectUpdate, LogicType::HitObject),
+            position: self.position,
+            velocity: self.velocity,
+            hit_times: self.hit_times,
+            death_frame: self.death_frame,
+            chara_pairs: self.chara_pairs.clone(),
+        })
+    }
+
+    /// Same as `state()`, but fills a pooled state instead of allocating a new one.
+    pub(crate) fn state_into(&self, mut state: Box<StateHitObjectUpdate>) -> Box<StateHitObjectUpdate> {
+        state._base = StateBase::new(self.id, StateType::HitObjectUpdate, LogicType::HitObject);
+        state.position = self.position;
+        state.velocity = self.velocity;
+        state.hit_times = self.hit_times;
+        state.death_frame = self.death_frame;
+        state.chara_pairs.clear();
+        state.chara_pairs.extend_from_slice(&self.chara_pairs);
+        state
+    }
+
+    pub fn restore(&mut self, ctx: &ContextRestore) -> XResult<()> {
+        let state = ctx.find_as::<StateHitObjectUpdate>(self.id)?;
+        self.position = state.position;
+        self.velocity = state.velocity;
+        self.hit_times = state.hit_times;
+        self.chara_pairs.clear();
+        self.chara_pairs.extend_from_slice(&state.chara_pairs);
+        self.death_frame = state.death_frame;
+        self.destroy_requested = false;
+        Ok(())
+    }
+
+    /// Moves the body back to the restored position, called after `restore()`.
+    pub fn restore_body(&mut self, ctx: &mut ContextUpdate) -> XResult<()> {
+        if !self.is_alive() {
+            self.cleanup(ctx);
+        }
+        // The body is missing, after restored from a state before its death.
+        else if !self.body_id.is_valid() {
+            self.create_body(ctx)?;
+        }
+        else {
+            ctx.physics
+                .body_itf()
+                .set_position_rotation(self.body_id, self.position, self.rotation(), true);
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, ctx: &mut ContextUpdate, characters: &HistoryVec<Box<LogicCharacter>>) -> XResult<()> {
+        if !self.is_alive() {
+            return Ok(());
+        }
+
+        let expired = !strict_lt!(ctx.time.time - self.spawn_time, self.inst.life_time);
+        let max_hits = self.inst.max_hits > 0 && self.hit_times >= self.inst.max_hits;
+        if self.destroy_requested || expired || max_hits {
+            self.cleanup(ctx);
+            self.death_frame = ctx.time.frame;
+            return Ok(());
+        }
+
+        if self.inst.homing_speed > 0.0 {
+            self.update_homing(characters, ctx.time.spf);
+        }
+        self.velocity.y -= self.inst.gravity * ctx.time.spf;
+        self.position += self.velocity * ctx.time.spf;
+
+        // The body is missing, after restored from a state before its death.
+        if !self.body_id.is_valid() {
+            self.create_body(ctx)?;
+        }
+        else {
+            ctx.physics
+                .body_itf()
+                .set_position_rotation(self.body_id, self.position, self.rotation(), true);
+        }
+        Ok(())
+    }
+
+    /// Turns the horizontal moving direction towards the nearest enemy in homing radius.
+    fn update_homing(&mut self, characters: &HistoryVec<Box<LogicCharacter>>, spf: f32) {
+        let velocity_xz = Vec2xz::new(self.velocity.x, self.velocity.z);
+        let speed_xz = velocity_xz.length();
+        if speed_xz < 1e-4 {
+            return;
+        }
+
+        let homing_radius_sq = self.inst.homing_radius * self.inst.homing_radius;
+        let target = characters
+            .iter()
+            .filter(|c| c.is_alive() && c.is_player() != self.shooter_is_player)
+            .map(|c| c.physics().position() - self.position)
+            .map(|vec| Vec2xz::new(vec.x, vec.z))
+            .filter(|vec| vec.length_squared() <= homing_radius_sq)
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+        let Some(target) = target
+        else {
+            return;
+        };
+        if target.length_squared() < 1e-8 {
+            return;
+        }
+
+        let current_angle = velocity_xz.to_angle();
+        let mut delta_angle = (target.to_angle() - current_angle).rem_euclid(TAU);
+        if delta_angle > PI {
+            delta_angle -= TAU;
+        }
+        let max_angle = self.inst.homing_speed * spf;
+        let new_velocity_xz = Vec2xz::from_angle(current_angle + delta_angle.clamp(-max_angle, max_angle)) * speed_xz;
+        self.velocity.x = new_velocity_xz.x;
+        self.velocity.z = new_velocity_xz.z;
+    }
+
+    /// The Y axis of the shape is aligned with the moving direction.
+    /// A static hit object (area of effect) keeps the shape upright.
+    #[inline]
+    fn rotation(&self) -> Quat {
+        match Vec3::from(self.velocity).try_normalize() {
+            Some(dir) => Quat::from_rotation_arc(Vec3::Y, dir),
+            None => Quat::IDENTITY,
+        }
+    }
+
+    pub(crate) fn detect_hits(
+        &mut self,
+        ctx: &mut ContextHitGenerate<HitCharacterEvent>,
+        dst_chara: &LogicCharacter,
+        phy_event: &PhyHitObjectEvent,
+        interaction: HitInteraction,
+    ) -> XResult<usize> {
+        if !self.is_alive() || self.destroy_requested || !dst_chara.is_alive() {
+            return Ok(0);
+        }
+
+        let interval = self.inst.chara_min_interval;
+        let pair = match self
+            .chara_pairs
+            .iter_mut()
+            .find(|p| p.dst_chara_id == phy_event.dst_chara_id)
+        {
+            Some(p) => p,
+            None => {
+                self.chara_pairs.push(StateHitObjectPair {
+                    dst_chara_id: phy_event.dst_chara_id,
+                    last_hit_time: ctx.time - interval,
+                    hit_times: 0,
+                });
+                self.chara_pairs.last_mut().unwrap()
+            }
+        };
+
+        let mut event_count = 0;
+        for _ in 0..MAX_HIT_TIMES_PER_FRAME {
+            if strict_lt!(ctx.time - pair.last_hit_time, interval) {
+                break;
+            }
+            if pair.hit_times >= self.inst.chara_max_times {
+                break;
+            }
+            if self.inst.max_hits > 0 && self.hit_times >= self.inst.max_hits {
+                break;
+            }
+
+            pair.last_hit_time += interval;
+            pair.hit_times += 1;
+            self.hit_times += 1;
+
+            ctx.events.push(HitCharacterEvent {
+                src_chara_id: self.shooter_id,
+                dst_chara_id: phy_event.dst_chara_id,
+                group: self.inst.group,
+                box_hit_times: pair.hit_times,
+                group_hit_times: self.hit_times,
+                // Hit objects never clash with attacks
+                interaction: match interaction {
+                    HitInteraction::Clash => HitInteraction::Normal,
+                    interaction => interaction,
+                },
+                collision_normal: phy_event.world_space_normal,
+                collision_point_average: phy_event.collision_point_average,
+                character_vector: dst_chara.physics().position() - self.position,
+                ..Default::default()
+            });
+
+            event_count += 1;
+        }
+
+        if self.inst.max_hits > 0 && self.hit_times >= self.inst.max_hits {
+            self.destroy_requested = true;
+        }
+        Ok(event_count)
+    }
+
+    pub(crate) fn on_hit_scenery(&mut self) {
+        if self.is_alive() && self.inst.destroy_on_scenery {
+            self.destroy_requested = true;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn shooter_id(&self) -> NumID {
+        self.shooter_id
+    }
+
+    #[inline]
+    pub(crate) fn position(&self) -> Vec3A {
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::{FPS, SPF};
+    use crate::logic::system::StateSet;
+    use crate::logic::test_utils::*;
+    use crate::parameter::ParamPlayer;
+    use crate::utils::id;
+
+    fn prepare(tenv: &mut TestEnv, tmpl_id: TmplID) -> (HistoryVec<Box<LogicCharacter>>, Box<LogicHitObject>) {
+        let mut ctx = tenv.context_update_ex();
+        ctx.input.init(1).unwrap();
+        let param_player = ParamPlayer {
+            character: id!("Character.Instance^1"),
+            style: id!("Style.Instance^1A"),
+            level: 4,
+            ..Default::default()
+        };
+        let (player, _) = LogicCharacter::new_player(&mut ctx, &param_player).unwrap();
+
+        let mut ctx = tenv.context_update();
+        let (obj, state_init) = LogicHitObject::new(&mut ctx, tmpl_id, &player).unwrap();
+        assert_eq!(state_init.typ(), StateType::HitObjectInit);
+        assert_eq!(state_init.logic_typ(), LogicType::HitObject);
+        assert_eq!(state_init.tmpl_id, tmpl_id);
+        assert_eq!(state_init.shooter_id, player.id());
+
+        let mut characters = HistoryVec::with_capacity(1);
+        characters.append_new(player);
+        (characters, obj)
+    }
+
+    #[test]
+    fn test_hit_object_projectile() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (characters, mut obj) = prepare(&mut tenv, id!("HitObject.Instance.Arrow"));
+        let player_phy = characters[0].physics();
+        assert_eq!(
+            obj.position(),
+            player_phy.position() + player_phy.rotation() * Vec3A::new(0.0, 1.2, 0.5)
+        );
+
+        let start = obj.position();
+        let mut ctx = tenv.context_update();
+        obj.update(&mut ctx, &characters).unwrap();
+        assert!(obj.is_alive());
+        let state = obj.state();
+        assert_eq!(state.velocity.y, -2.0 * SPF);
+        assert!((state.position - start).length() > 20.0 * SPF * 0.99);
+
+        obj.on_hit_scenery();
+        let mut ctx = tenv.context_update();
+        obj.update(&mut ctx, &characters).unwrap();
+        assert!(!obj.is_alive());
+        assert_eq!(obj.state().death_frame, tenv.time.frame);
+        assert!(!obj.body_id.is_valid());
+
+        // Restores the death frame of the state, not the frame restored to
+        let mut state_set = StateSet::new(tenv.time.frame + 10);
+        state_set.updates.push(obj.state());
+        obj.restore(&ContextRestore::new(Arc::new(state_set))).unwrap();
+        assert_eq!(obj.death_frame(), tenv.time.frame);
+    }
+
+    #[test]
+    fn test_hit_object_area() {
+        let mut tenv = TestEnv::new().unwrap();
+        let (characters, mut obj) = prepare(&mut tenv, id!("HitObject.Instance.FireArea"));
+        let dst = &characters[0];
+        let phy_event = PhyHitObjectEvent {
+            object_id: obj.id(),
+            dst_chara_id: dst.id(),
+            dst_box_index: None,
+            world_space_normal: Vec3A::Y,
+            collision_point_average: Vec3A::ZERO,
+        };
+
+        let mut events = Vec::new();
+        let mut ctx = ContextHitGenerate::new(10, FPS, &mut events);
+        assert_eq!(obj.detect_hits(&mut ctx, dst, &phy_event, HitInteraction::Normal).unwrap(), 1);
+        assert_eq!(obj.detect_hits(&mut ctx, dst, &phy_event, HitInteraction::Normal).unwrap(), 0);
+        assert_eq!(events[0].src_chara_id, obj.shooter_id());
+        assert_eq!(events[0].dst_chara_id, dst.id());
+        assert_eq!(events[0].group, "Fire");
+        assert_eq!(events[0].box_hit_times, 1);
+        assert_eq!(events[0].interaction, HitInteraction::Normal);
+
+        // 1s later, touching an attack box of the dst, hit objects never clash
+        let mut ctx = ContextHitGenerate::new(40, FPS, &mut events);
+        assert_eq!(obj.detect_hits(&mut ctx, dst, &phy_event, HitInteraction::Clash).unwrap(), 1);
+        assert_eq!(events[1].box_hit_times, 2);
+        assert_eq!(events[1].interaction, HitInteraction::Normal);
+        let state = obj.state();
+        assert_eq!(state.hit_times, 2);
+        assert_eq!(state.chara_pairs.len(), 1);
+        assert_eq!(state.chara_pairs[0].dst_chara_id, dst.id());
+        assert_eq!(state.chara_pairs[0].hit_times, 2);
+
+        // Static hit object is not destroyed by scenery.
+        obj.on_hit_scenery();
+        let mut ctx = tenv.context_update();
+        obj.update(&mut ctx, &characters).unwrap();
+        assert!(obj.is_alive());
+        assert_eq!(obj.state().velocity, Vec3A::ZERO);
+    }
+}
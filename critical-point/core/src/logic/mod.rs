@@ -3,6 +3,7 @@ mod ai_task;
 mod base;
 mod character;
 mod game;
+mod hit_object;
 mod physics;
 mod script;
 mod system;
@@ -16,6 +17,7 @@ pub use ai_task::*;
 pub use base::*;
 pub use character::*;
 pub use game::*;
+pub use hit_object::*;
 pub use physics::*;
 pub use script::*;
 pub use system::*;
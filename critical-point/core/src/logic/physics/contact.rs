@@ -1,7 +1,7 @@
 use glam::Vec3A;
 use jolt_physics_rs::{
     Body, CollideShapeResult, ContactListener, ContactListenerVTable, ContactManifold, ContactSettings, JVec3,
-    SubShapeID, SubShapeIDPair, ValidateResult, vdata,
+    SubShapeIDPair, ValidateResult, vdata,
 };
 use static_assertions::const_assert_eq;
 use std::mem;
@@ -16,6 +16,9 @@ pub(crate) enum PhyBodyUserData {
     Zone,
     Hit { chara_id: NumID, hit: u16 },
     Character { id: NumID },
+    Trigger { index: u16 },
+    HitObject { id: NumID },
+    HitTarget { chara_id: NumID, hit: u16 },
     _Padding_([u8; 7]),
 }
 
@@ -33,6 +36,28 @@ impl PhyBodyUserData {
     pub(crate) fn new_character(id: NumID) -> PhyBodyUserData {
         PhyBodyUserData::Character { id }
     }
+
+    pub(crate) fn new_trigger(index: u16) -> PhyBodyUserData {
+        PhyBodyUserData::Trigger { index }
+    }
+
+    pub(crate) fn new_hit_object(id: NumID) -> PhyBodyUserData {
+        PhyBodyUserData::HitObject { id }
+    }
+
+    pub(crate) fn new_hit_target(chara_id: NumID, hit: u16) -> PhyBodyUserData {
+        PhyBodyUserData::HitTarget { chara_id, hit }
+    }
+
+    /// The character (and its hit box) an attack can hit through this body.
+    #[inline]
+    fn as_hit_dst(&self) -> Option<(NumID, Option<u16>)> {
+        match *self {
+            PhyBodyUserData::Character { id } => Some((id, None)),
+            PhyBodyUserData::HitTarget { chara_id, hit } => Some((chara_id, Some(hit))),
+            _ => None,
+        }
+    }
 }
 
 const PHY_BODY_USER_DATA_PADDING: PhyBodyUserData = PhyBodyUserData::_Padding_([0; 7]);
@@ -61,20 +86,26 @@ pub(crate) enum PhyContactState {
     Removed,
 }
 
-#[derive(Debug)]
-pub(crate) struct PhyHitCharacterEvent<'t> {
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PhyHitCharacterEvent {
     pub(crate) src_chara_id: NumID,
     pub(crate) src_box_index: u16,
     pub(crate) dst_chara_id: NumID,
-    pub(crate) src_body: &'t Body,
-    pub(crate) dst_body: &'t Body,
-    pub(crate) src_sub_shape_id1: SubShapeID,
-    pub(crate) dst_sub_shape_id2: SubShapeID,
+    pub(crate) dst_box_index: Option<u16>, // The hit box touched, None for the target body
     pub(crate) world_space_normal: Vec3A,
     pub(crate) penetration_depth: f32,
     pub(crate) collision_point_average: Vec3A,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PhyHitObjectEvent {
+    pub(crate) object_id: NumID,
+    pub(crate) dst_chara_id: NumID,
+    pub(crate) dst_box_index: Option<u16>, // The hit box touched, None for the target body
+    pub(crate) world_space_normal: Vec3A,
+    pub(crate) collision_point_average: Vec3A,
+}
+
 #[vdata(ContactListenerVTable)]
 pub(crate) struct PhyContactCollector<'t> {
     game: &'t mut LogicGame,
@@ -91,43 +122,51 @@ impl<'t> PhyContactCollector<'t> {
         let ud1 = PhyBodyUserData::from(body1.get_user_data());
         let ud2 = PhyBodyUserData::from(body2.get_user_data());
 
-        let res = match (ud1, ud2) {
-            (
-                Hit {
-                    chara_id: src_chara_id,
-                    hit,
-                },
-                Character { id: dst_chara_id },
-            ) => self.game.on_hit_character(&PhyHitCharacterEvent {
-                src_chara_id,
-                src_box_index: hit,
-                dst_chara_id,
-                src_body: body1,
-                dst_body: body2,
-                src_sub_shape_id1: manifold.sub_shape_id1,
-                dst_sub_shape_id2: manifold.sub_shape_id2,
-                world_space_normal: manifold.world_space_normal,
-                penetration_depth: manifold.penetration_depth,
-                collision_point_average: Self::calc_collision_point_average(manifold),
-            }),
-            (
-                Character { id: dst_chara_id },
-                Hit {
-                    chara_id: src_chara_id,
-                    hit,
-                },
-            ) => self.game.on_hit_character(&PhyHitCharacterEvent {
-                src_chara_id,
-                src_box_index: hit,
-                dst_chara_id,
-                src_body: body2,
-                dst_body: body1,
-                src_sub_shape_id1: manifold.sub_shape_id2,
-                dst_sub_shape_id2: manifold.sub_shape_id1,
-                world_space_normal: -manifold.world_space_normal,
-                penetration_depth: manifold.penetration_depth,
-                collision_point_average: Self::calc_collision_point_average(manifold),
-            }),
+        let res = match (ud1, ud2, ud1.as_hit_dst(), ud2.as_hit_dst()) {
+            (Hit { chara_id, hit }, _, _, Some((dst_chara_id, dst_box_index))) => {
+                self.game.on_hit_character(&PhyHitCharacterEvent {
+                    src_chara_id: chara_id,
+                    src_box_index: hit,
+                    dst_chara_id,
+                    dst_box_index,
+                    world_space_normal: manifold.world_space_normal,
+                    penetration_depth: manifold.penetration_depth,
+                    collision_point_average: Self::calc_collision_point_average(manifold),
+                })
+            }
+            (_, Hit { chara_id, hit }, Some((dst_chara_id, dst_box_index)), _) => {
+                self.game.on_hit_character(&PhyHitCharacterEvent {
+                    src_chara_id: chara_id,
+                    src_box_index: hit,
+                    dst_chara_id,
+                    dst_box_index,
+                    world_space_normal: -manifold.world_space_normal,
+                    penetration_depth: manifold.penetration_depth,
+                    collision_point_average: Self::calc_collision_point_average(manifold),
+                })
+            }
+            (Trigger { index }, Character { id }, _, _) | (Character { id }, Trigger { index }, _, _) => {
+                self.game.on_trigger_character(index, id)
+            }
+            (HitObject { id: object_id }, _, _, Some((dst_chara_id, dst_box_index))) => {
+                self.game.on_hit_object_character(&PhyHitObjectEvent {
+                    object_id,
+                    dst_chara_id,
+                    dst_box_index,
+                    world_space_normal: manifold.world_space_normal,
+                    collision_point_average: Self::calc_collision_point_average(manifold),
+                })
+            }
+            (_, HitObject { id: object_id }, Some((dst_chara_id, dst_box_index)), _) => {
+                self.game.on_hit_object_character(&PhyHitObjectEvent {
+                    object_id,
+                    dst_chara_id,
+                    dst_box_index,
+                    world_space_normal: -manifold.world_space_normal,
+                    collision_point_average: Self::calc_collision_point_average(manifold),
+                })
+            }
+            (HitObject { id }, Zone, _, _) | (Zone, HitObject { id }, _, _) => self.game.on_hit_object_scenery(id),
             _ => Ok(()),
         };
 
@@ -194,7 +233,7 @@ mod tests {
         assert!(matches!(zero, PhyBodyUserData::None));
 
         let raw_padding: [u8; 8] = unsafe { mem::transmute(PHY_BODY_USER_DATA_PADDING) };
-        assert_eq!(raw_padding[0], 4);
+        assert_eq!(raw_padding[0], 7);
 
         let none_u64: u64 = PhyBodyUserData::None.into();
         let none = PhyBodyUserData::try_from(none_u64).unwrap();
@@ -218,5 +257,30 @@ mod tests {
         let character_u64: u64 = PhyBodyUserData::Character { id: NumID(7777) }.into();
         let character = PhyBodyUserData::try_from(character_u64).unwrap();
         assert_eq!(character, PhyBodyUserData::Character { id: NumID(7777) });
+
+        let trigger_u64: u64 = PhyBodyUserData::Trigger { index: 3 }.into();
+        let trigger = PhyBodyUserData::try_from(trigger_u64).unwrap();
+        assert_eq!(trigger, PhyBodyUserData::Trigger { index: 3 });
+
+        let hit_object_u64: u64 = PhyBodyUserData::HitObject { id: NumID(5555) }.into();
+        let hit_object = PhyBodyUserData::try_from(hit_object_u64).unwrap();
+        assert_eq!(hit_object, PhyBodyUserData::HitObject { id: NumID(5555) });
+
+        let hit_target_u64: u64 = PhyBodyUserData::HitTarget {
+            chara_id: NumID(4321),
+            hit: 7,
+        }
+        .into();
+        let hit_target = PhyBodyUserData::try_from(hit_target_u64).unwrap();
+        assert_eq!(hit_target, PhyBodyUserData::HitTarget {
+            chara_id: NumID(4321),
+            hit: 7
+        });
+        assert_eq!(hit_target.as_hit_dst(), Some((NumID(4321), Some(7))));
+        assert_eq!(
+            PhyBodyUserData::Character { id: NumID(7777) }.as_hit_dst(),
+            Some((NumID(7777), None))
+        );
+        assert_eq!(PhyBodyUserData::Zone.as_hit_dst(), None);
     }
 }
@@ -94,17 +94,17 @@ impl PhyLayerType {
             // StaticScenery
             BOUNDING | HIT_EX,
             // StaticTrigger
-            BOUNDING,
+            BOUNDING | TARGET,
             // DynamicScenery
             BOUNDING | HIT_EX,
             // BreakableScenery
             BOUNDING | HIT_EX | HIT,
             // DynamicTrigger
-            BOUNDING,
+            BOUNDING | TARGET,
             // Bounding
             STATIC_SCENERY | DYNAMIC_SCENERY | BREAKABLE_SCENERY | STATIC_TRIGGER | DYNAMIC_TRIGGER | BOUNDING,
             // Target
-            HIT | HIT_EX,
+            HIT | HIT_EX | STATIC_TRIGGER | DYNAMIC_TRIGGER,
             // Hit
             TARGET | BREAKABLE_SCENERY,
             // HitEx
@@ -122,7 +122,7 @@ impl PhyLayerType {
             0x2, // BreakableScenery
             0x2, // DynamicTrigger
             0x3, // Bounding
-            0x2, // Target
+            0x3, // Target
             0x2, // Hit
             0x3, // HitEx
         ];
@@ -199,7 +199,7 @@ mod tests {
         assert_eq!(itf.should_collide(phy_layer!(Bounding, All), 0), true);
         assert_eq!(itf.should_collide(phy_layer!(Bounding, All), 1), true);
 
-        assert_eq!(itf.should_collide(phy_layer!(Target, All), 0), false);
+        assert_eq!(itf.should_collide(phy_layer!(Target, All), 0), true);
         assert_eq!(itf.should_collide(phy_layer!(Target, All), 1), true);
 
         assert_eq!(itf.should_collide(phy_layer!(Hit, All), 0), false);
@@ -221,7 +221,8 @@ mod tests {
         assert_eq!(itf.should_collide(phy_layer!(StaticTrigger, All), phy_layer!(Bounding, Enemy)), true);
 
         assert_eq!(itf.should_collide(phy_layer!(StaticScenery, All), phy_layer!(Target, Player)), false);
-        assert_eq!(itf.should_collide(phy_layer!(StaticTrigger, All), phy_layer!(Target, Enemy)), false);
+        assert_eq!(itf.should_collide(phy_layer!(StaticTrigger, All), phy_layer!(Target, Enemy)), true);
+        assert_eq!(itf.should_collide(phy_layer!(DynamicTrigger, All), phy_layer!(Target, Player)), true);
 
         assert_eq!(itf.should_collide(phy_layer!(StaticScenery, All), phy_layer!(Hit, Player)), false);
         assert_eq!(itf.should_collide(phy_layer!(StaticTrigger, All), phy_layer!(Hit, Enemy)), false);
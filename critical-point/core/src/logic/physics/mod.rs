@@ -1,5 +1,7 @@
 mod contact;
 mod layer;
+mod query;
 
 pub(crate) use contact::*;
 pub use layer::*;
+pub(crate) use query::*;
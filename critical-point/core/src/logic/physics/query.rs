@@ -0,0 +1,77 @@
+use glam::Vec3A;
+use jolt_physics_rs::{BodyID, ObjectLayer, ObjectLayerFilter, ObjectLayerFilterVTable, PhysicsSystem, RRayCast, vdata};
+
+use crate::logic::physics::{PhyLayerType, extract_layer_type};
+
+#[vdata(ObjectLayerFilterVTable)]
+pub(crate) struct PhySceneryLayerFilter;
+
+impl ObjectLayerFilter for PhySceneryLayerFilter {
+    fn should_collide(&self, layer: ObjectLayer) -> bool {
+        const SCENERY: u32 = (1 << PhyLayerType::StaticScenery as u32)
+            | (1 << PhyLayerType::DynamicScenery as u32)
+            | (1 << PhyLayerType::BreakableScenery as u32);
+        (1 << extract_layer_type(layer)) & SCENERY != 0
+    }
+}
+
+/// The closest hit of a ray cast, in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PhyRayHit {
+    pub body_id: BodyID,
+    pub point: Vec3A,
+    /// Position of the hit along the ray, from 0 (`origin`) to 1 (`origin + direction`).
+    pub fraction: f32,
+}
+
+/// Casts a ray from `origin` to `origin + direction` against the scenery bodies, returns the closest hit.
+pub(crate) fn phy_cast_ray_scenery(physics: &PhysicsSystem, origin: Vec3A, direction: Vec3A) -> Option<PhyRayHit> {
+    let ray = RRayCast::new(origin, direction);
+    let result = physics.narrow_phase_query().cast_ray(&ray, &PhySceneryLayerFilter {})?;
+    Some(PhyRayHit {
+        body_id: result.body_id,
+        point: origin + direction * result.fraction,
+        fraction: result.fraction,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    use crate::logic::physics::phy_layer;
+    use crate::logic::test_utils::*;
+
+    #[test]
+    fn test_scenery_layer_filter() {
+        let filter = PhySceneryLayerFilter {};
+        assert!(filter.should_collide(phy_layer!(StaticScenery, All)));
+        assert!(filter.should_collide(phy_layer!(DynamicScenery, All)));
+        assert!(filter.should_collide(phy_layer!(BreakableScenery, Player)));
+        assert!(!filter.should_collide(phy_layer!(StaticTrigger, All)));
+        assert!(!filter.should_collide(phy_layer!(Bounding, Player)));
+        assert!(!filter.should_collide(phy_layer!(Target, Enemy)));
+        assert!(!filter.should_collide(phy_layer!(Hit, Enemy)));
+    }
+
+    #[test]
+    fn test_cast_ray_scenery() {
+        let mut tenv = TestEnv::new().unwrap();
+        tenv.systems.physics.optimize_broad_phase();
+        let physics = &tenv.systems.physics;
+
+        // The floor
+        let hit = phy_cast_ray_scenery(physics, Vec3A::new(0.0, 2.0, 0.0), Vec3A::new(0.0, -4.0, 0.0)).unwrap();
+        assert_abs_diff_eq!(hit.point, Vec3A::ZERO, epsilon = 1e-3);
+        assert_abs_diff_eq!(hit.fraction, 0.5, epsilon = 1e-3);
+
+        // A stair step
+        let hit = phy_cast_ray_scenery(physics, Vec3A::new(6.07, 2.0, -0.57), Vec3A::new(0.0, -4.0, 0.0)).unwrap();
+        assert_abs_diff_eq!(hit.point.y, 0.3, epsilon = 1e-3);
+
+        // Too short, or away from the ground
+        assert!(phy_cast_ray_scenery(physics, Vec3A::new(0.0, 2.0, 0.0), Vec3A::new(0.0, -1.0, 0.0)).is_none());
+        assert!(phy_cast_ray_scenery(physics, Vec3A::new(0.0, 2.0, 0.0), Vec3A::new(0.0, 4.0, 0.0)).is_none());
+    }
+}
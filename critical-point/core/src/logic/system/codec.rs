@@ -0,0 +1,829 @@
+use bytecheck::CheckBytes;
+use rkyv::api::high::{HighDeserializer, HighSerializer, HighValidator};
+use rkyv::rancor::Error as RkyvError;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::consts::DEFAULT_TICK_RATE;
+use crate::logic::action::{
+    StateActionAny, StateActionEmpty, StateActionGeneral, StateActionGeneralNpc, StateActionHit, StateActionIdle,
+    StateActionMove, StateActionMoveNpc,
+};
+use crate::logic::ai_task::{
+    StateAiTaskAny, StateAiTaskGeneral, StateAiTaskIdle, StateAiTaskMoveToCharacter, StateAiTaskPatrol,
+};
+use crate::logic::base::{StateAny, StateType};
+use crate::logic::character::{
+    StateCharaControl, StateCharaPhysics, StateCharaValue, StateCharacterInit, StateCharacterUpdate,
+};
+use crate::logic::game::{StateGameInit, StateGameUpdate};
+use crate::logic::hit_object::{StateHitObjectInit, StateHitObjectUpdate};
+use crate::logic::system::state::StateSet;
+use crate::logic::zone::{StateZoneInit, StateZoneUpdate};
+use crate::utils::{
+    ActionType, AiTaskType, Castable, CustomEvent, NumID, Symbol, TmplID, XResult, xerr, xres, xresf,
+};
+
+/// A field that changed since the base frame, `index` is the field's declaration order.
+#[derive(Debug, Default, Clone, PartialEq, Archive, Serialize, Deserialize)]
+pub struct DeltaField {
+    pub index: u8,
+    pub data: Vec<u8>,
+}
+
+/// A state encoded against the state with the same id in the base frame.
+/// Unchanged fields are omitted, without a base state every field is written.
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+pub struct DeltaState {
+    pub id: NumID,
+    pub typ: StateType,
+    pub fields: Vec<DeltaField>,
+}
+
+/// A StateSet encoded against a previous (acknowledged) frame.
+/// A keyframe has no base frame, and can be decoded alone.
+#[derive(Debug, Default, Clone, PartialEq, Archive, Serialize, Deserialize)]
+pub struct DeltaStateSet {
+    pub frame: u32,
+    pub base_frame: Option<u32>,
+    pub inits: Vec<DeltaState>,
+    pub updates: Vec<DeltaState>,
+    pub chara_updates: Vec<DeltaState>,
+}
+
+impl DeltaStateSet {
+    /// Count of objects that changed since the base frame.
+    pub fn changed_count(&self) -> usize {
+        self.updates
+            .iter()
+            .chain(self.chara_updates.iter())
+            .filter(|s| !s.fields.is_empty())
+            .count()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StateSetCodec {
+    keyframe_interval: u32,
+}
+
+impl Default for StateSetCodec {
+    fn default() -> Self {
+        StateSetCodec::with_tick_rate(DEFAULT_TICK_RATE)
+    }
+}
+
+impl StateSetCodec {
+    /// Every keyframe_interval frames, a keyframe is encoded whatever the base is.
+    pub fn new(keyframe_interval: u32) -> StateSetCodec {
+        StateSetCodec {
+            keyframe_interval: keyframe_interval.max(1),
+        }
+    }
+
+    /// A keyframe every 2 seconds at the tick rate.
+    pub fn with_tick_rate(tick_rate: u32) -> StateSetCodec {
+        StateSetCodec::new(2 * tick_rate)
+    }
+
+    #[inline]
+    pub fn keyframe_interval(&self) -> u32 {
+        self.keyframe_interval
+    }
+
+    #[inline]
+    pub fn is_keyframe(&self, frame: u32) -> bool {
+        frame % self.keyframe_interval == 0
+    }
+
+    pub fn encode(&self, state_set: &StateSet, base: Option<&StateSet>) -> XResult<Vec<u8>> {
+        let delta = self.encode_delta(state_set, base)?;
+        Ok(to_bytes(&delta)?.into_vec())
+    }
+
+    pub fn encode_delta(&self, state_set: &StateSet, base: Option<&StateSet>) -> XResult<DeltaStateSet> {
+        let base = match base {
+            Some(base) if !self.is_keyframe(state_set.frame) => {
+                if base.frame >= state_set.frame {
+                    return xresf!(BadArgument; "frame={}, base.frame={}", state_set.frame, base.frame);
+                }
+                Some(base)
+            }
+            _ => None,
+        };
+
+        let mut inits = Vec::with_capacity(state_set.inits.len());
+        for state in state_set.inits.iter() {
+            inits.push(diff_state(state.as_ref(), None)?);
+        }
+
+        let mut updates = Vec::with_capacity(state_set.updates.len());
+        for state in state_set.updates.iter() {
+            let base_state = base.and_then(|b| b.updates.iter().find(|s| s.id() == state.id()));
+            updates.push(diff_state(state.as_ref(), base_state.map(|s| s.as_ref()))?);
+        }
+
+        let mut chara_updates = Vec::with_capacity(state_set.chara_updates.len());
+        for state in state_set.chara_updates.iter() {
+            let base_state = base.and_then(|b| b.chara_updates.iter().find(|s| s.id == state.id));
+            chara_updates.push(DeltaState {
+                id: state.id,
+                typ: StateType::CharacterUpdate,
+                fields: state.diff_fields(base_state.map(|s| s.as_ref()))?,
+            });
+        }
+
+        Ok(DeltaStateSet {
+            frame: state_set.frame,
+            base_frame: base.map(|b| b.frame),
+            inits,
+            updates,
+            chara_updates,
+        })
+    }
+
+    /// The data comes from the network, it's validated before being decoded.
+    pub fn decode(&self, data: &[u8], base: Option<&StateSet>) -> XResult<StateSet> {
+        let delta = from_bytes::<DeltaStateSet>(data)?;
+        self.decode_delta(&delta, base)
+    }
+
+    pub fn decode_delta(&self, delta: &DeltaStateSet, base: Option<&StateSet>) -> XResult<StateSet> {
+        let base = match (delta.base_frame, base) {
+            (None, _) => None,
+            (Some(base_frame), Some(base)) if base.frame == base_frame => Some(base),
+            (Some(base_frame), base) => {
+                return xresf!(LogicBadState; "base_frame={}, base.frame={:?}", base_frame, base.map(|b| b.frame));
+            }
+        };
+
+        let mut state_set = StateSet::new(delta.frame);
+
+        for delta_state in delta.inits.iter() {
+            state_set.inits.push(Arc::from(patch_state(delta_state, None)?));
+        }
+
+        for delta_state in delta.updates.iter() {
+            let base_state = base.and_then(|b| b.updates.iter().find(|s| s.id() == delta_state.id));
+            state_set
+                .updates
+                .push(patch_state(delta_state, base_state.map(|s| s.as_ref()))?);
+        }
+
+        for delta_state in delta.chara_updates.iter() {
+            if delta_state.typ != StateType::CharacterUpdate {
+                return xresf!(LogicBadState; "typ={:?}", delta_state.typ);
+            }
+            let base_state = base.and_then(|b| b.chara_updates.iter().find(|s| s.id == delta_state.id));
+            let state = StateCharacterUpdate::patch_fields(&delta_state.fields, base_state.map(|s| s.as_ref()))?;
+            if state.id != delta_state.id {
+                return xresf!(LogicIDMismatch; "id={}, state.id={}", delta_state.id, state.id);
+            }
+            state_set.chara_updates.push(Box::new(state));
+        }
+        Ok(state_set)
+    }
+}
+
+fn diff_state(state: &dyn StateAny, base: Option<&dyn StateAny>) -> XResult<DeltaState> {
+    fn diff<T: DeltaFields + 'static>(state: &dyn StateAny, base: Option<&dyn StateAny>) -> XResult<Vec<DeltaField>> {
+        let base = base.map(|b| b.cast::<T>()).transpose()?;
+        state.cast::<T>()?.diff_fields(base)
+    }
+
+    use StateType::*;
+    let base = base.filter(|b| b.id() == state.id() && b.typ() == state.typ());
+    let fields = match state.typ() {
+        GameInit => diff::<StateGameInit>(state, base)?,
+        GameUpdate => diff::<StateGameUpdate>(state, base)?,
+        ZoneInit => diff::<StateZoneInit>(state, base)?,
+        ZoneUpdate => diff::<StateZoneUpdate>(state, base)?,
+        CharacterInit => diff::<StateCharacterInit>(state, base)?,
+        CharacterUpdate => diff::<StateCharacterUpdate>(state, base)?,
+        HitObjectInit => diff::<StateHitObjectInit>(state, base)?,
+        HitObjectUpdate => diff::<StateHitObjectUpdate>(state, base)?,
+    };
+    Ok(DeltaState {
+        id: state.id(),
+        typ: state.typ(),
+        fields,
+    })
+}
+
+fn patch_state(delta: &DeltaState, base: Option<&dyn StateAny>) -> XResult<Box<dyn StateAny>> {
+    fn patch<T: StateAny + DeltaFields>(
+        fields: &[DeltaField],
+        base: Option<&dyn StateAny>,
+    ) -> XResult<Box<dyn StateAny>> {
+        let base = base.map(|b| b.cast::<T>()).transpose()?;
+        Ok(Box::new(T::patch_fields(fields, base)?))
+    }
+
+    use StateType::*;
+    let base = base.filter(|b| b.typ() == delta.typ);
+    let state = match delta.typ {
+        GameInit => patch::<StateGameInit>(&delta.fields, base)?,
+        GameUpdate => patch::<StateGameUpdate>(&delta.fields, base)?,
+        ZoneInit => patch::<StateZoneInit>(&delta.fields, base)?,
+        ZoneUpdate => patch::<StateZoneUpdate>(&delta.fields, base)?,
+        CharacterInit => patch::<StateCharacterInit>(&delta.fields, base)?,
+        CharacterUpdate => patch::<StateCharacterUpdate>(&delta.fields, base)?,
+        HitObjectInit => patch::<StateHitObjectInit>(&delta.fields, base)?,
+        HitObjectUpdate => patch::<StateHitObjectUpdate>(&delta.fields, base)?,
+    };
+    if state.id() != delta.id {
+        return xresf!(LogicIDMismatch; "id={}, state.id={}", delta.id, state.id());
+    }
+    Ok(state)
+}
+
+//
+// Field level diff
+//
+
+trait DeltaFields: Sized {
+    fn diff_fields(&self, base: Option<&Self>) -> XResult<Vec<DeltaField>>;
+    fn patch_fields(fields: &[DeltaField], base: Option<&Self>) -> XResult<Self>;
+}
+
+trait FieldCodec<T> {
+    /// None if the field is unchanged since the base.
+    fn diff(curr: &T, base: Option<&T>) -> XResult<Option<Vec<u8>>>;
+    /// A None data takes the field from the base.
+    fn patch(data: Option<&[u8]>, base: Option<&T>) -> XResult<T>;
+}
+
+// Lists every field of a state with its codec, the struct literal in patch_fields() makes sure none is missing.
+macro_rules! delta_fields {
+    ($type:ty { $($field:ident: $codec:ty),+ $(,)? }) => {
+        impl DeltaFields for $type {
+            #[allow(unused_assignments)]
+            fn diff_fields(&self, base: Option<&Self>) -> XResult<Vec<DeltaField>> {
+                let mut fields = Vec::new();
+                let mut index: u8 = 0;
+                $(
+                    if let Some(data) = <$codec as FieldCodec<_>>::diff(&self.$field, base.map(|b| &b.$field))? {
+                        fields.push(DeltaField { index, data });
+                    }
+                    index += 1;
+                )+
+                Ok(fields)
+            }
+
+            #[allow(unused_assignments)]
+            fn patch_fields(fields: &[DeltaField], base: Option<&Self>) -> XResult<Self> {
+                let mut index: u8 = 0;
+                $(
+                    let data = fields.iter().find(|f| f.index == index).map(|f| f.data.as_slice());
+                    let $field = <$codec as FieldCodec<_>>::patch(data, base.map(|b| &b.$field))?;
+                    index += 1;
+                )+
+                if let Some(field) = fields.iter().find(|f| f.index >= index) {
+                    return xresf!(Rkyv; "type={}, index={}", stringify!($type), field.index);
+                }
+                Ok(Self { $($field),+ })
+            }
+        }
+    };
+}
+
+/// Writes the whole field when it changed.
+struct Whole;
+
+impl<T> FieldCodec<T> for Whole
+where
+    T: Clone + PartialEq + Archive + for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, RkyvError>>,
+    T::Archived: for<'a> CheckBytes<HighValidator<'a, RkyvError>> + Deserialize<T, HighDeserializer<RkyvError>>,
+{
+    fn diff(curr: &T, base: Option<&T>) -> XResult<Option<Vec<u8>>> {
+        if base == Some(curr) {
+            return Ok(None);
+        }
+        Ok(Some(to_bytes(curr)?.into_vec()))
+    }
+
+    fn patch(data: Option<&[u8]>, base: Option<&T>) -> XResult<T> {
+        match (data, base) {
+            (Some(data), _) => from_bytes::<T>(data),
+            (None, Some(base)) => Ok(base.clone()),
+            (None, None) => xres!(Rkyv; "missing field"),
+        }
+    }
+}
+
+/// Diffs a struct field by field.
+struct Nested;
+
+impl<T: DeltaFields> FieldCodec<T> for Nested {
+    fn diff(curr: &T, base: Option<&T>) -> XResult<Option<Vec<u8>>> {
+        let fields = curr.diff_fields(base)?;
+        if base.is_some() && fields.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(to_bytes(&fields)?.into_vec()))
+    }
+
+    fn patch(data: Option<&[u8]>, base: Option<&T>) -> XResult<T> {
+        match data {
+            Some(data) => T::patch_fields(&from_bytes::<Vec<DeltaField>>(data)?, base),
+            None => T::patch_fields(&[], base),
+        }
+    }
+}
+
+/// Diffs trait object states element by element, each element is encoded as its concrete type.
+struct Elements;
+
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+struct DeltaElement {
+    typ: u16,
+    data: Option<Vec<u8>>,
+}
+
+trait ElementCodec {
+    fn element_typ(&self) -> u16;
+    fn encode_element(&self) -> XResult<Vec<u8>>;
+    fn decode_element(typ: u16, data: &[u8]) -> XResult<Box<Self>>;
+}
+
+impl<E: ?Sized + ElementCodec + PartialEq> FieldCodec<Vec<Box<E>>> for Elements {
+    fn diff(curr: &Vec<Box<E>>, base: Option<&Vec<Box<E>>>) -> XResult<Option<Vec<u8>>> {
+        if base == Some(curr) {
+            return Ok(None);
+        }
+        let mut elements = Vec::with_capacity(curr.len());
+        for (idx, element) in curr.iter().enumerate() {
+            let data = match base.and_then(|b| b.get(idx)) {
+                Some(base_element) if base_element == element => None,
+                _ => Some(element.encode_element()?),
+            };
+            elements.push(DeltaElement {
+                typ: element.element_typ(),
+                data,
+            });
+        }
+        Ok(Some(to_bytes(&elements)?.into_vec()))
+    }
+
+    fn patch(data: Option<&[u8]>, base: Option<&Vec<Box<E>>>) -> XResult<Vec<Box<E>>> {
+        let clone = |element: &E| E::decode_element(element.element_typ(), &element.encode_element()?);
+        let Some(data) = data
+        else {
+            return match base {
+                Some(base) => base.iter().map(|e| clone(e.as_ref())).collect(),
+                None => xres!(Rkyv; "missing field"),
+            };
+        };
+
+        let elements = from_bytes::<Vec<DeltaElement>>(data)?;
+        let mut states = Vec::with_capacity(elements.len());
+        for (idx, element) in elements.iter().enumerate() {
+            let state = match (&element.data, base.and_then(|b| b.get(idx))) {
+                (Some(data), _) => E::decode_element(element.typ, data)?,
+                (None, Some(base_element)) if base_element.element_typ() == element.typ => {
+                    clone(base_element.as_ref())?
+                }
+                _ => return xresf!(Rkyv; "missing element, idx={}, typ={}", idx, element.typ),
+            };
+            states.push(state);
+        }
+        Ok(states)
+    }
+}
+
+impl ElementCodec for dyn StateActionAny {
+    #[inline]
+    fn element_typ(&self) -> u16 {
+        self.typ().into()
+    }
+
+    fn encode_element(&self) -> XResult<Vec<u8>> {
+        fn encode<T>(state: &dyn StateActionAny) -> XResult<Vec<u8>>
+        where
+            T: 'static + for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, RkyvError>>,
+        {
+            Ok(to_bytes(state.cast::<T>()?)?.into_vec())
+        }
+
+        use ActionType::*;
+        match self.typ() {
+            Empty => encode::<StateActionEmpty>(self),
+            Idle => encode::<StateActionIdle>(self),
+            Move => encode::<StateActionMove>(self),
+            MoveNpc => encode::<StateActionMoveNpc>(self),
+            General => encode::<StateActionGeneral>(self),
+            GeneralNpc => encode::<StateActionGeneralNpc>(self),
+            Hit => encode::<StateActionHit>(self),
+        }
+    }
+
+    fn decode_element(typ: u16, data: &[u8]) -> XResult<Box<dyn StateActionAny>> {
+        fn decode<T>(data: &[u8]) -> XResult<Box<dyn StateActionAny>>
+        where
+            T: StateActionAny + Archive,
+            T::Archived: for<'a> CheckBytes<HighValidator<'a, RkyvError>> + Deserialize<T, HighDeserializer<RkyvError>>,
+        {
+            Ok(Box::new(from_bytes::<T>(data)?))
+        }
+
+        use ActionType::*;
+        let typ = ActionType::try_from(typ)?;
+        let state = match typ {
+            Empty => decode::<StateActionEmpty>(data)?,
+            Idle => decode::<StateActionIdle>(data)?,
+            Move => decode::<StateActionMove>(data)?,
+            MoveNpc => decode::<StateActionMoveNpc>(data)?,
+            General => decode::<StateActionGeneral>(data)?,
+            GeneralNpc => decode::<StateActionGeneralNpc>(data)?,
+            Hit => decode::<StateActionHit>(data)?,
+        };
+        if state.typ() != typ {
+            return xresf!(Rkyv; "typ={:?}, state.typ={:?}", typ, state.typ());
+        }
+        Ok(state)
+    }
+}
+
+impl ElementCodec for dyn StateAiTaskAny {
+    #[inline]
+    fn element_typ(&self) -> u16 {
+        self.typ().into()
+    }
+
+    fn encode_element(&self) -> XResult<Vec<u8>> {
+        fn encode<T>(state: &dyn StateAiTaskAny) -> XResult<Vec<u8>>
+        where
+            T: 'static + for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, RkyvError>>,
+        {
+            Ok(to_bytes(state.cast::<T>()?)?.into_vec())
+        }
+
+        use AiTaskType::*;
+        match self.typ() {
+            Idle => encode::<StateAiTaskIdle>(self),
+            Patrol => encode::<StateAiTaskPatrol>(self),
+            MoveToCharacter => encode::<StateAiTaskMoveToCharacter>(self),
+            General => encode::<StateAiTaskGeneral>(self),
+        }
+    }
+
+    fn decode_element(typ: u16, data: &[u8]) -> XResult<Box<dyn StateAiTaskAny>> {
+        fn decode<T>(data: &[u8]) -> XResult<Box<dyn StateAiTaskAny>>
+        where
+            T: StateAiTaskAny + Archive,
+            T::Archived: for<'a> CheckBytes<HighValidator<'a, RkyvError>> + Deserialize<T, HighDeserializer<RkyvError>>,
+        {
+            Ok(Box::new(from_bytes::<T>(data)?))
+        }
+
+        use AiTaskType::*;
+        let typ = AiTaskType::try_from(typ)?;
+        let state = match typ {
+            Idle => decode::<StateAiTaskIdle>(data)?,
+            Patrol => decode::<StateAiTaskPatrol>(data)?,
+            MoveToCharacter => decode::<StateAiTaskMoveToCharacter>(data)?,
+            General => decode::<StateAiTaskGeneral>(data)?,
+        };
+        if state.typ() != typ {
+            return xresf!(Rkyv; "typ={:?}, state.typ={:?}", typ, state.typ());
+        }
+        Ok(state)
+    }
+}
+
+/// CustomEvent archives a process local symbol id, it's sent with the symbol string.
+struct CustomEvents;
+
+impl FieldCodec<Vec<CustomEvent>> for CustomEvents {
+    fn diff(curr: &Vec<CustomEvent>, base: Option<&Vec<CustomEvent>>) -> XResult<Option<Vec<u8>>> {
+        if base == Some(curr) {
+            return Ok(None);
+        }
+        let events: Vec<(TmplID, Symbol)> = curr.iter().map(|e| e.to_tuple()).collect();
+        Ok(Some(to_bytes(&events)?.into_vec()))
+    }
+
+    fn patch(data: Option<&[u8]>, base: Option<&Vec<CustomEvent>>) -> XResult<Vec<CustomEvent>> {
+        match (data, base) {
+            (Some(data), _) => {
+                let events = from_bytes::<Vec<(TmplID, Symbol)>>(data)?;
+                Ok(events.into_iter().map(CustomEvent::from).collect())
+            }
+            (None, Some(base)) => Ok(base.clone()),
+            (None, None) => xres!(Rkyv; "missing field"),
+        }
+    }
+}
+
+delta_fields!(StateGameInit { _base: Whole });
+
+delta_fields!(StateGameUpdate {
+    _base: Whole,
+    frame: Whole,
+    identity: Whole,
+    rand: Whole,
+    hit_events: Whole,
+});
+
+delta_fields!(StateZoneInit {
+    _base: Whole,
+    view_file: Whole,
+});
+
+delta_fields!(StateZoneUpdate {
+    _base: Whole,
+    overlaps: Whole,
+    disabled_triggers: Whole,
+    events: Whole,
+});
+
+delta_fields!(StateCharacterInit {
+    _base: Whole,
+    is_player: Whole,
+    skeleton_files: Whole,
+    animation_metas: Whole,
+    view_model: Whole,
+    init_position: Whole,
+    init_direction: Whole,
+});
+
+delta_fields!(StateCharacterUpdate {
+    _base: Whole,
+    control: Nested,
+    physics: Nested,
+    value: Nested,
+    buffs: Whole,
+    actions: Elements,
+    ai_tasks: Elements,
+    custom_events: CustomEvents,
+    shape_keys: Whole,
+});
+
+delta_fields!(StateCharaControl {
+    input_cursor_id: Whole,
+    derive_keeping: Whole,
+    action_changed: Whole,
+    animation_changed: Whole,
+    current_routine: Whole,
+    current_routine_exec: Whole,
+    target_chara: Whole,
+    aggro_last_time: Whole,
+    style_index: Whole,
+    additive_animations: Whole,
+});
+
+delta_fields!(StateCharaPhysics {
+    velocity: Whole,
+    position: Whole,
+    direction: Whole,
+    hit_boxes: Whole,
+    box_pairs: Whole,
+    group_pairs: Whole,
+});
+
+delta_fields!(StateCharaValue {
+    time_speed: Whole,
+    hit_lag_time: Whole,
+    health: Whole,
+    posture: Whole,
+    fire_buildup: Whole,
+    ice_buildup: Whole,
+    thunder_buildup: Whole,
+    burn_time: Whole,
+    freeze_time: Whole,
+    shock_time: Whole,
+    shock_pending: Whole,
+});
+
+delta_fields!(StateHitObjectInit {
+    _base: Whole,
+    tmpl_id: Whole,
+    shooter_id: Whole,
+    spawn_frame: Whole,
+});
+
+delta_fields!(StateHitObjectUpdate {
+    _base: Whole,
+    position: Whole,
+    velocity: Whole,
+    hit_times: Whole,
+    death_frame: Whole,
+    chara_pairs: Whole,
+});
+
+#[inline]
+fn to_bytes<T>(value: &T) -> XResult<AlignedVec>
+where
+    T: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, RkyvError>>,
+{
+    rkyv::to_bytes::<RkyvError>(value).map_err(|_| xerr!(Rkyv))
+}
+
+// The bytes are untrusted, they're validated by bytecheck before being deserialized.
+#[inline]
+fn from_bytes<T>(bytes: &[u8]) -> XResult<T>
+where
+    T: Archive,
+    T::Archived: for<'a> CheckBytes<HighValidator<'a, RkyvError>> + Deserialize<T, HighDeserializer<RkyvError>>,
+{
+    if bytes.is_empty() {
+        return xres!(Rkyv; "empty bytes");
+    }
+    let mut buf = AlignedVec::<16>::with_capacity(bytes.len());
+    buf.extend_from_slice(bytes);
+    rkyv::from_bytes::<T, RkyvError>(&buf).map_err(|_| xerr!(Rkyv))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::ShapeKeyValue;
+    use crate::logic::action::{ActionIdleMode, StateActionBase};
+    use crate::logic::base::{LogicType, StateBase};
+    use crate::logic::character::StateCharaBuff;
+    use crate::logic::system::{StateIdentity, StateRandom};
+    use crate::utils::{TimeRange, id, sb};
+
+    fn new_state_set(frame: u32, health: f32) -> StateSet {
+        let mut state_set = StateSet::new(frame);
+        state_set.updates.push(Box::new(StateGameUpdate {
+            _base: StateBase::new(NumID(1), StateType::GameUpdate, LogicType::Game),
+            frame,
+            identity: StateIdentity::default(),
+            rand: StateRandom::default(),
+            hit_events: Vec::new(),
+        }));
+        state_set.chara_updates.push(Box::new(StateCharacterUpdate {
+            _base: StateBase::new(NumID(100), StateType::CharacterUpdate, LogicType::Character),
+            control: StateCharaControl::default(),
+            physics: StateCharaPhysics::default(),
+            value: StateCharaValue {
+                time_speed: 1.0,
+                hit_lag_time: TimeRange::EMPTY,
+                health,
+                posture: 100.0,
+                ..Default::default()
+            },
+            buffs: Vec::new(),
+            actions: Vec::new(),
+            ai_tasks: Vec::new(),
+            custom_events: Vec::new(),
+            shape_keys: vec![ShapeKeyValue {
+                name: sb!("Idle_Vert"),
+                value: 0.5,
+                weight: 1.0,
+            }],
+        }));
+        state_set
+    }
+
+    #[test]
+    fn test_state_set_codec() {
+        assert_eq!(StateSetCodec::with_tick_rate(60).keyframe_interval(), 120);
+        let codec = StateSetCodec::new(60);
+        let ss1 = new_state_set(1, 500.0);
+        let ss2 = new_state_set(2, 480.0);
+
+        let key = codec.encode(&ss1, None).unwrap();
+        let ss1_decoded = codec.decode(&key, None).unwrap();
+        assert_eq!(ss1_decoded.frame, 1);
+        assert_eq!(ss1_decoded.chara_updates, ss1.chara_updates);
+        assert_eq!(ss1_decoded.find_as::<StateGameUpdate>(NumID(1)).unwrap().frame, 1);
+
+        let delta = codec.encode_delta(&ss2, Some(&ss1)).unwrap();
+        assert_eq!(delta.base_frame, Some(1));
+        assert_eq!(delta.changed_count(), 2);
+        let game = &delta.updates[0];
+        assert_eq!(game.fields.iter().map(|f| f.index).collect::<Vec<_>>(), vec![1]);
+        let chara = &delta.chara_updates[0];
+        assert_eq!(chara.fields.iter().map(|f| f.index).collect::<Vec<_>>(), vec![3]);
+        let value_fields = from_bytes::<Vec<DeltaField>>(&chara.fields[0].data).unwrap();
+        assert_eq!(value_fields.iter().map(|f| f.index).collect::<Vec<_>>(), vec![2]);
+
+        let data = codec.encode(&ss2, Some(&ss1)).unwrap();
+        let ss2_decoded = codec.decode(&data, Some(&ss1_decoded)).unwrap();
+        assert_eq!(ss2_decoded.chara_updates, ss2.chara_updates);
+        assert_eq!(ss2_decoded.find_as::<StateGameUpdate>(NumID(1)).unwrap().frame, 2);
+
+        // Wrong base
+        assert!(codec.decode(&data, None).is_err());
+        assert!(codec.decode(&data, Some(&ss2)).is_err());
+
+        // Keyframe ignores the base
+        let ss60 = new_state_set(60, 400.0);
+        let delta = codec.encode_delta(&ss60, Some(&ss2)).unwrap();
+        assert_eq!(delta.base_frame, None);
+        assert_eq!(delta.chara_updates[0].fields.len(), 9);
+    }
+
+    #[test]
+    fn test_state_set_codec_size() {
+        let codec = StateSetCodec::new(60);
+        let ss1 = new_state_set(1, 500.0);
+        let ss2 = new_state_set(2, 480.0);
+
+        let key = codec.encode(&ss2, None).unwrap();
+        let delta = codec.encode(&ss2, Some(&ss1)).unwrap();
+        assert!(delta.len() * 3 < key.len(), "delta={}, key={}", delta.len(), key.len());
+
+        let unchanged = codec.encode(&new_state_set(2, 500.0), Some(&ss1)).unwrap();
+        assert!(unchanged.len() < delta.len(), "unchanged={}, delta={}", unchanged.len(), delta.len());
+    }
+
+    #[test]
+    fn test_state_set_codec_shifted_fields() {
+        let codec = StateSetCodec::new(60);
+        let ss1 = new_state_set(1, 500.0);
+        let mut ss2 = new_state_set(2, 500.0);
+        ss2.chara_updates[0].buffs.push(StateCharaBuff {
+            tmpl_id: id!("Buff.Instance.AttackUp"),
+            src_chara_id: NumID(100),
+            stacks: 1,
+            start_time: 0.0,
+            end_time: 5.0,
+            next_tick_time: 1.0,
+        });
+
+        // A longer buff list moves the archived bytes behind it, but not the other fields
+        let delta = codec.encode_delta(&ss2, Some(&ss1)).unwrap();
+        let chara = &delta.chara_updates[0];
+        assert_eq!(chara.fields.iter().map(|f| f.index).collect::<Vec<_>>(), vec![4]);
+
+        let ss2_decoded = codec.decode_delta(&delta, Some(&ss1)).unwrap();
+        assert_eq!(ss2_decoded.chara_updates, ss2.chara_updates);
+    }
+
+    #[test]
+    fn test_state_set_codec_elements() {
+        let codec = StateSetCodec::new(60);
+        let mut ss1 = new_state_set(1, 500.0);
+        ss1.chara_updates[0]
+            .actions
+            .push(Box::new(StateActionEmpty::default()));
+        let mut ss2 = new_state_set(2, 500.0);
+        ss2.chara_updates[0]
+            .actions
+            .push(Box::new(StateActionEmpty::default()));
+        let mut idle = Box::new(StateActionIdle {
+            _base: StateActionBase::new(ActionType::Idle),
+            mode: ActionIdleMode::Idle,
+            idle_time: 1.0,
+            ready_time: 0.0,
+            auto_idle_time: 0.0,
+            switch_time: 0.0,
+        });
+        idle.id = 7;
+        idle.tmpl_id = id!("Action.Idle");
+        ss2.chara_updates[0].actions.push(idle);
+        ss2.chara_updates[0].custom_events.push(CustomEvent::new(id!("Action.Idle"), sb!("Event")));
+
+        let data = codec.encode(&ss2, Some(&ss1)).unwrap();
+        let ss2_decoded = codec.decode(&data, Some(&ss1)).unwrap();
+        assert_eq!(ss2_decoded.chara_updates, ss2.chara_updates);
+
+        let delta = codec.encode_delta(&ss2, Some(&ss1)).unwrap();
+        let actions = &delta.chara_updates[0].fields[0];
+        assert_eq!(actions.index, 5);
+        let elements = from_bytes::<Vec<DeltaElement>>(&actions.data).unwrap();
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].data, None);
+        assert_eq!(elements[1].typ, u16::from(ActionType::Idle));
+        assert!(elements[1].data.is_some());
+    }
+
+    #[test]
+    fn test_state_set_codec_invalid() {
+        let codec = StateSetCodec::new(60);
+        let ss1 = new_state_set(1, 500.0);
+        let ss2 = new_state_set(2, 480.0);
+        let data = codec.encode(&ss2, Some(&ss1)).unwrap();
+
+        assert!(codec.decode(&[], None).is_err());
+        assert!(codec.decode(&data[..data.len() / 2], Some(&ss1)).is_err());
+        let mut corrupted = data.clone();
+        corrupted.iter_mut().for_each(|b| *b = !*b);
+        assert!(codec.decode(&corrupted, Some(&ss1)).is_err());
+
+        let mut delta = codec.encode_delta(&ss2, Some(&ss1)).unwrap();
+        delta.chara_updates[0].fields[0].data = vec![1, 2, 3];
+        assert!(codec.decode_delta(&delta, Some(&ss1)).is_err());
+
+        let mut delta = codec.encode_delta(&ss2, Some(&ss1)).unwrap();
+        delta.chara_updates[0].fields.push(DeltaField { index: 9, data: vec![0; 8] });
+        assert!(codec.decode_delta(&delta, Some(&ss1)).is_err());
+
+        let mut delta = codec.encode_delta(&ss2, Some(&ss1)).unwrap();
+        delta.chara_updates[0].typ = StateType::GameUpdate;
+        assert!(codec.decode_delta(&delta, Some(&ss1)).is_err());
+
+        // Missing fields without a base
+        let delta = codec.encode_delta(&ss2, Some(&ss1)).unwrap();
+        let delta = DeltaStateSet {
+            base_frame: None,
+            ..delta
+        };
+        assert!(codec.decode_delta(&delta, None).is_err());
+    }
+}
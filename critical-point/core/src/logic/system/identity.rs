@@ -1,6 +1,5 @@
 use std::collections::VecDeque;
 
-use crate::consts::FPS_USIZE;
 use crate::utils::{NumID, XResult, xres};
 
 #[repr(C)]
@@ -36,9 +35,9 @@ pub(crate) struct SystemIdentity {
 }
 
 impl SystemIdentity {
-    pub(crate) fn new() -> SystemIdentity {
+    pub(crate) fn new(tick_rate: u32) -> SystemIdentity {
         SystemIdentity {
-            history: VecDeque::with_capacity(2 * FPS_USIZE),
+            history: VecDeque::with_capacity(2 * tick_rate as usize),
             player_id: NumID::MIN_PLAYER,
             auto_gen_id: NumID::MIN_AUTO_GEN,
             action_id: 0,
@@ -109,6 +108,16 @@ impl SystemIdentity {
         }
     }
 
+    /// Drops the history and starts from the given state, used by late-join.
+    pub(crate) fn reset(&mut self, frame: u32, state: &StateIdentity) {
+        self.history.clear();
+        self.player_id = state.player_id;
+        self.auto_gen_id = state.auto_gen_id;
+        self.action_id = state.action_id;
+        self.ai_task_id = state.ai_task_id;
+        self.history.push_back((frame, *state));
+    }
+
     pub(crate) fn discard(&mut self, frame: u32) {
         let first_frame = self.history.front().map_or(0, |(fr, _)| *fr);
         debug_assert!(first_frame <= frame);
@@ -139,10 +148,11 @@ impl SystemIdentity {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consts::FPS_U32;
 
     #[test]
     fn test_system_generation_state() {
-        let mut sys_gen = SystemIdentity::new();
+        let mut sys_gen = SystemIdentity::new(FPS_U32);
 
         sys_gen.update(1); // [1]
         let id1_action = sys_gen.gen_action_id();
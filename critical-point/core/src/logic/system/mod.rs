@@ -1,7 +1,9 @@
+mod codec;
 mod identity;
 mod random;
 mod state;
 
+pub use codec::*;
 pub use identity::*;
 pub use random::*;
 pub use state::*;
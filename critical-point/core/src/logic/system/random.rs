@@ -1,7 +1,19 @@
+use enum_iterator::{Sequence, all};
 use oorandom::{Rand32, Rand64};
 use std::collections::VecDeque;
 
-use crate::consts::FPS_USIZE;
+/// Named random streams, each stream is seeded independently.
+/// A new random consumer only shifts the rolls of its own stream.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Sequence)]
+pub enum RandomStream {
+    Combat,
+    Ai,
+    Loot,
+    Cosmetic,
+}
+
+pub const RANDOM_STREAM_COUNT: usize = <RandomStream as Sequence>::CARDINALITY;
 
 #[repr(C)]
 #[derive(
@@ -19,22 +31,43 @@ use crate::consts::FPS_USIZE;
     rkyv::Deserialize,
 )]
 #[rkyv(derive(Debug))]
-pub struct StateRandom {
+pub struct StateRandomStream {
     pub state32: (u64, u64),
     pub state64: (u128, u128),
 }
 
+#[repr(C)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct StateRandom {
+    pub streams: [StateRandomStream; RANDOM_STREAM_COUNT],
+}
+
 #[derive(Debug, Clone)]
-pub(crate) struct SystemRandom {
-    history: VecDeque<(u32, StateRandom)>,
+pub(crate) struct RandomGenerator {
     rand32: Rand32,
     rand64: Rand64,
 }
 
-impl SystemRandom {
-    pub(crate) fn new(seed32: u64, seed64: u128) -> SystemRandom {
-        SystemRandom {
-            history: VecDeque::with_capacity(2 * FPS_USIZE),
+impl RandomGenerator {
+    fn new(seed: u64, stream: RandomStream) -> RandomGenerator {
+        let mut sm = seed ^ ((stream as u64 + 1) << 56);
+        let seed32 = splitmix64(&mut sm);
+        let seed64 = ((splitmix64(&mut sm) as u128) << 64) | splitmix64(&mut sm) as u128;
+        RandomGenerator {
             rand32: Rand32::new(seed32),
             rand64: Rand64::new(seed64),
         }
@@ -60,14 +93,55 @@ impl SystemRandom {
         self.rand64.rand_float()
     }
 
-    pub(crate) fn update(&mut self, frame: u32) {
-        let last_frame = self.history.back().map_or(0, |(fr, _)| *fr);
-        debug_assert!(last_frame + 1 == frame || frame == 0);
-
-        self.history.push_back((frame, StateRandom {
+    #[inline]
+    fn state(&self) -> StateRandomStream {
+        StateRandomStream {
             state32: self.rand32.state(),
             state64: self.rand64.state(),
-        }));
+        }
+    }
+
+    #[inline]
+    fn restore(&mut self, state: &StateRandomStream) {
+        self.rand32 = Rand32::from_state(state.state32);
+        self.rand64 = Rand64::from_state(state.state64);
+    }
+}
+
+#[inline]
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SystemRandom {
+    history: VecDeque<(u32, StateRandom)>,
+    streams: [RandomGenerator; RANDOM_STREAM_COUNT],
+}
+
+impl SystemRandom {
+    pub(crate) fn new(seed: u64, tick_rate: u32) -> SystemRandom {
+        let mut streams = all::<RandomStream>().map(|stream| RandomGenerator::new(seed, stream));
+        SystemRandom {
+            history: VecDeque::with_capacity(2 * tick_rate as usize),
+            streams: std::array::from_fn(|_| streams.next().unwrap()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn stream(&mut self, stream: RandomStream) -> &mut RandomGenerator {
+        &mut self.streams[stream as usize]
+    }
+
+    pub(crate) fn update(&mut self, frame: u32) {
+        // The history may be emptied by discard(), when the synced frame catches up the current frame.
+        debug_assert!(self.history.back().is_none_or(|(fr, _)| fr + 1 == frame) || frame == 0);
+
+        self.history.push_back((frame, self.state()));
     }
 
     pub(crate) fn restore(&mut self, frame: u32) {
@@ -82,21 +156,32 @@ impl SystemRandom {
                 self.history.pop_back();
             }
             else {
-                self.rand32 = Rand32::from_state(state.state32);
-                self.rand64 = Rand64::from_state(state.state64);
+                for (generator, state) in self.streams.iter_mut().zip(state.streams.iter()) {
+                    generator.restore(state);
+                }
                 break;
             }
         }
     }
 
+    /// Drops the history and starts from the given state, used by late-join.
+    pub(crate) fn reset(&mut self, frame: u32, state: &StateRandom) {
+        self.history.clear();
+        for (generator, state) in self.streams.iter_mut().zip(state.streams.iter()) {
+            generator.restore(state);
+        }
+        self.history.push_back((frame, *state));
+    }
+
+    /// Drops the states before the given frame (the synced frame), which is never rolled back to.
     pub(crate) fn discard(&mut self, frame: u32) {
         let first_frame = self.history.front().map_or(0, |(fr, _)| *fr);
         debug_assert!(first_frame <= frame);
         let last_frame = self.history.back().map_or(0, |(fr, _)| *fr);
-        debug_assert!(last_frame > frame);
+        debug_assert!(last_frame + 1 >= frame);
 
         while let Some((fr, _)) = self.history.front() {
-            if *fr <= frame {
+            if *fr < frame {
                 self.history.pop_front();
             }
             else {
@@ -108,8 +193,7 @@ impl SystemRandom {
     #[inline]
     pub(crate) fn state(&self) -> StateRandom {
         StateRandom {
-            state32: self.rand32.state(),
-            state64: self.rand64.state(),
+            streams: std::array::from_fn(|idx| self.streams[idx].state()),
         }
     }
 }
@@ -117,54 +201,94 @@ impl SystemRandom {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::mem;
+
+    use crate::consts::FPS_U32;
 
     #[test]
     fn test_system_random_state() {
-        let mut sys_rand = SystemRandom::new(12345, 67890);
+        let mut sys_rand = SystemRandom::new(12345, FPS_U32);
 
         sys_rand.update(1); // [1]
-        let f1_32 = sys_rand.rand_f32();
-        let f1_64 = sys_rand.rand_f64();
+        let f1_32 = sys_rand.stream(RandomStream::Ai).rand_f32();
+        let f1_64 = sys_rand.stream(RandomStream::Ai).rand_f64();
 
         sys_rand.update(2); // [1, 2]
-        let f2_32 = sys_rand.rand_f32();
-        let f2_64 = sys_rand.rand_f64();
+        let f2_32 = sys_rand.stream(RandomStream::Ai).rand_f32();
+        let f2_64 = sys_rand.stream(RandomStream::Ai).rand_f64();
 
         sys_rand.restore(1); // [1]
-        assert_eq!(sys_rand.rand_f32(), f1_32);
-        assert_eq!(sys_rand.rand_f64(), f1_64);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_f32(), f1_32);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_f64(), f1_64);
 
         sys_rand.restore(1); // in => [1]
-        assert_eq!(sys_rand.rand_f32(), f1_32);
-        assert_eq!(sys_rand.rand_f64(), f1_64);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_f32(), f1_32);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_f64(), f1_64);
 
         sys_rand.update(2); // [1, 2]
-        assert_eq!(sys_rand.rand_f32(), f2_32);
-        assert_eq!(sys_rand.rand_f64(), f2_64);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_f32(), f2_32);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_f64(), f2_64);
 
         sys_rand.update(3); // [1, 2, 3]
-        let u3_32 = sys_rand.rand_u32();
-        let u3_64 = sys_rand.rand_u64();
+        let u3_32 = sys_rand.stream(RandomStream::Ai).rand_u32();
+        let u3_64 = sys_rand.stream(RandomStream::Ai).rand_u64();
 
-        sys_rand.discard(2); // [3]
+        sys_rand.discard(3); // [3]
         assert_eq!(sys_rand.history.len(), 1);
 
         sys_rand.restore(3); // [3]
-        assert_eq!(sys_rand.rand_u32(), u3_32);
-        assert_eq!(sys_rand.rand_u64(), u3_64);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_u32(), u3_32);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_u64(), u3_64);
 
         sys_rand.update(4); // [3, 4]
-        let u4_32 = sys_rand.rand_u32();
+        let u4_32 = sys_rand.stream(RandomStream::Ai).rand_u32();
         sys_rand.update(5); // [3, 4, 5]
-        let u5_32 = sys_rand.rand_u32();
+        let u5_32 = sys_rand.stream(RandomStream::Ai).rand_u32();
 
-        sys_rand.discard(3); // [4, 5]
+        sys_rand.discard(4); // [4, 5]
         assert_eq!(sys_rand.history.len(), 2);
 
         sys_rand.restore(5); // [4, 5]
-        assert_eq!(sys_rand.rand_u32(), u5_32);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_u32(), u5_32);
 
         sys_rand.restore(4); // [4]
-        assert_eq!(sys_rand.rand_u32(), u4_32);
+        assert_eq!(sys_rand.stream(RandomStream::Ai).rand_u32(), u4_32);
+    }
+
+    #[test]
+    fn test_system_random_streams() {
+        let mut rand1 = SystemRandom::new(12345, FPS_U32);
+        let mut rand2 = SystemRandom::new(12345, FPS_U32);
+        let mut rand3 = SystemRandom::new(54321, FPS_U32);
+
+        let ai1 = rand1.stream(RandomStream::Ai).rand_u32();
+        assert_eq!(rand2.stream(RandomStream::Ai).rand_u32(), ai1);
+        assert_ne!(rand3.stream(RandomStream::Ai).rand_u32(), ai1);
+        assert_eq!(rand1.state(), rand2.state());
+        assert_ne!(rand1.state(), rand3.state());
+
+        // Consuming one stream doesn't shift the others
+        rand1.stream(RandomStream::Combat).rand_u32();
+        rand1.stream(RandomStream::Loot).rand_f64();
+        rand1.stream(RandomStream::Cosmetic).rand_f32();
+        let ai1 = rand1.stream(RandomStream::Ai).rand_u32();
+        assert_eq!(rand2.stream(RandomStream::Ai).rand_u32(), ai1);
+
+        let state1 = rand1.state();
+        for (idx, stream) in state1.streams.iter().enumerate() {
+            assert!(state1.streams[idx + 1..].iter().all(|other| other != stream));
+        }
+        assert_ne!(state1, rand2.state());
+        rand2.stream(RandomStream::Combat).rand_u32();
+        rand2.stream(RandomStream::Loot).rand_f64();
+        rand2.stream(RandomStream::Cosmetic).rand_f32();
+        assert_eq!(state1, rand2.state());
+    }
+
+    #[test]
+    fn test_state_random_layout() {
+        // Keep in sync with the csharp_hide of StateGameUpdate.rand
+        assert_eq!(mem::size_of::<StateRandom>(), 192);
+        assert_eq!(mem::align_of::<StateRandom>(), 16);
     }
 }
@@ -3,10 +3,14 @@ use std::collections::{VecDeque, vec_deque};
 use std::ops::{Index, RangeBounds};
 use std::sync::Arc;
 
-use crate::consts::FPS_USIZE;
-use crate::logic::base::StateAny;
+use crate::consts::DEFAULT_TICK_RATE;
+use crate::logic::action::StateActionPool;
+use crate::logic::base::{StateAny, StateType};
 use crate::logic::character::StateCharacterUpdate;
-use crate::utils::{Castable, NumID, XResult, xres, xresf};
+use crate::logic::game::StateGameUpdate;
+use crate::logic::hit_object::StateHitObjectUpdate;
+use crate::logic::zone::StateZoneUpdate;
+use crate::utils::{Castable, NumID, XResult, xerr, xres, xresf};
 
 #[repr(C)]
 #[csharp_out(Ref)]
@@ -55,13 +59,140 @@ impl StateSet {
     pub fn find_as<T: StateAny + 'static>(&self, id: NumID) -> XResult<&T> {
         self.find(id)?.cast()
     }
+
+    /// Computes a stable 64-bit checksum of all update states in this frame.
+    /// Init states are skipped, they never change after spawning.
+    pub fn checksum(&self) -> XResult<StateChecksum> {
+        let mut objects = Vec::with_capacity(self.updates.len() + self.chara_updates.len());
+        for state in self.updates.iter() {
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(state).map_err(|_| xerr!(Rkyv))?;
+            objects.push((state.id(), fnv1a64(FNV_OFFSET_BASIS, &bytes)));
+        }
+        for state in self.chara_updates.iter() {
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(state).map_err(|_| xerr!(Rkyv))?;
+            objects.push((state.id, fnv1a64(FNV_OFFSET_BASIS, &bytes)));
+        }
+
+        let mut checksum = fnv1a64(FNV_OFFSET_BASIS, &self.frame.to_le_bytes());
+        for (id, obj_checksum) in objects.iter() {
+            checksum = fnv1a64(checksum, &id.0.to_le_bytes());
+            checksum = fnv1a64(checksum, &obj_checksum.to_le_bytes());
+        }
+        Ok(StateChecksum {
+            frame: self.frame,
+            checksum,
+            objects,
+        })
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+#[inline]
+fn fnv1a64(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// The checksum of a StateSet, used to detect desync between peers (or a live run and a replay).
+/// The per-object checksums are kept in the StateSet order, to locate the first diverging object.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct StateChecksum {
+    pub frame: u32,
+    pub checksum: u64,
+    pub objects: Vec<(NumID, u64)>,
+}
+
+impl StateChecksum {
+    /// Compares with a remote checksum of the same frame.
+    /// Returns a LogicDesync error naming the first diverging object.
+    pub fn verify(&self, remote: &StateChecksum) -> XResult<()> {
+        if self.frame != remote.frame {
+            return xresf!(BadArgument; "frame={}, remote.frame={}", self.frame, remote.frame);
+        }
+        if self.checksum == remote.checksum {
+            return Ok(());
+        }
+
+        let frame = self.frame;
+        for idx in 0..self.objects.len().max(remote.objects.len()) {
+            let desync = match (self.objects.get(idx), remote.objects.get(idx)) {
+                (Some(local_obj), Some(remote_obj)) if local_obj == remote_obj => continue,
+                (Some((id, local)), Some((remote_id, remote))) if id == remote_id => {
+                    xresf!(LogicDesync; "frame={}, id={}, local={:016x}, remote={:016x}", frame, id, local, remote)
+                }
+                (local_obj, remote_obj) => {
+                    let local_id = local_obj.map(|obj| obj.0);
+                    let remote_id = remote_obj.map(|obj| obj.0);
+                    xresf!(LogicDesync; "frame={}, local.id={:?}, remote.id={:?}", frame, local_id, remote_id)
+                }
+            };
+            return desync;
+        }
+        xresf!(LogicDesync; "frame={}, local={:016x}, remote={:016x}", frame, self.checksum, remote.checksum)
+    }
 }
 
+/// Counters of the StateSet pool, to watch for allocation regressions.
+/// In steady state, all the `*_allocs` counters should stop growing.
+/// AI task states are not pooled, an NPC allocates one box per frame while it runs a task.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StatePoolCounters {
+    /// StateSets allocated because the pool was empty.
+    pub state_set_allocs: u64,
+    /// StateSets taken from the pool.
+    pub state_set_reuses: u64,
+    /// Character update states allocated because the pool was empty.
+    pub chara_update_allocs: u64,
+    /// Character update states taken from the pool.
+    pub chara_update_reuses: u64,
+    /// Game, zone and hit object update states allocated because the pool was empty.
+    pub update_allocs: u64,
+    /// Game, zone and hit object update states taken from the pool.
+    pub update_reuses: u64,
+    /// Action states allocated because the pool was empty.
+    pub action_allocs: u64,
+    /// Action states taken from the pool.
+    pub action_reuses: u64,
+    /// Recycled StateSets dropped, because they were still referenced outside for too long.
+    pub recycle_misses: u64,
+}
+
+const MAX_POOLED_CHARA_UPDATES: usize = 256;
+const MAX_POOLED_UPDATES: usize = 256;
+
 #[derive(Debug)]
 pub struct SystemState {
     state_sets: VecDeque<Arc<StateSet>>,
     current_frame: u32,
     synced_frame: u32,
+
+    // StateSets held outside (by the caller or the save) are kept at most this number of frames.
+    max_recycling: usize,
+    max_pooled_state_sets: usize,
+    recycling: VecDeque<Arc<StateSet>>,
+    state_set_pool: Vec<Arc<StateSet>>,
+    chara_update_pool: Vec<Box<StateCharacterUpdate>>,
+    game_update_pool: Vec<Box<StateGameUpdate>>,
+    zone_update_pool: Vec<Box<StateZoneUpdate>>,
+    hit_object_update_pool: Vec<Box<StateHitObjectUpdate>>,
+    action_pool: StateActionPool,
+    counters: StatePoolCounters,
 }
 
 impl Default for SystemState {
@@ -74,10 +205,27 @@ impl Default for SystemState {
 impl SystemState {
     #[inline]
     pub fn new() -> SystemState {
+        SystemState::with_tick_rate(DEFAULT_TICK_RATE)
+    }
+
+    /// The history and the pools are sized to hold 2 seconds of frames.
+    pub fn with_tick_rate(tick_rate: u32) -> SystemState {
+        let frames = 2 * tick_rate as usize;
         SystemState {
-            state_sets: VecDeque::with_capacity(2 * FPS_USIZE),
+            state_sets: VecDeque::with_capacity(frames),
             current_frame: 0,
             synced_frame: 0,
+
+            max_recycling: frames,
+            max_pooled_state_sets: frames,
+            recycling: VecDeque::with_capacity(frames),
+            state_set_pool: Vec::with_capacity(frames),
+            chara_update_pool: Vec::new(),
+            game_update_pool: Vec::with_capacity(frames),
+            zone_update_pool: Vec::with_capacity(frames),
+            hit_object_update_pool: Vec::new(),
+            action_pool: StateActionPool::default(),
+            counters: StatePoolCounters::default(),
         }
     }
 
@@ -104,6 +252,14 @@ impl SystemState {
         Ok(())
     }
 
+    /// Drops all frames and starts from a confirmed keyframe, used by late-join.
+    pub fn reset(&mut self, state_set: Arc<StateSet>) {
+        self.state_sets.clear();
+        self.current_frame = state_set.frame;
+        self.synced_frame = state_set.frame;
+        self.state_sets.push_back(state_set);
+    }
+
     pub fn append(&mut self, state_set: Arc<StateSet>) -> XResult<()> {
         if state_set.frame != self.current_frame + 1 {
             return xresf!(BadArgument; "state_set.frame={}, current_frame={}", state_set.frame, self.current_frame);
@@ -144,7 +300,9 @@ impl SystemState {
         }
         while let Some(state) = self.state_sets.back() {
             if state.frame > frame {
-                self.state_sets.pop_front();
+                if let Some(state_set) = self.state_sets.pop_back() {
+                    self.recycle_one(state_set);
+                }
             }
             else {
                 break;
@@ -154,6 +312,145 @@ impl SystemState {
         Ok(())
     }
 
+    #[inline]
+    pub fn pool_counters(&self) -> StatePoolCounters {
+        let mut counters = self.counters;
+        counters.action_allocs = self.action_pool.allocs;
+        counters.action_reuses = self.action_pool.reuses;
+        counters
+    }
+
+    /// Takes an empty StateSet from the pool, or allocates a new one.
+    /// The returned StateSet is not shared, `Arc::get_mut()` always succeeds on it.
+    pub fn alloc_state_set(&mut self, frame: u32) -> Arc<StateSet> {
+        self.reclaim();
+        while let Some(mut state_set) = self.state_set_pool.pop() {
+            // A pooled StateSet still shared (e.g. by a Weak) is dropped, rather than returned stale.
+            if let Some(state_set_mut) = Arc::get_mut(&mut state_set) {
+                self.counters.state_set_reuses += 1;
+                state_set_mut.frame = frame;
+                return state_set;
+            }
+        }
+        self.counters.state_set_allocs += 1;
+        Arc::new(StateSet::new(frame))
+    }
+
+    /// Takes a character update state from the pool, its vectors are empty but keep their capacity.
+    /// Returns None if the pool is empty.
+    pub(crate) fn alloc_chara_update(&mut self) -> Option<Box<StateCharacterUpdate>> {
+        match self.chara_update_pool.pop() {
+            Some(state) => {
+                self.counters.chara_update_reuses += 1;
+                Some(state)
+            }
+            None => {
+                self.counters.chara_update_allocs += 1;
+                None
+            }
+        }
+    }
+
+    /// Takes a game update state from the pool. Returns None if the pool is empty.
+    pub(crate) fn alloc_game_update(&mut self) -> Option<Box<StateGameUpdate>> {
+        let state = self.game_update_pool.pop();
+        self.count_update(state.is_some());
+        state
+    }
+
+    /// Takes a zone update state from the pool. Returns None if the pool is empty.
+    pub(crate) fn alloc_zone_update(&mut self) -> Option<Box<StateZoneUpdate>> {
+        let state = self.zone_update_pool.pop();
+        self.count_update(state.is_some());
+        state
+    }
+
+    /// Takes a hit object update state from the pool. Returns None if the pool is empty.
+    pub(crate) fn alloc_hit_object_update(&mut self) -> Option<Box<StateHitObjectUpdate>> {
+        let state = self.hit_object_update_pool.pop();
+        self.count_update(state.is_some());
+        state
+    }
+
+    #[inline]
+    fn count_update(&mut self, reused: bool) {
+        if reused {
+            self.counters.update_reuses += 1;
+        }
+        else {
+            self.counters.update_allocs += 1;
+        }
+    }
+
+    /// The recycled action states, used by `LogicActionAny::save_pooled()`.
+    #[inline]
+    pub(crate) fn action_pool(&mut self) -> &mut StateActionPool {
+        &mut self.action_pool
+    }
+
+    /// Gives StateSets back to the pool. A StateSet still referenced outside is kept, and reclaimed once
+    /// the last outside reference is dropped.
+    pub fn recycle<I>(&mut self, state_sets: I)
+    where
+        I: IntoIterator<Item = Arc<StateSet>>,
+    {
+        for state_set in state_sets {
+            self.recycle_one(state_set);
+        }
+        self.reclaim();
+    }
+
+    fn recycle_one(&mut self, state_set: Arc<StateSet>) {
+        if self.recycling.len() >= self.max_recycling {
+            self.recycling.pop_front();
+            self.counters.recycle_misses += 1;
+        }
+        self.recycling.push_back(state_set);
+    }
+
+    fn reclaim(&mut self) {
+        for _ in 0..self.recycling.len() {
+            let Some(mut state_set) = self.recycling.pop_front()
+            else {
+                break;
+            };
+            // Keeps the Arc allocation, a StateSet is reclaimed once no reference is left outside.
+            match Arc::get_mut(&mut state_set) {
+                Some(state_set_mut) => {
+                    self.put_updates(state_set_mut);
+                    if self.state_set_pool.len() < self.max_pooled_state_sets {
+                        self.state_set_pool.push(state_set);
+                    }
+                }
+                None => self.recycling.push_back(state_set),
+            }
+        }
+    }
+
+    fn put_updates(&mut self, state_set: &mut StateSet) {
+        state_set.inits.clear();
+        for state in state_set.updates.drain(..) {
+            match state.typ() {
+                StateType::GameUpdate => put_update(&mut self.game_update_pool, state),
+                StateType::ZoneUpdate => put_update(&mut self.zone_update_pool, state),
+                StateType::HitObjectUpdate => put_update(&mut self.hit_object_update_pool, state),
+                _ => {}
+            }
+        }
+        for mut state in state_set.chara_updates.drain(..) {
+            for action in state.actions.drain(..) {
+                self.action_pool.put(action);
+            }
+            if self.chara_update_pool.len() < MAX_POOLED_CHARA_UPDATES {
+                state.buffs.clear();
+                state.ai_tasks.clear();
+                state.custom_events.clear();
+                state.shape_keys.clear();
+                self.chara_update_pool.push(state);
+            }
+        }
+    }
+
     #[inline]
     pub fn get(&self, frame: u32) -> Option<&Arc<StateSet>> {
         if frame < self.synced_frame || frame > self.current_frame {
@@ -190,6 +487,14 @@ impl SystemState {
     }
 }
 
+fn put_update<T: StateAny>(pool: &mut Vec<Box<T>>, state: Box<dyn StateAny>) {
+    if pool.len() < MAX_POOLED_UPDATES
+        && let Ok(state) = state.cast::<T>()
+    {
+        pool.push(state);
+    }
+}
+
 impl Index<u32> for SystemState {
     type Output = Arc<StateSet>;
 
@@ -202,6 +507,43 @@ impl Index<u32> for SystemState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logic::base::{LogicType, StateBase, StateType};
+    use crate::logic::game::StateGameUpdate;
+    use crate::logic::system::{StateIdentity, StateRandom};
+    use crate::utils::XError;
+
+    fn new_state_set(frame: u32, rand: StateRandom) -> StateSet {
+        let mut state_set = StateSet::new(frame);
+        state_set.updates.push(Box::new(StateGameUpdate {
+            _base: StateBase::new(NumID(1), StateType::GameUpdate, LogicType::Game),
+            frame,
+            identity: StateIdentity::default(),
+            rand,
+            hit_events: Vec::new(),
+        }));
+        state_set
+    }
+
+    #[test]
+    fn test_state_checksum() {
+        let cs1 = new_state_set(10, StateRandom::default()).checksum().unwrap();
+        let cs2 = new_state_set(10, StateRandom::default()).checksum().unwrap();
+        assert_eq!(cs1, cs2);
+        assert_eq!(cs1.objects.len(), 1);
+        assert_eq!(cs1.objects[0].0, NumID(1));
+        cs1.verify(&cs2).unwrap();
+
+        let cs3 = new_state_set(11, StateRandom::default()).checksum().unwrap();
+        assert!(matches!(cs1.verify(&cs3), Err(XError::BadArgument(_))));
+
+        let mut rand = StateRandom::default();
+        rand.streams[0].state32 = (1, 0);
+        let cs4 = new_state_set(10, rand).checksum().unwrap();
+        assert_ne!(cs1.checksum, cs4.checksum);
+        let err = cs1.verify(&cs4).unwrap_err();
+        assert!(matches!(err, XError::LogicDesync(_)));
+        assert!(err.msg().contains("id=1"));
+    }
 
     #[test]
     fn test_state_manager() {
@@ -262,5 +604,104 @@ mod tests {
         assert_eq!(ss.range(3..=4).unwrap().count(), 2);
 
         assert!(ss.confirm(5).is_err());
+
+        ss.reset(Arc::new(StateSet::new(10)));
+        assert_eq!(ss.current_frame(), 10);
+        assert_eq!(ss.synced_frame(), 10);
+        assert_eq!(ss.state_sets.len(), 1);
+        assert_eq!(ss[10].frame, 10);
+        ss.append(Arc::new(StateSet::new(11))).unwrap();
+        assert_eq!(ss.range(10..=11).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_state_pool() {
+        let mut ss = SystemState::new();
+        ss.init(Arc::new(StateSet::new(0))).unwrap();
+
+        let mut state_set = ss.alloc_state_set(1);
+        assert_eq!(state_set.frame, 1);
+        Arc::get_mut(&mut state_set)
+            .unwrap()
+            .updates
+            .push(new_state_set(1, StateRandom::default()).updates.pop().unwrap());
+        ss.append(state_set).unwrap();
+        assert_eq!(ss.pool_counters().state_set_allocs, 1);
+        assert_eq!(ss.pool_counters().state_set_reuses, 0);
+
+        // Still referenced outside, reclaimed later.
+        let outside = ss[1].clone();
+        let confirmed = ss.confirm(1).unwrap();
+        ss.recycle(confirmed);
+        let state_set = ss.alloc_state_set(2);
+        ss.append(state_set).unwrap();
+        assert_eq!(ss.pool_counters().state_set_reuses, 1);
+        let confirmed = ss.confirm(2).unwrap();
+        ss.recycle(confirmed);
+        let state_set = ss.alloc_state_set(3);
+        assert_eq!(ss.pool_counters().state_set_allocs, 2);
+        assert_eq!(ss.pool_counters().state_set_reuses, 1);
+
+        drop(outside);
+        let state_set2 = ss.alloc_state_set(4);
+        assert_eq!(state_set2.frame, 4);
+        assert!(state_set2.updates.is_empty());
+        assert!(state_set2.updates.capacity() >= 1);
+        assert_eq!(ss.pool_counters().state_set_reuses, 2);
+        drop((state_set, state_set2));
+
+        assert!(ss.alloc_chara_update().is_none());
+        assert_eq!(ss.pool_counters().chara_update_allocs, 1);
+
+        // The game update state of frame 1 is pooled too.
+        let game_update = ss.alloc_game_update().unwrap();
+        assert_eq!(game_update.frame, 1);
+        assert!(ss.alloc_game_update().is_none());
+        assert!(ss.alloc_zone_update().is_none());
+        assert_eq!(ss.pool_counters().update_reuses, 1);
+        assert_eq!(ss.pool_counters().update_allocs, 2);
+
+        // Rolled back frames are recycled too.
+        ss.append(Arc::new(StateSet::new(3))).unwrap();
+        ss.append(Arc::new(StateSet::new(4))).unwrap();
+        ss.restore(2).unwrap();
+        assert_eq!(ss.current_frame(), 2);
+        assert_eq!(ss.state_sets.len(), 1);
+        assert_eq!(ss[2].frame, 2);
+        let reuses = ss.pool_counters().state_set_reuses;
+        ss.alloc_state_set(3);
+        ss.alloc_state_set(3);
+        assert_eq!(ss.pool_counters().state_set_reuses, reuses + 2);
+        assert_eq!(ss.pool_counters().recycle_misses, 0);
+    }
+
+    #[test]
+    fn test_state_pool_shared() {
+        let mut ss = SystemState::new();
+        ss.init(Arc::new(StateSet::new(0))).unwrap();
+
+        let pooled = Arc::new(StateSet::new(1));
+        let weak = Arc::downgrade(&pooled);
+        ss.state_set_pool.push(pooled);
+
+        // The shared StateSet is not reused, a fresh one is allocated.
+        let mut state_set = ss.alloc_state_set(2);
+        assert_eq!(state_set.frame, 2);
+        assert!(Arc::get_mut(&mut state_set).is_some());
+        assert!(ss.state_set_pool.is_empty());
+        assert!(weak.upgrade().is_none());
+        assert_eq!(ss.pool_counters().state_set_allocs, 1);
+        assert_eq!(ss.pool_counters().state_set_reuses, 0);
+
+        // An unshared one behind it is still reused.
+        let pooled = Arc::new(StateSet::new(1));
+        let shared = Arc::new(StateSet::new(1));
+        ss.state_set_pool.push(pooled);
+        ss.state_set_pool.push(shared.clone());
+        let state_set = ss.alloc_state_set(3);
+        assert_eq!(state_set.frame, 3);
+        assert!(!Arc::ptr_eq(&state_set, &shared));
+        assert_eq!(ss.pool_counters().state_set_allocs, 1);
+        assert_eq!(ss.pool_counters().state_set_reuses, 1);
     }
 }
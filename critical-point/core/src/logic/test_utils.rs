@@ -1,6 +1,6 @@
 use jolt_physics_rs::PhysicsSystem;
 
-use crate::consts::TEST_ASSET_PATH;
+use crate::consts::{DEFAULT_TICK_RATE, TEST_ASSET_PATH};
 use crate::logic::game::{ContextUpdate, ContextUpdateEx, GameTime, LogicSystems};
 use crate::logic::physics::{PhyBroadPhaseLayerInterface, PhyObjectLayerPairFilter, PhyObjectVsBroadPhaseLayerFilter};
 use crate::logic::zone::LogicZone;
@@ -19,7 +19,7 @@ impl TestEnv {
 
     pub fn new() -> XResult<TestEnv> {
         let db = TmplDatabase::new(10240, 150)?;
-        let mut systems = LogicSystems::new(db, TEST_ASSET_PATH, None)?;
+        let mut systems = LogicSystems::new(db, TEST_ASSET_PATH, None, 0, DEFAULT_TICK_RATE)?;
         let time = GameTime::new(Self::FRAME, 0);
         let mut ctx = ContextUpdate::new(&mut systems, &time);
         let (zone, _) = LogicZone::new(&mut ctx, &ParamZone { zone: id!("Zone.Demo") })?;
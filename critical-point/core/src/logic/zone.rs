@@ -1,17 +1,20 @@
-use critical_point_macros::csharp_out;
+use critical_point_macros::{csharp_enum, csharp_out};
+use enum_iterator::Sequence;
 use glam::{Vec3, Vec3A};
-use jolt_physics_rs::{BodyCreationSettings, BodyID};
+use jolt_physics_rs::{BodyCreationSettings, BodyID, MotionType};
 use recastnavigation_rs::detour::{DtNavMesh, DtNavMeshQuery, DtPolyRef, DtQueryFilter};
 use std::cell::RefCell;
+use std::mem;
 use std::sync::Arc;
 
 use crate::instance::InstZone;
 use crate::logic::base::{LogicAny, LogicType, StateAny, StateBase, StateType, impl_state};
+use crate::logic::character::LogicCharacter;
 use crate::logic::game::{ContextRestore, ContextUpdate};
 use crate::logic::physics::{PhyBodyUserData, phy_layer};
 use crate::parameter::ParamZone;
 use crate::template::TmplZone;
-use crate::utils::{NumID, XResult, extend};
+use crate::utils::{HistoryVec, NumID, Symbol, XResult, extend, rkyv_self};
 
 const MAX_POLYS: usize = 256;
 const MAX_NODES: usize = 2048;
@@ -46,18 +49,86 @@ extend!(StateZoneInit, StateBase);
 
 impl_state!(StateZoneInit, Zone, ZoneInit, "ZoneInit");
 
+#[csharp_enum]
+#[repr(u8)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
+pub enum ZoneEventType {
+    #[default]
+    Enter,
+    Leave,
+    Interact,
+}
+
+rkyv_self!(ZoneEventType: Sequence);
+
+#[repr(C)]
+#[csharp_out(Value)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct ZoneEvent {
+    pub typ: ZoneEventType,
+    pub trigger_index: u16,
+    pub trigger: Symbol,
+    pub chara_id: NumID,
+}
+
+#[repr(C)]
+#[csharp_out(Value)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct StateZoneOverlap {
+    pub trigger_index: u16,
+    pub chara_id: NumID,
+}
+
 #[repr(C)]
 #[csharp_out(Ref)]
 #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(derive(Debug))]
 pub struct StateZoneUpdate {
     pub _base: StateBase,
+    pub overlaps: Vec<StateZoneOverlap>,
+    pub disabled_triggers: Vec<u16>,
+    pub events: Vec<ZoneEvent>,
 }
 
 extend!(StateZoneUpdate, StateBase);
 
 impl_state!(StateZoneUpdate, Zone, ZoneUpdate, "ZoneUpdate");
 
+#[derive(Debug)]
+struct ZoneTrigger {
+    name: Symbol,
+    body_id: BodyID,
+    interactable: bool,
+    once: bool,
+}
+
 #[derive(Debug)]
 pub struct LogicZone {
     id: NumID,
@@ -65,6 +136,12 @@ pub struct LogicZone {
     phy_bodies: Vec<BodyID>,
     nav_mesh: DtNavMesh,
     cache: RefCell<NavMeshCache>,
+
+    triggers: Vec<ZoneTrigger>,
+    overlaps: Vec<StateZoneOverlap>,
+    new_overlaps: Vec<StateZoneOverlap>,
+    disabled_triggers: Vec<u16>,
+    events: Vec<ZoneEvent>,
 }
 
 impl LogicAny for LogicZone {
@@ -113,6 +190,28 @@ impl LogicZone {
             phy_bodies.push(body_id);
         }
 
+        let mut triggers = Vec::with_capacity(zone_phy.triggers.len());
+        for (idx, asset_trigger) in zone_phy.triggers.iter().enumerate() {
+            let mut settings = BodyCreationSettings::new_sensor(
+                asset_trigger.shape.clone(),
+                phy_layer!(StaticTrigger, All),
+                MotionType::Static,
+                asset_trigger.position,
+                asset_trigger.rotation,
+            );
+            // Static sensor needs to detect the kinematic character bodies.
+            settings.collide_kinematic_vs_non_dynamic = true;
+            settings.user_data = PhyBodyUserData::new_trigger(idx as u16).into();
+
+            let body_id = bofy_itf.create_add_body(&settings, false)?;
+            triggers.push(ZoneTrigger {
+                name: asset_trigger.name,
+                body_id,
+                interactable: asset_trigger.interactable,
+                once: asset_trigger.once,
+            });
+        }
+
         let nav_mesh = asset.load_nav_mesh(inst_zone.files)?;
         let cache = RefCell::new(NavMeshCache::new(&nav_mesh)?);
 
@@ -122,6 +221,12 @@ impl LogicZone {
             phy_bodies,
             nav_mesh,
             cache,
+
+            triggers,
+            overlaps: Vec::with_capacity(8),
+            new_overlaps: Vec::with_capacity(8),
+            disabled_triggers: Vec::new(),
+            events: Vec::with_capacity(8),
         });
         let state = Arc::new(StateZoneInit {
             _base: StateBase::new(zone.id, StateType::ZoneInit, LogicType::Zone),
@@ -135,17 +240,41 @@ impl LogicZone {
         for body_id in self.phy_bodies.drain(..) {
             bofy_itf.remove_body(body_id);
         }
+        for trigger in self.triggers.drain(..) {
+            bofy_itf.remove_body(trigger.body_id);
+        }
         Ok(())
     }
 
     pub fn state(&mut self) -> Box<StateZoneUpdate> {
         Box::new(StateZoneUpdate {
             _base: StateBase::new(self.id, StateType::ZoneUpdate, LogicType::Zone),
+            overlaps: self.overlaps.clone(),
+            disabled_triggers: self.disabled_triggers.clone(),
+            events: mem::take(&mut self.events),
         })
     }
 
+    /// Same as `state()`, but fills a pooled state instead of allocating a new one.
+    pub(crate) fn state_into(&mut self, mut state: Box<StateZoneUpdate>) -> Box<StateZoneUpdate> {
+        state._base = StateBase::new(self.id, StateType::ZoneUpdate, LogicType::Zone);
+        state.overlaps.clear();
+        state.overlaps.extend_from_slice(&self.overlaps);
+        state.disabled_triggers.clear();
+        state.disabled_triggers.extend_from_slice(&self.disabled_triggers);
+        state.events.clear();
+        mem::swap(&mut state.events, &mut self.events);
+        state
+    }
+
     pub fn restore(&mut self, ctx: &ContextRestore) -> XResult<()> {
-        debug_assert!(ctx.find(self.id).is_ok());
+        let state = ctx.find_as::<StateZoneUpdate>(self.id)?;
+        self.overlaps.clear();
+        self.overlaps.extend_from_slice(&state.overlaps);
+        self.disabled_triggers.clear();
+        self.disabled_triggers.extend_from_slice(&state.disabled_triggers);
+        self.new_overlaps.clear();
+        self.events.clear();
         Ok(())
     }
 
@@ -153,6 +282,79 @@ impl LogicZone {
         Ok(())
     }
 
+    /// Records a trigger/character overlap reported by the physics step.
+    /// The overlaps are resolved into zone events in `update_triggers()`.
+    pub(crate) fn on_trigger_character(&mut self, trigger_index: u16, chara_id: NumID) {
+        let overlap = StateZoneOverlap { trigger_index, chara_id };
+        if !self.new_overlaps.contains(&overlap) {
+            self.new_overlaps.push(overlap);
+        }
+    }
+
+    /// Compares the overlaps of current frame with the previous frame, and generates zone events.
+    /// Must be called after characters updated, the Interact key is read from character control.
+    pub(crate) fn update_triggers(&mut self, characters: &HistoryVec<Box<LogicCharacter>>) -> XResult<()> {
+        // Contacts are reported in physics order, sort them to keep the events order stable.
+        self.new_overlaps.sort_unstable();
+        self.new_overlaps.retain(|ov| {
+            !self.disabled_triggers.contains(&ov.trigger_index)
+                && characters.iter().any(|c| c.id() == ov.chara_id && c.is_alive())
+        });
+
+        for ov in self.overlaps.iter() {
+            if !self.new_overlaps.contains(ov) {
+                self.events.push(self.new_event(ZoneEventType::Leave, ov));
+            }
+        }
+
+        for ov in self.new_overlaps.iter() {
+            if self.disabled_triggers.contains(&ov.trigger_index) {
+                continue;
+            }
+            let trigger = &self.triggers[ov.trigger_index as usize];
+
+            if !self.overlaps.contains(ov) {
+                self.events.push(self.new_event(ZoneEventType::Enter, ov));
+                if trigger.once && !trigger.interactable {
+                    self.disabled_triggers.push(ov.trigger_index);
+                    continue;
+                }
+            }
+
+            if trigger.interactable {
+                let interact = characters
+                    .iter()
+                    .find(|c| c.id() == ov.chara_id)
+                    .is_some_and(|c| c.control().interact_requested());
+                if interact {
+                    self.events.push(self.new_event(ZoneEventType::Interact, ov));
+                    if trigger.once {
+                        self.disabled_triggers.push(ov.trigger_index);
+                    }
+                }
+            }
+        }
+
+        mem::swap(&mut self.overlaps, &mut self.new_overlaps);
+        self.new_overlaps.clear();
+        Ok(())
+    }
+
+    #[inline]
+    fn new_event(&self, typ: ZoneEventType, overlap: &StateZoneOverlap) -> ZoneEvent {
+        ZoneEvent {
+            typ,
+            trigger_index: overlap.trigger_index,
+            trigger: self.triggers[overlap.trigger_index as usize].name,
+            chara_id: overlap.chara_id,
+        }
+    }
+
+    #[inline]
+    pub fn events(&self) -> &[ZoneEvent] {
+        &self.events
+    }
+
     #[inline]
     pub fn nav_mesh(&self) -> &DtNavMesh {
         &self.nav_mesh
@@ -239,7 +441,8 @@ impl LogicZone {
 mod tests {
     use super::*;
     use crate::logic::test_utils::*;
-    use crate::utils::{Castable, id};
+    use crate::parameter::ParamPlayer;
+    use crate::utils::{Castable, id, sb};
 
     #[test]
     fn test_zone_common() {
@@ -265,4 +468,52 @@ mod tests {
         assert_eq!(state_update.typ(), StateType::ZoneUpdate);
         assert_eq!(state_update.logic_typ(), LogicType::Zone);
     }
+
+    #[test]
+    fn test_zone_triggers() {
+        let mut tenv = TestEnv::new().unwrap();
+        let mut ctx = tenv.context_update_ex();
+        ctx.input.init(1).unwrap();
+        let param_player = ParamPlayer {
+            character: id!("Character.Instance^1"),
+            style: id!("Style.Instance^1A"),
+            level: 4,
+            ..Default::default()
+        };
+        let (player, _) = LogicCharacter::new_player(&mut ctx, &param_player).unwrap();
+        let player_id = player.id();
+        let mut characters = HistoryVec::with_capacity(1);
+        characters.append_new(player);
+
+        let zone = &mut tenv.zone;
+        assert_eq!(zone.triggers.len(), 1);
+        assert_eq!(zone.triggers[0].name, "Checkpoint");
+
+        zone.on_trigger_character(0, player_id);
+        zone.on_trigger_character(0, player_id);
+        zone.on_trigger_character(0, NumID(999));
+        zone.update_triggers(&characters).unwrap();
+        assert_eq!(zone.events(), &[ZoneEvent {
+            typ: ZoneEventType::Enter,
+            trigger_index: 0,
+            trigger: sb!("Checkpoint"),
+            chara_id: player_id,
+        }]);
+        let state_update = zone.state();
+        assert_eq!(state_update.overlaps, vec![StateZoneOverlap {
+            trigger_index: 0,
+            chara_id: player_id,
+        }]);
+        assert_eq!(state_update.events.len(), 1);
+        assert!(zone.events().is_empty());
+
+        zone.on_trigger_character(0, player_id);
+        zone.update_triggers(&characters).unwrap();
+        assert!(zone.events().is_empty());
+
+        zone.update_triggers(&characters).unwrap();
+        assert_eq!(zone.events()[0].typ, ZoneEventType::Leave);
+        let state_update = zone.state();
+        assert!(state_update.overlaps.is_empty());
+    }
 }
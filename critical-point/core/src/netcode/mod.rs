@@ -0,0 +1,9 @@
+mod packet;
+mod session;
+mod socket;
+mod spectator;
+
+pub use packet::*;
+pub use session::*;
+pub use socket::*;
+pub use spectator::*;
@@ -0,0 +1,58 @@
+use rkyv::util::AlignedVec;
+
+use crate::input::InputPlayerInputs;
+use crate::utils::{NumID, XResult, xerr};
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct NetPacket {
+    pub sender: NumID,
+    /// The last contiguous input frame received from the receiver.
+    pub ack_frame: u32,
+    /// Sender's inputs after the receiver's last ack.
+    /// Unacked frames are sent again in every packet, to recover from packet loss.
+    pub inputs: Vec<InputPlayerInputs>,
+}
+
+impl NetPacket {
+    pub fn encode(&self) -> XResult<Vec<u8>> {
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(self).map_err(|_| xerr!(Rkyv))?;
+        Ok(bytes.into_vec())
+    }
+
+    pub fn decode(data: &[u8]) -> XResult<NetPacket> {
+        let mut buf = AlignedVec::<16>::with_capacity(data.len());
+        buf.extend_from_slice(data);
+        rkyv::from_bytes::<NetPacket, rkyv::rancor::Error>(&buf).map_err(|_| xerr!(Rkyv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{RawInput, RawKey};
+
+    #[test]
+    fn test_net_packet_encode_decode() {
+        let packet = NetPacket {
+            sender: NumID::MIN_PLAYER,
+            ack_frame: 7,
+            inputs: vec![
+                InputPlayerInputs::new(NumID::MIN_PLAYER, 8, vec![RawInput::new_button(RawKey::Attack1, true)]),
+                InputPlayerInputs::new(NumID::MIN_PLAYER, 9, vec![]),
+            ],
+        };
+        let data = packet.encode().unwrap();
+        assert_eq!(NetPacket::decode(&data).unwrap(), packet);
+        assert!(NetPacket::decode(&data[..data.len() / 2]).is_err());
+    }
+}
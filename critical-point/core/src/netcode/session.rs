@@ -0,0 +1,532 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::consts::{DEFAULT_TICK_RATE, MAX_PLAYER, TICK_RATE_30, TICK_RATE_60};
+use crate::engine::LogicEngine;
+use crate::input::InputPlayerInputs;
+use crate::logic::StateSet;
+use crate::netcode::packet::NetPacket;
+use crate::netcode::socket::NetSocket;
+use crate::utils::{NumID, RawInput, XResult, xresf};
+
+#[derive(Debug, Clone)]
+pub struct NetSessionConfig {
+    pub local_player_id: NumID,
+    pub player_count: usize,
+    /// Same as `ParamGame::tick_rate` of the game, 0 for the default tick rate.
+    pub tick_rate: u32,
+    /// Local inputs are scheduled input_delay frames later, to hide the network latency.
+    pub input_delay: u32,
+    /// Max frames the game can run ahead of a remote player with predicted inputs.
+    pub max_prediction: u32,
+    /// Max input frames in a packet, 0 for one second of frames.
+    pub max_redundancy: u32,
+}
+
+impl Default for NetSessionConfig {
+    fn default() -> Self {
+        NetSessionConfig {
+            local_player_id: NumID::MIN_PLAYER,
+            player_count: 2,
+            tick_rate: 0,
+            input_delay: 2,
+            max_prediction: 8,
+            max_redundancy: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct NetPeer {
+    player_id: NumID,
+    ack_frame: u32,                        // The last local input frame received by the peer.
+    received_frame: u32,                   // The last contiguous input frame received from the peer.
+    delivered_frame: u32,                  // The last input frame of the peer delivered to the game.
+    received: VecDeque<InputPlayerInputs>, // Frames in (delivered_frame, received_frame].
+}
+
+/// A peer-to-peer session, exchanges inputs between players and feeds them into the game.
+///
+/// Remote inputs are not required to arrive before the game advances. A remote player without
+/// inputs for a frame keeps the last InputVariables in InputManager (the prediction), and the
+/// game is restored when the real inputs arrive.
+pub struct NetSession<S: NetSocket> {
+    socket: S,
+    config: NetSessionConfig,
+    game_frame: u32,                           // The last frame delivered to the game.
+    local_frame: u32,                          // The last frame of scheduled local inputs.
+    local_inputs: VecDeque<InputPlayerInputs>, // Local inputs not yet acked by all peers or not delivered.
+    peers: Vec<NetPeer>,
+}
+
+impl<S: NetSocket> NetSession<S> {
+    pub fn new(socket: S, mut config: NetSessionConfig) -> XResult<NetSession<S>> {
+        let tick_rate = match config.tick_rate {
+            0 => DEFAULT_TICK_RATE,
+            TICK_RATE_30 | TICK_RATE_60 => config.tick_rate,
+            _ => return xresf!(BadArgument; "tick_rate={}", config.tick_rate),
+        };
+        if config.player_count == 0 || config.player_count > MAX_PLAYER {
+            return xresf!(BadArgument; "player_count={}", config.player_count);
+        }
+        let local_idx = config.local_player_id.0.wrapping_sub(NumID::MIN_PLAYER.0) as usize;
+        if local_idx >= config.player_count {
+            return xresf!(BadArgument; "local_player_id={}", config.local_player_id);
+        }
+        if config.max_redundancy == 0 {
+            config.max_redundancy = tick_rate;
+        }
+
+        let mut peers = Vec::with_capacity(config.player_count - 1);
+        for idx in 0..config.player_count {
+            let player_id = NumID::MIN_PLAYER + idx as u32;
+            if player_id != config.local_player_id {
+                peers.push(NetPeer {
+                    player_id,
+                    ack_frame: 0,
+                    received_frame: 0,
+                    delivered_frame: 0,
+                    received: VecDeque::new(),
+                });
+            }
+        }
+
+        // The first input_delay frames have no local inputs.
+        let mut local_inputs = VecDeque::with_capacity((2 * tick_rate) as usize);
+        for frame in 1..=config.input_delay {
+            local_inputs.push_back(InputPlayerInputs::new(config.local_player_id, frame, Vec::new()));
+        }
+
+        Ok(NetSession {
+            socket,
+            local_frame: config.input_delay,
+            config,
+            game_frame: 0,
+            local_inputs,
+            peers,
+        })
+    }
+
+    #[inline]
+    pub fn local_player_id(&self) -> NumID {
+        self.config.local_player_id
+    }
+
+    #[inline]
+    pub fn game_frame(&self) -> u32 {
+        self.game_frame
+    }
+
+    #[inline]
+    pub fn local_frame(&self) -> u32 {
+        self.local_frame
+    }
+
+    /// The frames the game runs ahead of the slowest remote player.
+    #[inline]
+    pub fn predicted_frames(&self) -> u32 {
+        let delivered_frame = self.peers.iter().map(|p| p.delivered_frame).min();
+        delivered_frame.map_or(0, |frame| self.game_frame.saturating_sub(frame))
+    }
+
+    #[inline]
+    pub fn socket(&self) -> &S {
+        &self.socket
+    }
+
+    #[inline]
+    pub fn socket_mut(&mut self) -> &mut S {
+        &mut self.socket
+    }
+
+    /// Schedules the local inputs of this tick, returns the frame they are scheduled to.
+    pub fn add_local_input(&mut self, inputs: Vec<RawInput>) -> u32 {
+        self.local_frame += 1;
+        self.local_inputs.push_back(InputPlayerInputs::new(
+            self.config.local_player_id,
+            self.local_frame,
+            inputs,
+        ));
+        self.local_frame
+    }
+
+    /// Receives all pending packets from the socket.
+    /// Malformed packets are dropped, a bad datagram from the network must not stop the session.
+    pub fn poll(&mut self) -> XResult<()> {
+        while let Some((sender, data)) = self.socket.recv()? {
+            let packet = match NetPacket::decode(&data) {
+                Ok(packet) => packet,
+                Err(err) => {
+                    log::warn!("Drop packet (sender={}): {}", sender, err);
+                    continue;
+                }
+            };
+            if packet.sender != sender {
+                log::warn!("Drop packet (sender={}): packet.sender={}", sender, packet.sender);
+                continue;
+            }
+            let Some(peer) = self.peers.iter_mut().find(|p| p.player_id == sender)
+            else {
+                log::warn!("Drop packet (sender={}): unknown sender", sender);
+                continue;
+            };
+            if let Some(inputs) = packet.inputs.iter().find(|inputs| inputs.player_id != sender) {
+                log::warn!("Drop packet (sender={}): player_id={}", sender, inputs.player_id);
+                continue;
+            }
+
+            peer.ack_frame = peer.ack_frame.max(packet.ack_frame.min(self.local_frame));
+            for inputs in packet.inputs {
+                // Older frames are redundant, newer frames wait for the lost ones to be sent again.
+                if inputs.frame == peer.received_frame + 1 {
+                    peer.received_frame = inputs.frame;
+                    peer.received.push_back(inputs);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the unacked local inputs to all peers.
+    pub fn send(&mut self) -> XResult<()> {
+        let acked_frame = self.peers.iter().map(|p| p.ack_frame).min().unwrap_or(self.local_frame);
+        let discard_frame = acked_frame.min(self.game_frame);
+        while let Some(inputs) = self.local_inputs.front() {
+            if inputs.frame <= discard_frame {
+                self.local_inputs.pop_front();
+            }
+            else {
+                break;
+            }
+        }
+
+        for peer in self.peers.iter() {
+            let packet = NetPacket {
+                sender: self.config.local_player_id,
+                ack_frame: peer.received_frame,
+                inputs: self
+                    .local_inputs
+                    .iter()
+                    .filter(|inputs| inputs.frame > peer.ack_frame)
+                    .take(self.config.max_redundancy as usize)
+                    .cloned()
+                    .collect(),
+            };
+            self.socket.send(peer.player_id, &packet.encode()?)?;
+        }
+        Ok(())
+    }
+
+    /// Collects the player inputs of the next game frame.
+    /// Returns None if the game should wait, local inputs are not scheduled yet or a remote player
+    /// falls behind more than max_prediction frames.
+    ///
+    /// The remote inputs are delivered as they arrive, possibly none or several frames of a player.
+    /// The game predicts the missing frames, and rolls back once the late inputs are delivered.
+    pub fn advance(&mut self) -> XResult<Option<Vec<InputPlayerInputs>>> {
+        let next_frame = self.game_frame + 1;
+        if self.local_frame < next_frame {
+            return Ok(None);
+        }
+        if self
+            .peers
+            .iter()
+            .any(|p| next_frame > p.received_frame + self.config.max_prediction)
+        {
+            return Ok(None);
+        }
+
+        let mut player_inputs = Vec::with_capacity(self.config.player_count);
+        match self.local_inputs.iter().find(|inputs| inputs.frame == next_frame) {
+            Some(inputs) => player_inputs.push(inputs.clone()),
+            None => return xresf!(Unexpected; "local inputs frame={}", next_frame),
+        }
+        for peer in self.peers.iter_mut() {
+            while let Some(inputs) = peer.received.front() {
+                if inputs.frame > next_frame {
+                    break;
+                }
+                peer.delivered_frame = inputs.frame;
+                player_inputs.extend(peer.received.pop_front());
+            }
+        }
+
+        self.game_frame = next_frame;
+        Ok(Some(player_inputs))
+    }
+
+    /// Runs a whole tick: schedules local inputs, exchanges packets and updates the game.
+    /// Returns None if the game is waiting for remote players.
+    pub fn update(&mut self, engine: &mut LogicEngine, inputs: Vec<RawInput>) -> XResult<Option<Arc<StateSet>>> {
+        self.add_local_input(inputs);
+        self.poll()?;
+        self.send()?;
+        match self.advance()? {
+            Some(player_inputs) => Ok(Some(engine.update_game(player_inputs)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::consts::TEST_ASSET_PATH;
+    use crate::logic::StateChecksum;
+    use crate::netcode::socket::LoopbackSocket;
+    use crate::parameter::{ParamGame, ParamPlayer, ParamZone};
+    use crate::utils::{RawKey, id};
+
+    // Jolt physics is initialized by the ctor in logic tests.
+    #[ctor::ctor]
+    fn test_init_env_path() {
+        unsafe { crate::engine::ENV_PATH.asset_path = PathBuf::from(TEST_ASSET_PATH) };
+    }
+
+    fn new_sessions(config: NetSessionConfig) -> Vec<NetSession<LoopbackSocket>> {
+        LoopbackSocket::new_group(config.player_count)
+            .into_iter()
+            .map(|socket| {
+                let config = NetSessionConfig {
+                    local_player_id: socket.player_id(),
+                    ..config.clone()
+                };
+                NetSession::new(socket, config).unwrap()
+            })
+            .collect()
+    }
+
+    fn tick(sessions: &mut [NetSession<LoopbackSocket>], frame: u32) -> Vec<Option<Vec<InputPlayerInputs>>> {
+        for session in sessions.iter_mut() {
+            let key = if frame % 2 == 0 { RawKey::Attack1 } else { RawKey::Jump };
+            session.add_local_input(vec![RawInput::new_button(key, true)]);
+        }
+        for session in sessions.iter_mut() {
+            session.poll().unwrap();
+            session.send().unwrap();
+        }
+        sessions.iter_mut().map(|s| s.advance().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_net_session_new() {
+        let sockets = LoopbackSocket::new_group(4);
+        let mut iter = sockets.into_iter();
+        assert!(
+            NetSession::new(iter.next().unwrap(), NetSessionConfig {
+                player_count: 0,
+                ..Default::default()
+            })
+            .is_err()
+        );
+        assert!(
+            NetSession::new(iter.next().unwrap(), NetSessionConfig {
+                local_player_id: NumID::MIN_PLAYER + 2,
+                ..Default::default()
+            })
+            .is_err()
+        );
+        assert!(
+            NetSession::new(iter.next().unwrap(), NetSessionConfig {
+                tick_rate: 45,
+                ..Default::default()
+            })
+            .is_err()
+        );
+
+        // One second of input frames in a packet by default.
+        let session = NetSession::new(iter.next().unwrap(), NetSessionConfig {
+            tick_rate: TICK_RATE_60,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(session.config.max_redundancy, TICK_RATE_60);
+        assert!(session.local_inputs.capacity() >= 2 * TICK_RATE_60 as usize);
+    }
+
+    #[test]
+    fn test_net_session_input_delay() {
+        let mut sessions = new_sessions(NetSessionConfig {
+            input_delay: 2,
+            ..Default::default()
+        });
+
+        let outs = tick(&mut sessions, 1);
+        let inputs = outs[0].as_ref().unwrap();
+        assert_eq!(inputs[0], InputPlayerInputs::new(NumID::MIN_PLAYER, 1, vec![]));
+        assert_eq!(sessions[0].local_frame(), 3);
+        assert_eq!(sessions[0].game_frame(), 1);
+
+        // Remote inputs of frame 1, 2 arrive with the local inputs scheduled at frame 3
+        tick(&mut sessions, 2);
+        let outs = tick(&mut sessions, 3);
+        let inputs = outs[0].as_ref().unwrap();
+        assert_eq!(inputs[0].frame, 3);
+        assert_eq!(inputs[0].inputs[0].key, RawKey::Jump);
+        assert!(inputs[1..].iter().all(|i| i.player_id == NumID::MIN_PLAYER + 1));
+        assert_eq!(inputs.last().unwrap().frame, 3);
+        assert_eq!(sessions[0].predicted_frames(), 0);
+    }
+
+    #[test]
+    fn test_net_session_packet_loss() {
+        let mut sessions = new_sessions(NetSessionConfig {
+            input_delay: 0,
+            max_prediction: 100,
+            ..Default::default()
+        });
+        sessions[0].socket_mut().set_loss_interval(2);
+        sessions[1].socket_mut().set_loss_interval(3);
+
+        let mut remote_frames = vec![vec![], vec![]];
+        for frame in 1..=30 {
+            let outs = tick(&mut sessions, frame);
+            for (idx, out) in outs.into_iter().enumerate() {
+                let inputs = out.unwrap();
+                assert_eq!(inputs[0].frame, frame);
+                remote_frames[idx].extend(inputs[1..].iter().map(|i| i.frame));
+            }
+        }
+
+        // All remote frames are delivered in order, without gaps and duplicates
+        for frames in remote_frames.iter() {
+            assert!(frames.len() >= 25);
+            for (idx, frame) in frames.iter().enumerate() {
+                assert_eq!(*frame, idx as u32 + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_net_session_max_prediction() {
+        let mut sessions = new_sessions(NetSessionConfig {
+            input_delay: 0,
+            max_prediction: 3,
+            ..Default::default()
+        });
+        sessions[1].socket_mut().set_loss_interval(1); // All packets from player 1 are lost
+
+        for frame in 1..=3 {
+            assert!(tick(&mut sessions, frame)[0].is_some());
+        }
+        assert_eq!(sessions[0].predicted_frames(), 3);
+        assert!(tick(&mut sessions, 4)[0].is_none());
+        assert_eq!(sessions[0].game_frame(), 3);
+
+        // The packets arrive one tick later
+        sessions[1].socket_mut().set_loss_interval(0);
+        assert!(tick(&mut sessions, 5)[0].is_none());
+        let outs = tick(&mut sessions, 6);
+        let inputs = outs[0].as_ref().unwrap();
+        assert_eq!(inputs[0].frame, 4);
+        assert_eq!(inputs[1..].iter().map(|i| i.frame).collect::<Vec<_>>(), vec![
+            1, 2, 3, 4
+        ]);
+    }
+
+    #[test]
+    fn test_net_session_drop_bad_packets() {
+        let mut sessions = new_sessions(NetSessionConfig::default());
+        let id0 = sessions[0].local_player_id();
+        sessions[1].socket_mut().send(id0, &[1, 2, 3]).unwrap();
+        sessions[1].socket_mut().send(id0, &[]).unwrap();
+        sessions[0].poll().unwrap();
+        assert!(sessions[0].socket_mut().recv().unwrap().is_none());
+
+        // Still works after the bad packets
+        let outs = tick(&mut sessions, 1);
+        assert!(outs.iter().all(|out| out.is_some()));
+    }
+
+    fn param_game() -> ParamGame {
+        let player = ParamPlayer {
+            character: id!("Character.One"),
+            style: id!("Style.One^1"),
+            level: 4,
+            ..Default::default()
+        };
+        ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![
+                ParamPlayer {
+                    position: glam::Vec3A::new(-1.0, 0.0, 0.0),
+                    ..player.clone()
+                },
+                ParamPlayer {
+                    position: glam::Vec3A::new(1.0, 0.0, 0.0),
+                    ..player
+                },
+            ],
+            npcs: vec![],
+            local_mode: false,
+            seed: 12345,
+            update_threads: 0,
+            tick_rate: 0,
+        }
+    }
+
+    fn raw_inputs(player_id: NumID, frame: u32) -> Vec<RawInput> {
+        let frame = frame + 3 * (player_id.0 - NumID::MIN_PLAYER.0);
+        match frame % 12 {
+            1 => vec![RawInput::new_button(RawKey::Attack1, true)],
+            2 => vec![RawInput::new_button(RawKey::Attack1, false)],
+            5 => vec![RawInput::new_move(glam::Vec2::new(1.0, 0.0))],
+            9 => vec![RawInput::new_move(glam::Vec2::ZERO)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_net_session_logic_engine() {
+        let mut sessions = new_sessions(NetSessionConfig {
+            input_delay: 0,
+            max_prediction: 8,
+            ..Default::default()
+        });
+        let mut engines: Vec<LogicEngine> = (0..sessions.len())
+            .map(|_| {
+                let mut engine = LogicEngine::new().unwrap();
+                engine.start_game(param_game(), None).unwrap();
+                engine
+            })
+            .collect();
+        let mut checksums: Vec<HashMap<u32, StateChecksum>> = vec![HashMap::new(); sessions.len()];
+
+        // Lossy network, the late remote inputs are predicted and rolled back.
+        // Without the input delay, player 0 always predicts player 1 at least 1 frame.
+        sessions[0].socket_mut().set_loss_interval(4);
+        sessions[1].socket_mut().set_loss_interval(3);
+        let mut max_predicted = 0;
+        for frame in 1..=90 {
+            if frame == 61 {
+                sessions[0].socket_mut().set_loss_interval(0);
+                sessions[1].socket_mut().set_loss_interval(0);
+            }
+            for (idx, (session, engine)) in sessions.iter_mut().zip(engines.iter_mut()).enumerate() {
+                let inputs = raw_inputs(session.local_player_id(), frame);
+                let state_set = session.update(engine, inputs).unwrap().unwrap();
+                assert_eq!(state_set.frame, frame);
+
+                max_predicted = max_predicted.max(session.predicted_frames());
+                let synced_frame = frame - session.predicted_frames();
+                checksums[idx].insert(synced_frame, engine.checksum(synced_frame).unwrap());
+            }
+        }
+        assert!(max_predicted > 1);
+        assert_eq!(sessions[0].predicted_frames(), 1);
+        assert_eq!(sessions[1].predicted_frames(), 0);
+
+        // The confirmed frames are the same on both peers
+        let mut compared = 0;
+        for (frame, checksum) in checksums[0].iter() {
+            if let Some(remote) = checksums[1].get(frame) {
+                checksum.verify(remote).unwrap();
+                compared += 1;
+            }
+        }
+        assert!(compared >= 60);
+    }
+}
@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::utils::{NumID, XResult, xresf};
+
+/// A transport-agnostic datagram socket between players.
+/// Packets may be lost or duplicated, the session layer handles it.
+pub trait NetSocket {
+    /// Sends a packet to a remote player.
+    fn send(&mut self, dst_player_id: NumID, data: &[u8]) -> XResult<()>;
+
+    /// Receives a pending packet and its sender, returns None if there is nothing to receive.
+    fn recv(&mut self) -> XResult<Option<(NumID, Vec<u8>)>>;
+}
+
+type LoopbackMailboxes = Rc<RefCell<Vec<VecDeque<(NumID, Vec<u8>)>>>>;
+
+/// An in-memory socket, all sockets in a group share the mailboxes.
+#[derive(Debug)]
+pub struct LoopbackSocket {
+    player_id: NumID,
+    mailboxes: LoopbackMailboxes,
+    // Drops every Nth sent packet, 0 means no packet loss.
+    loss_interval: u32,
+    sent_count: u32,
+}
+
+impl LoopbackSocket {
+    pub fn new_group(player_count: usize) -> Vec<LoopbackSocket> {
+        let mailboxes = Rc::new(RefCell::new(vec![VecDeque::new(); player_count]));
+        (0..player_count)
+            .map(|idx| LoopbackSocket {
+                player_id: NumID::MIN_PLAYER + idx as u32,
+                mailboxes: mailboxes.clone(),
+                loss_interval: 0,
+                sent_count: 0,
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub fn player_id(&self) -> NumID {
+        self.player_id
+    }
+
+    #[inline]
+    pub fn set_loss_interval(&mut self, loss_interval: u32) {
+        self.loss_interval = loss_interval;
+    }
+}
+
+impl NetSocket for LoopbackSocket {
+    fn send(&mut self, dst_player_id: NumID, data: &[u8]) -> XResult<()> {
+        let mut mailboxes = self.mailboxes.borrow_mut();
+        let dst_idx = dst_player_id.0.wrapping_sub(NumID::MIN_PLAYER.0) as usize;
+        let Some(mailbox) = mailboxes.get_mut(dst_idx)
+        else {
+            return xresf!(NotFound; "dst_player_id={}", dst_player_id);
+        };
+
+        self.sent_count += 1;
+        if self.loss_interval > 0 && self.sent_count % self.loss_interval == 0 {
+            return Ok(());
+        }
+        mailbox.push_back((self.player_id, data.to_vec()));
+        Ok(())
+    }
+
+    fn recv(&mut self) -> XResult<Option<(NumID, Vec<u8>)>> {
+        let mut mailboxes = self.mailboxes.borrow_mut();
+        let idx = self.player_id.0.checked_sub(NumID::MIN_PLAYER.0).map(|idx| idx as usize);
+        let Some(mailbox) = idx.and_then(|idx| mailboxes.get_mut(idx))
+        else {
+            return xresf!(NotFound; "player_id={}", self.player_id);
+        };
+        Ok(mailbox.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loopback_socket() {
+        let mut sockets = LoopbackSocket::new_group(2);
+        let id0 = sockets[0].player_id();
+        let id1 = sockets[1].player_id();
+
+        sockets[0].send(id1, &[1, 2, 3]).unwrap();
+        assert!(sockets[0].recv().unwrap().is_none());
+        assert_eq!(sockets[1].recv().unwrap(), Some((id0, vec![1, 2, 3])));
+        assert!(sockets[1].recv().unwrap().is_none());
+        assert!(sockets[0].send(NumID::MIN_PLAYER + 5, &[1]).is_err());
+
+        sockets[1].set_loss_interval(2);
+        sockets[1].send(id0, &[1]).unwrap();
+        sockets[1].send(id0, &[2]).unwrap();
+        sockets[1].send(id0, &[3]).unwrap();
+        assert_eq!(sockets[0].recv().unwrap(), Some((id1, vec![1])));
+        assert_eq!(sockets[0].recv().unwrap(), Some((id1, vec![3])));
+        assert!(sockets[0].recv().unwrap().is_none());
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::engine::LogicEngine;
+use crate::input::{InputFrameInputs, InputPlayerInputs};
+use crate::logic::{LogicJoinSnapshot, StateSet};
+use crate::utils::{XResult, xresf};
+
+/// Follows a running match from confirmed inputs, a few frames behind the host.
+///
+/// The match is rebuilt from a `LogicJoinSnapshot`, then the confirmed inputs after the
+/// snapshot frame are fed in order. A reconnecting player uses the same path to catch up,
+/// before switching back to a `NetSession`.
+#[derive(Debug)]
+pub struct NetSpectator {
+    follow_delay: u32,
+    game_frame: u32,                      // The last frame delivered to the game.
+    received_frame: u32,                  // The last contiguous confirmed frame received.
+    received: VecDeque<InputFrameInputs>, // Frames in (game_frame, received_frame].
+}
+
+impl NetSpectator {
+    /// `follow_delay` frames are buffered before advancing, to absorb the network jitter.
+    pub fn new(join_frame: u32, follow_delay: u32) -> NetSpectator {
+        NetSpectator {
+            follow_delay,
+            game_frame: join_frame,
+            received_frame: join_frame,
+            received: VecDeque::new(),
+        }
+    }
+
+    /// Rebuilds the match in the engine, and returns a spectator following it.
+    pub fn join(
+        engine: &mut LogicEngine,
+        join: LogicJoinSnapshot,
+        follow_delay: u32,
+    ) -> XResult<(NetSpectator, Arc<StateSet>)> {
+        let join_frame = join.frame();
+        let state_set = engine.join_game(join)?;
+        Ok((NetSpectator::new(join_frame, follow_delay), state_set))
+    }
+
+    #[inline]
+    pub fn game_frame(&self) -> u32 {
+        self.game_frame
+    }
+
+    #[inline]
+    pub fn received_frame(&self) -> u32 {
+        self.received_frame
+    }
+
+    /// Adds the confirmed inputs of a frame. Already received frames are ignored.
+    pub fn add_inputs(&mut self, inputs: InputFrameInputs) -> XResult<()> {
+        if inputs.frame <= self.received_frame {
+            return Ok(());
+        }
+        if inputs.frame != self.received_frame + 1 {
+            return xresf!(BadArgument; "frame={}, received_frame={}", inputs.frame, self.received_frame);
+        }
+        self.received_frame = inputs.frame;
+        self.received.push_back(inputs);
+        Ok(())
+    }
+
+    /// Returns the inputs of the next frame, if the buffer holds more than `follow_delay` frames.
+    pub fn advance(&mut self) -> Option<Vec<InputPlayerInputs>> {
+        if self.received_frame - self.game_frame <= self.follow_delay {
+            return None;
+        }
+        let inputs = self.received.pop_front()?;
+        self.game_frame = inputs.frame;
+        Some(inputs.player_inputs)
+    }
+
+    /// Advances the game to `follow_delay` frames behind the received frame.
+    pub fn update(&mut self, engine: &mut LogicEngine) -> XResult<Vec<Arc<StateSet>>> {
+        let mut state_sets = Vec::new();
+        while let Some(player_inputs) = self.advance() {
+            state_sets.push(engine.update_game(player_inputs)?);
+        }
+        Ok(state_sets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{NumID, RawInput, RawKey};
+
+    fn frame_inputs(frame: u32) -> InputFrameInputs {
+        InputFrameInputs::new(frame, &[InputPlayerInputs::new(NumID::MIN_PLAYER, frame, vec![
+            RawInput::new_button(RawKey::Attack1, frame % 2 == 0),
+        ])])
+    }
+
+    #[test]
+    fn test_net_spectator_follow() {
+        let mut spectator = NetSpectator::new(30, 2);
+        assert_eq!(spectator.game_frame(), 30);
+        assert_eq!(spectator.received_frame(), 30);
+        assert!(spectator.advance().is_none());
+
+        assert!(spectator.add_inputs(frame_inputs(32)).is_err());
+        spectator.add_inputs(frame_inputs(30)).unwrap();
+        assert_eq!(spectator.received_frame(), 30);
+
+        spectator.add_inputs(frame_inputs(31)).unwrap();
+        spectator.add_inputs(frame_inputs(32)).unwrap();
+        assert!(spectator.advance().is_none());
+
+        spectator.add_inputs(frame_inputs(33)).unwrap();
+        spectator.add_inputs(frame_inputs(33)).unwrap();
+        let inputs = spectator.advance().unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].frame, 31);
+        assert_eq!(spectator.game_frame(), 31);
+        assert!(spectator.advance().is_none());
+
+        spectator.add_inputs(frame_inputs(34)).unwrap();
+        spectator.add_inputs(frame_inputs(35)).unwrap();
+        assert_eq!(spectator.advance().unwrap()[0].frame, 32);
+        assert_eq!(spectator.advance().unwrap()[0].frame, 33);
+        assert!(spectator.advance().is_none());
+        assert_eq!(spectator.received_frame() - spectator.game_frame(), 2);
+    }
+}
@@ -14,6 +14,16 @@ pub struct ParamGame {
     pub npcs: Vec<ParamNpc>,
     #[serde(default)]
     pub local_mode: bool,
+    /// The match seed of all random streams.
+    #[serde(default)]
+    pub seed: u64,
+    /// Threads used by the read-only parts of the character update (AI perception, animation sampling).
+    /// 0 or 1 runs single-threaded. The results don't depend on it.
+    #[serde(default)]
+    pub update_threads: u32,
+    /// Simulation frames per second, TICK_RATE_30 or TICK_RATE_60. 0 uses DEFAULT_TICK_RATE.
+    #[serde(default)]
+    pub tick_rate: u32,
 }
 
 #[csharp_in(Class)]
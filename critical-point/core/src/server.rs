@@ -0,0 +1,301 @@
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::engine::LogicEngine;
+use crate::input::InputPlayerInputs;
+use crate::logic::StateSet;
+use crate::parameter::ParamGame;
+use crate::utils::{DtHashMap, XError, XResult, xerrf, xfromf, xres};
+
+pub type RoomID = u32;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Number of worker threads. Each room is pinned to one worker for its whole lifetime.
+    pub worker_count: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            worker_count: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        }
+    }
+}
+
+enum RoomCommand {
+    Start {
+        room_id: RoomID,
+        param: ParamGame,
+    },
+    Update {
+        room_id: RoomID,
+        inputs: Vec<InputPlayerInputs>,
+    },
+    Stop {
+        room_id: RoomID,
+    },
+    Shutdown,
+}
+
+#[derive(Debug)]
+pub enum RoomEvent {
+    Started { room_id: RoomID, state_set: Arc<StateSet> },
+    Updated { room_id: RoomID, state_set: Arc<StateSet> },
+    Stopped { room_id: RoomID },
+    Failed { room_id: RoomID, error: XError },
+}
+
+impl RoomEvent {
+    #[inline]
+    pub fn room_id(&self) -> RoomID {
+        match self {
+            RoomEvent::Started { room_id, .. } => *room_id,
+            RoomEvent::Updated { room_id, .. } => *room_id,
+            RoomEvent::Stopped { room_id } => *room_id,
+            RoomEvent::Failed { room_id, .. } => *room_id,
+        }
+    }
+}
+
+struct RoomWorker {
+    sender: Sender<RoomCommand>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// Runs many `LogicEngine` rooms in one headless process.
+///
+/// A `LogicEngine` is not `Send`, so every room is created, updated and dropped on the same
+/// worker thread. Rooms only share the immutable template store, so workers never block each other.
+/// Commands to one room are handled in order, results are reported as `RoomEvent`s.
+pub struct ServerRoomPool {
+    workers: Vec<RoomWorker>,
+    events: Receiver<RoomEvent>,
+    room_counter: RoomID,
+}
+
+impl Drop for ServerRoomPool {
+    fn drop(&mut self) {
+        for worker in &self.workers {
+            let _ = worker.sender.send(RoomCommand::Shutdown);
+        }
+        for worker in &mut self.workers {
+            if let Some(handle) = worker.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl ServerRoomPool {
+    /// `LogicEngine::initialize()` must have been called before creating the pool.
+    pub fn new(config: ServerConfig) -> XResult<ServerRoomPool> {
+        if config.worker_count == 0 {
+            return xres!(BadArgument; "worker_count == 0");
+        }
+
+        let (event_sender, events) = mpsc::channel();
+        let mut workers = Vec::with_capacity(config.worker_count);
+        for idx in 0..config.worker_count {
+            let (sender, receiver) = mpsc::channel();
+            let event_sender = event_sender.clone();
+            let handle = thread::Builder::new()
+                .name(format!("room-worker-{}", idx))
+                .spawn(move || run_room_worker(receiver, event_sender))
+                .map_err(xfromf!("idx={}", idx))?;
+            workers.push(RoomWorker {
+                sender,
+                handle: Some(handle),
+            });
+        }
+
+        log::info!("ServerRoomPool::new() worker_count={}", config.worker_count);
+        Ok(ServerRoomPool {
+            workers,
+            events,
+            room_counter: 0,
+        })
+    }
+
+    #[inline]
+    pub fn worker_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Starts a new room. The result comes back as `RoomEvent::Started` or `RoomEvent::Failed`.
+    pub fn start_room(&mut self, param: ParamGame) -> XResult<RoomID> {
+        self.room_counter += 1;
+        let room_id = self.room_counter;
+        self.send(room_id, RoomCommand::Start { room_id, param })?;
+        Ok(room_id)
+    }
+
+    #[inline]
+    pub fn update_room(&self, room_id: RoomID, inputs: Vec<InputPlayerInputs>) -> XResult<()> {
+        self.send(room_id, RoomCommand::Update { room_id, inputs })
+    }
+
+    #[inline]
+    pub fn stop_room(&self, room_id: RoomID) -> XResult<()> {
+        self.send(room_id, RoomCommand::Stop { room_id })
+    }
+
+    fn send(&self, room_id: RoomID, command: RoomCommand) -> XResult<()> {
+        let worker = &self.workers[room_id as usize % self.workers.len()];
+        worker
+            .sender
+            .send(command)
+            .map_err(|_| xerrf!(Unexpected; "worker stopped, room_id={}", room_id))
+    }
+
+    #[inline]
+    pub fn try_recv_event(&self) -> Option<RoomEvent> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty | TryRecvError::Disconnected) => None,
+        }
+    }
+
+    #[inline]
+    pub fn recv_event_timeout(&self, timeout: Duration) -> Option<RoomEvent> {
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+fn run_room_worker(receiver: Receiver<RoomCommand>, events: Sender<RoomEvent>) {
+    let mut rooms: DtHashMap<RoomID, LogicEngine> = DtHashMap::default();
+
+    // The pool may be dropped at any time, so send errors are ignored.
+    while let Ok(command) = receiver.recv() {
+        match command {
+            RoomCommand::Start { room_id, param } => {
+                let res: XResult<_> = try {
+                    let mut engine = LogicEngine::new()?;
+                    let state_set = engine.start_game(param, None)?;
+                    (engine, state_set)
+                };
+                let _ = match res {
+                    Ok((engine, state_set)) => {
+                        rooms.insert(room_id, engine);
+                        events.send(RoomEvent::Started { room_id, state_set })
+                    }
+                    Err(error) => events.send(RoomEvent::Failed { room_id, error }),
+                };
+            }
+            RoomCommand::Update { room_id, inputs } => {
+                let res = match rooms.get_mut(&room_id) {
+                    Some(engine) => engine.update_game(inputs),
+                    None => Err(xerrf!(NotFound; "room_id={}", room_id)),
+                };
+                let _ = match res {
+                    Ok(state_set) => events.send(RoomEvent::Updated { room_id, state_set }),
+                    Err(error) => events.send(RoomEvent::Failed { room_id, error }),
+                };
+            }
+            RoomCommand::Stop { room_id } => {
+                let res = match rooms.remove(&room_id) {
+                    Some(mut engine) => engine.stop_game(),
+                    None => Err(xerrf!(NotFound; "room_id={}", room_id)),
+                };
+                let _ = match res {
+                    Ok(()) => events.send(RoomEvent::Stopped { room_id }),
+                    Err(error) => events.send(RoomEvent::Failed { room_id, error }),
+                };
+            }
+            RoomCommand::Shutdown => break,
+        }
+    }
+
+    for (room_id, mut engine) in rooms.drain() {
+        if let Err(err) = engine.stop_game() {
+            log::error!("run_room_worker() room_id={} err={}", room_id, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::consts::TEST_ASSET_PATH;
+    use crate::parameter::{ParamPlayer, ParamZone};
+    use crate::utils::{NumID, RawInput, RawKey, id};
+
+    // Jolt physics is initialized by the ctor in logic tests.
+    #[ctor::ctor]
+    fn test_init_env_path() {
+        unsafe { crate::engine::ENV_PATH.asset_path = PathBuf::from(TEST_ASSET_PATH) };
+    }
+
+    fn param_game(seed: u64) -> ParamGame {
+        ParamGame {
+            zone: ParamZone { zone: id!("Zone.Demo") },
+            players: vec![ParamPlayer {
+                character: id!("Character.One"),
+                style: id!("Style.One^1"),
+                level: 4,
+                ..Default::default()
+            }],
+            npcs: vec![],
+            local_mode: true,
+            seed,
+            update_threads: 0,
+            tick_rate: 0,
+        }
+    }
+
+    fn recv(pool: &ServerRoomPool) -> RoomEvent {
+        pool.recv_event_timeout(Duration::from_secs(30)).unwrap()
+    }
+
+    #[test]
+    fn test_server_room_pool() {
+        assert!(ServerRoomPool::new(ServerConfig { worker_count: 0 }).is_err());
+
+        let mut pool = ServerRoomPool::new(ServerConfig { worker_count: 2 }).unwrap();
+        assert_eq!(pool.worker_count(), 2);
+
+        let rooms: Vec<RoomID> = (0..4).map(|idx| pool.start_room(param_game(idx)).unwrap()).collect();
+        for _ in 0..rooms.len() {
+            let event = recv(&pool);
+            assert!(matches!(event, RoomEvent::Started { .. }), "{:?}", event);
+        }
+
+        let mut frames: DtHashMap<RoomID, u32> = DtHashMap::default();
+        for frame in 1..=3 {
+            for room_id in &rooms {
+                let inputs = InputPlayerInputs::new(NumID::MIN_PLAYER, frame, vec![RawInput::new_button(
+                    RawKey::Attack1,
+                    frame == 1,
+                )]);
+                pool.update_room(*room_id, vec![inputs]).unwrap();
+            }
+        }
+        for _ in 0..rooms.len() * 3 {
+            match recv(&pool) {
+                RoomEvent::Updated { room_id, state_set } => {
+                    let frame = frames.entry(room_id).or_default();
+                    *frame += 1;
+                    assert_eq!(state_set.frame, *frame);
+                }
+                event => panic!("{:?}", event),
+            }
+        }
+        assert!(rooms.iter().all(|room_id| frames[room_id] == 3));
+
+        pool.update_room(9999, vec![]).unwrap();
+        assert!(matches!(recv(&pool), RoomEvent::Failed { room_id: 9999, .. }));
+
+        pool.stop_room(rooms[0]).unwrap();
+        assert!(matches!(recv(&pool), RoomEvent::Stopped { room_id } if room_id == rooms[0]));
+        pool.update_room(rooms[0], vec![]).unwrap();
+        assert!(matches!(recv(&pool), RoomEvent::Failed { room_id, .. } if room_id == rooms[0]));
+        assert!(pool.try_recv_event().is_none());
+    }
+}
@@ -1,15 +1,17 @@
+use enum_iterator::Sequence;
+
 use crate::consts::MAX_ENTRY_PLUS;
 use crate::template::base::impl_tmpl;
 use crate::utils::{DtHashMap, RareLevel, TmplID, impl_for, rkyv_self};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum TmplAccessoryPattern {
     A,
     B,
     AB,
 }
 
-rkyv_self!(TmplAccessoryPattern);
+rkyv_self!(TmplAccessoryPattern: Sequence);
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(derive(Debug))]
@@ -1,7 +1,8 @@
+use enum_iterator::Sequence;
 use std::fmt;
 
 use crate::template::variable::TmplVar;
-use crate::utils::{TimeFragment, TmplID, VirtualKeyDir};
+use crate::utils::{TimeFragment, TmplID, VirtualKeyDir, XResult, rkyv_self};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(derive(Debug))]
@@ -14,6 +15,13 @@ pub struct TmplAnimation {
     pub weapon_motion: bool,
     pub hit_motion: bool,
     pub shape_key: bool,
+    /// Whether the animation has a notify track (`.nt-json`), whose events are emitted as custom events.
+    #[serde(default)]
+    pub notify: bool,
+    /// Name of a joint mask of the character, the animation only affects the masked joints and plays over
+    /// the full-body animations of the previous actions. Empty for full-body animations.
+    #[serde(default)]
+    pub joint_mask: String,
 }
 
 #[derive(
@@ -41,6 +49,128 @@ pub struct TmplHit {
     pub box_max_times: TmplVar<u16>,
     pub box_min_interval: TmplVar<f32>,
     pub group_max_times: TmplVar<u16>,
+    #[serde(default)]
+    pub buffs: Vec<TmplID>,
+    #[serde(default)]
+    pub fire_buildup: f32,
+    #[serde(default)]
+    pub ice_buildup: f32,
+    #[serde(default)]
+    pub thunder_buildup: f32,
+}
+
+/// Where a motion warp moves the character to.
+#[repr(u8)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
+pub enum TmplMotionWarpTarget {
+    /// The target character of the character control (the lock-on target of players).
+    #[default]
+    LockedTarget = 0,
+    /// The target character of the AI brain.
+    AiTarget = 1,
+    /// The destination the AI brain is navigating to.
+    NavPoint = 2,
+    /// The nearest living enemy within `search_radius`.
+    NearestEnemy = 3,
+}
+
+rkyv_self!(TmplMotionWarpTarget: Sequence);
+
+pub const DEFAULT_SEARCH_RADIUS: f32 = 10.0;
+
+#[inline]
+fn default_search_radius() -> f32 {
+    DEFAULT_SEARCH_RADIUS
+}
+
+/// Stretches the root motion in `[time, time + duration]`, so that the character ends at `distance`
+/// from the warp target, and turns (at most `max_angle`) to face it.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct TmplMotionWarp {
+    pub duration: f32,
+    pub target: TmplMotionWarpTarget,
+    pub distance: f32,
+    /// Limits of the scale applied to the horizontal root displacement.
+    pub min_scale: f32,
+    pub max_scale: f32,
+    pub max_angle: f32,
+    /// Only used by `NearestEnemy`.
+    #[serde(default = "default_search_radius")]
+    pub search_radius: f32,
+}
+
+impl TmplMotionWarp {
+    #[inline]
+    pub fn from_rkyv(archived: &ArchivedTmplMotionWarp) -> XResult<TmplMotionWarp> {
+        Ok(TmplMotionWarp {
+            duration: archived.duration.into(),
+            target: archived.target,
+            distance: archived.distance.into(),
+            min_scale: archived.min_scale.into(),
+            max_scale: archived.max_scale.into(),
+            max_angle: archived.max_angle.into(),
+            search_radius: archived.search_radius.into(),
+        })
+    }
+}
+
+/// Turns the spine and head (see `TmplLookAt` of the character) towards a target during the action.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct TmplActionLookAt {
+    pub target: TmplMotionWarpTarget,
+    /// Only used by `NearestEnemy`.
+    #[serde(default = "default_search_radius")]
+    pub search_radius: f32,
+    /// Height of the look-at point above the target position.
+    #[serde(default)]
+    pub height: f32,
+}
+
+impl TmplActionLookAt {
+    #[inline]
+    pub fn from_rkyv(archived: &ArchivedTmplActionLookAt) -> XResult<TmplActionLookAt> {
+        Ok(TmplActionLookAt {
+            target: archived.target,
+            search_radius: archived.search_radius.into(),
+            height: archived.height.into(),
+        })
+    }
+}
+
+/// An additive animation (such as a flinch or breathing) played over the actions.
+/// Its weight decays linearly from `weight` to 0 in `duration` seconds, unless it is looping.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplAdditiveAnimation {
+    pub files: String,
+    pub duration: f32,
+    pub weight: f32,
+    /// Loops the animation with a constant `weight` (such as breathing), while the action playing it is
+    /// the current action. `duration` is ignored.
+    #[serde(default)]
+    pub looping: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
@@ -1,4 +1,7 @@
-use crate::template::action::base::{TmplActionAttributes, TmplAnimation, TmplDeriveRule, TmplTimelineRange};
+use crate::template::action::base::{
+    TmplActionAttributes, TmplActionLookAt, TmplAdditiveAnimation, TmplAnimation, TmplDeriveRule, TmplMotionWarp,
+    TmplTimelineRange,
+};
 use crate::template::base::impl_tmpl;
 use crate::template::variable::TmplVar;
 use crate::template::{TmplHit, TmplTimelinePoint};
@@ -26,6 +29,10 @@ pub struct TmplActionGeneral {
     pub input_root_motion: Option<TmplActionGeneralRootMotion>,
     #[serde(default)]
     pub input_movements: TmplTimelinePoint<TmplActionGeneralMovement>,
+    #[serde(default)]
+    pub motion_warps: TmplTimelinePoint<TmplMotionWarp>,
+    #[serde(default)]
+    pub look_at: Option<TmplActionLookAt>,
     pub attributes: TmplTimelineRange<TmplActionAttributes>,
     pub keep_levels: TmplTimelineRange<TmplVar<u16>>,
     #[serde(default)]
@@ -36,6 +43,13 @@ pub struct TmplActionGeneral {
     pub hits: Vec<TmplHit>,
     #[serde(default)]
     pub custom_events: TmplTimelinePoint<String>,
+    #[serde(default)]
+    pub hit_objects: TmplTimelinePoint<TmplID>,
+    #[serde(default)]
+    pub additive_animations: TmplTimelinePoint<TmplAdditiveAnimation>,
+    /// Played when the character is hit in this action, but the hit doesn't switch the action.
+    #[serde(default)]
+    pub hit_additive: Option<TmplAdditiveAnimation>,
 }
 
 impl_tmpl!(TmplActionGeneral, ActionGeneral, "ActionGeneral");
@@ -154,6 +168,7 @@ mod tests {
         assert_eq!(act.anim_main.root_motion, true);
         assert_eq!(act.anim_main.weapon_motion, true);
         assert_eq!(act.anim_main.hit_motion, true);
+        assert_eq!(act.anim_main.notify, true);
 
         assert_eq!(act.enter_key, Some(VirtualKeyDir::new(VirtualKey::Attack1, None)));
         assert_eq!(act.enter_level, LEVEL_ATTACK);
@@ -1,4 +1,6 @@
-use crate::template::action::base::{TmplActionAttributes, TmplAnimation, TmplTimelineRange};
+use crate::template::action::base::{
+    TmplActionAttributes, TmplActionLookAt, TmplAdditiveAnimation, TmplAnimation, TmplMotionWarp, TmplTimelineRange,
+};
 use crate::template::base::impl_tmpl;
 use crate::template::variable::TmplVar;
 use crate::template::{TmplHit, TmplTimelinePoint};
@@ -14,12 +16,21 @@ pub struct TmplActionGeneralNpc {
     pub anim_main: TmplAnimation,
     #[serde(default)]
     pub adjust_movements: TmplTimelinePoint<TmplActionGeneralNpcMovement>,
+    #[serde(default)]
+    pub motion_warps: TmplTimelinePoint<TmplMotionWarp>,
+    #[serde(default)]
+    pub look_at: Option<TmplActionLookAt>,
     // pub attributes: TmplTimelineRange<TmplActionAttributes>,
     pub keep_levels: TmplTimelineRange<u16>,
     #[serde(default)]
     pub hits: Vec<TmplHit>,
     #[serde(default)]
     pub custom_events: TmplTimelinePoint<String>,
+    #[serde(default)]
+    pub additive_animations: TmplTimelinePoint<TmplAdditiveAnimation>,
+    /// Played when the character is hit in this action, but the hit doesn't switch the action.
+    #[serde(default)]
+    pub hit_additive: Option<TmplAdditiveAnimation>,
 }
 
 impl_tmpl!(TmplActionGeneralNpc, ActionGeneralNpc, "ActionGeneralNpc");
@@ -1,7 +1,9 @@
+use enum_iterator::Sequence;
+
 use crate::utils::rkyv_self;
 
 #[repr(u16)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum TmplAttribute {
     //
     // primary attributes
@@ -163,4 +165,4 @@ pub enum TmplAttribute {
     BreakArmor,
 }
 
-rkyv_self!(TmplAttribute);
+rkyv_self!(TmplAttribute: Sequence);
@@ -25,6 +25,8 @@ pub enum TmplType {
     Jewel,
 
     Zone,
+    HitObject,
+    Buff,
 
     ActionIdle,
     ActionMove,
@@ -44,7 +46,7 @@ pub enum TmplType {
     AiTaskMoveToCharacter,
 }
 
-rkyv_self!(TmplType);
+rkyv_self!(TmplType: Sequence);
 
 impl From<TmplType> for u16 {
     #[inline]
@@ -133,11 +135,13 @@ const _: () = {
         ArchivedTmplAiTaskGeneral, ArchivedTmplAiTaskIdle, ArchivedTmplAiTaskMoveToCharacter, ArchivedTmplAiTaskPatrol,
         TmplAiTaskGeneral, TmplAiTaskIdle, TmplAiTaskMoveToCharacter, TmplAiTaskPatrol,
     };
+    use super::buff::{ArchivedTmplBuff, TmplBuff};
     use super::character::{
         ArchivedTmplCharacter, ArchivedTmplCharacterNpc, ArchivedTmplStyle, TmplCharacter, TmplCharacterNpc, TmplStyle,
     };
     use super::entry::{ArchivedTmplEntry, TmplEntry};
     use super::equipment::{ArchivedTmplEquipment, TmplEquipment};
+    use super::hit_object::{ArchivedTmplHitObject, TmplHitObject};
     use super::jewel::{ArchivedTmplJewel, TmplJewel};
     use super::perk::{ArchivedTmplPerk, TmplPerk};
     use super::zone::{ArchivedTmplZone, TmplZone};
@@ -181,6 +185,8 @@ const _: () = {
                     AccessoryPool => mem::transmute_copy::<usize, &ArchivedTmplAccessoryPool>(&0),
                     Jewel => mem::transmute_copy::<usize, &ArchivedTmplJewel>(&0),
                     Zone => mem::transmute_copy::<usize, &ArchivedTmplZone>(&0),
+                    HitObject => mem::transmute_copy::<usize, &ArchivedTmplHitObject>(&0),
+                    Buff => mem::transmute_copy::<usize, &ArchivedTmplBuff>(&0),
                     ActionIdle => mem::transmute_copy::<usize, &ArchivedTmplActionIdle>(&0),
                     ActionMove => mem::transmute_copy::<usize, &ArchivedTmplActionMove>(&0),
                     ActionMoveNpc => mem::transmute_copy::<usize, &ArchivedTmplActionMoveNpc>(&0),
@@ -239,6 +245,8 @@ const _: () = {
                 AccessoryPool => serialize::<TmplAccessoryPool, _>(self, serializer),
                 Jewel => serialize::<TmplJewel, _>(self, serializer),
                 Zone => serialize::<TmplZone, _>(self, serializer),
+                HitObject => serialize::<TmplHitObject, _>(self, serializer),
+                Buff => serialize::<TmplBuff, _>(self, serializer),
                 ActionIdle => serialize::<TmplActionIdle, _>(self, serializer),
                 ActionMove => serialize::<TmplActionMove, _>(self, serializer),
                 ActionMoveNpc => serialize::<TmplActionMoveNpc, _>(self, serializer),
@@ -291,6 +299,8 @@ const _: () = {
                 AccessoryPool => deserialize::<TmplAccessoryPool, _>(self, deserializer, out),
                 Jewel => deserialize::<TmplJewel, _>(self, deserializer, out),
                 Zone => deserialize::<TmplZone, _>(self, deserializer, out),
+                HitObject => deserialize::<TmplHitObject, _>(self, deserializer, out),
+                Buff => deserialize::<TmplBuff, _>(self, deserializer, out),
                 ActionIdle => deserialize::<TmplActionIdle, _>(self, deserializer, out),
                 // NpcActionIdle => deserialize::<TmplNpcActionIdle, _>(self, deserializer, out),
                 ActionMove => deserialize::<TmplActionMove, _>(self, deserializer, out),
@@ -322,6 +332,8 @@ const _: () = {
                     AccessoryPool => mem::transmute_copy::<usize, &TmplAccessoryPool>(&0),
                     Jewel => mem::transmute_copy::<usize, &TmplJewel>(&0),
                     Zone => mem::transmute_copy::<usize, &TmplZone>(&0),
+                    HitObject => mem::transmute_copy::<usize, &TmplHitObject>(&0),
+                    Buff => mem::transmute_copy::<usize, &TmplBuff>(&0),
                     ActionIdle => mem::transmute_copy::<usize, &TmplActionIdle>(&0),
                     // NpcActionIdle => mem::transmute_copy::<usize, &TmplNpcActionIdle>(&0),
                     ActionMove => mem::transmute_copy::<usize, &TmplActionMove>(&0),
@@ -0,0 +1,75 @@
+use enum_iterator::Sequence;
+
+use crate::template::attribute::TmplAttribute;
+use crate::template::base::impl_tmpl;
+use crate::utils::{Table, TmplID, rkyv_self};
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
+pub enum TmplBuffStacking {
+    /// Keeps one stack, refreshes the duration.
+    Refresh,
+    /// Adds one stack (up to max_stacks), refreshes the duration.
+    Stack,
+    /// Keeps the existing buff untouched.
+    Ignore,
+}
+
+rkyv_self!(TmplBuffStacking: Sequence);
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplBuff {
+    pub id: TmplID,
+    pub name: String,
+    pub duration: f32,
+    pub stacking: TmplBuffStacking,
+    pub max_stacks: u16,
+    /// 0 for no tick.
+    pub tick_interval: f32,
+    /// Health changed per tick per stack, negative for damage.
+    #[serde(default)]
+    pub tick_health: f32,
+    /// Posture changed per tick per stack, negative for damage.
+    #[serde(default)]
+    pub tick_posture: f32,
+    /// Attribute modifiers for each stack count.
+    #[serde(default)]
+    pub attributes: Table<TmplAttribute, Vec<f32>>,
+}
+
+impl_tmpl!(TmplBuff, Buff, "Buff");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::database::TmplDatabase;
+    use crate::utils::id;
+
+    #[test]
+    fn test_load_buff() {
+        let db = TmplDatabase::new(10240, 150).unwrap();
+
+        let attack_up = db.find_as::<TmplBuff>(id!("Buff.Instance.AttackUp")).unwrap();
+        assert_eq!(attack_up.id, id!("Buff.Instance.AttackUp"));
+        assert_eq!(attack_up.name, "Attack Up");
+        assert_eq!(attack_up.duration, 10.0);
+        assert_eq!(attack_up.stacking, TmplBuffStacking::Stack);
+        assert_eq!(attack_up.max_stacks, 3);
+        assert_eq!(attack_up.tick_interval, 0.0);
+        assert_eq!(attack_up.attributes.len(), 1);
+        assert_eq!(attack_up.attributes[0].k, TmplAttribute::AttackUp);
+        assert_eq!(attack_up.attributes[0].v.as_slice(), &[0.1, 0.15, 0.2]);
+
+        let burning = db.find_as::<TmplBuff>(id!("Buff.Instance.Burning")).unwrap();
+        assert_eq!(burning.duration, 5.0);
+        assert_eq!(burning.stacking, TmplBuffStacking::Refresh);
+        assert_eq!(burning.max_stacks, 1);
+        assert_eq!(burning.tick_interval, 1.0);
+        assert_eq!(burning.tick_health, -20.0);
+        assert_eq!(burning.tick_posture, 0.0);
+        assert_eq!(burning.attributes.len(), 1);
+        assert_eq!(burning.attributes[0].k, TmplAttribute::FireDefenseDown);
+        assert_eq!(burning.attributes[0].v.as_slice(), &[0.2]);
+    }
+}
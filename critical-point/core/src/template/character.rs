@@ -1,4 +1,5 @@
 use critical_point_macros::csharp_enum;
+use enum_iterator::Sequence;
 use glam_ext::Vec2xz;
 
 use crate::template::attribute::TmplAttribute;
@@ -6,7 +7,7 @@ use crate::template::base::impl_tmpl;
 use crate::utils::{JewelSlots, Table, TmplID, U32Range, impl_for, rkyv_self};
 
 #[csharp_enum]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 #[repr(u8)]
 pub enum CharacterType {
     Melee,
@@ -14,7 +15,7 @@ pub enum CharacterType {
     Shot,
 }
 
-rkyv_self!(CharacterType);
+rkyv_self!(CharacterType: Sequence);
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(derive(Debug))]
@@ -26,6 +27,12 @@ pub struct TmplCharacter {
     pub equipments: Vec<TmplID>,
     pub skeleton_files: String,
     pub skeleton_toward: Vec2xz,
+    #[serde(default)]
+    pub foot_ik: Option<TmplFootIk>,
+    #[serde(default)]
+    pub look_at: Option<TmplLookAt>,
+    #[serde(default)]
+    pub joint_masks: Vec<TmplJointMask>,
 }
 
 impl_tmpl!(TmplCharacter, Character, "Character");
@@ -69,6 +76,12 @@ pub struct TmplCharacterNpc {
     pub ai_brains: Vec<TmplID>,
     pub skeleton_files: String,
     pub skeleton_toward: Vec2xz,
+    #[serde(default)]
+    pub foot_ik: Option<TmplFootIk>,
+    #[serde(default)]
+    pub look_at: Option<TmplLookAt>,
+    #[serde(default)]
+    pub joint_masks: Vec<TmplJointMask>,
     pub view_model: String,
 }
 
@@ -81,6 +94,68 @@ impl_for!(TmplCharacterNpc, ArchivedTmplCharacterNpc, {
     }
 });
 
+/// Foot IK settings of a skeleton, keeps the feet on the ground on slopes and stairs.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplFootIk {
+    pub pelvis: String,
+    pub legs: Vec<TmplFootIkLeg>,
+    /// Max distance the pelvis moves down, so that the lower foot can reach the ground.
+    pub max_pelvis_offset: f32,
+    /// Max distance a foot moves up or down.
+    pub max_foot_offset: f32,
+    /// Ratio of the ground slope applied to the whole body, usually 0 for bipeds.
+    #[serde(default)]
+    pub root_tilt: f32,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplFootIkLeg {
+    pub hip: String,
+    pub knee: String,
+    pub ankle: String,
+}
+
+/// Look-at settings of a skeleton, turns the spine and head towards the target of the action.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplLookAt {
+    /// Joints from the root to the end of the chain, e.g. spine, chest, neck and head.
+    pub joints: Vec<TmplLookAtJoint>,
+    /// Max yaw angle (in radians) of the whole chain.
+    pub max_yaw: f32,
+    /// Max pitch angle (in radians) of the whole chain.
+    pub max_pitch: f32,
+    /// Whether the weapons turn with the end of the chain, usually true for aiming.
+    #[serde(default)]
+    pub rotate_weapons: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplLookAtJoint {
+    pub joint: String,
+    /// Ratio of the chain rotation applied to this joint, all weights should sum to 1.
+    pub weight: f32,
+}
+
+/// Named joint weights of a skeleton, used by the partial-body animations (e.g. upper body) of the actions.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplJointMask {
+    pub name: String,
+    /// Each entry applies its weight to the joint and its children, later entries override earlier ones.
+    pub joints: Vec<TmplJointMaskJoint>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplJointMaskJoint {
+    pub joint: String,
+    pub weight: f32,
+}
+
 #[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct TmplFixedAttributes {
     pub damage_reduce_param_1: f32,
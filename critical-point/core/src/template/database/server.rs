@@ -1,3 +1,5 @@
+#![allow(unsafe_op_in_unsafe_fn)]
+
 use std::alloc::Layout;
 use std::fmt::Debug;
 use std::fs::File;
@@ -5,28 +7,34 @@ use std::marker::PhantomData;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::ptr::NonNull;
+use std::sync::{Arc, RwLock};
 use std::{alloc, fmt, fs, mem, slice, u32};
 
-use crate::template2::base::{ArchivedTmplAny, TmplAny};
-use crate::template2::database::base::{TmplIndexCache, load_json_to_rkyv, load_rkyv_into};
-use crate::template2::id::{TmplHashMap, TmplID};
-use crate::utils::{AsXResultIO, IdentityState, XResult};
+use crate::template::base::{ArchivedTmplAny, TmplAny};
+use crate::template::database::base::{TmplIndexCache, load_json_to_rkyv, load_rkyv_into};
+use crate::utils::{DtHashMap, TmplID, XResult, xerr, xerrf, xfromf, xresf};
 
 //
 // Database
 //
 
-static mut DATABASE_CACHE: TmplDatabaseCache = TmplDatabaseCache::EMPTY;
+// The server keeps every template resident in one immutable store.
+// All rooms in the process share the same store, so it is loaded only once.
+static DATABASE_STORE: RwLock<Option<Arc<TmplDatabaseStore>>> = RwLock::new(None);
 
-#[inline(always)]
-fn database_cache() -> &'static TmplDatabaseCache {
-    unsafe { &*(&raw const DATABASE_CACHE) }
+#[inline]
+fn database_store() -> XResult<Arc<TmplDatabaseStore>> {
+    let store = DATABASE_STORE.read().map_err(|_| xerr!(Unexpected; "lock poisoned"))?;
+    store
+        .clone()
+        .ok_or_else(|| xerr!(Unexpected; "database not initialized"))
 }
 
 pub(crate) unsafe fn init_database_static<P: AsRef<Path>>(path: P, force_reinit: bool) -> XResult<()> {
-    #[allow(static_mut_refs)]
-    if DATABASE_CACHE.is_empty() || force_reinit {
-        DATABASE_CACHE = TmplDatabaseCache::from_file(path)?;
+    let mut store = DATABASE_STORE.write().map_err(|_| xerr!(Unexpected; "lock poisoned"))?;
+    if store.is_none() || force_reinit {
+        // Databases created before a reinit keep the old store alive until they are dropped.
+        *store = Some(Arc::new(TmplDatabaseStore::from_file(path)?));
     }
     Ok(())
 }
@@ -42,18 +50,25 @@ fn test_init_database_static() {
     };
 }
 
-struct TmplDatabaseCache {
-    map: TmplHashMap<NonNull<AtInner>>,
+struct TmplDatabaseStore {
+    map: DtHashMap<TmplID, NonNull<AtInner>>,
     size: usize,
 }
 
-impl TmplDatabaseCache {
-    const EMPTY: TmplDatabaseCache = TmplDatabaseCache {
-        map: TmplHashMap::with_hasher(IdentityState),
-        size: 0,
-    };
+// The store is never mutated after loading, templates can be read from any thread.
+unsafe impl Send for TmplDatabaseStore {}
+unsafe impl Sync for TmplDatabaseStore {}
 
-    fn from_file<P: AsRef<Path>>(path: P) -> XResult<TmplDatabaseCache> {
+impl Drop for TmplDatabaseStore {
+    fn drop(&mut self) {
+        for (_, inner) in self.map.drain() {
+            unsafe { AtInner::delete(inner) };
+        }
+    }
+}
+
+impl TmplDatabaseStore {
+    fn from_file<P: AsRef<Path>>(path: P) -> XResult<TmplDatabaseStore> {
         let index_cache = TmplIndexCache::from_file(path.as_ref())?;
 
         let path = PathBuf::from(path.as_ref());
@@ -67,12 +82,15 @@ impl TmplDatabaseCache {
             (false, json_path)
         }
         else {
-            return Err(XError2::bad_argument("TmplDatabaseCache::from_file()"));
+            return xresf!(NotFound; "path={:?}", &path);
         };
-        let mut file = File::open(&file_path).xerr_with(&path)?;
+        let mut file = File::open(&file_path).map_err(xfromf!("path={:?}", path))?;
 
-        let mut map = TmplHashMap::with_capacity_and_hasher(index_cache.len(), IdentityState);
-        let mut size = 0;
+        let mut store = TmplDatabaseStore {
+            map: DtHashMap::default(),
+            size: 0,
+        };
+        store.map.reserve(index_cache.len());
         for (id, index) in index_cache.iter() {
             let inner = if is_rkyv {
                 unsafe {
@@ -90,44 +108,70 @@ impl TmplDatabaseCache {
                     })?
                 }
             };
-            map.insert(*id, inner);
-            size += unsafe { inner.as_ref().size as usize };
+            // Insert before accumulating, so that the store frees it if a later template fails.
+            store.map.insert(*id, inner);
+            store.size += unsafe { inner.as_ref().size as usize };
         }
-
-        Ok(TmplDatabaseCache { map, size })
-    }
-
-    #[inline]
-    pub(super) fn is_empty(&self) -> bool {
-        self.map.is_empty()
+        Ok(store)
     }
 }
 
-pub struct TmplDatabase {}
+/// The server-side template database.
+///
+/// All templates are loaded by `init_database_static()` into a process-wide immutable store,
+/// and every `TmplDatabase` is a cheap handle to that store. Unlike the client database,
+/// it is `Send + Sync` and never evicts templates.
+pub struct TmplDatabase {
+    store: Arc<TmplDatabaseStore>,
+}
 
 impl TmplDatabase {
+    /// The cache limits only apply to the client database. They are accepted here to keep
+    /// the same constructor on both sides, the server store always keeps every template resident.
     #[inline]
-    pub fn new(_cached_size: usize, _cached_frame_limit: u32) -> XResult<TmplDatabase> {
-        Ok(TmplDatabase {})
+    pub fn new(_cached_size_limit: usize, _cached_frame_limit: u32) -> XResult<TmplDatabase> {
+        Ok(TmplDatabase {
+            store: database_store()?,
+        })
+    }
+
+    pub(crate) fn clone(&self) -> TmplDatabase {
+        TmplDatabase {
+            store: self.store.clone(),
+        }
     }
 
     #[inline]
     pub fn size(&self) -> usize {
-        database_cache().size as usize
+        self.store.size
     }
 
+    /// Nothing is released on the server, so there is no cached (unreferenced) template.
     #[inline]
     pub fn cached_size(&self) -> usize {
-        database_cache().size as usize
+        0
     }
 
     #[inline]
     pub fn find(&self, id: TmplID) -> XResult<At<dyn TmplAny>> {
-        database_cache().find(id)
+        let inner = self
+            .store
+            .map
+            .get(&id)
+            .ok_or_else(|| xerrf!(TmplNotFound; "id={}", id))?;
+        Ok(unsafe { At::from_inner(*inner, self.store.clone()) })
+    }
+
+    #[inline]
+    pub fn exists(&self, id: TmplID) -> bool {
+        self.store.map.contains_key(&id)
     }
 
     #[inline]
     pub fn update_frame(&self, _current_frame: u32) {}
+
+    #[inline]
+    pub fn set_cache_size_limit(&self, _size: usize) {}
 }
 
 //
@@ -150,19 +194,32 @@ impl AtInner {
         id: TmplID,
         initialize: F,
     ) -> XResult<NonNull<AtInner>> {
-        let size = AT_INNER_SIZE + (tmpl_size + 0xF) & !0xF;
+        use rkyv::boxed::ArchivedBox;
+
+        let size = AT_INNER_SIZE + ((tmpl_size + 0xF) & !0xF);
         let mut inner =
             NonNull::new_unchecked(alloc::alloc(Layout::from_size_align_unchecked(size, 16)) as *mut AtInner);
         inner.as_mut().id = id;
-        inner.as_mut().size = tmpl_size as u32;
+        inner.as_mut().size = size as u32;
         inner.as_mut().tmpl_size = tmpl_size as u32;
 
-        initialize(inner.as_mut().buf_mut())?;
+        if let Err(err) = initialize(inner.as_mut().buf_mut()) {
+            AtInner::delete(inner);
+            return Err(err);
+        }
         inner.as_mut().padding_buf_mut().fill(0);
 
-        let archived_ref = rkyv::archived_unsized_root::<dyn TmplAny>(inner.as_ref().buf());
-        inner.as_mut().archived_ref = mem::transmute::<&dyn ArchivedTmplAny, [*const (); 2]>(archived_ref);
-        return Ok(inner);
+        let archived_ref = rkyv::access_unchecked::<ArchivedBox<dyn ArchivedTmplAny>>(inner.as_ref().buf());
+        inner.as_mut().archived_ref = mem::transmute::<&dyn ArchivedTmplAny, [*const (); 2]>(archived_ref.as_ref());
+        Ok(inner)
+    }
+
+    #[inline]
+    unsafe fn delete(inner: NonNull<AtInner>) {
+        alloc::dealloc(
+            inner.as_ptr() as *mut u8,
+            Layout::from_size_align_unchecked(inner.as_ref().size as usize, 16),
+        );
     }
 
     #[inline]
@@ -187,30 +244,33 @@ impl AtInner {
 
 pub struct At<T: ?Sized = dyn TmplAny> {
     inner: NonNull<AtInner>,
+    // Keeps the store (and so the archived bytes) alive while the template is referred.
+    store: Arc<TmplDatabaseStore>,
     _phantom: PhantomData<T>,
 }
 
-impl<T: ?Sized + TmplAny> At<T> {
+// Points into the immutable store, which is kept alive by `store`.
+unsafe impl<T: ?Sized> Send for At<T> {}
+unsafe impl<T: ?Sized> Sync for At<T> {}
+
+impl<T: ?Sized> At<T> {
     #[inline]
     fn inner(&self) -> &AtInner {
         unsafe { self.inner.as_ref() }
     }
-
-    #[inline]
-    fn inner_mut(&mut self) -> &mut AtInner {
-        unsafe { self.inner.as_mut() }
-    }
 }
 
 impl<T: ?Sized + TmplAny> At<T> {
     #[inline]
-    unsafe fn from_inner(inner: NonNull<AtInner>) -> At<T> {
+    unsafe fn from_inner(inner: NonNull<AtInner>, store: Arc<TmplDatabaseStore>) -> At<T> {
         At {
             inner,
+            store,
             _phantom: PhantomData,
         }
     }
 
+    /// Templates are never released on the server.
     #[inline]
     pub fn ref_count(&self) -> u32 {
         u32::MAX
@@ -227,6 +287,7 @@ impl<T: ?Sized + TmplAny> Clone for At<T> {
     fn clone(&self) -> At<T> {
         At {
             inner: self.inner,
+            store: self.store.clone(),
             _phantom: PhantomData,
         }
     }
@@ -291,3 +352,39 @@ where
         self.as_archived().fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::template::TmplZone;
+    use crate::utils::id;
+
+    #[test]
+    fn test_tmpl_database_shared() {
+        let db = TmplDatabase::new(0, 0).unwrap();
+        assert!(db.size() > 0);
+        assert_eq!(db.cached_size(), 0);
+        assert!(db.exists(id!("Zone.Demo")));
+        assert!(db.find(id!("Zone.Unknown")).is_err());
+
+        let zone1 = db.find_as::<TmplZone>(id!("Zone.Demo")).unwrap();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                thread::spawn(move || {
+                    let zone = db.find_as::<TmplZone>(id!("Zone.Demo")).unwrap();
+                    zone.id == id!("Zone.Demo")
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+
+        let zone2 = zone1.clone();
+        mem::drop(db);
+        assert_eq!(zone2.id, id!("Zone.Demo"));
+    }
+}
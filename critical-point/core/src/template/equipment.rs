@@ -1,16 +1,18 @@
+use enum_iterator::Sequence;
+
 use crate::template::attribute::TmplAttribute;
 use crate::template::base::impl_tmpl;
 use crate::utils::{JewelSlots, PiecePlus, Table, TmplID, U32Range, impl_for, rkyv_self};
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum TmplEquipmentSlot {
     Slot1 = 1,
     Slot2 = 2,
     Slot3 = 3,
 }
 
-rkyv_self!(TmplEquipmentSlot);
+rkyv_self!(TmplEquipmentSlot: Sequence);
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(derive(Debug))]
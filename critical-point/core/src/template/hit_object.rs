@@ -0,0 +1,84 @@
+use glam::Vec3A;
+
+use crate::template::base::impl_tmpl;
+use crate::utils::{ShapeBox, ShapeCapsule, ShapeCylinder, ShapeSphere, TmplID};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+#[serde(tag = "T")]
+pub enum TmplHitObjectShape {
+    Sphere(ShapeSphere),
+    Capsule(ShapeCapsule),
+    Cylinder(ShapeCylinder),
+    Box(ShapeBox),
+}
+
+impl TmplHitObjectShape {
+    #[inline]
+    pub fn from_rkyv(archived: &ArchivedTmplHitObjectShape) -> TmplHitObjectShape {
+        match archived {
+            ArchivedTmplHitObjectShape::Sphere(shape) => TmplHitObjectShape::Sphere(*shape),
+            ArchivedTmplHitObjectShape::Capsule(shape) => TmplHitObjectShape::Capsule(*shape),
+            ArchivedTmplHitObjectShape::Cylinder(shape) => TmplHitObjectShape::Cylinder(*shape),
+            ArchivedTmplHitObjectShape::Box(shape) => TmplHitObjectShape::Box(*shape),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct TmplHitObject {
+    pub id: TmplID,
+    pub group: String,
+    pub shape: TmplHitObjectShape,
+    /// Spawn position in shooter space.
+    pub spawn_offset: Vec3A,
+    /// Initial speed along the shooter direction, 0 for a static AoE.
+    pub speed: f32,
+    pub gravity: f32,
+    /// Max turning speed towards the nearest target (rad/s), 0 to disable homing.
+    pub homing_speed: f32,
+    pub homing_radius: f32,
+    pub life_time: f32,
+    /// Max characters hit times before destroyed, 0 for unlimited.
+    pub max_hits: u16,
+    pub chara_max_times: u16,
+    pub chara_min_interval: f32,
+    pub destroy_on_scenery: bool,
+}
+
+impl_tmpl!(TmplHitObject, HitObject, "HitObject");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::database::TmplDatabase;
+    use crate::utils::id;
+
+    #[test]
+    fn test_load_hit_object() {
+        let db = TmplDatabase::new(10240, 150).unwrap();
+
+        let arrow = db.find_as::<TmplHitObject>(id!("HitObject.Instance.Arrow")).unwrap();
+        assert_eq!(arrow.id, id!("HitObject.Instance.Arrow"));
+        assert_eq!(arrow.group, "Arrow");
+        assert!(matches!(arrow.shape, ArchivedTmplHitObjectShape::Capsule(_)));
+        assert_eq!(arrow.spawn_offset, Vec3A::new(0.0, 1.2, 0.5));
+        assert_eq!(arrow.speed, 20.0);
+        assert_eq!(arrow.gravity, 2.0);
+        assert_eq!(arrow.homing_speed, 0.0);
+        assert_eq!(arrow.life_time, 3.0);
+        assert_eq!(arrow.max_hits, 1);
+        assert_eq!(arrow.destroy_on_scenery, true);
+
+        let area = db.find_as::<TmplHitObject>(id!("HitObject.Instance.FireArea")).unwrap();
+        assert!(matches!(area.shape, ArchivedTmplHitObjectShape::Cylinder(_)));
+        assert_eq!(area.speed, 0.0);
+        assert_eq!(area.max_hits, 0);
+        assert_eq!(area.chara_max_times, 5);
+        assert_eq!(area.chara_min_interval, 1.0);
+        assert_eq!(area.destroy_on_scenery, false);
+    }
+}
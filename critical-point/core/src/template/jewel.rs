@@ -1,3 +1,4 @@
+use enum_iterator::Sequence;
 use rkyv::option::ArchivedOption;
 
 use crate::consts::MAX_ENTRY_PLUS;
@@ -5,14 +6,14 @@ use crate::template::base::impl_tmpl;
 use crate::utils::{PiecePlus, RareLevel, TmplID, rkyv_self};
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum TmplJewelSlot {
     Special = 3,
     Attack = 1,
     Defense = 2,
 }
 
-rkyv_self!(TmplJewelSlot);
+rkyv_self!(TmplJewelSlot: Sequence);
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(derive(Debug))]
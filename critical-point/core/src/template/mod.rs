@@ -5,10 +5,12 @@ mod ai_routine;
 mod ai_task;
 mod attribute;
 mod base;
+mod buff;
 mod character;
 mod database;
 mod entry;
 mod equipment;
+mod hit_object;
 mod jewel;
 mod perk;
 mod variable;
@@ -21,10 +23,12 @@ pub use ai_routine::*;
 pub use ai_task::*;
 pub use attribute::*;
 pub use base::*;
+pub use buff::*;
 pub use character::*;
 pub use database::*;
 pub use entry::*;
 pub use equipment::*;
+pub use hit_object::*;
 pub use jewel::*;
 pub use perk::*;
 pub use variable::*;
@@ -236,6 +236,14 @@ impl<T> HistoryQueue<T> {
         Ok(&mut self.queue[end])
     }
 
+    /// Removes all elements, including the past and future (rolled back) ones.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.current_start = 0;
+        self.current_end = 0;
+    }
+
     // func returns:
     // - true to dequeue the element
     // - false to stop dequeuing
@@ -1,7 +1,7 @@
 use std::hint::unlikely;
 use std::iter::FusedIterator;
 use std::ops::{Index, IndexMut};
-use std::{fmt, slice};
+use std::{fmt, slice, vec};
 
 use crate::utils::XResult;
 
@@ -201,6 +201,12 @@ impl<T> HistoryVec<T> {
         Ok(())
     }
 
+    /// Remove the elements after current end (rolled back elements), and return them.
+    #[inline]
+    pub fn drain_future(&mut self) -> vec::Drain<'_, T> {
+        self.vec.drain(self.current_end..)
+    }
+
     /// Take a element and return it with a rest vec.
     pub fn taken_rest<'t>(&'t mut self, idx: usize) -> (Option<&'t mut T>, HistoryVecRest<'t, T>) {
         let item_mut = match self.get_mut(idx) {
@@ -223,6 +229,15 @@ impl<T> HistoryVec<T> {
         (item_mut, rest)
     }
 
+    /// Return a read-only rest view without taking the element.
+    #[inline]
+    pub fn rest<'t>(&'t self, idx: usize) -> HistoryVecRest<'t, T> {
+        HistoryVecRest {
+            vec: self,
+            taken_idx: idx,
+        }
+    }
+
     /// Iterate over each element and return it with a rest view.
     #[inline]
     pub fn taken_rest_iter<'t>(&'t mut self) -> HistoryVecTakenRestIter<'t, T> {
@@ -494,6 +509,19 @@ mod tests {
         assert_eq!(hv.current_end, 0);
     }
 
+    #[test]
+    fn test_history_vec_drain_future() {
+        let mut hv = new_history_vec();
+        hv.restore(|p| p.key - 4);
+        assert_eq!(hv.drain_future().collect::<Vec<_>>(), vec![Payload::new(5, "five")]);
+        assert_eq!(hv.len(), 4);
+        assert_eq!(hv.future_len(), 0);
+
+        let mut hv = new_history_vec();
+        assert_eq!(hv.drain_future().count(), 0);
+        assert_eq!(hv.len(), 5);
+    }
+
     #[test]
     fn test_history_vec_taken_rest() {
         let mut hv = new_history_vec();
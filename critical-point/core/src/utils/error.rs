@@ -177,6 +177,7 @@ pub enum XError {
     LogicBadState(MixedError<()>),
     LogicIDMismatch(MixedError<()>),
     LogicException(MixedError<()>),
+    LogicDesync(MixedError<()>),
 
     IO(MixedError<std::io::Error>),
     Utf8(MixedError<std::str::Utf8Error>),
@@ -232,6 +233,7 @@ macro_rules! switch_call {
             XError::LogicBadState(e) => $func(e),
             XError::LogicIDMismatch(e) => $func(e),
             XError::LogicException(e) => $func(e),
+            XError::LogicDesync(e) => $func(e),
             XError::IO(e) => $func(e),
             XError::Utf8(e) => $func(e),
             XError::Json(e) => $func(e),
@@ -271,6 +273,7 @@ macro_rules! switch_new {
             XError::LogicBadState(e) => XError::LogicBadState($func(e)),
             XError::LogicIDMismatch(e) => XError::LogicIDMismatch($func(e)),
             XError::LogicException(e) => XError::LogicException($func(e)),
+            XError::LogicDesync(e) => XError::LogicDesync($func(e)),
             XError::IO(e) => XError::IO($func(e)),
             XError::Utf8(e) => XError::Utf8($func(e)),
             XError::Json(e) => XError::Json($func(e)),
@@ -328,6 +331,7 @@ impl fmt::Display for XError {
             XError::LogicBadState(e) => e.to_string(f, "LogicBadState"),
             XError::LogicIDMismatch(e) => e.to_string(f, "LogicIDMismatch"),
             XError::LogicException(e) => e.to_string(f, "LogicException"),
+            XError::LogicDesync(e) => e.to_string(f, "LogicDesync"),
             XError::IO(e) => e.to_string(f, "IO"),
             XError::Utf8(e) => e.to_string(f, "Utf8"),
             XError::Json(e) => e.to_string(f, "Json"),
@@ -1,4 +1,5 @@
 use critical_point_macros::{csharp_enum, wasm_enum, wasm_impl, wasm_struct};
+use enum_iterator::Sequence;
 use lasso::{Capacity, MiniSpur, Rodeo, RodeoReader};
 use regex::Regex;
 use rustc_hash::FxBuildHasher;
@@ -158,7 +159,7 @@ impl TmplKeyCache {
 #[repr(u8)]
 #[csharp_enum]
 #[wasm_enum]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum TmplPrefix {
     Invalid = 0,
     Var,
@@ -177,9 +178,11 @@ pub enum TmplPrefix {
     AiRoutine,
     AiTask,
     Zone,
+    HitObject,
+    Buff,
 }
 
-rkyv_self!(TmplPrefix);
+rkyv_self!(TmplPrefix: Sequence);
 
 #[wasm_impl]
 impl FromStr for TmplPrefix {
@@ -203,6 +206,8 @@ impl FromStr for TmplPrefix {
             "AiRoutine" => TmplPrefix::AiRoutine,
             "AiTask" => TmplPrefix::AiTask,
             "Zone" => TmplPrefix::Zone,
+            "HitObject" => TmplPrefix::HitObject,
+            "Buff" => TmplPrefix::Buff,
             _ => return xres!(InvalidTmplID; "prefix"),
         };
         Ok(prefix)
@@ -256,6 +261,8 @@ impl AsRef<str> for TmplPrefix {
             TmplPrefix::AiRoutine => "AiRoutine",
             TmplPrefix::AiTask => "AiTask",
             TmplPrefix::Zone => "Zone",
+            TmplPrefix::HitObject => "HitObject",
+            TmplPrefix::Buff => "Buff",
             TmplPrefix::Invalid => "?",
         }
     }
@@ -284,7 +291,7 @@ pub struct TmplID {
 
 const_assert_eq!(mem::size_of::<Option<TmplID>>(), 12);
 
-rkyv_self!(TmplID);
+rkyv_self!(TmplID { prefix });
 
 #[wasm_impl]
 impl Default for TmplID {
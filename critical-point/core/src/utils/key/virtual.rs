@@ -1,4 +1,5 @@
 use critical_point_macros::csharp_enum;
+use enum_iterator::Sequence;
 use glam::{Vec3, Vec3A};
 use glam_ext::Vec2xz;
 
@@ -9,7 +10,7 @@ use crate::utils::serde_by;
 
 #[csharp_enum]
 #[repr(u8)]
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum VirtualKey {
     #[default]
     None,
@@ -66,7 +67,7 @@ pub enum VirtualKey {
     Hit3,
 }
 
-rkyv_self!(VirtualKey);
+rkyv_self!(VirtualKey: Sequence);
 
 impl From<RawKey> for VirtualKey {
     fn from(key: RawKey) -> VirtualKey {
@@ -191,7 +192,7 @@ pub enum InputDir {
     Right(f32),
 }
 
-rkyv_self!(InputDir);
+rkyv_self!(InputDir; unchecked);
 
 impl InputDir {
     #[inline]
@@ -216,7 +217,7 @@ pub struct VirtualKeyDir {
     pub dir: Option<InputDir>,
 }
 
-rkyv_self!(VirtualKeyDir);
+rkyv_self!(VirtualKeyDir; unchecked);
 serde_by!(
     VirtualKeyDir,
     (VirtualKey, Option<InputDir>),
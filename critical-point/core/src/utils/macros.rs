@@ -104,11 +104,75 @@ pub(crate) use interface;
 /// This macro assumes that we only support little-endian systems, so we will not encounter byte order issues.
 ///
 /// (Known issue, ignore in code review.)
+///
+/// The type is archived as itself, so `CheckBytes` depends on which bit patterns are valid values:
+/// - `rkyv_self!(Type)`: every bit pattern is valid, like plain numbers.
+/// - `rkyv_self!(Type: Sequence)`: the valid values are listed by `enum_iterator::all()`, like fieldless
+///   enums. The type must have no padding bytes.
+/// - `rkyv_self!(Type { field, ... })`: the listed fields are checked, the others accept every bit pattern.
+/// - `rkyv_self!(Type; unchecked)`: holds in-process data (like `Symbol`), no `CheckBytes`. It can only be
+///   accessed unchecked, from trusted bytes.
 macro_rules! rkyv_self {
     ($type:ty) => {
+        $crate::utils::rkyv_self!(@archive $type);
+
         const _: () = {
             use bytecheck::CheckBytes;
             use rkyv::rancor::Fallible;
+
+            unsafe impl<C: Fallible + ?Sized> CheckBytes<C> for $type {
+                unsafe fn check_bytes(_v: *const Self, _c: &mut C) -> Result<(), C::Error> {
+                    Ok(())
+                }
+            }
+        };
+    };
+    ($type:ty: Sequence) => {
+        $crate::utils::rkyv_self!(@archive $type);
+
+        const _: () = {
+            use bytecheck::CheckBytes;
+            use rkyv::rancor::{Fallible, Source};
+
+            unsafe impl<C> CheckBytes<C> for $type
+            where
+                C: Fallible + ?Sized,
+                C::Error: Source,
+            {
+                unsafe fn check_bytes(v: *const Self, _c: &mut C) -> Result<(), C::Error> {
+                    match unsafe { $crate::utils::is_sequence_bits(v) } {
+                        true => Ok(()),
+                        false => Err(C::Error::new($crate::utils::InvalidBits(stringify!($type)))),
+                    }
+                }
+            }
+        };
+    };
+    ($type:ty { $($field:ident),+ $(,)? }) => {
+        $crate::utils::rkyv_self!(@archive $type);
+
+        const _: () = {
+            use bytecheck::CheckBytes;
+            use rkyv::rancor::{Fallible, Source};
+
+            unsafe impl<C> CheckBytes<C> for $type
+            where
+                C: Fallible + ?Sized,
+                C::Error: Source,
+            {
+                unsafe fn check_bytes(v: *const Self, c: &mut C) -> Result<(), C::Error> {
+                    $(unsafe { <_ as CheckBytes<C>>::check_bytes(std::ptr::addr_of!((*v).$field), c)? };)+
+                    Ok(())
+                }
+            }
+        };
+    };
+    ($type:ty; unchecked) => {
+        $crate::utils::rkyv_self!(@archive $type);
+    };
+    (@archive $type:ty) => {
+        const _: () = {
+            use rkyv::rancor::Fallible;
             use rkyv::traits::NoUndef;
             use rkyv::{Archive, Deserialize, Place, Portable, Serialize};
 
@@ -138,17 +202,38 @@ macro_rules! rkyv_self {
                     Ok(*self)
                 }
             }
-
-            unsafe impl<C: Fallible + ?Sized> CheckBytes<C> for $type {
-                unsafe fn check_bytes(_v: *const Self, _c: &mut C) -> Result<(), C::Error> {
-                    Ok(())
-                }
-            }
         };
     };
 }
 pub(crate) use rkyv_self;
 
+/// The error of a `rkyv_self!` value with invalid bits.
+#[derive(Debug)]
+pub(crate) struct InvalidBits(pub &'static str);
+
+impl std::fmt::Display for InvalidBits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid bits of {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidBits {}
+
+/// Whether the bytes at `ptr` are one of the values listed by `enum_iterator::all()`.
+///
+/// # Safety
+///
+/// `ptr` must point to `size_of::<T>()` readable bytes, and `T` must have no padding bytes.
+#[inline]
+pub(crate) unsafe fn is_sequence_bits<T: enum_iterator::Sequence>(ptr: *const T) -> bool {
+    let size = std::mem::size_of::<T>();
+    let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, size) };
+    enum_iterator::all::<T>().any(|val| {
+        let val_bytes = unsafe { std::slice::from_raw_parts(&val as *const T as *const u8, size) };
+        val_bytes == bytes
+    })
+}
+
 macro_rules! serde_by {
     ($type:ty, $tuple:ty, $from:expr, $to:expr) => {
         const _: () = {
@@ -238,4 +323,25 @@ mod tests {
         assert_eq!(dd.d2, 50);
         assert_eq!(dd.d3, "bbb");
     }
+
+    #[test]
+    fn test_rkyv_self_check_bytes() {
+        use crate::logic::StateType;
+        use crate::utils::{TmplID, TmplPrefix, id};
+        use rkyv::rancor::Error;
+        use std::mem;
+
+        let mut bytes = rkyv::to_bytes::<Error>(&StateType::ZoneUpdate).unwrap();
+        assert_eq!(rkyv::from_bytes::<StateType, Error>(&bytes).unwrap(), StateType::ZoneUpdate);
+        let pos = bytes.len() - mem::size_of::<StateType>();
+        bytes[pos..].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert!(rkyv::from_bytes::<StateType, Error>(&bytes).is_err());
+
+        let mut bytes = rkyv::to_bytes::<Error>(&id!("Zone.Demo")).unwrap();
+        let tmpl_id = rkyv::from_bytes::<TmplID, Error>(&bytes).unwrap();
+        assert_eq!(tmpl_id.prefix, TmplPrefix::Zone);
+        let pos = bytes.len() - mem::size_of::<TmplID>();
+        bytes[pos] = u8::MAX;
+        assert!(rkyv::from_bytes::<TmplID, Error>(&bytes).is_err());
+    }
 }
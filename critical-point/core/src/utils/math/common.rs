@@ -2,17 +2,10 @@ use glam::{Quat, Vec3A};
 use glam_ext::Vec2xz;
 use std::hint::likely;
 
-use crate::consts::{CFG_FPS, DEFAULT_TOWARD_DIR_2D, DEFAULT_TOWARD_DIR_3D, FPS};
+use crate::consts::{CFG_FPS, DEFAULT_TOWARD_DIR_2D, DEFAULT_TOWARD_DIR_3D};
 
-#[inline(always)]
-pub fn f2s(frame: u32) -> f32 {
-    frame as f32 / FPS
-}
-
-#[inline(always)]
-pub fn ff2s(frame: f32) -> f32 {
-    frame / FPS
-}
+// Config frames are the time unit of the templates, a fixed CFG_FPS independent of the game tick rate.
+// Logic frames depend on the tick rate, they are converted with GameTime.
 
 #[inline(always)]
 pub fn cf2s(frame: u32) -> f32 {
@@ -24,46 +17,6 @@ pub fn cff2s(frame: f32) -> f32 {
     frame / CFG_FPS
 }
 
-#[inline(always)]
-pub fn s2f(second: f32) -> u32 {
-    (second * FPS).round() as u32
-}
-
-#[inline(always)]
-pub fn s2ff(second: f32) -> f32 {
-    (second * FPS).round()
-}
-
-#[inline(always)]
-pub fn s2f_round(second: f32) -> u32 {
-    (second * FPS).round() as u32
-}
-
-#[inline(always)]
-pub fn s2ff_round(second: f32) -> f32 {
-    (second * FPS).round()
-}
-
-#[inline(always)]
-pub fn s2f_floor(second: f32) -> u32 {
-    (second * FPS).floor() as u32
-}
-
-#[inline(always)]
-pub fn s2ff_floor(second: f32) -> f32 {
-    (second * FPS).floor()
-}
-
-#[inline(always)]
-pub fn s2f_ceil(second: f32) -> u32 {
-    (second * FPS).ceil() as u32
-}
-
-#[inline(always)]
-pub fn s2ff_ceil(second: f32) -> f32 {
-    (second * FPS).ceil()
-}
-
 /// a (- eps) <= b
 #[macro_export]
 macro_rules! loose_le {
@@ -15,14 +15,14 @@ pub const LEVEL_ULTIMATE: u16 = 400;
 pub const LEVEL_ACTION: u16 = 500;
 pub const LEVEL_UNBREAKABLE: u16 = 600;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum RareLevel {
     Rare1 = 1,
     Rare2 = 2,
     Rare3 = 3,
 }
 
-rkyv_self!(RareLevel);
+rkyv_self!(RareLevel: Sequence);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Sequence)]
 pub enum DeriveContinue {
@@ -32,7 +32,7 @@ pub enum DeriveContinue {
     PerfectGuard,
 }
 
-rkyv_self!(DeriveContinue);
+rkyv_self!(DeriveContinue: Sequence);
 
 unsafe impl Bitsetable for DeriveContinue {
     #[inline]
@@ -62,7 +62,7 @@ pub enum ActionType {
     Hit,
 }
 
-rkyv_self!(ActionType);
+rkyv_self!(ActionType: Sequence);
 
 impl From<ActionType> for u16 {
     #[inline]
@@ -115,7 +115,29 @@ pub enum HitType {
     Counter = 4,
 }
 
-rkyv_self!(HitType);
+rkyv_self!(HitType: Sequence);
+
+//
+// HitInteraction
+//
+
+/// How an attack box meets the dst character, resolved by the active boxes of the dst character.
+#[repr(u8)]
+#[csharp_enum]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Sequence, serde::Serialize, serde::Deserialize)]
+pub enum HitInteraction {
+    /// Attack vs Health, a normal hit.
+    #[default]
+    Normal,
+    /// Attack vs Guard, the hit is blocked.
+    Blocked,
+    /// Attack vs Counter (an armored startup window), a counter hit with bonus deposture.
+    Counter,
+    /// Attack vs Attack, both characters get the hit-lag.
+    Clash,
+}
+
+rkyv_self!(HitInteraction: Sequence);
 
 //
 // AiTaskType
@@ -131,7 +153,7 @@ pub enum AiTaskType {
     General,
 }
 
-rkyv_self!(AiTaskType);
+rkyv_self!(AiTaskType: Sequence);
 
 impl From<AiTaskType> for u16 {
     #[inline]
@@ -187,4 +209,4 @@ pub enum AiIntention {
     SquareOff,
 }
 
-rkyv_self!(AiIntention);
+rkyv_self!(AiIntention: Sequence);
@@ -16,7 +16,7 @@ pub struct TmplIDLevel {
     pub level: u32,
 }
 
-rkyv_self!(TmplIDLevel);
+rkyv_self!(TmplIDLevel { id });
 serde_by!(TmplIDLevel, (TmplID, u32), TmplIDLevel::from, TmplIDLevel::to_tuple);
 
 impl TmplIDLevel {
@@ -56,7 +56,7 @@ pub struct TmplIDPlus {
     pub plus: u32,
 }
 
-rkyv_self!(TmplIDPlus);
+rkyv_self!(TmplIDPlus { id });
 serde_by!(TmplIDPlus, (TmplID, u32), TmplIDPlus::from, TmplIDPlus::to_tuple);
 
 impl TmplIDPlus {
@@ -386,7 +386,7 @@ pub struct CustomEvent {
     pub name: Symbol,
 }
 
-rkyv_self!(CustomEvent);
+rkyv_self!(CustomEvent; unchecked);
 serde_by!(CustomEvent, (TmplID, Symbol), CustomEvent::from, CustomEvent::to_tuple);
 
 impl CustomEvent {
@@ -5,6 +5,7 @@ mod key;
 mod macros;
 mod math;
 mod miscs;
+mod parallel;
 mod ptr;
 mod shape;
 mod symbol;
@@ -19,6 +20,7 @@ pub use key::*;
 pub(crate) use macros::*;
 pub use math::*;
 pub use miscs::*;
+pub use parallel::*;
 pub use ptr::*;
 pub use shape::*;
 pub use symbol::*;
@@ -0,0 +1,81 @@
+use std::thread;
+
+use crate::utils::{XError, XResult};
+
+/// Calls `func(index, item)` for every item, with the items split into contiguous chunks
+/// over at most `threads` scoped threads. The first chunk runs on the current thread.
+///
+/// The returned error is always the one with the lowest index, so the result doesn't depend
+/// on thread scheduling. Runs in place when `threads <= 1`.
+pub fn parallel_for_each<T, F>(threads: usize, items: &mut [T], func: F) -> XResult<()>
+where
+    T: Send,
+    F: Fn(usize, &mut T) -> XResult<()> + Sync,
+{
+    if threads <= 1 || items.len() <= 1 {
+        for (idx, item) in items.iter_mut().enumerate() {
+            func(idx, item)?;
+        }
+        return Ok(());
+    }
+
+    let chunk_size = items.len().div_ceil(threads);
+    let run_chunk = |offset: usize, chunk: &mut [T]| -> Result<(), (usize, XError)> {
+        for (idx, item) in chunk.iter_mut().enumerate() {
+            func(offset + idx, item).map_err(|err| (offset + idx, err))?;
+        }
+        Ok(())
+    };
+
+    let mut chunks = items.chunks_mut(chunk_size).enumerate();
+    let (_, first_chunk) = chunks.next().unwrap();
+    let results = thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .map(|(chunk_idx, chunk)| {
+                let run_chunk = &run_chunk;
+                scope.spawn(move || run_chunk(chunk_idx * chunk_size, chunk))
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(handles.len() + 1);
+        results.push(run_chunk(0, first_chunk));
+        for handle in handles {
+            match handle.join() {
+                Ok(res) => results.push(res),
+                Err(payload) => std::panic::resume_unwind(payload),
+            }
+        }
+        results
+    });
+
+    // Chunks are ordered by index, the first error is the lowest one.
+    for res in results {
+        res.map_err(|(_, err)| err)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::xres;
+
+    #[test]
+    fn test_parallel_for_each() {
+        for threads in [0, 1, 3, 8, 64] {
+            let mut items: Vec<u64> = (0..37).collect();
+            parallel_for_each(threads, &mut items, |idx, item| Ok(*item = *item * 2 + idx as u64)).unwrap();
+            assert!(items.iter().enumerate().all(|(idx, item)| *item == idx as u64 * 3));
+
+            let res = parallel_for_each(threads, &mut items, |idx, _| match idx {
+                5 => xres!(BadArgument; "first"),
+                30 => xres!(BadOperation; "second"),
+                _ => Ok(()),
+            });
+            assert!(matches!(res, Err(XError::BadArgument(_))));
+        }
+
+        let mut empty: Vec<u64> = Vec::new();
+        parallel_for_each(4, &mut empty, |_, _| xres!(Unexpected)).unwrap();
+    }
+}
@@ -310,6 +310,11 @@ impl Symbol {
         drop(cache);
         let mut cache = SYMBOL_CACHE.write().unwrap();
 
+        // Another thread may insert the same string between the two locks.
+        if let Some(node) = cache.find(string, hash) {
+            return Ok(Symbol(SymbolNode::to_str_ptr(node)));
+        }
+
         let node = cache.insert(string, hash)?;
         Ok(Symbol(SymbolNode::to_str_ptr(node)))
     }
@@ -155,7 +155,7 @@ const _: () = {
 };
 
 #[repr(transparent)]
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, rkyv::Portable)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, rkyv::Portable, bytecheck::CheckBytes)]
 pub struct ArchivedSymbol {
     inner: rkyv::string::ArchivedString,
 }
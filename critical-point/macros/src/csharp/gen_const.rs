@@ -0,0 +1,23 @@
+use anyhow::{Result, anyhow};
+use syn::{Expr, ExprLit, ItemConst, Lit};
+
+use crate::csharp::base::*;
+
+pub fn parse_const(input: &ItemConst) -> Result<(String, u32)> {
+    let name = input.ident.to_string();
+    let value = match input.expr.as_ref() {
+        Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) => lit.base10_parse::<u32>()?,
+        _ => return Err(anyhow!("Const ({}) must be an integer literal", name)),
+    };
+    Ok((name, value))
+}
+
+pub fn generate_consts(consts: &[(String, u32)]) -> String {
+    let mut ls = Lines::new(consts.len() + 2);
+    ls += "  public static class Consts {";
+    for (name, value) in consts {
+        ls += f!("    public const uint {} = {};", name, value);
+    }
+    ls += "  }\r\n";
+    ls.join()
+}
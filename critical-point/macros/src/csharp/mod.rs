@@ -1,4 +1,5 @@
 mod base;
+mod gen_const;
 mod gen_enum;
 mod gen_struct_in;
 mod gen_struct_out;
@@ -13,12 +14,14 @@ use std::sync::{LazyLock, Mutex, MutexGuard};
 use syn::*;
 
 use crate::csharp::base::*;
+use crate::csharp::gen_const::{generate_consts, parse_const};
 use crate::csharp::gen_enum::parse_enum;
 use crate::csharp::gen_struct_in::parse_struct_in;
 use crate::csharp::gen_struct_out::{LayoutTask, parse_struct_out};
 
 pub(crate) struct CSharpGenerator {
     consts: HashMap<String, u32>,
+    exported_consts: Vec<(String, u32)>,
     types_in: HashMap<String, TypeIn>,
     types_out: HashMap<String, TypeOut>,
     generate_tasks: Vec<Box<dyn GenerateTask>>,
@@ -41,8 +44,8 @@ pub(crate) fn lock_csharp_generator() -> MutexGuard<'static, CSharpGenerator> {
 impl CSharpGenerator {
     fn new() -> CSharpGenerator {
         let mut consts = HashMap::new();
-        consts.insert("FPS".into(), 15);
         consts.insert("MAX_ACTION_ANIMATION".into(), 5);
+        consts.insert("MAX_ADDITIVE_ANIMATION".into(), 4);
         consts.insert("MAX_ACCESSORY_COUNT".into(), 4);
         consts.insert("MAX_ENTRY_PLUS".into(), 3);
         consts.insert("MAX_EQUIPMENT_COUNT".into(), 3);
@@ -129,6 +132,7 @@ impl CSharpGenerator {
 
         CSharpGenerator {
             consts,
+            exported_consts: Vec::new(),
             types_in,
             types_out,
             generate_tasks: Vec::new(),
@@ -137,6 +141,13 @@ impl CSharpGenerator {
         }
     }
 
+    pub(crate) fn parse_const(&mut self, input: &ItemConst) -> Result<()> {
+        let (name, value) = parse_const(input)?;
+        self.consts.insert(name.clone(), value);
+        self.exported_consts.push((name, value));
+        Ok(())
+    }
+
     pub(crate) fn parse_enum(&mut self, input: &ItemEnum) -> Result<()> {
         let er = parse_enum(input)?;
         self.generate_tasks.push(er.task);
@@ -198,6 +209,10 @@ impl CSharpGenerator {
             .as_bytes(),
         )?;
 
+        self.exported_consts.sort();
+        file.write_all(generate_consts(&self.exported_consts).as_bytes())?;
+        file.write_all("\r\n".as_bytes())?;
+
         println!("Rust -> C# types:\r\n");
         let mut rs_types = Vec::with_capacity(self.layout_tasks.len());
         for task in self.layout_tasks.values() {
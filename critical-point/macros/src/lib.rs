@@ -5,7 +5,7 @@ mod wasm;
 use proc_macro::TokenStream;
 use quote::quote;
 use std::sync::Once;
-use syn::{ItemEnum, ItemImpl, ItemStruct, parse_macro_input};
+use syn::{ItemConst, ItemEnum, ItemImpl, ItemStruct, parse_macro_input};
 
 static INIT: Once = Once::new();
 
@@ -77,6 +77,18 @@ pub fn csharp_enum(_attr: TokenStream, input: TokenStream) -> TokenStream {
     TokenStream::from(quote! { #input })
 }
 
+/// Exports an integer const to C# (`Consts.NAME`), and makes it usable as an array length in C# types.
+/// The value must be an integer literal.
+#[proc_macro_attribute]
+pub fn csharp_const(_attr: TokenStream, input: TokenStream) -> TokenStream {
+    init();
+    let input = parse_macro_input!(input as ItemConst);
+    if GEN_CSHARP {
+        csharp::lock_csharp_generator().parse_const(&input).unwrap();
+    }
+    TokenStream::from(quote! { #input })
+}
+
 #[proc_macro_attribute]
 pub fn csharp_in(attr: TokenStream, input: TokenStream) -> TokenStream {
     init();
@@ -32,6 +32,8 @@ impl TmplKeyCache {
             ("AiRoutine", "AiRoutine"),
             ("AiTask", "AiTask"),
             ("Zone", "Zone"),
+            ("HitObject", "HitObject"),
+            ("Buff", "Buff"),
         ]);
 
         let path = match env::var("TMPL_KEYS_PATH") {
@@ -0,0 +1,8 @@
+use critical_point_core::consts::{TICK_RATE_30, TICK_RATE_60};
+use napi_derive::napi;
+
+/// The logic tick rates a game can run at. Template times are checked against them.
+#[napi]
+pub fn logic_tick_rates() -> Vec<u32> {
+    vec![TICK_RATE_30, TICK_RATE_60]
+}
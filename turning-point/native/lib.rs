@@ -1,2 +1,3 @@
 pub mod animation;
+pub mod consts;
 mod error;
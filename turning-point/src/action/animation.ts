@@ -1,4 +1,13 @@
-import { FilePath, float, int, parseBool, parseFile, parseTime } from '../common';
+import {
+    FilePath,
+    float,
+    int,
+    parseBool,
+    parseFile,
+    parseFloat,
+    parseString,
+    parseTime,
+} from '../common';
 import * as native from '../native';
 
 export type AnimationArgs = {
@@ -10,6 +19,7 @@ export type AnimationArgs = {
      * - xxx.wm-ozz 武器轨迹
      * - xxx.sk-ozz 形态键
      * - xxx.hm-rkyv/xxx.hm-json 攻击判定盒
+     * - xxx.nt-json 动画通知
      */
     files: FilePath;
 
@@ -30,6 +40,12 @@ export type AnimationArgs = {
 
     /** 是否启用形态键 */
     shape_key?: boolean;
+
+    /** 是否启用动画通知 通知事件（如脚步声 特效）作为自定义事件发出 */
+    notify?: boolean;
+
+    /** 角色的骨骼遮罩名 局部动画只作用于遮罩内的骨骼 并覆盖在之前动作的全身动画上 为空时为全身动画 */
+    joint_mask?: string;
 };
 
 export class Animation {
@@ -40,6 +56,7 @@ export class Animation {
      * - xxx.rm-ozz 根运动RootMotion
      * - xxx.wm-ozz 武器轨迹
      * - xxx.hm-rkyv/xxx.hm-json 攻击判定盒
+     * - xxx.nt-json 动画通知
      */
     public readonly files: FilePath;
 
@@ -67,6 +84,12 @@ export class Animation {
     /** 是否启用形态键 */
     public readonly shape_key: boolean;
 
+    /** 是否启用动画通知 通知事件（如脚步声 特效）作为自定义事件发出 */
+    public readonly notify: boolean;
+
+    /** 角色的骨骼遮罩名 局部动画只作用于遮罩内的骨骼 并覆盖在之前动作的全身动画上 为空时为全身动画 */
+    public readonly joint_mask: string;
+
     public constructor(
         args: AnimationArgs,
         where: string,
@@ -108,6 +131,13 @@ export class Animation {
 
         this.shape_key = parseBool(args.shape_key ?? false, `${where}.shape_key`);
 
+        this.notify = parseBool(args.notify ?? false, `${where}.notify`);
+        if (this.notify && !native.existAnimationNotify(this.files)) {
+            throw new Error(`${where}.files: notify file not found (${this.files})`);
+        }
+
+        this.joint_mask = parseString(args.joint_mask ?? '', `${where}.joint_mask`);
+
         this.local_id = 65535;
     }
 
@@ -163,3 +193,60 @@ export class Animation {
         return move_speed / rm_speed;
     }
 }
+
+export type AdditiveAnimationArgs = {
+    /** 动画文件 一个通配的路径前缀 */
+    files: FilePath;
+
+    /** 持续时间 权重在此时间内线性衰减到0 循环时忽略 */
+    duration?: float | string;
+
+    /** 初始权重 */
+    weight?: float;
+
+    /** 是否循环 循环时权重恒定 随播放它的动作结束（如呼吸） */
+    looping?: boolean;
+};
+
+/**
+ * 叠加动画 叠加在动作动画之上（如受击抖动、呼吸）
+ */
+export class AdditiveAnimation {
+    /** 动画文件 一个通配的路径前缀 */
+    public readonly files: FilePath;
+
+    /** 持续时间 权重在此时间内线性衰减到0 循环时忽略 */
+    public readonly duration: float;
+
+    /** 初始权重 */
+    public readonly weight: float;
+
+    /** 是否循环 循环时权重恒定 随播放它的动作结束（如呼吸） */
+    public readonly looping: boolean;
+
+    public constructor(args: AdditiveAnimationArgs, where: string) {
+        this.files = parseFile(args.files, `${where}.files`, { extension: '.*' });
+        // 确保动画存在
+        const anim = native.loadAnimationMeta(
+            this.files,
+            `${where}.files: file not found (${this.files})`,
+        );
+        this.looping = parseBool(args.looping ?? false, `${where}.looping`);
+        this.duration = parseTime(args.duration ?? anim.duration, `${where}.duration`, {
+            min: 0,
+            type: 'f32',
+        });
+        this.weight = parseFloat(args.weight ?? 1, `${where}.weight`, {
+            min: 0,
+            max: 1,
+            type: 'f32',
+        });
+    }
+}
+
+export function parseAdditiveAnimation(
+    raw: AdditiveAnimationArgs,
+    where: string,
+): AdditiveAnimation {
+    return new AdditiveAnimation(raw, where);
+}
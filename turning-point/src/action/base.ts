@@ -3,12 +3,15 @@ import {
     ID,
     IDPrefix,
     int,
+    parseAngleXz,
     parseArray,
+    parseFloat,
     parseID,
     parseIDArray,
     parseInt,
     parseIntArray,
     parseStringArray,
+    parseTime,
 } from '../common';
 import { Resource } from '../resource';
 import { Character, CharacterNpc, Style } from '../character';
@@ -546,6 +549,129 @@ export function parseActionAttributes(raw: ActionAttributesArgs, where: string):
 //     return parseArray(raw, where, (item, where) => parseActionAttributes(item, where), opts);
 // }
 
+export const ACTION_TARGET = ['LockedTarget', 'AiTarget', 'NavPoint', 'NearestEnemy'] as const;
+
+/**
+ * 动作目标
+ * LockedTarget: 角色锁定的目标 AiTarget: AI的目标 NavPoint: AI的寻路终点 NearestEnemy: 搜索半径内最近的敌人
+ */
+export type ActionTarget = (typeof ACTION_TARGET)[number];
+
+export function parseActionTarget(raw: string, where: string): ActionTarget {
+    if (!ACTION_TARGET.includes(raw as ActionTarget)) {
+        throw new Error(where + ': must be a ActionTarget');
+    }
+    return raw as ActionTarget;
+}
+
+/** 默认的目标搜索半径 */
+export const DEFAULT_SEARCH_RADIUS = 10;
+
+export type MotionWarpArgs = {
+    /** 持续时间 */
+    duration: float | string;
+
+    /** 目标 */
+    target: ActionTarget;
+
+    /** 结束时与目标的距离 */
+    distance: float;
+
+    /** 根运动水平位移的最小缩放 */
+    min_scale: float;
+
+    /** 根运动水平位移的最大缩放 */
+    max_scale: float;
+
+    /** 最大旋转角度 表示区间[-angle, angle]内角度范围 */
+    max_angle: float | string;
+
+    /** 目标搜索半径 仅用于NearestEnemy */
+    search_radius?: float;
+};
+
+/**
+ * 运动扭曲 拉伸根运动使角色停在与目标相距distance处 并转向目标
+ */
+export class MotionWarp {
+    /** 持续时间 */
+    public readonly duration: float;
+
+    /** 目标 */
+    public readonly target: ActionTarget;
+
+    /** 结束时与目标的距离 */
+    public readonly distance: float;
+
+    /** 根运动水平位移的最小缩放 */
+    public readonly min_scale: float;
+
+    /** 根运动水平位移的最大缩放 */
+    public readonly max_scale: float;
+
+    /** 最大旋转角度 表示区间[-angle, angle]内角度范围 */
+    public readonly max_angle: float;
+
+    /** 目标搜索半径 仅用于NearestEnemy */
+    public readonly search_radius: float;
+
+    public constructor(args: MotionWarpArgs, where: string) {
+        this.duration = parseTime(args.duration, `${where}.duration`, { min: 0, type: 'f32' });
+        this.target = parseActionTarget(args.target, `${where}.target`);
+        this.distance = parseFloat(args.distance, `${where}.distance`, { min: 0, type: 'f32' });
+        this.min_scale = parseFloat(args.min_scale, `${where}.min_scale`, { min: 0, type: 'f32' });
+        this.max_scale = parseFloat(args.max_scale, `${where}.max_scale`, {
+            min: this.min_scale,
+            type: 'f32',
+        });
+        this.max_angle = parseAngleXz(args.max_angle, `${where}.max_angle`);
+        this.search_radius = parseFloat(
+            args.search_radius ?? DEFAULT_SEARCH_RADIUS,
+            `${where}.search_radius`,
+            { min: 0, type: 'f32' },
+        );
+    }
+}
+
+export function parseMotionWarp(raw: MotionWarpArgs, where: string): MotionWarp {
+    return new MotionWarp(raw, where);
+}
+
+export type ActionLookAtArgs = {
+    /** 目标 */
+    target: ActionTarget;
+
+    /** 目标搜索半径 仅用于NearestEnemy */
+    search_radius?: float;
+
+    /** 注视点距目标位置的高度 */
+    height?: float;
+};
+
+/**
+ * 动作期间 脊柱与头部转向目标
+ */
+export class ActionLookAt {
+    /** 目标 */
+    public readonly target: ActionTarget;
+
+    /** 目标搜索半径 仅用于NearestEnemy */
+    public readonly search_radius: float;
+
+    /** 注视点距目标位置的高度 */
+    public readonly height: float;
+
+    public constructor(args: ActionLookAtArgs, where: string) {
+        this.target = parseActionTarget(args.target, `${where}.target`);
+        this.search_radius = parseFloat(
+            args.search_radius ?? DEFAULT_SEARCH_RADIUS,
+            `${where}.search_radius`,
+            { min: 0, type: 'f32' },
+        );
+        this.height = parseFloat(args.height ?? 0, `${where}.height`, { type: 'f32' });
+    }
+}
+
 export type ActionHitbox = {
     name: string;
     window: readonly [int | string, int | string];
@@ -5,6 +5,7 @@ import {
     int,
     parseAngleXz,
     parseBool,
+    parseID,
     parseString,
     parseTime,
     TimelinePoint,
@@ -12,18 +13,30 @@ import {
     TimelineRange,
     TimelineRangeArgs,
 } from '../common';
+import { HitObject } from '../hit_object';
 import { Resource } from '../resource';
 import { parseVarInt, parseVarTime, Var, VarValueArgs } from '../variable';
-import { Animation, AnimationArgs } from './animation';
+import {
+    AdditiveAnimation,
+    AdditiveAnimationArgs,
+    Animation,
+    AnimationArgs,
+    parseAdditiveAnimation,
+} from './animation';
 import {
     Action,
     ActionArgs,
     ActionAttributes,
     ActionAttributesArgs,
+    ActionLookAt,
+    ActionLookAtArgs,
     DeriveContinue,
     LEVEL_IDLE,
+    MotionWarp,
+    MotionWarpArgs,
     parseActionAttributes,
     parseActionLevel,
+    parseMotionWarp,
     parseVarDeriveContinueSet,
 } from './base';
 import { Hit, HitArgs } from './hit_attr';
@@ -151,6 +164,12 @@ export type ActionGeneralArgs = ActionArgs & {
     /** 用户输入控制的移动与旋转 */
     input_movements?: TimelinePointArgs<ActionGeneralMovetionArgs>;
 
+    /** 运动扭曲 向目标拉伸根运动 */
+    motion_warps?: TimelinePointArgs<MotionWarpArgs>;
+
+    /** 注视 脊柱与头部转向目标 */
+    look_at?: ActionLookAtArgs;
+
     /** 各阶段详细数值配置 */
     attributes: TimelineRangeArgs<ActionAttributesArgs>;
 
@@ -165,6 +184,15 @@ export type ActionGeneralArgs = ActionArgs & {
 
     /** 攻击判定表 */
     hits?: ReadonlyArray<HitArgs>;
+
+    /** 生成命中物体（投射物/范围判定）的时间点 */
+    hit_objects?: TimelinePointArgs<ID>;
+
+    /** 叠加动画的播放时间点 */
+    additive_animations?: TimelinePointArgs<AdditiveAnimationArgs>;
+
+    /** 受击但未切换动作时（如霸体）播放的叠加动画 */
+    hit_additive?: AdditiveAnimationArgs;
 };
 
 /**
@@ -200,6 +228,12 @@ export class ActionGeneral extends Action {
     /** 用户输入控制的移动与旋转 */
     public readonly input_movements?: TimelinePoint<ActionGeneralMovetion>;
 
+    /** 运动扭曲 向目标拉伸根运动 */
+    public readonly motion_warps?: TimelinePoint<MotionWarp>;
+
+    /** 注视 脊柱与头部转向目标 */
+    public readonly look_at?: ActionLookAt;
+
     /** 各阶段详细数值配置 */
     public readonly attributes: TimelineRange<ActionAttributes>;
 
@@ -217,6 +251,15 @@ export class ActionGeneral extends Action {
     /** 攻击判定表 */
     public hits?: ReadonlyArray<Hit>;
 
+    /** 生成命中物体（投射物/范围判定）的时间点 */
+    public readonly hit_objects?: TimelinePoint<ID>;
+
+    /** 叠加动画的播放时间点 */
+    public readonly additive_animations?: TimelinePoint<AdditiveAnimation>;
+
+    /** 受击但未切换动作时（如霸体）播放的叠加动画 */
+    public readonly hit_additive?: AdditiveAnimation;
+
     public constructor(id: ID, args: ActionGeneralArgs) {
         super(id, args);
         this.anim_main = new Animation(args.anim_main, this.w('anim_main'), {
@@ -249,6 +292,16 @@ export class ActionGeneral extends Action {
                   {},
                   ActionGeneral.parseInputMovement,
               );
+        this.motion_warps = !args.motion_warps
+            ? undefined
+            : new TimelinePoint(
+                  args.motion_warps,
+                  this.w('motion_warps'),
+                  { duration: this.anim_main.duration, type: 'f32' },
+                  {},
+                  parseMotionWarp,
+              );
+        this.look_at = args.look_at && new ActionLookAt(args.look_at, this.w('look_at'));
         this.attributes = new TimelineRange(
             args.attributes,
             this.w('attributes'),
@@ -273,6 +326,26 @@ export class ActionGeneral extends Action {
             args.hits == null
                 ? undefined
                 : Hit.parseArray(args.hits ?? [], this.w('hits'), { files: args.anim_main.files });
+        this.hit_objects = !args.hit_objects
+            ? undefined
+            : new TimelinePoint(
+                  args.hit_objects,
+                  this.w('hit_objects'),
+                  { duration: this.anim_main.duration, type: 'f32' },
+                  {},
+                  (raw, where) => parseID(raw, 'HitObject', where),
+              );
+        this.additive_animations = !args.additive_animations
+            ? undefined
+            : new TimelinePoint(
+                  args.additive_animations,
+                  this.w('additive_animations'),
+                  { duration: this.anim_main.duration, type: 'f32' },
+                  {},
+                  parseAdditiveAnimation,
+              );
+        this.hit_additive =
+            args.hit_additive && new AdditiveAnimation(args.hit_additive, this.w('hit_additive'));
 
         Animation.generateLocalID([this.anim_main]);
     }
@@ -302,5 +375,10 @@ export class ActionGeneral extends Action {
         if (this.hits) {
             Hit.verifyArray(this.hits, { styles: this.styles }, this.w('hits'));
         }
+        if (this.hit_objects) {
+            for (const [idx, [, id]] of this.hit_objects.pairs.entries()) {
+                HitObject.find(id, this.w(`hit_objects[${idx}]`));
+            }
+        }
     }
 }
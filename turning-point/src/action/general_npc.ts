@@ -13,14 +13,25 @@ import {
     TimelineRangeArgs,
 } from '../common';
 import { Resource } from '../resource';
-import { Animation, AnimationArgs } from './animation';
+import {
+    AdditiveAnimation,
+    AdditiveAnimationArgs,
+    Animation,
+    AnimationArgs,
+    parseAdditiveAnimation,
+} from './animation';
 import {
     Action,
     ActionArgs,
     ActionAttributes,
     ActionAttributesArgs,
+    ActionLookAt,
+    ActionLookAtArgs,
+    MotionWarp,
+    MotionWarpArgs,
     parseActionAttributes,
     parseActionLevel,
+    parseMotionWarp,
 } from './base';
 import { Hit, HitArgs } from './hit_attr';
 
@@ -111,6 +122,12 @@ export type ActionGeneralNpcArgs = ActionArgs & {
     /** AI控制的移动调节 */
     adjust_movements?: TimelinePointArgs<ActionGeneralNpcMovementArgs>;
 
+    /** 运动扭曲 向目标拉伸根运动 */
+    motion_warps?: TimelinePointArgs<MotionWarpArgs>;
+
+    /** 注视 脊柱与头部转向目标 */
+    look_at?: ActionLookAtArgs;
+
     // /** 各阶段详细数值配置 */
     // attributes: TimelineRangeArgs<ActionAttributesArgs>;
 
@@ -119,6 +136,12 @@ export type ActionGeneralNpcArgs = ActionArgs & {
 
     /** 攻击判定表 */
     hits?: ReadonlyArray<HitArgs>;
+
+    /** 叠加动画的播放时间点 */
+    additive_animations?: TimelinePointArgs<AdditiveAnimationArgs>;
+
+    /** 受击但未切换动作时（如霸体）播放的叠加动画 */
+    hit_additive?: AdditiveAnimationArgs;
 };
 
 /**
@@ -139,6 +162,12 @@ export class ActionGeneralNpc extends Action {
     /** AI控制的移动调节 */
     public readonly adjust_movements?: TimelinePoint<ActionGeneralNpcMovement>;
 
+    /** 运动扭曲 向目标拉伸根运动 */
+    public readonly motion_warps?: TimelinePoint<MotionWarp>;
+
+    /** 注视 脊柱与头部转向目标 */
+    public readonly look_at?: ActionLookAt;
+
     // /** 各阶段详细数值配置 */
     // public readonly attributes: TimelineRange<ActionAttributes>;
 
@@ -148,6 +177,12 @@ export class ActionGeneralNpc extends Action {
     /** 攻击判定表 */
     public readonly hits?: ReadonlyArray<Hit>;
 
+    /** 叠加动画的播放时间点 */
+    public readonly additive_animations?: TimelinePoint<AdditiveAnimation>;
+
+    /** 受击但未切换动作时（如霸体）播放的叠加动画 */
+    public readonly hit_additive?: AdditiveAnimation;
+
     public constructor(id: ID, args: ActionGeneralNpcArgs) {
         super(id, args, { character: 'npc' });
         this.anim_main = new Animation(args.anim_main, this.w('anim_main'), {
@@ -163,6 +198,16 @@ export class ActionGeneralNpc extends Action {
                   {},
                   ActionGeneralNpc.parseMovement,
               );
+        this.motion_warps = !args.motion_warps
+            ? undefined
+            : new TimelinePoint(
+                  args.motion_warps,
+                  this.w('motion_warps'),
+                  { duration: this.anim_main.duration, type: 'f32' },
+                  {},
+                  parseMotionWarp,
+              );
+        this.look_at = args.look_at && new ActionLookAt(args.look_at, this.w('look_at'));
         // this.attributes = new TimelineRange(
         //     args.attributes,
         //     this.w('attributes'),
@@ -181,6 +226,17 @@ export class ActionGeneralNpc extends Action {
             args.hits == null
                 ? undefined
                 : Hit.parseArray(args.hits ?? [], this.w('hits'), { files: this.anim_main.files });
+        this.additive_animations = !args.additive_animations
+            ? undefined
+            : new TimelinePoint(
+                  args.additive_animations,
+                  this.w('additive_animations'),
+                  { duration: this.anim_main.duration, type: 'f32' },
+                  {},
+                  parseAdditiveAnimation,
+              );
+        this.hit_additive =
+            args.hit_additive && new AdditiveAnimation(args.hit_additive, this.w('hit_additive'));
 
         Animation.generateLocalID([this.anim_main]);
     }
@@ -1,9 +1,12 @@
+import { Buff } from '../buff';
 import {
     float,
     ID,
     int,
     MAX_HIT_TIMES,
     MAX_HIT_TIMES_PER_FRAME,
+    parseFloat,
+    parseIDArray,
     parseString,
     SPF,
 } from '../common';
@@ -23,6 +26,18 @@ export type HitArgs = {
     /** 整个判定组(HitGroup)内所有判定体的共计最大判定次数 */
     group_max_times?: int | VarValueArgs<int>;
 
+    /** 命中时对目标施加的增益/减益状态 */
+    buffs?: ReadonlyArray<ID>;
+
+    /** 命中时目标火焰积累值 积满后进入灼烧状态 */
+    fire_buildup?: float;
+
+    /** 命中时目标冰冻积累值 积满后进入冰冻状态 */
+    ice_buildup?: float;
+
+    /** 命中时目标雷电积累值 积满后进入感电状态 */
+    thunder_buildup?: float;
+
     /** 属性 伤害等效果 */
     attributes?: any;
 };
@@ -40,6 +55,18 @@ export class Hit {
     /** 整个判定组(HitGroup)内所有判定体的共计最大判定次数 */
     public group_max_times: int | Var<int>;
 
+    /** 命中时对目标施加的增益/减益状态 */
+    public buffs: ReadonlyArray<ID>;
+
+    /** 命中时目标火焰积累值 积满后进入灼烧状态 */
+    public fire_buildup: float;
+
+    /** 命中时目标冰冻积累值 积满后进入冰冻状态 */
+    public ice_buildup: float;
+
+    /** 命中时目标雷电积累值 积满后进入感电状态 */
+    public thunder_buildup: float;
+
     #default: boolean = false;
 
     public constructor(
@@ -79,6 +106,19 @@ export class Hit {
                       max: MAX_HIT_TIMES,
                       type: 'u16',
                   });
+        this.buffs = args.buffs == null ? [] : parseIDArray(args.buffs, 'Buff', `${where}.buffs`);
+        this.fire_buildup =
+            args.fire_buildup == null
+                ? 0
+                : parseFloat(args.fire_buildup, `${where}.fire_buildup`, { min: 0, type: 'f32' });
+        this.ice_buildup =
+            args.ice_buildup == null
+                ? 0
+                : parseFloat(args.ice_buildup, `${where}.ice_buildup`, { min: 0, type: 'f32' });
+        this.thunder_buildup =
+            args.thunder_buildup == null
+                ? 0
+                : parseFloat(args.thunder_buildup, `${where}.thunder_buildup`, { min: 0, type: 'f32' });
     }
 
     public verify(
@@ -91,6 +131,7 @@ export class Hit {
         verifyVarValue(this.box_max_times, consumers, where);
         verifyVarValue(this.box_min_interval, consumers, where);
         verifyVarValue(this.group_max_times, consumers, where);
+        this.buffs.forEach((id, idx) => Buff.find(id, `${where}.buffs[${idx}]`));
     }
 
     public static parseArray(
@@ -0,0 +1,135 @@
+import {
+    parseAttributeTable,
+    PRIMARY_ATTRIBUTES,
+    PrimaryAttribute,
+    SECONDARY_ATTRIBUTES,
+    SecondaryAttribute,
+} from './attribute';
+import {
+    float,
+    ID,
+    IDPrefix,
+    int,
+    MAX_NAME_LEN,
+    parseFloat,
+    parseInt,
+    parseString,
+    parseTime,
+    SPF,
+} from './common';
+import { Resource } from './resource';
+
+export type BuffStacking = 'Refresh' | 'Stack' | 'Ignore';
+
+const BUFF_STACKINGS: ReadonlySet<BuffStacking> = new Set(['Refresh', 'Stack', 'Ignore']);
+
+export type BuffArgs = {
+    /** 名称 */
+    name: string;
+
+    /** 持续时间 */
+    duration: float | string;
+
+    /**
+     * 重复施加时的叠加规则
+     * Refresh: 保持1层 刷新持续时间
+     * Stack: 增加1层(不超过max_stacks) 刷新持续时间
+     * Ignore: 保持原状
+     */
+    stacking?: BuffStacking;
+
+    /** 最大层数 */
+    max_stacks?: int;
+
+    /** 周期效果的间隔时间 0表示无周期效果 */
+    tick_interval?: float | string;
+
+    /** 每次周期效果每层的生命值变化 负数为伤害 */
+    tick_health?: float;
+
+    /** 每次周期效果每层的架势值变化 负数为伤害 */
+    tick_posture?: float;
+
+    /** 每一层数的属性列表 */
+    attributes?: Readonly<
+        Partial<Record<PrimaryAttribute | SecondaryAttribute, ReadonlyArray<float | string>>>
+    >;
+};
+
+/**
+ * 增益/减益状态 由命中、道具或脚本施加 持续期间修改角色属性面板
+ */
+export class Buff extends Resource {
+    public static override readonly prefix: IDPrefix = 'Buff';
+
+    public static override find(id: string, where: string): Buff {
+        const res = Resource.find(id, where);
+        if (!(res instanceof Buff)) {
+            throw new Error(`${where}: Resource type miss match`);
+        }
+        return res;
+    }
+
+    /** 名称 */
+    public readonly name: string;
+
+    /** 持续时间 */
+    public readonly duration: float;
+
+    /** 重复施加时的叠加规则 */
+    public readonly stacking: BuffStacking;
+
+    /** 最大层数 */
+    public readonly max_stacks: int;
+
+    /** 周期效果的间隔时间 0表示无周期效果 */
+    public readonly tick_interval: float;
+
+    /** 每次周期效果每层的生命值变化 负数为伤害 */
+    public readonly tick_health: float;
+
+    /** 每次周期效果每层的架势值变化 负数为伤害 */
+    public readonly tick_posture: float;
+
+    /** 每一层数的属性列表 */
+    public readonly attributes?: Readonly<
+        Partial<Record<PrimaryAttribute | SecondaryAttribute, ReadonlyArray<float>>>
+    >;
+
+    public constructor(id: ID, args: BuffArgs) {
+        super(id);
+        this.name = parseString(args.name, this.w('name'), { max_len: MAX_NAME_LEN });
+        this.duration = parseTime(args.duration, this.w('duration'), { min: SPF, type: 'f32' });
+        this.stacking = Buff.parseStacking(args.stacking ?? 'Refresh', this.w('stacking'));
+        this.max_stacks = parseInt(
+            args.max_stacks ?? 1,
+            this.w('max_stacks'),
+            this.stacking === 'Stack' ? { min: 1, type: 'u16' } : { min: 1, max: 1, type: 'u16' },
+        );
+        this.tick_interval =
+            args.tick_interval == null
+                ? 0
+                : parseTime(args.tick_interval, this.w('tick_interval'), { min: SPF, type: 'f32' });
+        this.tick_health = parseFloat(args.tick_health ?? 0, this.w('tick_health'), { type: 'f32' });
+        this.tick_posture = parseFloat(args.tick_posture ?? 0, this.w('tick_posture'), {
+            type: 'f32',
+        });
+        this.attributes = !args.attributes
+            ? undefined
+            : parseAttributeTable(
+                  args.attributes,
+                  [PRIMARY_ATTRIBUTES, SECONDARY_ATTRIBUTES],
+                  this.w('attributes'),
+                  { len: this.max_stacks },
+              );
+    }
+
+    private static parseStacking(raw: BuffStacking, where: string): BuffStacking {
+        if (!BUFF_STACKINGS.has(raw)) {
+            throw new Error(`${where}: must be a BuffStacking`);
+        }
+        return raw;
+    }
+
+    public override verify() {}
+}
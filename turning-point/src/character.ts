@@ -1,5 +1,6 @@
 import {
     Capsule,
+    checkArray,
     checkType,
     FilePath,
     float,
@@ -7,6 +8,8 @@ import {
     IDPrefix,
     int,
     MAX_NAME_LEN,
+    parseAngleXz,
+    parseBool,
     parseFile,
     parseFloat,
     parseID,
@@ -52,6 +55,15 @@ export type CharacterArgs = {
      * - xxx.cp-rkyv/xxx.cp-json 角色物理
      */
     skeleton_files: FilePath;
+
+    /** 脚部IK 使双脚贴合斜坡与台阶 */
+    foot_ik?: FootIkArgs;
+
+    /** 注视 动作期间脊柱与头部转向目标 */
+    look_at?: LookAtArgs;
+
+    /** 骨骼遮罩列表 供动作的局部动画（如上半身）使用 */
+    joint_masks?: ReadonlyArray<JointMaskArgs>;
 };
 
 /**
@@ -89,6 +101,15 @@ export class Character extends Resource {
      */
     public readonly skeleton_files: FilePath;
 
+    /** 脚部IK 使双脚贴合斜坡与台阶 */
+    public readonly foot_ik?: FootIk;
+
+    /** 注视 动作期间脊柱与头部转向目标 */
+    public readonly look_at?: LookAt;
+
+    /** 骨骼遮罩列表 供动作的局部动画（如上半身）使用 */
+    public readonly joint_masks: ReadonlyArray<JointMask>;
+
     public constructor(id: ID, args: CharacterArgs) {
         super(id);
         this.name = parseString(args.name, this.w('name'), { max_len: MAX_NAME_LEN });
@@ -98,6 +119,9 @@ export class Character extends Resource {
         this.skeleton_files = parseFile(args.skeleton_files, this.w('skeleton_files'), {
             extension: '.*',
         });
+        this.foot_ik = args.foot_ik && new FootIk(args.foot_ik, this.w('foot_ik'));
+        this.look_at = args.look_at && new LookAt(args.look_at, this.w('look_at'));
+        this.joint_masks = parseJointMasks(args.joint_masks || [], this.w('joint_masks'));
 
         this.checkSkeletonFiles();
     }
@@ -319,6 +343,15 @@ export type CharacterNpcArgs = {
      */
     skeleton_files: FilePath;
 
+    /** 脚部IK 使双脚贴合斜坡与台阶 */
+    foot_ik?: FootIkArgs;
+
+    /** 注视 动作期间脊柱与头部转向目标 */
+    look_at?: LookAtArgs;
+
+    /** 骨骼遮罩列表 供动作的局部动画（如上半身）使用 */
+    joint_masks?: ReadonlyArray<JointMaskArgs>;
+
     /** 角色模型（渲染） */
     view_model: FilePath;
 };
@@ -367,6 +400,15 @@ export class CharacterNpc extends Resource {
      */
     public readonly skeleton_files: FilePath;
 
+    /** 脚部IK 使双脚贴合斜坡与台阶 */
+    public readonly foot_ik?: FootIk;
+
+    /** 注视 动作期间脊柱与头部转向目标 */
+    public readonly look_at?: LookAt;
+
+    /** 骨骼遮罩列表 供动作的局部动画（如上半身）使用 */
+    public readonly joint_masks: ReadonlyArray<JointMask>;
+
     /** 角色模型（渲染） */
     public readonly view_model: FilePath;
 
@@ -389,6 +431,9 @@ export class CharacterNpc extends Resource {
         this.skeleton_files = parseFile(args.skeleton_files, this.w('skeleton_files'), {
             extension: '.*',
         });
+        this.foot_ik = args.foot_ik && new FootIk(args.foot_ik, this.w('foot_ik'));
+        this.look_at = args.look_at && new LookAt(args.look_at, this.w('look_at'));
+        this.joint_masks = parseJointMasks(args.joint_masks || [], this.w('joint_masks'));
         this.view_model = parseFile(args.view_model, this.w('view_model'), {
             extension: ['.vrm', '.prefab', '.unity'],
         });
@@ -527,3 +572,183 @@ export class FixedAttributes {
         });
     }
 }
+
+/** 脚部IK的最大腿数 */
+export const MAX_FOOT_IK_LEG = 4;
+
+export type FootIkLegArgs = {
+    /** 大腿骨骼名 */
+    hip: string;
+
+    /** 小腿骨骼名 */
+    knee: string;
+
+    /** 脚踝骨骼名 */
+    ankle: string;
+};
+
+export type FootIkArgs = {
+    /** 骨盆骨骼名 */
+    pelvis: string;
+
+    /** 腿列表 */
+    legs: ReadonlyArray<FootIkLegArgs>;
+
+    /** 骨盆的最大下移距离 使较低的脚能够触地 */
+    max_pelvis_offset: float;
+
+    /** 单只脚的最大上下移动距离 */
+    max_foot_offset: float;
+
+    /** 整个身体随地面坡度倾斜的比例 双足角色通常为0 */
+    root_tilt?: float;
+};
+
+/**
+ * 脚部IK 动画混合后检测脚下地面 下移骨盆、弯曲双腿并使脚贴合坡面
+ */
+export class FootIk {
+    /** 骨盆骨骼名 */
+    public readonly pelvis: string;
+
+    /** 腿列表 */
+    public readonly legs: ReadonlyArray<{ hip: string; knee: string; ankle: string }>;
+
+    /** 骨盆的最大下移距离 使较低的脚能够触地 */
+    public readonly max_pelvis_offset: float;
+
+    /** 单只脚的最大上下移动距离 */
+    public readonly max_foot_offset: float;
+
+    /** 整个身体随地面坡度倾斜的比例 双足角色通常为0 */
+    public readonly root_tilt: float;
+
+    public constructor(args: FootIkArgs, where: string) {
+        this.pelvis = parseString(args.pelvis, `${where}.pelvis`);
+        checkArray(args.legs, `${where}.legs`, { min_len: 1, max_len: MAX_FOOT_IK_LEG });
+        this.legs = args.legs.map((leg, idx) => ({
+            hip: parseString(leg.hip, `${where}.legs[${idx}].hip`),
+            knee: parseString(leg.knee, `${where}.legs[${idx}].knee`),
+            ankle: parseString(leg.ankle, `${where}.legs[${idx}].ankle`),
+        }));
+        this.max_pelvis_offset = parseFloat(args.max_pelvis_offset, `${where}.max_pelvis_offset`, {
+            min: 0,
+            type: 'f32',
+        });
+        this.max_foot_offset = parseFloat(args.max_foot_offset, `${where}.max_foot_offset`, {
+            min: 0,
+            type: 'f32',
+        });
+        this.root_tilt = parseFloat(args.root_tilt ?? 0, `${where}.root_tilt`, {
+            min: 0,
+            max: 1,
+            type: 'f32',
+        });
+    }
+}
+
+export type LookAtJointArgs = {
+    /** 骨骼名 */
+    joint: string;
+
+    /** 该骨骼分得的旋转比例 所有骨骼之和应为1 */
+    weight: float;
+};
+
+export type LookAtArgs = {
+    /** 从根到末端的骨骼链 如脊柱、胸、颈、头 */
+    joints: ReadonlyArray<LookAtJointArgs>;
+
+    /** 整条骨骼链的最大偏航角 */
+    max_yaw: float | string;
+
+    /** 整条骨骼链的最大俯仰角 */
+    max_pitch: float | string;
+
+    /** 武器是否随骨骼链末端旋转 通常用于瞄准 */
+    rotate_weapons?: boolean;
+};
+
+/**
+ * 注视 动作期间脊柱与头部转向动作的目标
+ */
+export class LookAt {
+    /** 从根到末端的骨骼链 如脊柱、胸、颈、头 */
+    public readonly joints: ReadonlyArray<{ joint: string; weight: float }>;
+
+    /** 整条骨骼链的最大偏航角 */
+    public readonly max_yaw: float;
+
+    /** 整条骨骼链的最大俯仰角 */
+    public readonly max_pitch: float;
+
+    /** 武器是否随骨骼链末端旋转 通常用于瞄准 */
+    public readonly rotate_weapons: boolean;
+
+    public constructor(args: LookAtArgs, where: string) {
+        checkArray(args.joints, `${where}.joints`, { min_len: 1 });
+        this.joints = args.joints.map((joint, idx) => ({
+            joint: parseString(joint.joint, `${where}.joints[${idx}].joint`),
+            weight: parseFloat(joint.weight, `${where}.joints[${idx}].weight`, {
+                min: 0,
+                max: 1,
+                type: 'f32',
+            }),
+        }));
+        this.max_yaw = parseAngleXz(args.max_yaw, `${where}.max_yaw`, { min: 0 });
+        this.max_pitch = parseAngleXz(args.max_pitch, `${where}.max_pitch`, { min: 0, max: 90 });
+        this.rotate_weapons = parseBool(args.rotate_weapons ?? false, `${where}.rotate_weapons`);
+    }
+}
+
+export type JointMaskJointArgs = {
+    /** 骨骼名 该权重作用于此骨骼及其子骨骼 */
+    joint: string;
+
+    /** 权重 */
+    weight: float;
+};
+
+export type JointMaskArgs = {
+    /** 遮罩名 */
+    name: string;
+
+    /** 骨骼权重列表 靠后的覆盖靠前的 */
+    joints: ReadonlyArray<JointMaskJointArgs>;
+};
+
+/**
+ * 骨骼遮罩 局部动画只作用于遮罩内的骨骼 并覆盖在之前动作的全身动画上
+ */
+export class JointMask {
+    /** 遮罩名 */
+    public readonly name: string;
+
+    /** 骨骼权重列表 靠后的覆盖靠前的 */
+    public readonly joints: ReadonlyArray<{ joint: string; weight: float }>;
+
+    public constructor(args: JointMaskArgs, where: string) {
+        this.name = parseString(args.name, `${where}.name`);
+        checkArray(args.joints, `${where}.joints`, { min_len: 1 });
+        this.joints = args.joints.map((joint, idx) => ({
+            joint: parseString(joint.joint, `${where}.joints[${idx}].joint`),
+            weight: parseFloat(joint.weight, `${where}.joints[${idx}].weight`, {
+                min: 0,
+                max: 1,
+                type: 'f32',
+            }),
+        }));
+    }
+}
+
+function parseJointMasks(args: ReadonlyArray<JointMaskArgs>, where: string): ReadonlyArray<JointMask> {
+    const masks = args.map((mask, idx) => new JointMask(mask, `${where}[${idx}]`));
+    const names = new Set<string>();
+    for (const [idx, mask] of masks.entries()) {
+        if (names.has(mask.name)) {
+            throw new Error(`${where}[${idx}].name: duplicate joint mask (${mask.name})`);
+        }
+        names.add(mask.name);
+    }
+    return masks;
+}
@@ -16,7 +16,9 @@ export type IDPrefix =
     | 'AiRoutine'
     | 'AiTask'
     | 'Material'
-    | 'Zone';
+    | 'Zone'
+    | 'HitObject'
+    | 'Buff';
 
 export const RE_TMPL_ID_EXTRA =
     /^\.(\#|[\w]{1,64})(?:\.([\w]{1,64}))?(?:\.([\w]{1,64}))?(?:\^([0-9]?[0-9A-Z]|[A-Z][0-9]))?$/;
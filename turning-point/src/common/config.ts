@@ -2,8 +2,6 @@ import process from 'node:process';
 
 export const FPS = 60;
 export const SPF = 1.0 / FPS;
-export const LOGIC_FPS = 30;
-export const LOGIC_SPF = 1.0 / LOGIC_FPS;
 export const ENABLE_TIME_WARNING = true;
 
 export const MAX_NAME_LEN = 48;
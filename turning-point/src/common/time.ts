@@ -1,5 +1,10 @@
+import native from '../native/native';
 import { checkArray, checkOrder, float, int } from './builtin';
-import { ENABLE_TIME_WARNING, FPS, LOGIC_SPF } from './config';
+import { ENABLE_TIME_WARNING, FPS } from './config';
+
+// The longest logic frame, at the lowest tick rate supported by critical-point.
+// A time accurate at the lowest tick rate is accurate at all of them.
+export const LOGIC_SPF = 1.0 / Math.min(...native.logicTickRates());
 
 const RE_TIME = /^(\d+(?:\.\d+)?)(s|m|min|h|hr|ms|F)$/;
 
@@ -0,0 +1,161 @@
+import {
+    Box,
+    Capsule,
+    Cylinder,
+    float,
+    ID,
+    IDPrefix,
+    int,
+    MAX_HIT_TIMES,
+    parseBool,
+    parseFloat,
+    parseInt,
+    parseString,
+    parseTime,
+    parseVec3,
+    SPF,
+    Sphere,
+} from './common';
+import { Resource } from './resource';
+
+export type HitObjectShape = Sphere | Capsule | Cylinder | Box;
+
+export type HitObjectArgs = {
+    /** 判定分组 写入HitCharacterEvent.group */
+    group: string;
+
+    /** 判定体形状 */
+    shape: HitObjectShape;
+
+    /** 生成位置（发射者空间） */
+    spawn_offset?: readonly [float, float, float];
+
+    /** 初始速度 沿发射者朝向 0表示静止的范围判定 */
+    speed?: float | string;
+
+    /** 重力加速度 */
+    gravity?: float | string;
+
+    /** 追踪转向速度（角度/秒） 0表示不追踪 */
+    homing_speed?: float | string;
+
+    /** 追踪目标的搜索半径 */
+    homing_radius?: float | string;
+
+    /** 存在时间 */
+    life_time: float | string;
+
+    /** 最大命中次数 达到后销毁 0表示不限 */
+    max_hits?: int;
+
+    /** 对单个角色的最大命中次数 */
+    chara_max_times?: int;
+
+    /** 对单个角色两次命中间的最小时间间隔 */
+    chara_min_interval?: float | string;
+
+    /** 接触场景时是否销毁 */
+    destroy_on_scenery?: boolean;
+};
+
+/**
+ * 命中物体 由动作时间轴生成的投射物或范围判定
+ */
+export class HitObject extends Resource {
+    public static override readonly prefix: IDPrefix = 'HitObject';
+
+    public static override find(id: string, where: string): HitObject {
+        const res = Resource.find(id, where);
+        if (!(res instanceof HitObject)) {
+            throw new Error(`${where}: Resource type miss match`);
+        }
+        return res;
+    }
+
+    /** 判定分组 写入HitCharacterEvent.group */
+    public readonly group: string;
+
+    /** 判定体形状 */
+    public readonly shape: HitObjectShape;
+
+    /** 生成位置（发射者空间） */
+    public readonly spawn_offset: readonly [float, float, float];
+
+    /** 初始速度 沿发射者朝向 0表示静止的范围判定 */
+    public readonly speed: float;
+
+    /** 重力加速度 */
+    public readonly gravity: float;
+
+    /** 追踪转向速度（弧度/秒） 0表示不追踪 */
+    public readonly homing_speed: float;
+
+    /** 追踪目标的搜索半径 */
+    public readonly homing_radius: float;
+
+    /** 存在时间 */
+    public readonly life_time: float;
+
+    /** 最大命中次数 达到后销毁 0表示不限 */
+    public readonly max_hits: int;
+
+    /** 对单个角色的最大命中次数 */
+    public readonly chara_max_times: int;
+
+    /** 对单个角色两次命中间的最小时间间隔 */
+    public readonly chara_min_interval: float;
+
+    /** 接触场景时是否销毁 */
+    public readonly destroy_on_scenery: boolean;
+
+    public constructor(id: ID, args: HitObjectArgs) {
+        super(id);
+        this.group = parseString(args.group, this.w('group'));
+        this.shape = HitObject.parseShape(args.shape, this.w('shape'));
+        this.spawn_offset = parseVec3(args.spawn_offset ?? [0, 0, 0], this.w('spawn_offset'));
+        this.speed = parseFloat(args.speed ?? 0, this.w('speed'), { min: 0, type: 'f32' });
+        this.gravity = parseFloat(args.gravity ?? 0, this.w('gravity'), { type: 'f32' });
+        this.homing_speed =
+            (parseFloat(args.homing_speed ?? 0, this.w('homing_speed'), { min: 0, type: 'f32' }) *
+                Math.PI) /
+            180;
+        this.homing_radius = parseFloat(args.homing_radius ?? 0, this.w('homing_radius'), {
+            min: 0,
+            type: 'f32',
+        });
+        this.life_time = parseTime(args.life_time, this.w('life_time'), { min: SPF, type: 'f32' });
+        this.max_hits = parseInt(args.max_hits ?? 0, this.w('max_hits'), {
+            min: 0,
+            max: MAX_HIT_TIMES,
+            type: 'u16',
+        });
+        this.chara_max_times = parseInt(args.chara_max_times ?? 1, this.w('chara_max_times'), {
+            min: 1,
+            max: MAX_HIT_TIMES,
+            type: 'u16',
+        });
+        this.chara_min_interval = parseTime(
+            args.chara_min_interval ?? 1e10,
+            this.w('chara_min_interval'),
+            { min: SPF, type: 'f32', ignore_warning: args.chara_min_interval == null },
+        );
+        this.destroy_on_scenery = parseBool(
+            args.destroy_on_scenery ?? this.speed > 0,
+            this.w('destroy_on_scenery'),
+        );
+    }
+
+    private static parseShape(raw: HitObjectShape, where: string): HitObjectShape {
+        if (
+            raw instanceof Sphere ||
+            raw instanceof Capsule ||
+            raw instanceof Cylinder ||
+            raw instanceof Box
+        ) {
+            return raw;
+        }
+        throw new Error(`${where}: must be a Sphere/Capsule/Cylinder/Box`);
+    }
+
+    public override verify() {}
+}
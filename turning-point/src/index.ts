@@ -3,10 +3,12 @@ export * from './action';
 export * from './ai';
 export * from './asset';
 export * from './attribute';
+export * from './buff';
 export * from './common';
 export * from './character';
 export * from './entry';
 export * from './equipment';
+export * from './hit_object';
 export * from './jewel';
 export * from './perk';
 export * from './resource';
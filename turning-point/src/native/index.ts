@@ -110,6 +110,11 @@ export function loadHitMotionMeta(path: string, err?: string): native.HitMotionM
     }
 }
 
+export function existAnimationNotify(path: string) {
+    const jsonPath = `${OUTPUT_ASSET}/${path.replace('.*', '.nt-json')}`;
+    return fs.existsSync(jsonPath);
+}
+
 export function existCharacterPhysics(path: string) {
     const rkyvPath = `${OUTPUT_ASSET}/${path.replace('.*', '.cp-rkyv')}`;
     if (fs.existsSync(rkyvPath)) {
@@ -8,7 +8,11 @@ const zones = ['/Zones'];
 Asset.copyFiles('../test-asset/', (dir, file) => {
     if (zones.includes(dir)) {
         return `${dir}/${file}`;
-    } else if (file.endsWith('.hm-json') || file.endsWith('.cp-json')) {
+    } else if (
+        file.endsWith('.hm-json') ||
+        file.endsWith('.cp-json') ||
+        file.endsWith('.nt-json')
+    ) {
         return `${dir}/${file}`;
     }
     return null;
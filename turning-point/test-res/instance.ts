@@ -13,11 +13,14 @@ import {
     AiTaskPatrol,
     Attack1,
     Attack2,
+    Buff,
     Capsule,
     Character,
     CharacterNpc,
+    Cylinder,
     Equipment,
     Hit1,
+    HitObject,
     LEVEL_ACTION,
     LEVEL_ATTACK,
     LEVEL_MOVE,
@@ -27,6 +30,7 @@ import {
     Slot1,
     Slot3,
     Style,
+    Switch,
     TaperedCapsule,
     Var,
     Walk,
@@ -56,11 +60,39 @@ const fixed_attributes = {
 new Character('Character.Instance^1', {
     name: 'Character 1',
     level: [1, 6],
-    styles: ['Style.Instance^1A'],
+    styles: ['Style.Instance^1A', 'Style.Instance^1B'],
     equipments: ['Equipment.Instance^1A', 'Equipment.Instance^1B'],
     bounding: new TaperedCapsule(0.6, 0.3, 0.1),
     skeleton_files: 'Girl/Girl.*',
     skeleton_toward: [0, 1],
+    foot_ik: {
+        pelvis: 'Hips',
+        legs: [
+            { hip: 'LeftUpperLeg', knee: 'LeftLowerLeg', ankle: 'LeftFoot' },
+            { hip: 'RightUpperLeg', knee: 'RightLowerLeg', ankle: 'RightFoot' },
+        ],
+        max_pelvis_offset: 0.3,
+        max_foot_offset: 0.4,
+    },
+    look_at: {
+        joints: [
+            { joint: 'Spine', weight: 0.3 },
+            { joint: 'Chest', weight: 0.3 },
+            { joint: 'Neck', weight: 0.2 },
+            { joint: 'Head', weight: 0.2 },
+        ],
+        max_yaw: 60,
+        max_pitch: 30,
+    },
+    joint_masks: [
+        {
+            name: 'UpperBody',
+            joints: [
+                { joint: 'Spine', weight: 0.5 },
+                { joint: 'Chest', weight: 1 },
+            ],
+        },
+    ],
 });
 
 new Style('Style.Instance^1A', {
@@ -93,6 +125,29 @@ new Style('Style.Instance^1A', {
     view_model: 'StyleOne-1.vrm',
 });
 
+new Style('Style.Instance^1B', {
+    name: 'Style 2',
+    character: 'Character.Instance^1',
+    tags: ['Player'],
+    attributes: {
+        MaxHealth: [500, 650, 800, 1000, 1200, 1400],
+        MaxPosture: [80, 90, 100, 110, 120, 130],
+        PostureRecovery: [10, 11, 12, 13, 14, 15],
+        PhysicalAttack: [8, 12, 16, 20, 24, 28],
+        PhysicalDefense: [20, 25, 30, 35, 40, 45],
+        ElementalAttack: [8, 12, 16, 20, 24, 28],
+        ElementalDefense: [10, 15, 20, 25, 30, 35],
+        ArcaneAttack: [9, 13, 17, 21, 25, 30],
+        ArcaneDefense: [5, 8, 11, 14, 17, 20],
+        CriticalChance: ['10%', '10%', '10%', '10%', '10%', '10%'],
+        CriticalDamage: ['30%', '30%', '30%', '30%', '30%', '30%'],
+    },
+    slots: ['A2D2', 'A2D2', 'A3D3', 'A3D3S2', 'A5D4S2', 'A5D4S3'],
+    fixed_attributes,
+    actions: ['Action.Instance.Idle^1A', 'Action.Instance.Run^1A', 'Action.Instance.Switch^1B'],
+    view_model: 'StyleOne-2.vrm',
+});
+
 new Equipment('Equipment.Instance^1A', {
     name: 'Weapon 1A',
     character: 'Character.Instance^1',
@@ -174,7 +229,7 @@ new Perk('Perk.Instance^1B', {
 
 new ActionIdle('Action.Instance.Idle^1A', {
     character: 'Character.Instance^1',
-    styles: ['Style.Instance^1A'],
+    styles: ['Style.Instance^1A', 'Style.Instance^1B'],
     tags: ['Idle'],
     anim_idle: {
         files: 'Girl/Idle_Empty.*',
@@ -190,7 +245,7 @@ new ActionIdle('Action.Instance.Idle^1A', {
 
 new ActionMove('Action.Instance.Run^1A', {
     character: 'Character.Instance^1',
-    styles: ['Style.Instance^1A'],
+    styles: ['Style.Instance^1A', 'Style.Instance^1B'],
     tags: ['Run'],
     enter_key: Run,
     anim_move: {
@@ -260,6 +315,7 @@ new ActionGeneral('Action.Instance.Attack^1A', {
         duration: '4s!',
         root_motion: true,
         hit_motion: true,
+        notify: true,
     },
     enter_key: Attack1,
     enter_level: LEVEL_ATTACK,
@@ -267,6 +323,7 @@ new ActionGeneral('Action.Instance.Attack^1A', {
         '0F': { duration: '8F', max_angle: 60 },
         '24F': { move_ex: true },
     },
+    look_at: { target: 'NearestEnemy', height: 1.5 },
     attributes: {
         '0-4s': {
             damage_rdc: '20%',
@@ -296,6 +353,8 @@ new ActionGeneral('Action.Instance.Attack^1A', {
             box_max_times: 2,
             box_min_interval: '1F',
             group_max_times: 4,
+            buffs: ['Buff.Instance.Burning'],
+            fire_buildup: 25,
         },
         {
             group: 'Counter',
@@ -306,6 +365,79 @@ new ActionGeneral('Action.Instance.Attack^1A', {
         '2s': 'Event2s',
         '1s': 'Event1s',
     },
+    hit_objects: {
+        '1.5s': 'HitObject.Instance.Arrow',
+    },
+    hit_additive: {
+        files: 'Girl/Idle_Empty.*',
+        duration: '0.5s',
+        weight: 0.5,
+    },
+});
+
+new ActionGeneral('Action.Instance.Switch^1B', {
+    character: 'Character.Instance^1',
+    styles: ['Style.Instance^1B'],
+    tags: ['Switch'],
+    anim_main: {
+        files: 'Girl/Idle_Axe.*',
+        duration: '1s!',
+    },
+    enter_key: Switch,
+    enter_level: LEVEL_ACTION,
+    attributes: {
+        '0-1s': {},
+    },
+    keep_levels: {
+        '0-1s': LEVEL_ACTION,
+    },
+});
+
+new HitObject('HitObject.Instance.Arrow', {
+    group: 'Arrow',
+    shape: new Capsule(0.3, 0.05),
+    spawn_offset: [0, 1.2, 0.5],
+    speed: 20,
+    gravity: 2,
+    life_time: '3s',
+    max_hits: 1,
+});
+
+new HitObject('HitObject.Instance.FireArea', {
+    group: 'Fire',
+    shape: new Cylinder(0.5, 2),
+    life_time: '5s',
+    chara_max_times: 5,
+    chara_min_interval: '1s',
+});
+
+new Buff('Buff.Instance.AttackUp', {
+    name: 'Attack Up',
+    duration: '10s',
+    stacking: 'Stack',
+    max_stacks: 3,
+    attributes: {
+        AttackUp: [0.1, 0.15, 0.2],
+    },
+});
+
+new Buff('Buff.Instance.Burning', {
+    name: 'Burning',
+    duration: '5s',
+    tick_interval: '1s',
+    tick_health: -20,
+    attributes: {
+        FireDefenseDown: [0.2],
+    },
+});
+
+new Buff('Buff.Instance.Poison', {
+    name: 'Poison',
+    duration: '3s',
+    stacking: 'Stack',
+    max_stacks: 3,
+    tick_interval: '1s',
+    tick_health: -10,
 });
 
 new ActionGeneral('Action.Instance.AttackDerive^1A', {
@@ -318,6 +450,16 @@ new ActionGeneral('Action.Instance.AttackDerive^1A', {
         duration: '5s!',
         root_motion: true,
     },
+    motion_warps: {
+        '0.5s': {
+            duration: '1s',
+            target: 'AiTarget',
+            distance: 1,
+            min_scale: 0.5,
+            max_scale: 3,
+            max_angle: 60,
+        },
+    },
     attributes: {
         '0-5s': {},
     },
@@ -344,6 +486,24 @@ new ActionGeneral('Action.Instance.AttackUnused^1A', {
     },
 });
 
+new ActionGeneral('Action.Instance.UpperAttack^1A', {
+    character: 'Character.Instance^1',
+    tags: ['Attack'],
+    styles: ['Style.Instance^1A'],
+    anim_main: {
+        files: 'Girl/Attack_01A.*',
+        duration: '2s!',
+        root_motion: true,
+        joint_mask: 'UpperBody',
+    },
+    attributes: {
+        '0-2s': {},
+    },
+    keep_levels: {
+        '0-2s': LEVEL_ATTACK,
+    },
+});
+
 //
 // NPC
 //
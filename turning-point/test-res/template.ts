@@ -307,6 +307,7 @@ new ActionGeneral('Action.One.Attack^1', {
         root_motion: true,
         weapon_motion: true,
         hit_motion: true,
+        notify: true,
     },
     character: ONE.id,
     tags: ['Attack'],